@@ -0,0 +1,555 @@
+use crate::detectors::finding::level;
+use crate::engine::Detector;
+use crate::network::flow::{Flow, PROTO_TCP, PROTO_UDP};
+use crate::network::flow_table::{FlowTable, FlowTableConfig};
+use crate::network::format::service_label;
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+
+// Tuplas, inicios por tupla y conexiones TCP abiertas seguidos como mucho
+const MAX_TUPLES: usize = 100_000;
+const MAX_EVENTS_PER_TUPLE: usize = 10_000;
+const MAX_CONNS: usize = 200_000;
+// Conexiones TCP sin tráfico en este plazo (half-open de escaneos, cierres no vistos) se
+// olvidan; solo importan para sumar bytes al inicio que las abrió
+const CONN_IDLE_TIMEOUT_US: u64 = 120_000_000;
+const MAX_REPORTED: usize = 1000;
+// Un paquete UDP tras este silencio en la tupla abre una sesión nueva
+const UDP_SESSION_GAP_US: u64 = 1_000_000;
+// Mínimo de inicios de sesión (intervalos + 1) para puntuar periodicidad
+const MIN_EVENTS: usize = 6;
+// Por debajo de este intervalo mediano es tráfico continuo, no un beacon
+const MIN_INTERVAL_US: u64 = 1_000_000;
+// Número de intervalos con el que la puntuación de recuento llega a 1
+const FULL_COUNT_INTERVALS: f64 = 20.0;
+const MIN_BEACON_SCORE: f64 = 0.7;
+const HIGH_BEACON_SCORE: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Tuple {
+    src: IpAddr,
+    dst: IpAddr,
+    port: u16,
+    proto: u8,
+}
+
+impl Tuple {
+    fn label(&self) -> String {
+        let proto = if self.proto == PROTO_TCP {
+            "TCP"
+        } else {
+            "UDP"
+        };
+        service_label(self.src, self.dst, self.port, proto)
+    }
+}
+
+/// Inicio de conexión TCP o de ráfaga UDP, con los bytes que movió en ambos sentidos
+#[derive(Debug, Clone, Copy)]
+struct Event {
+    ts_us: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Default)]
+struct TupleState {
+    events: Vec<Event>,
+    events_truncated: u64,
+    last_packet_us: u64,
+}
+
+/// Mediana, MAD y asimetría de Bowley: estadísticos robustos a beacons perdidos o
+/// intervalos atípicos, a diferencia de media y desviación típica
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct RobustStats {
+    median: f64,
+    mad: f64,
+    bowley_skew: f64,
+    min: f64,
+    max: f64,
+}
+
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = (sorted.len() - 1) as f64 * q;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+fn robust_stats(values: &[f64]) -> RobustStats {
+    if values.is_empty() {
+        return RobustStats::default();
+    }
+    let mut v = values.to_vec();
+    v.sort_by(f64::total_cmp);
+    let (q1, median, q3) = (quantile(&v, 0.25), quantile(&v, 0.5), quantile(&v, 0.75));
+    let mut dev: Vec<f64> = v.iter().map(|x| (x - median).abs()).collect();
+    dev.sort_by(f64::total_cmp);
+    let iqr = q3 - q1;
+    RobustStats {
+        median,
+        mad: quantile(&dev, 0.5),
+        bowley_skew: if iqr > 0.0 {
+            (q3 + q1 - 2.0 * median) / iqr
+        } else {
+            0.0
+        },
+        min: v[0],
+        max: v[v.len() - 1],
+    }
+}
+
+/// Regularidad en 0..=1: simetría (Bowley) y dispersión relativa (MAD / mediana)
+fn regularity(s: &RobustStats) -> f64 {
+    let skew = 1.0 - s.bowley_skew.abs().min(1.0);
+    let dispersion = if s.median > 0.0 {
+        1.0 - (s.mad / s.median).min(1.0)
+    } else if s.mad == 0.0 {
+        1.0
+    } else {
+        0.0
+    };
+    (skew + dispersion) / 2.0
+}
+
+struct Candidate {
+    tuple: Tuple,
+    events: usize,
+    events_truncated: u64,
+    first_us: u64,
+    last_us: u64,
+    interval: RobustStats,
+    size: RobustStats,
+    interval_score: f64,
+    size_score: f64,
+    beacon_score: f64,
+}
+
+fn analyze(tuple: Tuple, st: &TupleState) -> Option<Candidate> {
+    if st.events.len() < MIN_EVENTS {
+        return None;
+    }
+    // Los timestamps de la captura pueden ir hacia atrás: la serie se ordena antes de
+    // medir intervalos
+    let mut events = st.events.clone();
+    events.sort_by_key(|e| e.ts_us);
+    let intervals: Vec<f64> = events
+        .windows(2)
+        .map(|w| (w[1].ts_us - w[0].ts_us) as f64)
+        .collect();
+    let sizes: Vec<f64> = events.iter().map(|e| e.bytes as f64).collect();
+    let interval = robust_stats(&intervals);
+    if interval.median < MIN_INTERVAL_US as f64 {
+        return None;
+    }
+    let size = robust_stats(&sizes);
+    let count = (intervals.len() as f64 / FULL_COUNT_INTERVALS).min(1.0);
+    let interval_score = (2.0 * regularity(&interval) + count) / 3.0;
+    let size_score = regularity(&size);
+    Some(Candidate {
+        tuple,
+        events: events.len(),
+        events_truncated: st.events_truncated,
+        first_us: events[0].ts_us,
+        last_us: events[events.len() - 1].ts_us,
+        interval,
+        size,
+        interval_score,
+        size_score,
+        // La cadencia pesa el doble que el tamaño: muchos C2 varían la carga
+        beacon_score: (2.0 * interval_score + size_score) / 3.0,
+    })
+}
+
+fn candidate_json(c: &Candidate, score: u32) -> Value {
+    let s = |us: f64| us / 1_000_000.0;
+    json!({
+        "src": c.tuple.src.to_string(),
+        "dst": c.tuple.dst.to_string(),
+        "port": c.tuple.port,
+        "proto": if c.tuple.proto == PROTO_TCP { "tcp" } else { "udp" },
+        "events": c.events,
+        "events_truncated": c.events_truncated,
+        "first_ts_us": c.first_us,
+        "last_ts_us": c.last_us,
+        "duration_s": s((c.last_us - c.first_us) as f64),
+        "interval_s": {
+            "median": s(c.interval.median),
+            "mad": s(c.interval.mad),
+            "min": s(c.interval.min),
+            "max": s(c.interval.max),
+            "jitter_ratio": c.interval.mad / c.interval.median,
+            "bowley_skew": c.interval.bowley_skew
+        },
+        "bytes": {
+            "median": c.size.median,
+            "mad": c.size.mad,
+            "min": c.size.min,
+            "max": c.size.max,
+            "bowley_skew": c.size.bowley_skew
+        },
+        "interval_score": c.interval_score,
+        "size_score": c.size_score,
+        "beacon_score": c.beacon_score,
+        "score": { "value": score, "level": level(score) }
+    })
+}
+
+/// Beaconing / C2: periodicidad de los inicios de conexión TCP y de las ráfagas UDP por
+/// (origen, destino, puerto), puntuando regularidad de intervalos, jitter y tamaño
+pub struct BeaconDetector {
+    tuples: HashMap<Tuple, TupleState>,
+    tuples_truncated: u64,
+    // Conexión TCP abierta -> tupla cuyo inicio registró
+    conns: FlowTable<Option<Tuple>>,
+}
+
+impl Default for BeaconDetector {
+    fn default() -> Self {
+        BeaconDetector {
+            tuples: HashMap::new(),
+            tuples_truncated: 0,
+            conns: FlowTable::new(FlowTableConfig {
+                max_flows: MAX_CONNS,
+                idle_timeout_us: CONN_IDLE_TIMEOUT_US,
+            }),
+        }
+    }
+}
+
+impl BeaconDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn start_event(&mut self, tuple: Tuple, bytes: u64, ts: u64) -> bool {
+        if !self.tuples.contains_key(&tuple) && self.tuples.len() >= MAX_TUPLES {
+            self.tuples_truncated += 1;
+            return false;
+        }
+        let st = self.tuples.entry(tuple).or_default();
+        st.last_packet_us = ts;
+        if st.events.len() < MAX_EVENTS_PER_TUPLE {
+            st.events.push(Event { ts_us: ts, bytes });
+        } else {
+            st.events_truncated += 1;
+        }
+        true
+    }
+
+    /// Suma bytes al último inicio registrado de la tupla
+    fn add_bytes(&mut self, tuple: &Tuple, bytes: u64, ts: u64) {
+        if let Some(st) = self.tuples.get_mut(tuple) {
+            st.last_packet_us = ts;
+            if st.events_truncated == 0 {
+                if let Some(e) = st.events.last_mut() {
+                    e.bytes += bytes;
+                }
+            }
+        }
+    }
+
+    fn on_tcp(
+        &mut self,
+        src: IpAddr,
+        dst: IpAddr,
+        tcp: &etherparse::TcpSlice,
+        bytes: u64,
+        ts: u64,
+    ) {
+        let flow = Flow {
+            source_ip: src,
+            source_port: tcp.source_port(),
+            destination_ip: dst,
+            destination_port: tcp.destination_port(),
+        };
+        // Expulsa inactivas y, con la tabla llena, las menos recientes: un escaneo SYN
+        // no deja sin sitio a las conexiones posteriores
+        self.conns.evict(ts);
+        let known = self.conns.get_mut(&flow).is_some();
+        if tcp.syn() && !tcp.ack() {
+            // Las retransmisiones del SYN no abren una conexión nueva
+            if known {
+                return;
+            }
+            let tuple = Tuple {
+                src,
+                dst,
+                port: flow.destination_port,
+                proto: PROTO_TCP,
+            };
+            if self.start_event(tuple, bytes, ts) {
+                *self.conns.get_or_insert(flow, ts).1 = Some(tuple);
+            }
+            return;
+        }
+        if !known {
+            return;
+        }
+        // Refresca la actividad de la conexión
+        let Some(tuple) = *self.conns.get_or_insert(flow, ts).1 else {
+            return;
+        };
+        self.add_bytes(&tuple, bytes, ts);
+        if tcp.fin() || tcp.rst() {
+            self.conns.remove(&flow);
+        }
+    }
+
+    fn on_udp(&mut self, src: IpAddr, dst: IpAddr, sport: u16, dport: u16, bytes: u64, ts: u64) {
+        let forward = Tuple {
+            src,
+            dst,
+            port: dport,
+            proto: PROTO_UDP,
+        };
+        let reverse = Tuple {
+            src: dst,
+            dst: src,
+            port: sport,
+            proto: PROTO_UDP,
+        };
+        // Las respuestas cuentan en la tupla de quien habló primero
+        let tuple = if self.tuples.contains_key(&forward) || !self.tuples.contains_key(&reverse) {
+            forward
+        } else {
+            reverse
+        };
+        match self.tuples.get(&tuple) {
+            Some(st) if ts.saturating_sub(st.last_packet_us) < UDP_SESSION_GAP_US => {
+                self.add_bytes(&tuple, bytes, ts)
+            }
+            // Tras un silencio solo el iniciador abre sesión; una respuesta tardía no
+            Some(_) if tuple != forward => self.add_bytes(&tuple, bytes, ts),
+            _ => {
+                self.start_event(tuple, bytes, ts);
+            }
+        }
+    }
+}
+
+impl Detector for BeaconDetector {
+    fn name(&self) -> &'static str {
+        "beacon"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        let bytes = packet_data.len() as u64;
+        match &sliced.transport {
+            Some(TransportSlice::Tcp(tcp)) => self.on_tcp(src, dst, tcp, bytes, ts_micros),
+            Some(TransportSlice::Udp(udp)) => self.on_udp(
+                src,
+                dst,
+                udp.source_port(),
+                udp.destination_port(),
+                bytes,
+                ts_micros,
+            ),
+            _ => {}
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        let conn_stats = self.conns.stats();
+        let mut candidates: Vec<Candidate> = self
+            .tuples
+            .iter()
+            .filter_map(|(t, st)| analyze(*t, st))
+            .filter(|c| c.beacon_score >= MIN_BEACON_SCORE)
+            .collect();
+        candidates.sort_by(|a, b| {
+            b.beacon_score
+                .total_cmp(&a.beacon_score)
+                .then_with(|| b.events.cmp(&a.events))
+                .then_with(|| a.tuple.cmp(&b.tuple))
+        });
+
+        let mut findings = Vec::new();
+        let mut reported = Vec::new();
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for c in &candidates {
+            let score = if c.beacon_score >= HIGH_BEACON_SCORE {
+                100
+            } else {
+                60
+            };
+            *counts.entry("BEACONING").or_default() += 1;
+            if findings.len() < MAX_REPORTED {
+                findings.push(json!({
+                    "code": "BEACONING",
+                    "subject": c.tuple.label(),
+                    "score": { "value": score, "level": level(score) },
+                    "reason": format!(
+                        "{} inicios cada {:.1} s (MAD {:.2} s), tamaño mediano {:.0} B, puntuación {:.2}",
+                        c.events,
+                        c.interval.median / 1_000_000.0,
+                        c.interval.mad / 1_000_000.0,
+                        c.size.median,
+                        c.beacon_score
+                    ),
+                    "count": c.events,
+                    "detail": {
+                        "interval_s": c.interval.median / 1_000_000.0,
+                        "beacon_score": c.beacon_score
+                    }
+                }));
+                reported.push(candidate_json(c, score));
+            }
+        }
+
+        json!({
+            "tuples_total": self.tuples.len(),
+            "tuples_truncated": self.tuples_truncated,
+            "connection_table": {
+                "max_connections": MAX_CONNS,
+                "idle_timeout_s": CONN_IDLE_TIMEOUT_US / 1_000_000,
+                "peak_active": conn_stats.peak_flows,
+                "evicted_idle": conn_stats.evicted_idle,
+                "evicted_capacity": conn_stats.evicted_capacity
+            },
+            "candidates_total": candidates.len(),
+            "candidates": reported,
+            "findings": findings,
+            "counts_by_code": counts
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::test_frames::{tcp, udp};
+
+    const IMPLANT: [u8; 4] = [10, 0, 0, 5];
+    const C2: [u8; 4] = [203, 0, 113, 9];
+
+    #[test]
+    fn periodic_https_callbacks_with_jitter_rank_as_beacon() {
+        let mut d = BeaconDetector::new();
+        // 30 conexiones cada 60 s ±2 s, con un beacon perdido y el mismo volumen por sesión
+        let mut ts = 1_000_000u64;
+        for i in 0..30u64 {
+            let jitter = [0, 1_500_000, 400_000, 2_000_000, 900_000][i as usize % 5];
+            ts += 60_000_000 + jitter - 1_000_000;
+            if i == 17 {
+                continue;
+            }
+            let port = 50000 + i as u16;
+            d.on_packet(&tcp(IMPLANT, C2, 64, port, 443, "S", &[]), ts);
+            d.on_packet(&tcp(C2, IMPLANT, 64, 443, port, "SA", &[]), ts + 30_000);
+            d.on_packet(
+                &tcp(IMPLANT, C2, 64, port, 443, "A", &[7; 200]),
+                ts + 60_000,
+            );
+            d.on_packet(
+                &tcp(C2, IMPLANT, 64, 443, port, "AF", &[9; 80]),
+                ts + 90_000,
+            );
+        }
+        // Navegación a ráfagas al mismo host, otro puerto
+        for (i, start) in [5u64, 9, 140, 152, 400, 410, 415, 900, 1300]
+            .iter()
+            .enumerate()
+        {
+            d.on_packet(
+                &tcp(IMPLANT, C2, 64, 40000 + i as u16, 80, "S", &[]),
+                start * 1_000_000,
+            );
+        }
+
+        let r = d.finalize();
+        assert_eq!(r["candidates_total"], 1);
+        let c = &r["candidates"][0];
+        assert_eq!(c["port"], 443);
+        assert_eq!(c["events"], 29);
+        let median = c["interval_s"]["median"].as_f64().unwrap();
+        assert!((59.0..=62.0).contains(&median), "{median}");
+        assert!(c["beacon_score"].as_f64().unwrap() >= HIGH_BEACON_SCORE);
+        assert_eq!(c["score"]["level"], "ALTA");
+        assert_eq!(r["counts_by_code"]["BEACONING"], 1);
+    }
+
+    #[test]
+    fn udp_sessions_split_by_silence_and_replies_join_the_initiator() {
+        let mut d = BeaconDetector::new();
+        for i in 0..8u64 {
+            let ts = i * 10_000_000;
+            d.on_packet(&udp(IMPLANT, C2, 5353, 53, &[1; 40]), ts);
+            d.on_packet(&udp(C2, IMPLANT, 53, 5353, &[2; 100]), ts + 20_000);
+        }
+        // Tráfico continuo: una única sesión, nunca candidato
+        for i in 0..50u64 {
+            d.on_packet(
+                &udp(IMPLANT, [10, 0, 0, 9], 6000, 7000, &[0; 20]),
+                i * 500_000,
+            );
+        }
+
+        let r = d.finalize();
+        assert_eq!(r["tuples_total"], 2);
+        assert_eq!(r["candidates_total"], 1);
+        let dns = r["candidates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["port"] == 53)
+            .unwrap();
+        assert_eq!(dns["events"], 8);
+        assert_eq!(dns["interval_s"]["median"], 10.0);
+        assert_eq!(dns["interval_s"]["mad"], 0.0);
+        // 82 + 142 bytes de trama por sesión
+        assert_eq!(dns["bytes"]["median"], 224.0);
+    }
+
+    #[test]
+    fn out_of_order_timestamps_and_syn_scan_do_not_break_tracking() {
+        let mut d = BeaconDetector::new();
+        // Flood de SYN sin cerrar desde 4 orígenes: más half-open que el tope de conexiones
+        for i in 0..(MAX_CONNS as u32 + 10) {
+            let src = [10, 0, 1, (i % 4) as u8];
+            let sport = 1024 + (i / 4) as u16;
+            d.on_packet(&tcp(src, [10, 0, 0, 80], 64, sport, 80, "S", &[]), 1_000);
+        }
+        // Beacon cada 30 s; dos inicios llegan con el reloj invertido
+        let starts = [10u64, 40, 70, 130, 100, 160, 190, 220, 250, 280];
+        for (i, s) in starts.iter().enumerate() {
+            let ts = s * 1_000_000;
+            let port = 50000 + i as u16;
+            d.on_packet(&tcp(IMPLANT, C2, 64, port, 443, "S", &[]), ts);
+            d.on_packet(
+                &tcp(IMPLANT, C2, 64, port, 443, "A", &[7; 100]),
+                ts + 50_000,
+            );
+        }
+
+        let r = d.finalize();
+        assert!(r["connection_table"]["evicted_capacity"].as_u64().unwrap() > 0);
+        let c = r["candidates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["port"] == 443)
+            .expect("beacon tras el escaneo");
+        assert_eq!(c["events"], 10);
+        assert_eq!(c["first_ts_us"], 10_000_000);
+        assert_eq!(c["last_ts_us"], 280_000_000);
+        assert_eq!(c["interval_s"]["median"], 30.0);
+        // Los bytes de cada sesión siguen sumándose a su inicio
+        assert_eq!(c["bytes"]["median"], 208.0);
+    }
+}
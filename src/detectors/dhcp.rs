@@ -0,0 +1,1008 @@
+use crate::detectors::finding::{level, Finding};
+use crate::detectors::latency::LatencyStats;
+use crate::engine::Detector;
+use crate::network::arp::format_mac;
+use crate::network::dhcp::{
+    self, duid_mac, fingerprint, message_type_name, v6_message_type_name, v6_status_name,
+    DhcpMessage, Dhcpv6Message,
+};
+use crate::network::format::hex;
+use etherparse::{InternetSlice, LinkSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+// Transacciones, clientes y servidores con detalle; los que exceden el tope solo suman
+// en los contadores `*_truncated`
+const MAX_TRANSACTIONS: usize = 100_000;
+const MAX_CLIENTS: usize = 100_000;
+const MAX_SERVERS: usize = 256;
+const MAX_ADDRESSES: usize = 16;
+const MAX_REPORTED: usize = 1000;
+// Sin mensajes en este plazo la transacción se cierra (los clientes reintentan a 4, 8, 16 s)
+const TRANSACTION_TIMEOUT_US: u64 = 30_000_000;
+// Respuesta de servidor (p95) por encima de esto se reporta como lenta
+const SLOW_REPLY_US: u64 = 1_000_000;
+const NAK_HIGH: u64 = 3;
+
+/// Configuración del detector DHCP
+#[derive(Debug, Clone, Default)]
+pub struct DhcpConfig {
+    /// Servidores autorizados (IP, MAC o DUID en hex). Vacío: se toma como legítimo el primero visto
+    pub trusted_servers: Vec<String>,
+}
+
+/// Intercambio identificado por versión, xid y MAC del cliente
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct TxKey {
+    v6: bool,
+    xid: u32,
+    mac: [u8; 6],
+}
+
+#[derive(Debug)]
+struct Transaction {
+    first_us: u64,
+    last_us: u64,
+    // Último mensaje del cliente: la respuesta se mide contra él (los reintentos reusan el xid)
+    last_client_us: u64,
+    discovered: bool,
+    requested: bool,
+    offers: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+struct Server {
+    v6: bool,
+    ip: IpAddr,
+    mac: [u8; 6],
+    offers: u64,
+    acks: u64,
+    naks: u64,
+    clients: BTreeSet<[u8; 6]>,
+    latency: LatencyStats,
+    first_us: u64,
+    last_us: u64,
+}
+
+/// Entrada de inventario: lo que el cliente dice de sí mismo y lo que recibió
+#[derive(Debug, Default)]
+struct Client {
+    hostname: Option<String>,
+    fingerprint: Option<String>,
+    fingerprint_v6: Option<String>,
+    vendor_class: Option<String>,
+    duid: Option<String>,
+    addresses: BTreeSet<IpAddr>,
+    declined: BTreeSet<IpAddr>,
+    relay: Option<IpAddr>,
+    servers: BTreeSet<String>,
+    transactions: u64,
+    completed: u64,
+    naks: u64,
+    declines: u64,
+    unanswered: u64,
+    first_us: u64,
+    last_us: u64,
+}
+
+#[derive(Debug, Default)]
+struct TxCounters {
+    started: u64,
+    completed: u64,
+    nak: u64,
+    declined: u64,
+    no_offer: u64,
+    no_ack: u64,
+    retransmissions: u64,
+}
+
+/// Detector DHCP / DHCPv6: transacciones DORA/SARR con latencia, NAKs, DECLINEs,
+/// servidores no autorizados e inventario pasivo de clientes (huella de opción 55)
+pub struct DhcpDetector {
+    trusted_servers: BTreeSet<String>,
+    transactions: HashMap<TxKey, Transaction>,
+    transactions_truncated: u64,
+    clients: BTreeMap<[u8; 6], Client>,
+    clients_truncated: u64,
+    servers: BTreeMap<String, Server>,
+    servers_truncated: u64,
+    v4_messages: BTreeMap<&'static str, u64>,
+    v6_messages: BTreeMap<&'static str, u64>,
+    v6_status: BTreeMap<&'static str, u64>,
+    counters: TxCounters,
+    offer_latency: LatencyStats,
+    ack_latency: LatencyStats,
+    total_latency: LatencyStats,
+    malformed: u64,
+    last_sweep_us: u64,
+    last_ts_us: u64,
+}
+
+impl Default for DhcpDetector {
+    fn default() -> Self {
+        Self::with_config(DhcpConfig::default())
+    }
+}
+
+fn ips_json(ips: &BTreeSet<IpAddr>) -> Vec<String> {
+    ips.iter().map(|ip| ip.to_string()).collect()
+}
+
+/// Mensaje ya normalizado entre DHCPv4 y DHCPv6
+enum Kind {
+    Discover,
+    Request,
+    Offer,
+    Ack,
+    Nak,
+    Decline,
+    Other,
+}
+
+impl DhcpDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: DhcpConfig) -> Self {
+        DhcpDetector {
+            trusted_servers: config
+                .trusted_servers
+                .iter()
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| match s.parse::<IpAddr>() {
+                    Ok(ip) => ip.to_string(),
+                    Err(_) => s.to_ascii_lowercase(),
+                })
+                .collect(),
+            transactions: HashMap::new(),
+            transactions_truncated: 0,
+            clients: BTreeMap::new(),
+            clients_truncated: 0,
+            servers: BTreeMap::new(),
+            servers_truncated: 0,
+            v4_messages: BTreeMap::new(),
+            v6_messages: BTreeMap::new(),
+            v6_status: BTreeMap::new(),
+            counters: TxCounters::default(),
+            offer_latency: LatencyStats::default(),
+            ack_latency: LatencyStats::default(),
+            total_latency: LatencyStats::default(),
+            malformed: 0,
+            last_sweep_us: 0,
+            last_ts_us: 0,
+        }
+    }
+
+    fn client(&mut self, mac: [u8; 6], ts: u64) -> Option<&mut Client> {
+        if !self.clients.contains_key(&mac) && self.clients.len() >= MAX_CLIENTS {
+            self.clients_truncated += 1;
+            return None;
+        }
+        let c = self.clients.entry(mac).or_insert_with(|| Client {
+            first_us: ts,
+            ..Client::default()
+        });
+        c.last_us = ts;
+        Some(c)
+    }
+
+    fn track_server(&mut self, id: &str, v6: bool, ip: IpAddr, mac: [u8; 6], ts: u64) {
+        if !self.servers.contains_key(id) && self.servers.len() >= MAX_SERVERS {
+            self.servers_truncated += 1;
+            return;
+        }
+        let s = self
+            .servers
+            .entry(id.to_string())
+            .or_insert_with(|| Server {
+                v6,
+                ip,
+                mac,
+                offers: 0,
+                acks: 0,
+                naks: 0,
+                clients: BTreeSet::new(),
+                latency: LatencyStats::default(),
+                first_us: ts,
+                last_us: ts,
+            });
+        s.last_us = ts;
+    }
+
+    /// Mensaje de cliente: abre la transacción o cuenta el reintento
+    fn on_client(&mut self, key: TxKey, kind: Kind, ts: u64) {
+        if let Some(tx) = self.transactions.get_mut(&key) {
+            match kind {
+                Kind::Discover if tx.discovered => self.counters.retransmissions += 1,
+                Kind::Request if tx.requested => self.counters.retransmissions += 1,
+                Kind::Discover => tx.discovered = true,
+                Kind::Request => tx.requested = true,
+                _ => {}
+            }
+            tx.last_us = ts;
+            tx.last_client_us = ts;
+            return;
+        }
+        if !matches!(kind, Kind::Discover | Kind::Request) {
+            return;
+        }
+        if self.transactions.len() >= MAX_TRANSACTIONS {
+            self.transactions_truncated += 1;
+            return;
+        }
+        self.counters.started += 1;
+        if let Some(c) = self.client(key.mac, ts) {
+            c.transactions += 1;
+        }
+        self.transactions.insert(
+            key,
+            Transaction {
+                first_us: ts,
+                last_us: ts,
+                last_client_us: ts,
+                discovered: matches!(kind, Kind::Discover),
+                requested: matches!(kind, Kind::Request),
+                offers: BTreeSet::new(),
+            },
+        );
+    }
+
+    /// Respuesta de servidor: OFFER/ADVERTISE, ACK/REPLY o NAK
+    fn on_server(&mut self, key: TxKey, kind: Kind, server_id: &str, ts: u64) {
+        let tx = self.transactions.get_mut(&key);
+        let reply_us = tx.as_ref().map(|tx| ts.saturating_sub(tx.last_client_us));
+        if let Some(s) = self.servers.get_mut(server_id) {
+            if let Some(us) = reply_us {
+                s.latency.add_sample(us);
+            }
+            match kind {
+                Kind::Offer => s.offers += 1,
+                Kind::Ack => s.acks += 1,
+                Kind::Nak => s.naks += 1,
+                _ => {}
+            }
+            if s.clients.len() < MAX_REPORTED {
+                s.clients.insert(key.mac);
+            }
+        }
+        let Some(tx) = tx else {
+            return;
+        };
+        tx.last_us = ts;
+        match kind {
+            Kind::Offer => {
+                if tx.offers.is_empty() {
+                    self.offer_latency
+                        .add_sample(ts.saturating_sub(tx.last_client_us));
+                }
+                tx.offers.insert(server_id.to_string());
+            }
+            Kind::Ack => {
+                self.ack_latency
+                    .add_sample(ts.saturating_sub(tx.last_client_us));
+                if tx.discovered {
+                    self.total_latency
+                        .add_sample(ts.saturating_sub(tx.first_us));
+                }
+                self.transactions.remove(&key);
+                self.counters.completed += 1;
+                if let Some(c) = self.client(key.mac, ts) {
+                    c.completed += 1;
+                    c.servers.insert(server_id.to_string());
+                }
+            }
+            Kind::Nak => {
+                self.transactions.remove(&key);
+                self.counters.nak += 1;
+                if let Some(c) = self.client(key.mac, ts) {
+                    c.naks += 1;
+                    c.servers.insert(server_id.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_decline(&mut self, key: TxKey, addresses: Vec<IpAddr>, ts: u64) {
+        self.transactions.remove(&key);
+        self.counters.declined += 1;
+        if let Some(c) = self.client(key.mac, ts) {
+            c.declines += 1;
+            for a in addresses {
+                if c.declined.len() < MAX_ADDRESSES {
+                    c.declined.insert(a);
+                }
+            }
+        }
+    }
+
+    fn on_v4(&mut self, m: DhcpMessage, src: IpAddr, eth_src: [u8; 6], ts: u64) {
+        let Some(t) = m.message_type else {
+            self.malformed += 1;
+            return;
+        };
+        *self.v4_messages.entry(message_type_name(t)).or_default() += 1;
+        let Some(mac) = m.client_mac else {
+            return;
+        };
+        let key = TxKey {
+            v6: false,
+            xid: m.xid,
+            mac,
+        };
+        let kind = match t {
+            dhcp::DISCOVER => Kind::Discover,
+            dhcp::REQUEST | dhcp::INFORM => Kind::Request,
+            dhcp::OFFER => Kind::Offer,
+            dhcp::ACK => Kind::Ack,
+            dhcp::NAK => Kind::Nak,
+            dhcp::DECLINE => Kind::Decline,
+            _ => Kind::Other,
+        };
+        match kind {
+            Kind::Offer | Kind::Ack | Kind::Nak => {
+                // Sin opción 54 el servidor se identifica por su IP de origen
+                let id = m.server_id.map(IpAddr::V4).unwrap_or(src).to_string();
+                self.track_server(&id, false, src, eth_src, ts);
+                if matches!(kind, Kind::Ack) && !m.yiaddr.is_unspecified() {
+                    if let Some(c) = self.client(mac, ts) {
+                        if c.addresses.len() < MAX_ADDRESSES {
+                            c.addresses.insert(IpAddr::V4(m.yiaddr));
+                        }
+                    }
+                }
+                self.on_server(key, kind, &id, ts);
+            }
+            Kind::Decline => {
+                let declined = m.requested_ip.map(IpAddr::V4).into_iter().collect();
+                self.on_decline(key, declined, ts);
+            }
+            _ => {
+                if let Some(c) = self.client(mac, ts) {
+                    if m.hostname.is_some() {
+                        c.hostname = m.hostname.clone();
+                    }
+                    if !m.param_request_list.is_empty() {
+                        c.fingerprint = Some(fingerprint(&m.param_request_list));
+                    }
+                    if m.vendor_class.is_some() {
+                        c.vendor_class = m.vendor_class.clone();
+                    }
+                    if !m.giaddr.is_unspecified() {
+                        c.relay = Some(IpAddr::V4(m.giaddr));
+                    }
+                }
+                self.on_client(key, kind, ts);
+            }
+        }
+    }
+
+    fn on_v6(&mut self, m: Dhcpv6Message, src: IpAddr, eth: ([u8; 6], [u8; 6]), ts: u64) {
+        *self
+            .v6_messages
+            .entry(v6_message_type_name(m.msg_type))
+            .or_default() += 1;
+        if let Some(code) = m.status_code {
+            *self.v6_status.entry(v6_status_name(code)).or_default() += 1;
+        }
+        let from_server = matches!(m.msg_type, dhcp::V6_ADVERTISE | dhcp::V6_REPLY);
+        // Sin DUID basado en MAC, la trama Ethernet identifica al cliente salvo que venga de un relay
+        let link_mac = if from_server { eth.1 } else { eth.0 };
+        let Some(mac) = m
+            .client_duid
+            .as_deref()
+            .and_then(duid_mac)
+            .or((m.relay_hops == 0).then_some(link_mac))
+        else {
+            return;
+        };
+        let key = TxKey {
+            v6: true,
+            xid: m.xid,
+            mac,
+        };
+        let kind = match m.msg_type {
+            dhcp::V6_SOLICIT => Kind::Discover,
+            dhcp::V6_REQUEST
+            | dhcp::V6_RENEW
+            | dhcp::V6_REBIND
+            | dhcp::V6_CONFIRM
+            | dhcp::V6_INFORMATION_REQUEST => Kind::Request,
+            dhcp::V6_ADVERTISE => Kind::Offer,
+            // Un REPLY con código de error equivale a un NAK
+            dhcp::V6_REPLY if m.status_code.is_some() => Kind::Nak,
+            dhcp::V6_REPLY => Kind::Ack,
+            dhcp::V6_DECLINE => Kind::Decline,
+            _ => Kind::Other,
+        };
+        let addresses: Vec<IpAddr> = m.addresses.iter().map(|a| IpAddr::V6(*a)).collect();
+        if from_server {
+            let id = match &m.server_duid {
+                Some(duid) => hex(duid),
+                None => src.to_string(),
+            };
+            self.track_server(&id, true, src, eth.0, ts);
+            if matches!(kind, Kind::Ack) {
+                if let Some(c) = self.client(mac, ts) {
+                    for a in addresses {
+                        if c.addresses.len() < MAX_ADDRESSES {
+                            c.addresses.insert(a);
+                        }
+                    }
+                }
+            }
+            self.on_server(key, kind, &id, ts);
+            return;
+        }
+        if matches!(kind, Kind::Decline) {
+            self.on_decline(key, addresses, ts);
+            return;
+        }
+        if let Some(c) = self.client(mac, ts) {
+            if m.fqdn.is_some() {
+                c.hostname = m.fqdn.clone();
+            }
+            if !m.option_request.is_empty() {
+                c.fingerprint_v6 = Some(fingerprint(&m.option_request));
+            }
+            if m.vendor_class.is_some() {
+                c.vendor_class = m.vendor_class.clone();
+            }
+            if let Some(duid) = &m.client_duid {
+                c.duid = Some(hex(duid));
+            }
+        }
+        self.on_client(key, kind, ts);
+    }
+
+    fn sweep(&mut self, now: u64) {
+        if now.saturating_sub(self.last_sweep_us) < 1_000_000 {
+            return;
+        }
+        self.last_sweep_us = now;
+        self.expire(now);
+    }
+
+    /// Transacciones sin actividad: sin OFFER/ADVERTISE o sin ACK/REPLY
+    fn expire(&mut self, now: u64) {
+        let mut expired: Vec<TxKey> = self
+            .transactions
+            .iter()
+            .filter(|(_, tx)| now.saturating_sub(tx.last_us) >= TRANSACTION_TIMEOUT_US)
+            .map(|(k, _)| *k)
+            .collect();
+        expired.sort_by_key(|k| (k.mac, k.v6, k.xid));
+        for key in expired {
+            let Some(tx) = self.transactions.remove(&key) else {
+                continue;
+            };
+            if tx.offers.is_empty() {
+                self.counters.no_offer += 1;
+            } else {
+                self.counters.no_ack += 1;
+            }
+            if let Some(c) = self.clients.get_mut(&key.mac) {
+                c.unanswered += 1;
+            }
+        }
+    }
+
+    fn is_trusted(&self, id: &str, s: &Server) -> bool {
+        self.trusted_servers.contains(id)
+            || self.trusted_servers.contains(&s.ip.to_string())
+            || self.trusted_servers.contains(&format_mac(&s.mac))
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        let mut out = Vec::new();
+
+        // Sin lista de confianza, el primer servidor visto de cada versión se toma como legítimo
+        let first = |v6: bool| {
+            self.servers
+                .iter()
+                .filter(|(_, s)| s.v6 == v6)
+                .min_by_key(|(id, s)| (s.first_us, id.as_str()))
+                .map(|(id, _)| id.clone())
+        };
+        let (first_v4, first_v6) = (first(false), first(true));
+        for (id, s) in &self.servers {
+            let primary = if s.v6 { &first_v6 } else { &first_v4 };
+            let (mut score, mut reason) = if !self.trusted_servers.is_empty() {
+                if self.is_trusted(id, s) {
+                    continue;
+                }
+                (
+                    100,
+                    "respuestas DHCP de un servidor no autorizado".to_string(),
+                )
+            } else if Some(id) != primary.as_ref() {
+                (
+                    60,
+                    format!(
+                        "servidor DHCP adicional (el primero visto fue {})",
+                        primary.clone().unwrap_or_default()
+                    ),
+                )
+            } else {
+                continue;
+            };
+            if s.acks > 0 && score < 100 {
+                score += 40;
+                reason.push_str(&format!("; confirmó {} concesiones", s.acks));
+            }
+            out.push(Finding {
+                code: "ROGUE_DHCP_SERVER",
+                subject: id.clone(),
+                score,
+                level: level(score),
+                reason,
+                count: s.offers + s.acks + s.naks,
+                examples: None,
+                detail: json!({
+                    "ip": s.ip.to_string(),
+                    "mac": format_mac(&s.mac),
+                    "offers": s.offers,
+                    "acks": s.acks,
+                    "clients": s.clients.len()
+                }),
+            });
+        }
+
+        for (id, s) in &self.servers {
+            let p95_ms = s.latency.percentile_ms(0.95);
+            if p95_ms * 1000.0 < SLOW_REPLY_US as f64 {
+                continue;
+            }
+            out.push(Finding {
+                code: "DHCP_SLOW_SERVER",
+                subject: id.clone(),
+                score: 40,
+                level: "MEDIA",
+                reason: format!("respuestas lentas del servidor (p95 {p95_ms:.0} ms)"),
+                count: s.offers + s.acks + s.naks,
+                examples: None,
+                detail: json!({ "latency_ms": s.latency.to_json() }),
+            });
+        }
+
+        for (mac, c) in &self.clients {
+            let subject = format_mac(mac);
+            let servers: Vec<&String> = c.servers.iter().collect();
+            if c.naks > 0 {
+                let score = if c.naks >= NAK_HIGH { 60 } else { 40 };
+                out.push(Finding {
+                    code: "DHCP_NAK",
+                    subject: subject.clone(),
+                    score,
+                    level: level(score),
+                    reason: format!(
+                        "el servidor rechazó la concesión ({} NAK): dirección fuera de la subred o concesión inválida",
+                        c.naks
+                    ),
+                    count: c.naks,
+                    examples: None,
+                    detail: json!({ "servers": servers, "hostname": c.hostname }),
+                });
+            }
+            if c.declines > 0 {
+                out.push(Finding {
+                    code: "DHCP_DECLINE",
+                    subject: subject.clone(),
+                    score: 60,
+                    level: "MEDIA",
+                    reason: format!(
+                        "el cliente rechazó la dirección asignada ({}): conflicto de IP en la red",
+                        ips_json(&c.declined).join(", ")
+                    ),
+                    count: c.declines,
+                    examples: None,
+                    detail: json!({ "declined": ips_json(&c.declined), "hostname": c.hostname }),
+                });
+            }
+            if c.unanswered > 0 {
+                let (score, reason) = if c.completed == 0 {
+                    (
+                        100,
+                        "el cliente nunca obtuvo dirección: solicitudes sin respuesta",
+                    )
+                } else {
+                    (20, "algunas solicitudes quedaron sin respuesta")
+                };
+                out.push(Finding {
+                    code: "DHCP_NO_RESPONSE",
+                    subject,
+                    score,
+                    level: level(score),
+                    reason: format!("{reason} ({} transacciones)", c.unanswered),
+                    count: c.unanswered,
+                    examples: None,
+                    detail: json!({ "completed": c.completed, "hostname": c.hostname }),
+                });
+            }
+        }
+
+        out.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.code.cmp(b.code))
+                .then_with(|| a.subject.cmp(&b.subject))
+        });
+        out
+    }
+
+    fn servers_json(&self) -> Vec<Value> {
+        self.servers
+            .iter()
+            .map(|(id, s)| {
+                json!({
+                    "id": id,
+                    "version": if s.v6 { "v6" } else { "v4" },
+                    "ip": s.ip.to_string(),
+                    "mac": format_mac(&s.mac),
+                    "offers": s.offers,
+                    "acks": s.acks,
+                    "naks": s.naks,
+                    "clients": s.clients.len(),
+                    "trusted": self.is_trusted(id, s),
+                    "latency_ms": s.latency.to_json(),
+                    "first_ts_us": s.first_us,
+                    "last_ts_us": s.last_us
+                })
+            })
+            .collect()
+    }
+
+    /// Inventario pasivo por MAC (orden determinista por MAC)
+    fn inventory_json(&self) -> Vec<Value> {
+        self.clients
+            .iter()
+            .take(MAX_REPORTED)
+            .map(|(mac, c)| {
+                json!({
+                    "mac": format_mac(mac),
+                    "hostname": c.hostname,
+                    "fingerprint": c.fingerprint,
+                    "fingerprint_v6": c.fingerprint_v6,
+                    "vendor_class": c.vendor_class,
+                    "duid": c.duid,
+                    "addresses": ips_json(&c.addresses),
+                    "relay": c.relay.map(|r| r.to_string()),
+                    "servers": c.servers,
+                    "transactions": c.transactions,
+                    "completed": c.completed,
+                    "naks": c.naks,
+                    "declines": c.declines,
+                    "unanswered": c.unanswered,
+                    "first_ts_us": c.first_us,
+                    "last_ts_us": c.last_us
+                })
+            })
+            .collect()
+    }
+}
+
+impl Detector for DhcpDetector {
+    fn name(&self) -> &'static str {
+        "dhcp"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        self.last_ts_us = self.last_ts_us.max(ts_micros);
+        self.sweep(ts_micros);
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let eth = match &sliced.link {
+            Some(LinkSlice::Ethernet2(eth)) => (eth.source(), eth.destination()),
+            _ => return,
+        };
+        let src = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => IpAddr::V4(ip.header().source_addr()),
+            Some(InternetSlice::Ipv6(ip)) => IpAddr::V6(ip.header().source_addr()),
+            None => return,
+        };
+        let Some(TransportSlice::Udp(udp)) = &sliced.transport else {
+            return;
+        };
+        let ports = [udp.source_port(), udp.destination_port()];
+        let is_port = |a: u16, b: u16| ports.iter().all(|p| *p == a || *p == b);
+        if src.is_ipv4() && is_port(dhcp::DHCP_SERVER_PORT, dhcp::DHCP_CLIENT_PORT) {
+            match dhcp::parse(udp.payload()) {
+                Some(m) => self.on_v4(m, src, eth.0, ts_micros),
+                None => self.malformed += 1,
+            }
+        } else if src.is_ipv6() && is_port(dhcp::DHCPV6_CLIENT_PORT, dhcp::DHCPV6_SERVER_PORT) {
+            match dhcp::parse_v6(udp.payload()) {
+                Some(m) => self.on_v6(m, src, eth, ts_micros),
+                None => self.malformed += 1,
+            }
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        self.expire(self.last_ts_us);
+        let pending_at_end = self.transactions.len();
+
+        let findings = self.findings();
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for f in &findings {
+            *counts.entry(f.code).or_default() += 1;
+        }
+        let c = &self.counters;
+        json!({
+            "messages": {
+                "v4": self.v4_messages,
+                "v6": self.v6_messages,
+                "v6_status": self.v6_status,
+                "malformed": self.malformed
+            },
+            "transactions": {
+                "started": c.started,
+                "completed": c.completed,
+                "nak": c.nak,
+                "declined": c.declined,
+                "no_offer": c.no_offer,
+                "no_ack": c.no_ack,
+                "retransmissions": c.retransmissions,
+                "pending_at_end": pending_at_end,
+                "truncated": self.transactions_truncated
+            },
+            "latency_ms": {
+                "offer": self.offer_latency.to_json(),
+                "ack": self.ack_latency.to_json(),
+                "transaction": self.total_latency.to_json()
+            },
+            "servers": self.servers_json(),
+            "servers_truncated": self.servers_truncated,
+            "clients_total": self.clients.len(),
+            "clients_truncated": self.clients_truncated,
+            "inventory": self.inventory_json(),
+            "findings": findings.iter().take(MAX_REPORTED).map(Finding::to_json).collect::<Vec<_>>(),
+            "counts_by_code": counts
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::dhcp::tests::{dhcp_body, dhcpv6_body, duid_ll};
+    use etherparse::PacketBuilder;
+    use std::net::Ipv6Addr;
+
+    const CLIENT_MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x10];
+    const OTHER_MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x20];
+    const SERVER_MAC: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const ROGUE_MAC: [u8; 6] = [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb];
+    const SERVER: [u8; 4] = [192, 168, 1, 1];
+    const ROGUE: [u8; 4] = [192, 168, 1, 250];
+
+    fn v4_frame(src_mac: [u8; 6], src: [u8; 4], from_server: bool, body: &[u8]) -> Vec<u8> {
+        let (sport, dport) = if from_server { (67, 68) } else { (68, 67) };
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2(src_mac, [0xff; 6])
+            .ipv4(src, [255; 4], 64)
+            .udp(sport, dport)
+            .write(&mut out, body)
+            .unwrap();
+        out
+    }
+
+    fn client_msg(mac: [u8; 6], xid: u32, t: u8, extra: Vec<(u8, Vec<u8>)>) -> Vec<u8> {
+        let mut opts = vec![(53, vec![t])];
+        opts.extend(extra);
+        v4_frame(mac, [0; 4], false, &dhcp_body(1, xid, mac, [0; 4], &opts))
+    }
+
+    fn server_msg(
+        server: ([u8; 6], [u8; 4]),
+        client: [u8; 6],
+        xid: u32,
+        t: u8,
+        yiaddr: [u8; 4],
+    ) -> Vec<u8> {
+        let opts = [(53, vec![t]), (54, server.1.to_vec())];
+        v4_frame(
+            server.0,
+            server.1,
+            true,
+            &dhcp_body(2, xid, client, yiaddr, &opts),
+        )
+    }
+
+    #[test]
+    fn dora_latency_inventory_nak_decline_and_rogue_server() {
+        let mut d = DhcpDetector::new();
+        let lease = [192, 168, 1, 50];
+        let identity = vec![
+            (12, b"laptop-ana".to_vec()),
+            (55, vec![1, 3, 6, 15, 119, 252]),
+            (60, b"MSFT 5.0".to_vec()),
+        ];
+        // DORA completo con un reintento de DISCOVER; el servidor pirata también ofrece
+        d.on_packet(
+            &client_msg(CLIENT_MAC, 1, dhcp::DISCOVER, identity.clone()),
+            0,
+        );
+        d.on_packet(
+            &client_msg(CLIENT_MAC, 1, dhcp::DISCOVER, identity.clone()),
+            4_000_000,
+        );
+        d.on_packet(
+            &server_msg((SERVER_MAC, SERVER), CLIENT_MAC, 1, dhcp::OFFER, lease),
+            4_010_000,
+        );
+        d.on_packet(
+            &server_msg(
+                (ROGUE_MAC, ROGUE),
+                CLIENT_MAC,
+                1,
+                dhcp::OFFER,
+                [10, 0, 0, 9],
+            ),
+            4_020_000,
+        );
+        d.on_packet(
+            &client_msg(CLIENT_MAC, 1, dhcp::REQUEST, identity.clone()),
+            4_030_000,
+        );
+        d.on_packet(
+            &server_msg((SERVER_MAC, SERVER), CLIENT_MAC, 1, dhcp::ACK, lease),
+            4_050_000,
+        );
+        // Conflicto de IP: el cliente declina y luego recibe un NAK al renovar
+        d.on_packet(
+            &client_msg(CLIENT_MAC, 2, dhcp::DECLINE, vec![(50, lease.to_vec())]),
+            5_000_000,
+        );
+        d.on_packet(&client_msg(CLIENT_MAC, 3, dhcp::REQUEST, vec![]), 6_000_000);
+        d.on_packet(
+            &server_msg((ROGUE_MAC, ROGUE), CLIENT_MAC, 3, dhcp::NAK, [0; 4]),
+            6_001_000,
+        );
+        // Otro cliente sin respuesta alguna
+        d.on_packet(&client_msg(OTHER_MAC, 9, dhcp::DISCOVER, vec![]), 7_000_000);
+        d.on_packet(
+            &client_msg(OTHER_MAC, 9, dhcp::DISCOVER, vec![]),
+            11_000_000,
+        );
+        d.on_packet(
+            &client_msg(CLIENT_MAC, 4, dhcp::RELEASE, vec![]),
+            60_000_000,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["messages"]["v4"]["DISCOVER"], 4);
+        assert_eq!(r["messages"]["v4"]["OFFER"], 2);
+        let tx = &r["transactions"];
+        assert_eq!(tx["started"], 3);
+        assert_eq!(tx["completed"], 1);
+        assert_eq!(tx["nak"], 1);
+        assert_eq!(tx["declined"], 1);
+        assert_eq!(tx["no_offer"], 1);
+        assert_eq!(tx["retransmissions"], 2);
+        // OFFER medido contra el reintento; transacción completa desde el primer DISCOVER
+        assert_eq!(r["latency_ms"]["offer"]["p50"], 10.0);
+        assert_eq!(r["latency_ms"]["ack"]["p50"], 20.0);
+        assert_eq!(r["latency_ms"]["transaction"]["max"], 4050.0);
+
+        let client = &r["inventory"][0];
+        assert_eq!(client["mac"], "02:00:00:00:00:10");
+        assert_eq!(client["hostname"], "laptop-ana");
+        assert_eq!(client["fingerprint"], "1,3,6,15,119,252");
+        assert_eq!(client["vendor_class"], "MSFT 5.0");
+        assert_eq!(client["addresses"][0], "192.168.1.50");
+
+        let found: Vec<(&str, &str, &str)> = r["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| {
+                (
+                    f["code"].as_str().unwrap(),
+                    f["subject"].as_str().unwrap(),
+                    f["score"]["level"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(found[0], ("DHCP_NO_RESPONSE", "02:00:00:00:00:20", "ALTA"));
+        assert!(found.contains(&("ROGUE_DHCP_SERVER", "192.168.1.250", "MEDIA")));
+        assert!(found.contains(&("DHCP_DECLINE", "02:00:00:00:00:10", "MEDIA")));
+        assert!(found.contains(&("DHCP_NAK", "02:00:00:00:00:10", "MEDIA")));
+        assert!(!found.iter().any(|(_, s, _)| *s == "192.168.1.1"));
+    }
+
+    #[test]
+    fn dhcpv6_exchange_with_trusted_server_list() {
+        let client_ip: Ipv6Addr = "fe80::10".parse().unwrap();
+        let server_ip: Ipv6Addr = "fe80::1".parse().unwrap();
+        let leased: Ipv6Addr = "2001:db8::10".parse().unwrap();
+        let mut d = DhcpDetector::with_config(DhcpConfig {
+            trusted_servers: vec!["00:11:22:33:44:55".to_string()],
+        });
+        let frame = |from_server: bool, body: Vec<u8>| {
+            let (smac, dmac, src, dst, sport, dport) = if from_server {
+                (SERVER_MAC, CLIENT_MAC, server_ip, client_ip, 547, 546)
+            } else {
+                (
+                    CLIENT_MAC,
+                    [0x33, 0x33, 0, 1, 0, 2],
+                    client_ip,
+                    server_ip,
+                    546,
+                    547,
+                )
+            };
+            let mut out = Vec::new();
+            PacketBuilder::ethernet2(smac, dmac)
+                .ipv6(src.octets(), dst.octets(), 1)
+                .udp(sport, dport)
+                .write(&mut out, &body)
+                .unwrap();
+            out
+        };
+        let client_id = (1u16, duid_ll(CLIENT_MAC));
+        let server_id = (2u16, vec![0, 2, 0, 0, 0, 9, 7]);
+        let mut ia_na = vec![0; 12];
+        ia_na.extend(5u16.to_be_bytes());
+        ia_na.extend(24u16.to_be_bytes());
+        ia_na.extend(leased.octets());
+        ia_na.extend([0; 8]);
+        let oro = (6u16, vec![0, 23, 0, 24, 0, 39]);
+        let fqdn = (39u16, b"\x01\x04host\x03lan\x00".to_vec());
+
+        d.on_packet(
+            &frame(
+                false,
+                dhcpv6_body(dhcp::V6_SOLICIT, 7, &[client_id.clone(), oro.clone(), fqdn]),
+            ),
+            0,
+        );
+        d.on_packet(
+            &frame(
+                true,
+                dhcpv6_body(
+                    dhcp::V6_ADVERTISE,
+                    7,
+                    &[client_id.clone(), server_id.clone()],
+                ),
+            ),
+            2_000,
+        );
+        d.on_packet(
+            &frame(
+                false,
+                dhcpv6_body(
+                    dhcp::V6_REQUEST,
+                    7,
+                    &[client_id.clone(), server_id.clone(), oro],
+                ),
+            ),
+            3_000,
+        );
+        d.on_packet(
+            &frame(
+                true,
+                dhcpv6_body(dhcp::V6_REPLY, 7, &[client_id, server_id, (3, ia_na)]),
+            ),
+            8_000,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["messages"]["v6"]["SOLICIT"], 1);
+        assert_eq!(r["transactions"]["completed"], 1);
+        assert_eq!(r["latency_ms"]["transaction"]["max"], 8.0);
+        let client = &r["inventory"][0];
+        assert_eq!(client["hostname"], "host.lan");
+        assert_eq!(client["fingerprint_v6"], "23,24,39");
+        assert_eq!(client["duid"], "00030001020000000010");
+        assert_eq!(client["addresses"][0], "2001:db8::10");
+        assert_eq!(r["servers"][0]["id"], "00020000000907");
+        assert_eq!(r["servers"][0]["trusted"], true);
+        assert_eq!(r["findings"].as_array().unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,560 @@
+use crate::detectors::dns_threats::ThreatTracker;
+use crate::detectors::latency::LatencyStats;
+use crate::engine::Detector;
+use crate::network::dns::{self, DnsMessage};
+use crate::network::format::rate;
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+const DNS_PORT: u16 = 53;
+// Una consulta sin respuesta tras este tiempo se da por perdida
+const QUERY_TIMEOUT_US: u64 = 10 * 1_000_000;
+const MAX_PENDING: usize = 100_000;
+const MAX_EXAMPLES: usize = 5;
+// Por debajo de este número de consultas no se escala a ALTA
+const MIN_QUERIES_FOR_HIGH: u64 = 5;
+
+/// Clave de emparejamiento consulta/respuesta: 5-tupla + transaction ID
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct QueryKey {
+    client: IpAddr,
+    client_port: u16,
+    resolver: IpAddr,
+    resolver_port: u16,
+    tcp: bool,
+    id: u16,
+}
+
+/// Misma pregunta del mismo cliente al mismo resolver (reintentos con otro ID/puerto)
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+struct NameKey {
+    client: IpAddr,
+    resolver: IpAddr,
+    name: String,
+    qtype: u16,
+}
+
+#[derive(Debug)]
+struct PendingQuery {
+    ts_us: u64,
+    name: String,
+    qtype: u16,
+    // Reenviada con el mismo ID: la respuesta no permite medir latencia sin ambigüedad
+    retransmitted: bool,
+}
+
+#[derive(Debug, Default)]
+struct ResolverStats {
+    queries: u64,
+    responses: u64,
+    retries: u64,
+    unanswered: u64,
+    unmatched_responses: u64,
+    truncated: u64,
+    rcodes: BTreeMap<u8, u64>,
+    latency: LatencyStats,
+    unanswered_examples: BTreeSet<String>,
+    servfail_examples: BTreeSet<String>,
+    nxdomain_examples: BTreeSet<String>,
+    truncated_examples: BTreeSet<String>,
+}
+
+impl ResolverStats {
+    fn rcode_count(&self, rcode: u8) -> u64 {
+        self.rcodes.get(&rcode).copied().unwrap_or(0)
+    }
+}
+
+fn add_example(set: &mut BTreeSet<String>, name: &str) {
+    if set.len() < MAX_EXAMPLES && !name.is_empty() {
+        set.insert(name.to_string());
+    }
+}
+
+/// Detector DNS: latencia por resolver, códigos de respuesta, consultas sin
+/// respuesta/reintentadas y respuestas truncadas (UDP y TCP, puerto 53), más
+/// heurísticas de amenazas sobre los nombres consultados (DGA, homógrafos, túneles)
+#[derive(Default)]
+pub struct DnsDetector {
+    pending: HashMap<QueryKey, PendingQuery>,
+    pending_names: HashMap<NameKey, QueryKey>,
+    resolvers: BTreeMap<IpAddr, ResolverStats>,
+    threats: ThreatTracker,
+    qtypes: BTreeMap<u16, u64>,
+    queries_total: u64,
+    responses_total: u64,
+    malformed: u64,
+    last_sweep_us: u64,
+    last_ts_us: u64,
+}
+
+impl DnsDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn on_message(
+        &mut self,
+        src: (IpAddr, u16),
+        dst: (IpAddr, u16),
+        tcp: bool,
+        msg: DnsMessage,
+        ts_us: u64,
+    ) {
+        if msg.header.response {
+            let key = QueryKey {
+                client: dst.0,
+                client_port: dst.1,
+                resolver: src.0,
+                resolver_port: src.1,
+                tcp,
+                id: msg.header.id,
+            };
+            self.on_response(key, &msg, ts_us);
+        } else {
+            let Some(q) = msg.question() else {
+                return;
+            };
+            let key = QueryKey {
+                client: src.0,
+                client_port: src.1,
+                resolver: dst.0,
+                resolver_port: dst.1,
+                tcp,
+                id: msg.header.id,
+            };
+            let (name, qtype) = (q.name.clone(), q.qtype);
+            self.on_query(key, name, qtype, ts_us);
+        }
+    }
+
+    fn on_query(&mut self, key: QueryKey, name: String, qtype: u16, ts_us: u64) {
+        self.queries_total += 1;
+        *self.qtypes.entry(qtype).or_default() += 1;
+        self.threats.on_query(key.client, &name, qtype);
+        let stats = self.resolvers.entry(key.resolver).or_default();
+        stats.queries += 1;
+
+        // Mismo ID y 5-tupla: retransmisión del stub resolver
+        if let Some(p) = self.pending.get_mut(&key) {
+            stats.retries += 1;
+            p.retransmitted = true;
+            p.ts_us = ts_us;
+            return;
+        }
+
+        // Misma pregunta con otro ID/puerto mientras la anterior sigue pendiente
+        let name_key = NameKey {
+            client: key.client,
+            resolver: key.resolver,
+            name: name.clone(),
+            qtype,
+        };
+        if let Some(prev) = self.pending_names.insert(name_key, key) {
+            if self.pending.remove(&prev).is_some() {
+                stats.retries += 1;
+            }
+        }
+
+        if self.pending.len() >= MAX_PENDING {
+            self.expire_oldest();
+        }
+        self.pending.insert(
+            key,
+            PendingQuery {
+                ts_us,
+                name,
+                qtype,
+                retransmitted: false,
+            },
+        );
+    }
+
+    fn on_response(&mut self, key: QueryKey, msg: &DnsMessage, ts_us: u64) {
+        self.responses_total += 1;
+        let stats = self.resolvers.entry(key.resolver).or_default();
+
+        let Some(p) = self.pending.remove(&key) else {
+            stats.unmatched_responses += 1;
+            return;
+        };
+        let name_key = NameKey {
+            client: key.client,
+            resolver: key.resolver,
+            name: p.name.clone(),
+            qtype: p.qtype,
+        };
+        if self.pending_names.get(&name_key) == Some(&key) {
+            self.pending_names.remove(&name_key);
+        }
+
+        stats.responses += 1;
+        *stats.rcodes.entry(msg.header.rcode).or_default() += 1;
+        if !p.retransmitted {
+            stats.latency.add_sample(ts_us.saturating_sub(p.ts_us));
+        }
+        match msg.header.rcode {
+            dns::RCODE_SERVFAIL => add_example(&mut stats.servfail_examples, &p.name),
+            dns::RCODE_NXDOMAIN => add_example(&mut stats.nxdomain_examples, &p.name),
+            _ => {}
+        }
+        if msg.header.truncated {
+            stats.truncated += 1;
+            add_example(&mut stats.truncated_examples, &p.name);
+        }
+    }
+
+    /// Marca como sin respuesta las consultas que superan el timeout
+    fn expire(&mut self, now_us: u64, force: bool) {
+        if !force && now_us.saturating_sub(self.last_sweep_us) < 1_000_000 {
+            return;
+        }
+        self.last_sweep_us = now_us;
+        let mut expired: Vec<(u64, &str, QueryKey)> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| force || now_us.saturating_sub(p.ts_us) > QUERY_TIMEOUT_US)
+            .map(|(k, p)| (p.ts_us, p.name.as_str(), *k))
+            .collect();
+        // Orden determinista (los ejemplos se toman de los primeros)
+        expired.sort_unstable_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        let keys = expired.into_iter().map(|(_, _, k)| k).collect();
+        self.mark_unanswered(keys);
+    }
+
+    /// Con la tabla llena se da por perdida la décima parte más antigua
+    fn expire_oldest(&mut self) {
+        let mut by_age: Vec<(u64, &str, QueryKey)> = self
+            .pending
+            .iter()
+            .map(|(k, p)| (p.ts_us, p.name.as_str(), *k))
+            .collect();
+        by_age.sort_unstable_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        let oldest = by_age
+            .into_iter()
+            .take(MAX_PENDING / 10)
+            .map(|(_, _, k)| k)
+            .collect();
+        self.mark_unanswered(oldest);
+    }
+
+    fn mark_unanswered(&mut self, keys: Vec<QueryKey>) {
+        for key in keys {
+            let Some(p) = self.pending.remove(&key) else {
+                continue;
+            };
+            let name_key = NameKey {
+                client: key.client,
+                resolver: key.resolver,
+                name: p.name.clone(),
+                qtype: p.qtype,
+            };
+            if self.pending_names.get(&name_key) == Some(&key) {
+                self.pending_names.remove(&name_key);
+            }
+            let stats = self.resolvers.entry(key.resolver).or_default();
+            stats.unanswered += 1;
+            add_example(&mut stats.unanswered_examples, &p.name);
+        }
+    }
+}
+
+fn compute_severity(s: &ResolverStats) -> (u32, &'static str, Vec<String>) {
+    let unanswered_rate = rate(s.unanswered, s.queries);
+    let servfail = s.rcode_count(dns::RCODE_SERVFAIL);
+    let refused = s.rcode_count(dns::RCODE_REFUSED);
+    let nxdomain = s.rcode_count(dns::RCODE_NXDOMAIN);
+    let servfail_rate = rate(servfail, s.responses);
+    let refused_rate = rate(refused, s.responses);
+    let nxdomain_rate = rate(nxdomain, s.responses);
+    let p95 = s.latency.percentile_ms(0.95);
+
+    let mut score_f = 150.0 * unanswered_rate
+        + 120.0 * servfail_rate
+        + 100.0 * refused_rate
+        + 30.0 * nxdomain_rate
+        + 50.0 * rate(s.retries, s.queries);
+    if p95 > 500.0 {
+        score_f += 40.0;
+    } else if p95 > 200.0 {
+        score_f += 20.0;
+    }
+    let score = score_f.round() as u32;
+
+    let mut reasons = Vec::<String>::new();
+    if s.unanswered >= 1 {
+        reasons.push(format!(
+            "consultas sin respuesta {:.1}% ({})",
+            unanswered_rate * 100.0,
+            s.unanswered
+        ));
+    }
+    if servfail >= 1 {
+        reasons.push(format!(
+            "SERVFAIL {:.1}% ({servfail})",
+            servfail_rate * 100.0
+        ));
+    }
+    if refused >= 1 {
+        reasons.push(format!("REFUSED {:.1}% ({refused})", refused_rate * 100.0));
+    }
+    if nxdomain >= 3 && nxdomain_rate >= 0.2 {
+        reasons.push(format!(
+            "NXDOMAIN elevado {:.1}% ({nxdomain})",
+            nxdomain_rate * 100.0
+        ));
+    }
+    if p95 > 200.0 {
+        reasons.push(format!("latencia alta p95={p95:.1} ms"));
+    }
+    if s.retries >= 1 {
+        reasons.push(format!("consultas reintentadas ({})", s.retries));
+    }
+    if s.truncated >= 1 {
+        reasons.push(format!("respuestas truncadas (TC) ({})", s.truncated));
+    }
+
+    let mut level = if score >= 100 || unanswered_rate >= 0.5 || servfail_rate >= 0.5 {
+        "ALTA"
+    } else if score >= 50 || s.unanswered >= 1 || servfail >= 1 || refused >= 1 || p95 > 200.0 {
+        "MEDIA"
+    } else {
+        "BAJA"
+    };
+
+    // Con muy pocas consultas una tasa alta no es concluyente
+    if level == "ALTA" && s.queries < MIN_QUERIES_FOR_HIGH {
+        level = "MEDIA";
+    }
+
+    (score, level, reasons)
+}
+
+fn resolver_json(ip: &IpAddr, s: &ResolverStats) -> Value {
+    let (score, level, reasons) = compute_severity(s);
+    let rcodes: serde_json::Map<String, Value> = s
+        .rcodes
+        .iter()
+        .map(|(rc, n)| (dns::rcode_name(*rc), json!(n)))
+        .collect();
+    json!({
+        "resolver": ip.to_string(),
+        "score": { "value": score, "level": level },
+        "reasons": reasons,
+        "queries": s.queries,
+        "responses": s.responses,
+        "retries": s.retries,
+        "unanswered": s.unanswered,
+        "unmatched_responses": s.unmatched_responses,
+        "truncated": s.truncated,
+        "rcodes": rcodes,
+        "rates": {
+            "unanswered": rate(s.unanswered, s.queries),
+            "nxdomain": rate(s.rcode_count(dns::RCODE_NXDOMAIN), s.responses),
+            "servfail": rate(s.rcode_count(dns::RCODE_SERVFAIL), s.responses),
+            "refused": rate(s.rcode_count(dns::RCODE_REFUSED), s.responses)
+        },
+        "latency_ms": s.latency.to_json(),
+        "examples": {
+            "unanswered": s.unanswered_examples,
+            "servfail": s.servfail_examples,
+            "nxdomain": s.nxdomain_examples,
+            "truncated": s.truncated_examples
+        }
+    })
+}
+
+impl Detector for DnsDetector {
+    fn name(&self) -> &'static str {
+        "dns"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        self.last_ts_us = self.last_ts_us.max(ts_micros);
+        self.expire(ts_micros, false);
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src_ip, dst_ip) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+
+        match &sliced.transport {
+            Some(TransportSlice::Udp(udp)) => {
+                let (sp, dp) = (udp.source_port(), udp.destination_port());
+                if sp != DNS_PORT && dp != DNS_PORT {
+                    return;
+                }
+                match dns::parse(udp.payload()) {
+                    Some(msg) => self.on_message((src_ip, sp), (dst_ip, dp), false, msg, ts_micros),
+                    None => self.malformed += 1,
+                }
+            }
+            Some(TransportSlice::Tcp(tcp)) => {
+                let (sp, dp) = (tcp.source_port(), tcp.destination_port());
+                if sp != DNS_PORT && dp != DNS_PORT {
+                    return;
+                }
+                // Mensajes con prefijo de longitud completos dentro del segmento
+                let mut rest = tcp.payload();
+                while rest.len() >= 2 {
+                    let len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+                    let Some(body) = rest.get(2..2 + len) else {
+                        break;
+                    };
+                    match dns::parse(body) {
+                        Some(msg) => {
+                            self.on_message((src_ip, sp), (dst_ip, dp), true, msg, ts_micros)
+                        }
+                        None => self.malformed += 1,
+                    }
+                    rest = &rest[2 + len..];
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        // Lo pendiente al final de la captura queda sin respuesta
+        self.expire(self.last_ts_us, true);
+
+        let mut resolvers: Vec<(u32, Value)> = self
+            .resolvers
+            .iter()
+            .map(|(ip, s)| {
+                let v = resolver_json(ip, s);
+                (v["score"]["value"].as_u64().unwrap_or(0) as u32, v)
+            })
+            .collect();
+        // Orden estable: score desc, luego IP (BTreeMap ya ordenado)
+        resolvers.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let qtypes: serde_json::Map<String, Value> = self
+            .qtypes
+            .iter()
+            .map(|(t, n)| (dns::type_name(*t), json!(n)))
+            .collect();
+
+        json!({
+            "queries_total": self.queries_total,
+            "responses_total": self.responses_total,
+            "malformed": self.malformed,
+            "qtypes": qtypes,
+            "resolvers_total": resolvers.len(),
+            "top_by_severity": resolvers.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+            "threats": self.threats.to_json()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::dns::tests::message;
+    use crate::network::dns::{RCODE_NOERROR, RCODE_NXDOMAIN, RCODE_SERVFAIL, TYPE_A};
+    use etherparse::PacketBuilder;
+
+    const CLIENT: [u8; 4] = [192, 168, 1, 10];
+    const RESOLVER: [u8; 4] = [192, 168, 1, 1];
+
+    fn query(port: u16, id: u16, name: &str) -> Vec<u8> {
+        let b = PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(CLIENT, RESOLVER, 64)
+            .udp(port, DNS_PORT);
+        let mut out = Vec::new();
+        b.write(&mut out, &message(id, name, TYPE_A, None)).unwrap();
+        out
+    }
+
+    fn response(port: u16, id: u16, name: &str, rcode: u8) -> Vec<u8> {
+        let b = PacketBuilder::ethernet2([2; 6], [1; 6])
+            .ipv4(RESOLVER, CLIENT, 64)
+            .udp(DNS_PORT, port);
+        let mut out = Vec::new();
+        b.write(&mut out, &message(id, name, TYPE_A, Some(rcode)))
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn latency_and_rcodes_per_resolver() {
+        let mut d = DnsDetector::new();
+        d.on_packet(&query(5000, 1, "ok.example"), 0);
+        d.on_packet(&response(5000, 1, "ok.example", RCODE_NOERROR), 20_000);
+        d.on_packet(&query(5001, 2, "nope.example"), 100_000);
+        d.on_packet(&response(5001, 2, "nope.example", RCODE_NXDOMAIN), 130_000);
+        d.on_packet(&query(5002, 3, "broken.example"), 200_000);
+        d.on_packet(
+            &response(5002, 3, "broken.example", RCODE_SERVFAIL),
+            210_000,
+        );
+        let out = d.finalize();
+
+        assert_eq!(out["queries_total"], 3);
+        let r = &out["top_by_severity"][0];
+        assert_eq!(r["resolver"], "192.168.1.1");
+        assert_eq!(r["responses"], 3);
+        assert_eq!(r["rcodes"]["NXDOMAIN"], 1);
+        assert_eq!(r["rcodes"]["SERVFAIL"], 1);
+        assert_eq!(r["latency_ms"]["p50"], 20.0);
+        assert_eq!(r["latency_ms"]["max"], 30.0);
+        assert_eq!(r["examples"]["servfail"][0], "broken.example");
+        assert_eq!(r["score"]["level"], "MEDIA");
+    }
+
+    #[test]
+    fn retries_and_unanswered_queries() {
+        let mut d = DnsDetector::new();
+        // Retransmisión con el mismo ID y luego reintento con otro puerto/ID
+        d.on_packet(&query(5000, 7, "slow.example"), 0);
+        d.on_packet(&query(5000, 7, "slow.example"), 1_000_000);
+        d.on_packet(&query(5003, 8, "slow.example"), 2_000_000);
+        d.on_packet(&response(5003, 8, "slow.example", RCODE_NOERROR), 2_010_000);
+        // Nunca respondida
+        d.on_packet(&query(5004, 9, "lost.example"), 3_000_000);
+        d.on_packet(&query(5005, 10, "other.example"), 20_000_000);
+        d.on_packet(
+            &response(5005, 10, "other.example", RCODE_NOERROR),
+            20_001_000,
+        );
+        let out = d.finalize();
+
+        let r = &out["top_by_severity"][0];
+        assert_eq!(r["queries"], 5);
+        assert_eq!(r["retries"], 2);
+        assert_eq!(r["unanswered"], 1);
+        assert_eq!(r["examples"]["unanswered"][0], "lost.example");
+        // La retransmisión con mismo ID no aporta latencia ambigua
+        assert_eq!(r["latency_ms"]["samples"], 2);
+    }
+
+    #[test]
+    fn tcp_messages_are_length_prefixed() {
+        let mut d = DnsDetector::new();
+        let mut payload = Vec::new();
+        let msg = message(42, "big.example", TYPE_A, None);
+        payload.extend_from_slice(&(msg.len() as u16).to_be_bytes());
+        payload.extend_from_slice(&msg);
+        let b = PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(CLIENT, RESOLVER, 64)
+            .tcp(40000, DNS_PORT, 1, 1024);
+        let mut frame = Vec::new();
+        b.write(&mut frame, &payload).unwrap();
+        d.on_packet(&frame, 0);
+        let out = d.finalize();
+        assert_eq!(out["queries_total"], 1);
+        assert_eq!(out["top_by_severity"][0]["unanswered"], 1);
+    }
+}
@@ -0,0 +1,728 @@
+//! Heurísticas de amenazas sobre nombres DNS: nombres tipo DGA (entropía,
+//! longitud, estructura y rareza de bigramas frente a un modelo offline),
+//! dominios IDN/punycode con homógrafos y tunelización DNS.
+
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use crate::detectors::finding::Finding;
+use crate::network::dns::{TYPE_NULL, TYPE_TXT};
+use crate::network::format::round2;
+
+// ---- Parámetros ----
+
+/// Modelo de bigramas incluido en el binario
+const BIGRAM_MODEL: &str = include_str!("dns_bigrams.txt");
+
+// DGA: etiqueta del dominio registrado
+const DGA_MIN_LEN: usize = 8;
+const DGA_MIN_RARITY: f64 = 7.0; // bits por bigrama
+const DGA_HEX_MIN_LEN: usize = 12;
+const DGA_HEX_MIN_ENTROPY: f64 = 3.3;
+
+// Tunelización por dominio base
+const TUNNEL_MIN_UNIQUE: usize = 100;
+const TUNNEL_HIGH_UNIQUE: usize = 500;
+const TUNNEL_MIN_AVG_SUBDOMAIN: f64 = 20.0;
+const TUNNEL_MIN_TXT: u64 = 20;
+const TUNNEL_MIN_NULL: u64 = 5;
+const LONG_LABEL: usize = 50;
+const TUNNEL_MIN_LONG_LABELS: u64 = 3;
+
+// Límites de memoria
+const MAX_BASE_DOMAINS: usize = 50_000;
+const MAX_UNIQUE_TRACKED: usize = 2048;
+const MAX_DGA_NAMES: usize = 10_000;
+const MAX_EXAMPLES: usize = 5;
+
+/// Marcas frecuentes en campañas de homógrafos
+const BRANDS: &[&str] = &[
+    "amazon",
+    "apple",
+    "binance",
+    "chase",
+    "coinbase",
+    "dropbox",
+    "ebay",
+    "facebook",
+    "github",
+    "google",
+    "icloud",
+    "instagram",
+    "linkedin",
+    "microsoft",
+    "netflix",
+    "office",
+    "outlook",
+    "paypal",
+    "twitter",
+    "wellsfargo",
+    "whatsapp",
+    "yahoo",
+    "youtube",
+];
+
+// Segundos niveles usados como sufijo público bajo ccTLDs (co.uk, com.au, ...)
+const CC_SECOND_LEVELS: &[&str] = &[
+    "ac", "co", "com", "edu", "go", "gob", "gov", "ne", "net", "or", "org",
+];
+
+// ---- Modelo de bigramas ----
+
+struct BigramModel {
+    // -log2 P(b | a) con suavizado de Laplace
+    cost: [[f64; 26]; 26],
+}
+
+fn bigram_model() -> &'static BigramModel {
+    static MODEL: OnceLock<BigramModel> = OnceLock::new();
+    MODEL.get_or_init(|| {
+        let mut cost = [[0.0; 26]; 26];
+        for line in BIGRAM_MODEL.lines().filter(|l| !l.starts_with('#')) {
+            let Some((letter, row)) = line.split_once(':') else {
+                continue;
+            };
+            let Some(a) = letter.bytes().next().filter(u8::is_ascii_lowercase) else {
+                continue;
+            };
+            let counts: Vec<f64> = row
+                .split_whitespace()
+                .filter_map(|v| v.parse::<f64>().ok())
+                .collect();
+            let total: f64 = counts.iter().sum::<f64>() + 26.0;
+            for (b, c) in counts.iter().take(26).enumerate() {
+                cost[(a - b'a') as usize][b] = -((c + 1.0) / total).log2();
+            }
+        }
+        BigramModel { cost }
+    })
+}
+
+/// Rareza media (bits por bigrama) de los pares de letras consecutivas
+fn bigram_rarity(label: &str) -> f64 {
+    let model = bigram_model();
+    let bytes = label.as_bytes();
+    let (sum, n) = bytes
+        .windows(2)
+        .filter(|w| w[0].is_ascii_lowercase() && w[1].is_ascii_lowercase())
+        .fold((0.0, 0u32), |(s, n), w| {
+            (
+                s + model.cost[(w[0] - b'a') as usize][(w[1] - b'a') as usize],
+                n + 1,
+            )
+        });
+    if n == 0 {
+        0.0
+    } else {
+        sum / n as f64
+    }
+}
+
+/// Entropía de Shannon (bits por carácter)
+fn shannon_entropy(s: &str) -> f64 {
+    let mut counts = [0u32; 256];
+    for b in s.bytes() {
+        counts[b as usize] += 1;
+    }
+    let n = s.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / n;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+// ---- Estructura del nombre ----
+
+/// Dominio base registrable (heurística sin lista de sufijos públicos completa)
+fn base_domain(name: &str) -> Option<String> {
+    let labels: Vec<&str> = name.split('.').filter(|l| !l.is_empty()).collect();
+    if labels.len() < 2 {
+        return None;
+    }
+    let tld = labels[labels.len() - 1];
+    let sld = labels[labels.len() - 2];
+    let take = if labels.len() >= 3 && tld.len() == 2 && CC_SECOND_LEVELS.contains(&sld) {
+        3
+    } else {
+        2
+    };
+    Some(labels[labels.len() - take..].join("."))
+}
+
+/// Nombres que no son resoluciones de dominios reales (reversas, locales)
+fn is_infrastructure_name(name: &str) -> bool {
+    name.ends_with(".arpa")
+        || name.ends_with(".local")
+        || name.ends_with(".lan")
+        || name.ends_with(".internal")
+        || name.ends_with(".home.arpa")
+}
+
+struct DgaFeatures {
+    entropy: f64,
+    rarity: f64,
+}
+
+/// Evalúa la etiqueta registrada (la de más a la izquierda del dominio base)
+fn dga_features(base: &str) -> Option<DgaFeatures> {
+    let label = base.split('.').next()?;
+    if label.starts_with("xn--") {
+        return None;
+    }
+    let chars: String = label.chars().filter(|c| *c != '-').collect();
+    if chars.len() < DGA_MIN_LEN {
+        return None;
+    }
+    let entropy = shannon_entropy(&chars);
+    let rarity = bigram_rarity(&chars);
+    let digits = chars.bytes().filter(u8::is_ascii_digit).count();
+    let hex_like = chars.len() >= DGA_HEX_MIN_LEN
+        && chars.bytes().all(|b| b.is_ascii_hexdigit())
+        && digits * 10 >= chars.len() * 3
+        && entropy >= DGA_HEX_MIN_ENTROPY;
+    (rarity >= DGA_MIN_RARITY || hex_like).then_some(DgaFeatures { entropy, rarity })
+}
+
+// ---- IDN / punycode ----
+
+/// Decodifica una etiqueta punycode (sin el prefijo "xn--"), RFC 3492
+fn punycode_decode(input: &str) -> Option<String> {
+    const BASE: u32 = 36;
+    const TMIN: u32 = 1;
+    const TMAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+
+    fn adapt(mut delta: u32, num_points: u32, first: bool) -> u32 {
+        delta /= if first { DAMP } else { 2 };
+        delta += delta / num_points;
+        let mut k = 0;
+        while delta > ((BASE - TMIN) * TMAX) / 2 {
+            delta /= BASE - TMIN;
+            k += BASE;
+        }
+        k + (BASE - TMIN + 1) * delta / (delta + SKEW)
+    }
+
+    let (basic, extended) = match input.rfind('-') {
+        Some(pos) => (&input[..pos], &input[pos + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+    let (mut n, mut i, mut bias) = (128u32, 0u32, 72u32);
+    let mut digits = extended.bytes().peekable();
+
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut w = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = match digits.next()? {
+                c @ b'a'..=b'z' => (c - b'a') as u32,
+                c @ b'0'..=b'9' => (c - b'0') as u32 + 26,
+                _ => return None,
+            };
+            i = i.checked_add(digit.checked_mul(w)?)?;
+            let t = if k <= bias {
+                TMIN
+            } else if k >= bias + TMAX {
+                TMAX
+            } else {
+                k - bias
+            };
+            if digit < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+/// Letra latina que imita un carácter cirílico/griego (subconjunto de confusables de Unicode)
+fn latin_lookalike(c: char) -> Option<char> {
+    let mapped = match c {
+        // Cirílico
+        'а' => 'a',
+        'в' => 'b',
+        'ԁ' => 'd',
+        'е' | 'ё' => 'e',
+        'һ' => 'h',
+        'і' | 'ї' => 'i',
+        'ј' => 'j',
+        'к' => 'k',
+        'ӏ' => 'l',
+        'м' => 'm',
+        'п' => 'n',
+        'о' => 'o',
+        'р' => 'p',
+        'ԛ' => 'q',
+        'г' => 'r',
+        'ѕ' => 's',
+        'т' => 't',
+        'ц' => 'u',
+        'ѵ' => 'v',
+        'ԝ' => 'w',
+        'х' => 'x',
+        'у' => 'y',
+        // Griego
+        'α' => 'a',
+        'β' => 'b',
+        'ε' => 'e',
+        'η' => 'n',
+        'ι' => 'i',
+        'κ' => 'k',
+        'ν' => 'v',
+        'ο' => 'o',
+        'ρ' => 'p',
+        'τ' => 't',
+        'υ' => 'u',
+        'χ' => 'x',
+        'γ' => 'y',
+        _ => return None,
+    };
+    Some(mapped)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IdnVerdict {
+    unicode: String,
+    mixed_script: bool,
+    /// Esqueleto ASCII si toda la etiqueta se puede leer como letras latinas
+    skeleton: Option<String>,
+    impersonates: Option<&'static str>,
+}
+
+fn idn_verdict(label: &str) -> Option<IdnVerdict> {
+    let unicode = punycode_decode(label.strip_prefix("xn--")?)?;
+    let has_latin = unicode.chars().any(|c| c.is_ascii_alphabetic());
+    let has_cyrillic = unicode
+        .chars()
+        .any(|c| ('\u{0400}'..='\u{04FF}').contains(&c));
+    let has_greek = unicode
+        .chars()
+        .any(|c| ('\u{0370}'..='\u{03FF}').contains(&c));
+    let mixed_script = [has_latin, has_cyrillic, has_greek]
+        .iter()
+        .filter(|&&b| b)
+        .count()
+        >= 2;
+
+    let skeleton: Option<String> = unicode
+        .chars()
+        .map(|c| {
+            if c.is_ascii() {
+                Some(c)
+            } else {
+                latin_lookalike(c)
+            }
+        })
+        .collect();
+    let impersonates = skeleton
+        .as_deref()
+        .and_then(|s| BRANDS.iter().copied().find(|b| *b == s));
+
+    Some(IdnVerdict {
+        unicode,
+        mixed_script,
+        skeleton,
+        impersonates,
+    })
+}
+
+// ---- Estado ----
+
+#[derive(Debug, Default)]
+struct BaseDomainStats {
+    queries: u64,
+    txt: u64,
+    null: u64,
+    long_labels: u64,
+    subdomain_queries: u64,
+    subdomain_len_sum: u64,
+    unique_subdomains: HashSet<String>,
+    unique_saturated: bool,
+    examples: BTreeSet<String>,
+    long_label_examples: BTreeSet<String>,
+    clients: BTreeSet<IpAddr>,
+}
+
+#[derive(Debug, Default)]
+struct DgaClientStats {
+    names: HashSet<String>,
+    examples: BTreeMap<String, (f64, f64)>, // nombre -> (entropía, rareza)
+}
+
+#[derive(Debug)]
+struct IdnStats {
+    verdict: IdnVerdict,
+    queries: u64,
+    examples: BTreeSet<String>,
+}
+
+/// Acumula las heurísticas por consulta y produce hallazgos con código de razón
+#[derive(Debug, Default)]
+pub(crate) struct ThreatTracker {
+    base_domains: HashMap<String, BaseDomainStats>,
+    base_domains_untracked: u64,
+    dga: BTreeMap<IpAddr, DgaClientStats>,
+    idn: BTreeMap<String, IdnStats>,
+}
+
+fn push_example(set: &mut BTreeSet<String>, name: &str) {
+    if set.len() < MAX_EXAMPLES {
+        set.insert(name.to_string());
+    }
+}
+
+impl ThreatTracker {
+    pub(crate) fn on_query(&mut self, client: IpAddr, name: &str, qtype: u16) {
+        if name.is_empty() || is_infrastructure_name(name) {
+            return;
+        }
+        let Some(base) = base_domain(name) else {
+            return;
+        };
+
+        self.track_tunneling(client, name, &base, qtype);
+
+        if let Some(f) = dga_features(&base) {
+            let stats = self.dga.entry(client).or_default();
+            if stats.names.len() < MAX_DGA_NAMES {
+                stats.names.insert(base.clone());
+            }
+            if stats.examples.len() < MAX_EXAMPLES {
+                stats.examples.insert(base.clone(), (f.entropy, f.rarity));
+            }
+        }
+
+        for label in name.split('.').filter(|l| l.starts_with("xn--")) {
+            let Some(verdict) = idn_verdict(label) else {
+                continue;
+            };
+            let stats = self.idn.entry(base.clone()).or_insert_with(|| IdnStats {
+                verdict,
+                queries: 0,
+                examples: BTreeSet::new(),
+            });
+            stats.queries += 1;
+            push_example(&mut stats.examples, name);
+            break;
+        }
+    }
+
+    fn track_tunneling(&mut self, client: IpAddr, name: &str, base: &str, qtype: u16) {
+        if !self.base_domains.contains_key(base) && self.base_domains.len() >= MAX_BASE_DOMAINS {
+            self.base_domains_untracked += 1;
+            return;
+        }
+        let stats = self.base_domains.entry(base.to_string()).or_default();
+        stats.queries += 1;
+        match qtype {
+            TYPE_TXT => stats.txt += 1,
+            TYPE_NULL => stats.null += 1,
+            _ => {}
+        }
+        if stats.clients.len() < MAX_EXAMPLES {
+            stats.clients.insert(client);
+        }
+
+        let subdomain = name
+            .strip_suffix(base)
+            .map(|s| s.trim_end_matches('.'))
+            .unwrap_or("");
+        if subdomain.is_empty() {
+            return;
+        }
+        stats.subdomain_queries += 1;
+        stats.subdomain_len_sum += subdomain.len() as u64;
+        if stats.unique_subdomains.len() < MAX_UNIQUE_TRACKED {
+            stats.unique_subdomains.insert(subdomain.to_string());
+        } else {
+            stats.unique_saturated = true;
+        }
+        push_example(&mut stats.examples, name);
+        if subdomain.split('.').any(|l| l.len() >= LONG_LABEL) {
+            stats.long_labels += 1;
+            push_example(&mut stats.long_label_examples, name);
+        }
+    }
+
+    /// Hallazgos ordenados por score desc, luego código y sujeto
+    pub(crate) fn to_json(&self) -> Value {
+        let mut findings: Vec<Finding> = Vec::new();
+
+        for (client, s) in &self.dga {
+            let count = s.names.len();
+            let score = (20 + 10 * count as u32).min(150);
+            let level = if count >= 10 {
+                "ALTA"
+            } else if count >= 3 {
+                "MEDIA"
+            } else {
+                "BAJA"
+            };
+            findings.push(Finding {
+                code: "DGA_LIKE",
+                subject: client.to_string(),
+                score,
+                level,
+                reason: format!(
+                    "nombres con aspecto de DGA (entropía/rareza de bigramas altas) ({count})"
+                ),
+                count: count as u64,
+                examples: Some(s.examples.keys().cloned().collect()),
+                detail: json!({
+                    "metrics": s.examples.iter().map(|(n, (e, r))| json!({
+                        "name": n,
+                        "entropy": round2(*e),
+                        "bigram_rarity": round2(*r)
+                    })).collect::<Vec<_>>()
+                }),
+            });
+        }
+
+        for (base, s) in &self.idn {
+            let v = &s.verdict;
+            let (code, score, level, reason) = if let Some(brand) = v.impersonates {
+                (
+                    "IDN_HOMOGLYPH",
+                    120,
+                    "ALTA",
+                    format!("dominio IDN que imita a «{brand}» con caracteres homógrafos"),
+                )
+            } else if v.mixed_script {
+                (
+                    "IDN_HOMOGLYPH",
+                    60,
+                    "MEDIA",
+                    "etiqueta IDN que mezcla alfabetos (latino/cirílico/griego)".to_string(),
+                )
+            } else {
+                (
+                    "PUNYCODE",
+                    5,
+                    "BAJA",
+                    "dominio internacionalizado (punycode)".to_string(),
+                )
+            };
+            findings.push(Finding {
+                code,
+                subject: base.clone(),
+                score,
+                level,
+                reason,
+                count: s.queries,
+                examples: Some(s.examples.iter().cloned().collect()),
+                detail: json!({
+                    "unicode": v.unicode,
+                    "skeleton": v.skeleton,
+                    "mixed_script": v.mixed_script,
+                    "impersonates": v.impersonates
+                }),
+            });
+        }
+
+        let mut bases: Vec<(&String, &BaseDomainStats)> = self.base_domains.iter().collect();
+        bases.sort_by(|a, b| a.0.cmp(b.0));
+        for (base, s) in bases {
+            findings.extend(tunnel_findings(base, s));
+        }
+
+        findings.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.code.cmp(b.code))
+                .then_with(|| a.subject.cmp(&b.subject))
+        });
+
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for f in &findings {
+            *counts.entry(f.code).or_default() += 1;
+        }
+
+        json!({
+            "findings": findings.iter().map(Finding::to_json).collect::<Vec<_>>(),
+            "counts_by_code": counts,
+            "base_domains_tracked": self.base_domains.len(),
+            "base_domains_untracked": self.base_domains_untracked
+        })
+    }
+}
+
+fn tunnel_findings(base: &str, s: &BaseDomainStats) -> Vec<Finding> {
+    let mut out = Vec::new();
+    let unique = s.unique_subdomains.len();
+    let avg_sub = if s.subdomain_queries > 0 {
+        s.subdomain_len_sum as f64 / s.subdomain_queries as f64
+    } else {
+        0.0
+    };
+    let detail = json!({
+        "queries": s.queries,
+        "unique_subdomains": unique,
+        "unique_saturated": s.unique_saturated,
+        "avg_subdomain_len": round2(avg_sub),
+        "txt_queries": s.txt,
+        "null_queries": s.null,
+        "long_labels": s.long_labels,
+        "clients": s.clients.iter().map(|c| c.to_string()).collect::<Vec<_>>()
+    });
+
+    if unique >= TUNNEL_MIN_UNIQUE && avg_sub >= TUNNEL_MIN_AVG_SUBDOMAIN {
+        let (score, level) = if unique >= TUNNEL_HIGH_UNIQUE {
+            (100, "ALTA")
+        } else {
+            (60, "MEDIA")
+        };
+        out.push(Finding {
+            code: "TUNNEL_HIGH_VOLUME",
+            subject: base.to_string(),
+            score,
+            level,
+            reason: format!(
+                "volumen de subdominios únicos y largos (media {avg_sub:.0} caracteres) ({unique})"
+            ),
+            count: s.queries,
+            examples: Some(s.examples.iter().cloned().collect()),
+            detail: detail.clone(),
+        });
+    }
+
+    let txt_null = s.txt + s.null;
+    if s.null >= TUNNEL_MIN_NULL || (s.txt >= TUNNEL_MIN_TXT && txt_null * 2 >= s.queries) {
+        // NULL apenas se usa fuera de túneles (iodine)
+        let (score, level) = if s.null >= TUNNEL_MIN_NULL {
+            (100, "ALTA")
+        } else {
+            (50, "MEDIA")
+        };
+        out.push(Finding {
+            code: "TUNNEL_TXT_NULL",
+            subject: base.to_string(),
+            score,
+            level,
+            reason: format!(
+                "uso intensivo de registros TXT/NULL (TXT {}, NULL {}) ({txt_null})",
+                s.txt, s.null
+            ),
+            count: txt_null,
+            examples: Some(s.examples.iter().cloned().collect()),
+            detail: detail.clone(),
+        });
+    }
+
+    if s.long_labels >= TUNNEL_MIN_LONG_LABELS {
+        out.push(Finding {
+            code: "TUNNEL_LONG_LABELS",
+            subject: base.to_string(),
+            score: 50,
+            level: "MEDIA",
+            reason: format!(
+                "etiquetas de ≥{LONG_LABEL} caracteres (datos codificados) ({})",
+                s.long_labels
+            ),
+            count: s.long_labels,
+            examples: Some(s.long_label_examples.iter().cloned().collect()),
+            detail,
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::dns::TYPE_A;
+    use std::net::Ipv4Addr;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5));
+
+    #[test]
+    fn dga_features_separate_random_from_real_names() {
+        for real in [
+            "google.com",
+            "stackoverflow.com",
+            "cloudflare.net",
+            "wikipedia.org",
+        ] {
+            assert!(dga_features(real).is_none(), "{real}");
+        }
+        for dga in [
+            "wqcbhgwufvbyxr.com",
+            "ydxqmrbqkjbmfjx.net",
+            "a3f9c2e1b7d4e8f0.info",
+        ] {
+            assert!(dga_features(dga).is_some(), "{dga}");
+        }
+        assert_eq!(base_domain("www.bbc.co.uk").as_deref(), Some("bbc.co.uk"));
+        assert_eq!(
+            base_domain("a.b.example.com").as_deref(),
+            Some("example.com")
+        );
+    }
+
+    #[test]
+    fn punycode_homoglyph_impersonating_brand() {
+        assert_eq!(punycode_decode("pple-43d").as_deref(), Some("аpple"));
+        assert_eq!(punycode_decode("mnchen-3ya").as_deref(), Some("münchen"));
+        let v = idn_verdict("xn--pple-43d").unwrap();
+        assert!(v.mixed_script);
+        assert_eq!(v.impersonates, Some("apple"));
+
+        let mut t = ThreatTracker::default();
+        t.on_query(CLIENT, "login.xn--pple-43d.com", TYPE_A);
+        t.on_query(CLIENT, "xn--mnchen-3ya.de", TYPE_A);
+        let out = t.to_json();
+        let f = &out["findings"][0];
+        assert_eq!(f["code"], "IDN_HOMOGLYPH");
+        assert_eq!(f["score"]["level"], "ALTA");
+        assert_eq!(f["examples"][0], "login.xn--pple-43d.com");
+        assert_eq!(out["counts_by_code"]["PUNYCODE"], 1);
+    }
+
+    #[test]
+    fn tunneling_by_volume_txt_and_long_labels() {
+        let mut t = ThreatTracker::default();
+        for i in 0..120u32 {
+            let chunk = format!("{:0>60x}", i as u128 * 0x9e3779b97f4a7c15);
+            t.on_query(CLIENT, &format!("{chunk}.t.evil-tunnel.com"), TYPE_TXT);
+        }
+        // Tráfico normal no genera hallazgos
+        for _ in 0..50 {
+            t.on_query(CLIENT, "www.example.com", TYPE_A);
+        }
+        let out = t.to_json();
+        let codes: Vec<&str> = out["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|f| f["subject"] == "evil-tunnel.com")
+            .map(|f| f["code"].as_str().unwrap())
+            .collect();
+        assert!(codes.contains(&"TUNNEL_HIGH_VOLUME"));
+        assert!(codes.contains(&"TUNNEL_TXT_NULL"));
+        assert!(codes.contains(&"TUNNEL_LONG_LABELS"));
+        assert!(out["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|f| f["subject"] != "example.com"));
+    }
+}
@@ -0,0 +1,81 @@
+//! Hallazgo común a los detectores de anomalías (amenazas DNS, DHCP, vecinos, SIP,
+//! credenciales, escaneos, SYN flood, beaconing) y su escala de severidad.
+
+use serde_json::{json, Value};
+
+pub(crate) struct Finding {
+    pub code: &'static str,
+    pub subject: String,
+    pub score: u32,
+    pub level: &'static str,
+    pub reason: String,
+    pub count: u64,
+    /// Nombres u objetos de ejemplo (solo en el JSON si el detector los aporta)
+    pub examples: Option<Vec<String>>,
+    pub detail: Value,
+}
+
+impl Finding {
+    pub(crate) fn to_json(&self) -> Value {
+        let mut v = json!({
+            "code": self.code,
+            "subject": self.subject,
+            "score": { "value": self.score, "level": self.level },
+            "reason": self.reason,
+            "count": self.count,
+            "detail": self.detail
+        });
+        if let Some(examples) = &self.examples {
+            v["examples"] = json!(examples);
+        }
+        v
+    }
+}
+
+/// Nivel de una puntuación: ≥100 ALTA, ≥40 MEDIA, el resto BAJA
+pub(crate) fn level(score: u32) -> &'static str {
+    if score >= 100 {
+        "ALTA"
+    } else if score >= 40 {
+        "MEDIA"
+    } else {
+        "BAJA"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_thresholds() {
+        assert_eq!(level(39), "BAJA");
+        assert_eq!(level(40), "MEDIA");
+        assert_eq!(level(99), "MEDIA");
+        assert_eq!(level(100), "ALTA");
+    }
+
+    #[test]
+    fn finding_json_shape() {
+        let f = Finding {
+            code: "TEST",
+            subject: "10.0.0.1".to_string(),
+            score: 60,
+            level: level(60),
+            reason: "prueba".to_string(),
+            count: 2,
+            examples: None,
+            detail: json!({ "x": 1 }),
+        };
+        let v = f.to_json();
+        assert_eq!(v["score"], json!({ "value": 60, "level": "MEDIA" }));
+        assert_eq!(v["detail"]["x"], 1);
+        assert!(v.get("examples").is_none());
+        let v = Finding {
+            examples: Some(vec!["a.example".to_string()]),
+            ..f
+        }
+        .to_json();
+        assert_eq!(v["examples"], json!(["a.example"]));
+    }
+}
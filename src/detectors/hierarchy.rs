@@ -0,0 +1,383 @@
+use crate::detectors::talkers::ip_protocol_name;
+use crate::engine::Detector;
+use crate::network::format::percent;
+use crate::network::{dhcp, dns, http, quic, sip, snmp};
+use etherparse::{EtherType, LaxNetSlice, LaxSlicedPacket, LinkSlice, TransportSlice, VlanSlice};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const ROOT: &str = "Frame";
+// Hoja para lo que el decodificador no pudo interpretar en ese nivel
+const UNDECODED: &str = "[No decodificable]";
+// Hoja de aplicación para payloads que no encajan con ningún protocolo conocido
+const DATA: &str = "Datos";
+
+#[derive(Debug, Default)]
+struct Node {
+    packets: u64,
+    bytes: u64,
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn add(&mut self, path: &[String], bytes: u64) {
+        self.packets += 1;
+        self.bytes += bytes;
+        if let Some((first, rest)) = path.split_first() {
+            self.children
+                .entry(first.clone())
+                .or_default()
+                .add(rest, bytes);
+        }
+    }
+
+    /// Nodo con porcentajes sobre el total; hijos por bytes descendente
+    fn to_json(&self, name: &str, total: &Node) -> Value {
+        let mut children: Vec<(&String, &Node)> = self.children.iter().collect();
+        children.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(b.0)));
+        json!({
+            "name": name,
+            "packets": self.packets,
+            "bytes": self.bytes,
+            "packets_pct": percent(self.packets, total.packets),
+            "bytes_pct": percent(self.bytes, total.bytes),
+            "children": children
+                .into_iter()
+                .map(|(n, c)| c.to_json(n, total))
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+fn ether_type_name(t: EtherType) -> String {
+    match t {
+        EtherType::IPV4 => "IPv4".to_string(),
+        EtherType::IPV6 => "IPv6".to_string(),
+        EtherType::ARP => "ARP".to_string(),
+        EtherType::WAKE_ON_LAN => "Wake-on-LAN".to_string(),
+        EtherType::VLAN_TAGGED_FRAME => "802.1Q".to_string(),
+        EtherType::PROVIDER_BRIDGING | EtherType::VLAN_DOUBLE_TAGGED_FRAME => "802.1ad".to_string(),
+        EtherType(0x8035) => "RARP".to_string(),
+        EtherType(0x8847) | EtherType(0x8848) => "MPLS".to_string(),
+        EtherType(0x8863) | EtherType(0x8864) => "PPPoE".to_string(),
+        EtherType(0x888e) => "EAPOL".to_string(),
+        EtherType(0x88cc) => "LLDP".to_string(),
+        EtherType(0x88f7) => "PTP".to_string(),
+        EtherType(0x8809) => "LACP".to_string(),
+        // Valores < 0x0600 son longitudes 802.3 (LLC: STP, CDP...)
+        EtherType(t) if t < 0x0600 => "802.3 LLC".to_string(),
+        EtherType(t) => format!("EtherType 0x{t:04x}"),
+    }
+}
+
+/// Protocolo de aplicación: primero por contenido con los parsers del crate, luego por
+/// puerto bien conocido; `Datos` si nada encaja
+fn application(udp: bool, sport: u16, dport: u16, payload: &[u8]) -> &'static str {
+    let port = |p: u16| sport == p || dport == p;
+    if udp {
+        if port(53) || port(5353) || port(5355) {
+            if dns::parse(payload).is_some() {
+                return match (port(5353), port(5355)) {
+                    (true, _) => "mDNS",
+                    (_, true) => "LLMNR",
+                    _ => "DNS",
+                };
+            }
+        } else if port(dhcp::DHCP_SERVER_PORT) || port(dhcp::DHCP_CLIENT_PORT) {
+            if dhcp::parse(payload).is_some() {
+                return "DHCP";
+            }
+        } else if port(dhcp::DHCPV6_SERVER_PORT) || port(dhcp::DHCPV6_CLIENT_PORT) {
+            if dhcp::parse_v6(payload).is_some() {
+                return "DHCPv6";
+            }
+        } else if port(snmp::SNMP_PORT) || port(snmp::SNMP_TRAP_PORT) {
+            if snmp::parse(payload).is_some() {
+                return "SNMP";
+            }
+        } else if port(443) && !quic::parse_datagram(payload).is_empty() {
+            return "QUIC";
+        }
+        if sip::looks_like_sip(payload) {
+            return "SIP";
+        }
+        return match (sport.min(dport), sport.max(dport)) {
+            (123, _) => "NTP",
+            (137, _) | (138, _) => "NetBIOS",
+            (514, _) => "Syslog",
+            (1900, _) | (_, 1900) => "SSDP",
+            (_, 4789) => "VXLAN",
+            _ => DATA,
+        };
+    }
+
+    if http::looks_like_request(payload) || http::looks_like_response(payload) {
+        return "HTTP";
+    }
+    // Registro TLS: tipo 20..=23 y versión 3.x
+    if let [20..=23, 3, 0..=4, ..] = payload {
+        return "TLS";
+    }
+    if payload.starts_with(b"SSH-") {
+        return "SSH";
+    }
+    if sip::looks_like_sip(payload) {
+        return "SIP";
+    }
+    // Segmentos de continuación (cuerpos, registros TLS partidos) por puerto
+    match sport.min(dport) {
+        80 | 3128 | 8000 | 8080 => "HTTP",
+        443 | 465 | 636 | 993 | 995 | 8443 => "TLS",
+        21 => "FTP",
+        22 => "SSH",
+        23 => "Telnet",
+        25 | 587 => "SMTP",
+        53 => "DNS",
+        110 => "POP3",
+        143 => "IMAP",
+        179 => "BGP",
+        445 => "SMB",
+        3389 => "RDP",
+        _ => DATA,
+    }
+}
+
+/// Jerarquía de protocolos (como "Protocol Hierarchy" de Wireshark): enlace → red →
+/// transporte → aplicación con paquetes, bytes y porcentajes; lo no decodificable cuelga
+/// del último nivel que sí se entendió
+#[derive(Default)]
+pub struct HierarchyDetector {
+    root: Node,
+    undecoded: Node,
+    undecoded_by_layer: BTreeMap<String, u64>,
+}
+
+impl HierarchyDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn path(&mut self, packet_data: &[u8]) -> Vec<String> {
+        let mut path = Vec::new();
+        let sliced = match LaxSlicedPacket::from_ethernet(packet_data) {
+            Ok(s) => s,
+            Err(_) => {
+                path.push(UNDECODED.to_string());
+                *self
+                    .undecoded_by_layer
+                    .entry("Ethernet 2 header".to_string())
+                    .or_default() += 1;
+                return path;
+            }
+        };
+
+        let mut ether_payload = None;
+        if let Some(link) = &sliced.link {
+            if let LinkSlice::Ethernet2(_) = link {
+                path.push("Ethernet".to_string());
+            }
+            ether_payload = Some(link.payload().ether_type);
+        }
+        match &sliced.vlan {
+            Some(VlanSlice::SingleVlan(v)) => {
+                path.push("802.1Q".to_string());
+                ether_payload = Some(v.ether_type());
+            }
+            Some(VlanSlice::DoubleVlan(v)) => {
+                path.push("802.1ad".to_string());
+                path.push("802.1Q".to_string());
+                ether_payload = Some(v.inner().ether_type());
+            }
+            None => {}
+        }
+
+        let ip_payload = match &sliced.net {
+            Some(LaxNetSlice::Ipv4(ip)) => {
+                path.push("IPv4".to_string());
+                Some(ip.payload())
+            }
+            Some(LaxNetSlice::Ipv6(ip)) => {
+                path.push("IPv6".to_string());
+                Some(ip.payload())
+            }
+            None => {
+                if sliced.stop_err.is_none() {
+                    if let Some(t) = ether_payload {
+                        path.push(ether_type_name(t));
+                    }
+                }
+                None
+            }
+        };
+
+        if let Some(p) = ip_payload {
+            match &sliced.transport {
+                Some(TransportSlice::Tcp(tcp)) => {
+                    path.push("TCP".to_string());
+                    if !tcp.payload().is_empty() {
+                        let app = application(
+                            false,
+                            tcp.source_port(),
+                            tcp.destination_port(),
+                            tcp.payload(),
+                        );
+                        path.push(app.to_string());
+                    }
+                }
+                Some(TransportSlice::Udp(udp)) => {
+                    path.push("UDP".to_string());
+                    if !udp.payload().is_empty() {
+                        let app = application(
+                            true,
+                            udp.source_port(),
+                            udp.destination_port(),
+                            udp.payload(),
+                        );
+                        path.push(app.to_string());
+                    }
+                }
+                Some(TransportSlice::Icmpv4(_)) => path.push("ICMP".to_string()),
+                Some(TransportSlice::Icmpv6(_)) => path.push("ICMPv6".to_string()),
+                None if sliced.stop_err.is_none() => {
+                    path.push(ip_protocol_name(p.ip_number.0));
+                    // Fragmentos no iniciales: sin cabecera de transporte que leer
+                    if p.fragmented {
+                        path.push("[Fragmento]".to_string());
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if let Some((_, layer)) = &sliced.stop_err {
+            path.push(UNDECODED.to_string());
+            *self
+                .undecoded_by_layer
+                .entry(layer.to_string())
+                .or_default() += 1;
+        }
+        path
+    }
+}
+
+impl Detector for HierarchyDetector {
+    fn name(&self) -> &'static str {
+        "hierarchy"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], _ts_micros: u64) {
+        let bytes = packet_data.len() as u64;
+        let path = self.path(packet_data);
+        if path.last().is_some_and(|p| p == UNDECODED) {
+            self.undecoded.add(&[], bytes);
+        }
+        self.root.add(&path, bytes);
+    }
+
+    fn finalize(&mut self) -> Value {
+        json!({
+            "frames_total": self.root.packets,
+            "bytes_total": self.root.bytes,
+            "undecoded": {
+                "frames": self.undecoded.packets,
+                "bytes": self.undecoded.bytes,
+                "frames_pct": percent(self.undecoded.packets, self.root.packets),
+                "by_layer": self.undecoded_by_layer
+            },
+            "tree": self.root.to_json(ROOT, &self.root)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::snmp::tests::snmp_request;
+    use etherparse::PacketBuilder;
+
+    const A: [u8; 4] = [10, 0, 0, 1];
+    const B: [u8; 4] = [10, 0, 0, 2];
+
+    fn child<'a>(node: &'a Value, name: &str) -> &'a Value {
+        node["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == name)
+            .unwrap_or_else(|| panic!("sin nodo {name}: {node:#}"))
+    }
+
+    fn tcp(payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(A, B, 64)
+            .tcp(50000, 80, 1, 1024)
+            .write(&mut out, payload)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn builds_tree_with_counts_and_percentages() {
+        let mut d = HierarchyDetector::new();
+        d.on_packet(&tcp(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n"), 0);
+        d.on_packet(&tcp(&[]), 0);
+
+        let mut snmp = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .single_vlan(etherparse::VlanId::try_new(10).unwrap())
+            .ipv4(A, B, 64)
+            .udp(40000, 161)
+            .write(&mut snmp, &snmp_request(1, "public", snmp::PDU_GET))
+            .unwrap();
+        d.on_packet(&snmp, 0);
+
+        let mut arp = vec![0xff; 6];
+        arp.extend([1; 6]);
+        arp.extend([0x08, 0x06]);
+        arp.extend([0; 28]);
+        d.on_packet(&arp, 0);
+
+        let r = d.finalize();
+        assert_eq!(r["frames_total"], 4);
+        assert_eq!(r["undecoded"]["frames"], 0);
+        let eth = child(&r["tree"], "Ethernet");
+        assert_eq!(eth["packets_pct"], 100.0);
+        let tcp = child(child(eth, "IPv4"), "TCP");
+        assert_eq!(tcp["packets"], 2);
+        assert_eq!(tcp["packets_pct"], 50.0);
+        // El ACK vacío cuenta en TCP pero no en ninguna aplicación
+        assert_eq!(child(tcp, "HTTP")["packets"], 1);
+        let udp = child(child(child(eth, "802.1Q"), "IPv4"), "UDP");
+        assert_eq!(child(udp, "SNMP")["packets"], 1);
+        assert_eq!(child(eth, "ARP")["bytes"], 42);
+    }
+
+    #[test]
+    fn truncated_and_unknown_frames_are_counted() {
+        let mut d = HierarchyDetector::new();
+        // Cabecera TCP cortada por el snaplen
+        let frame = tcp(b"hola");
+        d.on_packet(&frame[..14 + 20 + 10], 0);
+        // Trama más corta que una cabecera Ethernet
+        d.on_packet(&[0; 8], 0);
+        // IPv4 con un protocolo sin decodificador (GRE)
+        let mut gre = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(A, B, 64)
+            .udp(1, 2)
+            .write(&mut gre, &[])
+            .unwrap();
+        gre[14 + 9] = 47;
+        d.on_packet(&gre, 0);
+
+        let r = d.finalize();
+        assert_eq!(r["undecoded"]["frames"], 2);
+        assert_eq!(r["undecoded"]["by_layer"]["TCP header"], 1);
+        assert_eq!(r["undecoded"]["by_layer"]["Ethernet 2 header"], 1);
+        assert_eq!(child(&r["tree"], UNDECODED)["packets"], 1);
+        let ipv4 = child(child(&r["tree"], "Ethernet"), "IPv4");
+        assert_eq!(child(ipv4, UNDECODED)["bytes"], 44);
+        assert_eq!(child(ipv4, "GRE")["packets"], 1);
+    }
+}
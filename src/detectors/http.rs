@@ -0,0 +1,857 @@
+use crate::detectors::latency::LatencyStats;
+use crate::engine::Detector;
+use crate::network::flow::Flow;
+use crate::network::flow_table::{FlowTable, FlowTableConfig};
+use crate::network::format::{endpoint_label, flow_label, rate};
+use crate::network::http::{self, BodyDecoder, MAX_HEAD_BYTES};
+use crate::network::reassembly::StreamReassembler;
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::IpAddr;
+
+// Transacciones con detalle en el reporte (el resto solo cuenta en los agregados)
+const MAX_TRANSACTIONS_REPORTED: usize = 1000;
+// Peticiones en vuelo por conexión (pipelining)
+const MAX_IN_FLIGHT: usize = 64;
+const MAX_ENDPOINTS_PER_HOST: usize = 1000;
+const SLOWEST_ENDPOINTS: usize = 5;
+const MAX_URI_LEN: usize = 512;
+// Por debajo de este número de peticiones no se escala a ALTA
+const MIN_REQUESTS_FOR_HIGH: u64 = 5;
+
+#[derive(Debug, Clone, Default)]
+struct Transaction {
+    method: String,
+    host: String,
+    uri: String,
+    status: Option<u16>,
+    req_start_us: u64,
+    req_end_us: Option<u64>,
+    resp_first_us: Option<u64>,
+    resp_end_us: Option<u64>,
+    req_body_bytes: u64,
+    resp_body_bytes: u64,
+}
+
+impl Transaction {
+    fn ttfb_us(&self) -> Option<u64> {
+        let end = self.req_end_us.unwrap_or(self.req_start_us);
+        self.resp_first_us.map(|f| f.saturating_sub(end))
+    }
+
+    fn response_us(&self) -> Option<u64> {
+        self.resp_end_us
+            .map(|e| e.saturating_sub(self.req_start_us))
+    }
+}
+
+/// Estado de un sentido del parser (cabecera en curso o cuerpo)
+#[derive(Debug, Default)]
+struct SideParser {
+    head: Vec<u8>,
+    head_start_us: Option<u64>,
+    body: Option<BodyDecoder>,
+    // Tras un hueco o error se espera a un inicio de mensaje reconocible
+    desynced: bool,
+}
+
+impl SideParser {
+    fn reset(&mut self, desynced: bool) {
+        self.head.clear();
+        self.head_start_us = None;
+        self.body = None;
+        self.desynced = desynced;
+    }
+}
+
+#[derive(Debug, Default)]
+struct HttpConnState {
+    flow: Flow,
+    server: Option<(IpAddr, u16)>,
+    client_forward: Option<bool>,
+    not_http: bool,
+    upgraded: bool,
+    fwd: StreamReassembler,
+    rev: StreamReassembler,
+    parser: ConnParser,
+}
+
+/// Emparejamiento petición/respuesta de una conexión (FIFO para pipelining)
+#[derive(Debug, Default)]
+struct ConnParser {
+    req: SideParser,
+    resp: SideParser,
+    server_label: String,
+    in_flight: VecDeque<Transaction>,
+    current: Option<Transaction>,
+    completed: Vec<Transaction>,
+    unmatched_responses: u64,
+    parse_errors: u64,
+    upgraded: bool,
+}
+
+impl ConnParser {
+    fn on_request_bytes(&mut self, mut data: &[u8], ts_us: u64, after_gap: bool) {
+        if after_gap {
+            self.req.reset(true);
+        }
+        while !data.is_empty() && !self.upgraded {
+            if let Some(body) = self.req.body.as_mut() {
+                let Some(used) = body.consume(data) else {
+                    self.parse_errors += 1;
+                    self.req.reset(true);
+                    return;
+                };
+                data = &data[used..];
+                if body.is_done() {
+                    let bytes = body.body_bytes;
+                    if let Some(t) = self.in_flight.back_mut() {
+                        t.req_body_bytes = bytes;
+                        t.req_end_us = Some(ts_us);
+                    }
+                    self.req.reset(false);
+                }
+                continue;
+            }
+
+            if self.req.desynced {
+                if !http::looks_like_request(data) {
+                    return;
+                }
+                self.req.desynced = false;
+            }
+
+            let Some(used) = self.read_head(true, data, ts_us) else {
+                return;
+            };
+            data = &data[used..];
+        }
+    }
+
+    fn on_response_bytes(&mut self, mut data: &[u8], ts_us: u64, after_gap: bool) {
+        if after_gap {
+            // La respuesta en curso queda incompleta
+            if let Some(t) = self.current.take() {
+                self.completed.push(t);
+            }
+            self.resp.reset(true);
+        }
+        while !data.is_empty() && !self.upgraded {
+            if let Some(body) = self.resp.body.as_mut() {
+                let Some(used) = body.consume(data) else {
+                    self.parse_errors += 1;
+                    self.resp.reset(true);
+                    return;
+                };
+                data = &data[used..];
+                let (done, bytes) = (body.is_done(), body.body_bytes);
+                if let Some(t) = self.current.as_mut() {
+                    t.resp_body_bytes = bytes;
+                    t.resp_end_us = Some(ts_us);
+                }
+                if done {
+                    self.finish_response();
+                }
+                continue;
+            }
+
+            if self.resp.desynced {
+                if !http::looks_like_response(data) {
+                    return;
+                }
+                self.resp.desynced = false;
+            }
+
+            let Some(used) = self.read_head(false, data, ts_us) else {
+                return;
+            };
+            data = &data[used..];
+        }
+    }
+
+    /// Acumula cabeceras; devuelve los bytes consumidos si se completó la cabecera
+    fn read_head(&mut self, request: bool, data: &[u8], ts_us: u64) -> Option<usize> {
+        let side = if request {
+            &mut self.req
+        } else {
+            &mut self.resp
+        };
+        let prev_len = side.head.len();
+        side.head_start_us.get_or_insert(ts_us);
+        side.head.extend_from_slice(data);
+
+        let Some(end) = http::head_end(&side.head) else {
+            if side.head.len() > MAX_HEAD_BYTES {
+                self.parse_errors += 1;
+                side.reset(true);
+            }
+            return None;
+        };
+        let head: Vec<u8> = side.head[..end].to_vec();
+        let start_us = side.head_start_us.unwrap_or(ts_us);
+        let used = end - prev_len;
+        side.head.clear();
+        side.head_start_us = None;
+
+        if request {
+            self.on_request_head(&head, start_us, ts_us);
+        } else {
+            self.on_response_head(&head, start_us, ts_us);
+        }
+        Some(used)
+    }
+
+    fn on_request_head(&mut self, head: &[u8], start_us: u64, ts_us: u64) {
+        let Some(h) = http::parse_request_head(head) else {
+            self.parse_errors += 1;
+            self.req.reset(true);
+            return;
+        };
+        let mut uri = h.uri;
+        if uri.len() > MAX_URI_LEN {
+            let mut cut = MAX_URI_LEN;
+            while !uri.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            uri.truncate(cut);
+        }
+        let body = BodyDecoder::new(h.framing);
+        let done = body.is_done();
+        let t = Transaction {
+            method: h.method,
+            host: h.host.unwrap_or_else(|| self.server_label.clone()),
+            uri,
+            req_start_us: start_us,
+            req_end_us: done.then_some(ts_us),
+            ..Transaction::default()
+        };
+        if self.in_flight.len() >= MAX_IN_FLIGHT {
+            // Sin respuesta tras demasiadas peticiones encadenadas
+            if let Some(old) = self.in_flight.pop_front() {
+                self.completed.push(old);
+            }
+        }
+        self.in_flight.push_back(t);
+        if !done {
+            self.req.body = Some(body);
+        }
+    }
+
+    fn on_response_head(&mut self, head: &[u8], start_us: u64, ts_us: u64) {
+        let method = self.in_flight.front().map(|t| t.method.clone());
+        let Some(h) = http::parse_response_head(head, method.as_deref()) else {
+            self.parse_errors += 1;
+            self.resp.reset(true);
+            return;
+        };
+        // Respuestas intermedias (100 Continue): la transacción sigue abierta
+        if (100..200).contains(&h.status) && h.status != 101 {
+            if let Some(t) = self.in_flight.front_mut() {
+                t.resp_first_us.get_or_insert(start_us);
+            }
+            return;
+        }
+
+        let mut t = match self.in_flight.pop_front() {
+            Some(t) => t,
+            None => {
+                self.unmatched_responses += 1;
+                Transaction {
+                    method: "?".to_string(),
+                    host: self.server_label.clone(),
+                    uri: "?".to_string(),
+                    req_start_us: start_us,
+                    ..Transaction::default()
+                }
+            }
+        };
+        t.status = Some(h.status);
+        t.resp_first_us.get_or_insert(start_us);
+        t.resp_end_us = Some(ts_us);
+        self.current = Some(t);
+
+        if h.status == 101 {
+            // Cambio de protocolo (WebSocket, h2c): deja de ser HTTP/1.x
+            self.finish_response();
+            self.upgraded = true;
+            return;
+        }
+        let body = BodyDecoder::new(h.framing);
+        if body.is_done() {
+            self.finish_response();
+        } else {
+            self.resp.body = Some(body);
+        }
+    }
+
+    fn finish_response(&mut self) {
+        if let Some(t) = self.current.take() {
+            self.completed.push(t);
+        }
+        self.resp.reset(false);
+    }
+
+    /// Cierre o fin de captura: cierra cuerpos hasta-cierre y vacía lo pendiente
+    fn close(&mut self) {
+        if let Some(body) = self.resp.body.as_mut() {
+            body.on_close();
+            if body.is_done() {
+                self.finish_response();
+            }
+        }
+        if let Some(t) = self.current.take() {
+            self.completed.push(t);
+        }
+        self.completed.extend(self.in_flight.drain(..));
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointStats {
+    requests: u64,
+    response: LatencyStats,
+    total_us: u64,
+}
+
+#[derive(Debug, Default)]
+struct HostStats {
+    requests: u64,
+    responses: u64,
+    unanswered: u64,
+    incomplete: u64,
+    status_classes: BTreeMap<u16, u64>, // 2 -> 2xx, ...
+    ttfb: LatencyStats,
+    response: LatencyStats,
+    request_bytes: u64,
+    response_bytes: u64,
+    endpoints: HashMap<String, EndpointStats>,
+    endpoints_truncated: u64,
+}
+
+/// Detector de transacciones HTTP/1.x sobre TCP reensamblado
+pub struct HttpDetector {
+    conns: FlowTable<HttpConnState>,
+    hosts: BTreeMap<String, HostStats>,
+    transactions: Vec<Value>,
+    transactions_total: u64,
+    connections_http: u64,
+    unmatched_responses: u64,
+    parse_errors: u64,
+    stream_gaps: u64,
+}
+
+impl Default for HttpDetector {
+    fn default() -> Self {
+        Self::with_flow_table(FlowTableConfig::default())
+    }
+}
+
+/// Ruta sin query string (agrupa endpoints)
+fn endpoint_key(method: &str, uri: &str) -> String {
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let path: String = path.chars().take(128).collect();
+    format!("{method} {path}")
+}
+
+impl HttpDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_flow_table(config: FlowTableConfig) -> Self {
+        HttpDetector {
+            conns: FlowTable::new(config),
+            hosts: BTreeMap::new(),
+            transactions: Vec::new(),
+            transactions_total: 0,
+            connections_http: 0,
+            unmatched_responses: 0,
+            parse_errors: 0,
+            stream_gaps: 0,
+        }
+    }
+
+    fn on_tcp(&mut self, flow: Flow, tcp: &etherparse::TcpSlice, ts_us: u64) {
+        let (key, st, forward) = self.conns.get_or_insert(flow, ts_us);
+        st.flow = key;
+        if st.not_http || st.upgraded {
+            return;
+        }
+
+        let seq = tcp.sequence_number();
+        let payload = tcp.payload();
+        if tcp.syn() {
+            let r = if forward { &mut st.fwd } else { &mut st.rev };
+            r.on_syn(seq);
+            if !tcp.ack() && st.client_forward.is_none() {
+                st.client_forward = Some(forward);
+            }
+        }
+
+        if !payload.is_empty() {
+            // Sin SYN visto, el primer payload decide quién es el cliente
+            if st.client_forward.is_none() {
+                if http::looks_like_request(payload) {
+                    st.client_forward = Some(forward);
+                } else if http::looks_like_response(payload) {
+                    st.client_forward = Some(!forward);
+                } else {
+                    st.not_http = true;
+                    return;
+                }
+            }
+            let client_dir = st.client_forward == Some(forward);
+            if st.server.is_none() {
+                let server = if client_dir {
+                    (flow.destination_ip, flow.destination_port)
+                } else {
+                    (flow.source_ip, flow.source_port)
+                };
+                st.server = Some(server);
+                st.parser.server_label = endpoint_label(server.0, server.1);
+            }
+
+            let HttpConnState {
+                fwd, rev, parser, ..
+            } = st;
+            let reasm = if forward { fwd } else { rev };
+            let mut first_bytes = reasm.delivered_bytes() == 0;
+            let mut not_http = false;
+            reasm.push(seq, payload, ts_us, &mut |data, ts, gap| {
+                // Cada sentido debe empezar con un mensaje HTTP reconocible
+                let recognized = if client_dir {
+                    http::looks_like_request(data)
+                } else {
+                    http::looks_like_response(data)
+                };
+                if first_bytes && !gap && !recognized {
+                    not_http = true;
+                }
+                first_bytes = false;
+                if not_http {
+                    return;
+                }
+                if client_dir {
+                    parser.on_request_bytes(data, ts, gap);
+                } else {
+                    parser.on_response_bytes(data, ts, gap);
+                }
+            });
+            if not_http {
+                st.not_http = true;
+                return;
+            }
+            if parser.upgraded {
+                st.upgraded = true;
+            }
+        }
+
+        if tcp.fin() || tcp.rst() {
+            let r = if forward { &mut st.fwd } else { &mut st.rev };
+            r.close();
+            let server_side = st.client_forward.is_some_and(|c| c != forward);
+            if tcp.rst() || server_side {
+                st.parser.close();
+            }
+        }
+
+        self.collect_completed(key);
+    }
+
+    /// Mueve las transacciones terminadas de la conexión a los agregados
+    fn collect_completed(&mut self, key: Flow) {
+        let Some((_, st, _)) = self.conns.get_mut(&key) else {
+            return;
+        };
+        if st.parser.completed.is_empty() {
+            return;
+        }
+        let done = std::mem::take(&mut st.parser.completed);
+        let label = flow_label(&st.flow, "TCP");
+        for t in done {
+            self.record(&label, t);
+        }
+    }
+
+    fn finish_conn(&mut self, mut st: HttpConnState) {
+        let has_data = st.fwd.delivered_bytes() + st.rev.delivered_bytes() > 0;
+        if st.client_forward.is_none() || st.not_http || !has_data {
+            return;
+        }
+        // Lo que quedó esperando un hueco se entrega tal cual
+        let HttpConnState {
+            fwd,
+            rev,
+            parser,
+            client_forward,
+            ..
+        } = &mut st;
+        let client_forward = client_forward.unwrap_or(true);
+        for (reasm, forward) in [(fwd, true), (rev, false)] {
+            let client_dir = forward == client_forward;
+            reasm.skip_gap(&mut |data, ts, gap| {
+                if client_dir {
+                    parser.on_request_bytes(data, ts, gap);
+                } else {
+                    parser.on_response_bytes(data, ts, gap);
+                }
+            });
+        }
+        st.parser.close();
+        self.stream_gaps += (st.fwd.gaps() + st.rev.gaps()) as u64;
+        self.unmatched_responses += st.parser.unmatched_responses;
+        self.parse_errors += st.parser.parse_errors;
+        self.connections_http += 1;
+
+        let label = flow_label(&st.flow, "TCP");
+        for t in std::mem::take(&mut st.parser.completed) {
+            self.record(&label, t);
+        }
+    }
+
+    fn finish_conns(&mut self, mut conns: Vec<(Flow, HttpConnState)>) {
+        // Orden determinista de las transacciones finalizadas en bloque
+        conns.sort_by_cached_key(|(f, _)| flow_label(f, "TCP"));
+        for (_, st) in conns {
+            self.finish_conn(st);
+        }
+    }
+
+    fn record(&mut self, flow: &str, t: Transaction) {
+        self.transactions_total += 1;
+        let host = self.hosts.entry(t.host.clone()).or_default();
+        host.requests += 1;
+        host.request_bytes += t.req_body_bytes;
+        host.response_bytes += t.resp_body_bytes;
+
+        let ttfb = t.ttfb_us();
+        let response = t.response_us();
+        match t.status {
+            Some(status) => {
+                host.responses += 1;
+                *host.status_classes.entry(status / 100).or_default() += 1;
+                if let Some(v) = ttfb {
+                    host.ttfb.add_sample(v);
+                }
+                if let Some(v) = response {
+                    host.response.add_sample(v);
+                }
+                let key = endpoint_key(&t.method, &t.uri);
+                if host.endpoints.len() < MAX_ENDPOINTS_PER_HOST
+                    || host.endpoints.contains_key(&key)
+                {
+                    let ep = host.endpoints.entry(key).or_default();
+                    ep.requests += 1;
+                    if let Some(v) = response {
+                        ep.response.add_sample(v);
+                        ep.total_us += v;
+                    }
+                } else {
+                    host.endpoints_truncated += 1;
+                }
+            }
+            None if t.resp_first_us.is_some() => host.incomplete += 1,
+            None => host.unanswered += 1,
+        }
+
+        if self.transactions.len() < MAX_TRANSACTIONS_REPORTED {
+            let ms = |us: Option<u64>| us.map(|v| v as f64 / 1000.0);
+            self.transactions.push(json!({
+                "flow": flow,
+                "method": t.method,
+                "host": t.host,
+                "uri": t.uri,
+                "status": t.status,
+                "ttfb_ms": ms(ttfb),
+                "response_ms": ms(response),
+                "request_body_bytes": t.req_body_bytes,
+                "response_body_bytes": t.resp_body_bytes
+            }));
+        }
+    }
+}
+
+fn compute_severity(h: &HostStats) -> (u32, &'static str, Vec<String>) {
+    let class = |c: u16| h.status_classes.get(&c).copied().unwrap_or(0);
+    let (c4, c5) = (class(4), class(5));
+    let rate_5xx = rate(c5, h.responses);
+    let rate_4xx = rate(c4, h.responses);
+    let unanswered_rate = rate(h.unanswered, h.requests);
+    let ttfb_p95 = h.ttfb.percentile_ms(0.95);
+
+    let mut score_f = 150.0 * rate_5xx + 30.0 * rate_4xx + 100.0 * unanswered_rate;
+    if ttfb_p95 > 1000.0 {
+        score_f += 40.0;
+    } else if ttfb_p95 > 500.0 {
+        score_f += 20.0;
+    }
+    let score = score_f.round() as u32;
+
+    let mut reasons = Vec::<String>::new();
+    if c5 >= 1 {
+        reasons.push(format!("respuestas 5xx {:.1}% ({c5})", rate_5xx * 100.0));
+    }
+    if c4 >= 3 && rate_4xx >= 0.2 {
+        reasons.push(format!("respuestas 4xx {:.1}% ({c4})", rate_4xx * 100.0));
+    }
+    if h.unanswered >= 1 {
+        reasons.push(format!("peticiones sin respuesta ({})", h.unanswered));
+    }
+    if h.incomplete >= 1 {
+        reasons.push(format!("respuestas incompletas ({})", h.incomplete));
+    }
+    if ttfb_p95 > 500.0 {
+        reasons.push(format!("TTFB alto p95={ttfb_p95:.1} ms"));
+    }
+
+    let mut level = if score >= 100 || rate_5xx >= 0.5 {
+        "ALTA"
+    } else if score >= 50 || c5 >= 1 || h.unanswered >= 1 || ttfb_p95 > 500.0 {
+        "MEDIA"
+    } else {
+        "BAJA"
+    };
+    if level == "ALTA" && h.requests < MIN_REQUESTS_FOR_HIGH {
+        level = "MEDIA";
+    }
+    (score, level, reasons)
+}
+
+fn host_json(name: &str, h: &HostStats) -> (u32, Value) {
+    let (score, level, reasons) = compute_severity(h);
+    let statuses: serde_json::Map<String, Value> = h
+        .status_classes
+        .iter()
+        .map(|(c, n)| (format!("{c}xx"), json!(n)))
+        .collect();
+
+    let mut endpoints: Vec<(&String, &EndpointStats)> = h.endpoints.iter().collect();
+    endpoints.sort_by(|a, b| {
+        let ma = a.1.response.percentile_ms(1.0);
+        let mb = b.1.response.percentile_ms(1.0);
+        mb.total_cmp(&ma).then_with(|| a.0.cmp(b.0))
+    });
+    let slowest: Vec<Value> = endpoints
+        .iter()
+        .take(SLOWEST_ENDPOINTS)
+        .map(|(k, e)| {
+            json!({
+                "endpoint": k,
+                "requests": e.requests,
+                "avg_ms": if e.requests > 0 { e.total_us as f64 / e.requests as f64 / 1000.0 } else { 0.0 },
+                "p95_ms": e.response.percentile_ms(0.95),
+                "max_ms": e.response.percentile_ms(1.0)
+            })
+        })
+        .collect();
+
+    let v = json!({
+        "host": name,
+        "score": { "value": score, "level": level },
+        "reasons": reasons,
+        "requests": h.requests,
+        "responses": h.responses,
+        "unanswered": h.unanswered,
+        "incomplete": h.incomplete,
+        "status_classes": statuses,
+        "rate_4xx": rate(h.status_classes.get(&4).copied().unwrap_or(0), h.responses),
+        "rate_5xx": rate(h.status_classes.get(&5).copied().unwrap_or(0), h.responses),
+        "ttfb_ms": h.ttfb.to_json(),
+        "response_ms": h.response.to_json(),
+        "request_body_bytes": h.request_bytes,
+        "response_body_bytes": h.response_bytes,
+        "slowest_endpoints": slowest,
+        "endpoints_truncated": h.endpoints_truncated
+    });
+    (score, v)
+}
+
+impl Detector for HttpDetector {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        let evicted = self.conns.evict(ts_micros);
+        if !evicted.is_empty() {
+            self.finish_conns(evicted);
+        }
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        if let Some(TransportSlice::Tcp(tcp)) = &sliced.transport {
+            let flow = Flow {
+                source_ip: src,
+                source_port: tcp.source_port(),
+                destination_ip: dst,
+                destination_port: tcp.destination_port(),
+            };
+            self.on_tcp(flow, tcp, ts_micros);
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        let live = self.conns.drain();
+        self.finish_conns(live);
+
+        let mut hosts: Vec<(u32, Value)> = self
+            .hosts
+            .iter()
+            .map(|(name, h)| host_json(name, h))
+            .collect();
+        // Orden estable: score desc, luego host (BTreeMap ya ordenado)
+        hosts.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let reported = self.transactions.len() as u64;
+        json!({
+            "connections_total": self.connections_http,
+            "transactions_total": self.transactions_total,
+            "unmatched_responses": self.unmatched_responses,
+            "parse_errors": self.parse_errors,
+            "stream_gaps": self.stream_gaps,
+            "hosts_total": hosts.len(),
+            "top_by_severity": hosts.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+            "transactions": std::mem::take(&mut self.transactions),
+            "transactions_truncated": self.transactions_total - reported
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etherparse::PacketBuilder;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    struct Conn {
+        c_seq: u32,
+        s_seq: u32,
+    }
+
+    fn seg(from_client: bool, seq: u32, ack: u32, syn: bool, payload: &[u8]) -> Vec<u8> {
+        let (s, d, sp, dp) = if from_client {
+            (CLIENT, SERVER, 40000, 80)
+        } else {
+            (SERVER, CLIENT, 80, 40000)
+        };
+        let mut b = PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(s, d, 64)
+            .tcp(sp, dp, seq, 65535);
+        if syn {
+            b = b.syn();
+        }
+        let b = b.ack(ack);
+        let mut out = Vec::new();
+        b.write(&mut out, payload).unwrap();
+        out
+    }
+
+    impl Conn {
+        fn open(d: &mut HttpDetector) -> Conn {
+            d.on_packet(&seg(true, 100, 0, true, b""), 0);
+            d.on_packet(&seg(false, 500, 101, true, b""), 10);
+            Conn {
+                c_seq: 101,
+                s_seq: 501,
+            }
+        }
+        fn client(&mut self, d: &mut HttpDetector, data: &[u8], ts: u64) {
+            d.on_packet(&seg(true, self.c_seq, self.s_seq, false, data), ts);
+            self.c_seq += data.len() as u32;
+        }
+        fn server(&mut self, d: &mut HttpDetector, data: &[u8], ts: u64) {
+            d.on_packet(&seg(false, self.s_seq, self.c_seq, false, data), ts);
+            self.s_seq += data.len() as u32;
+        }
+    }
+
+    #[test]
+    fn pipelined_requests_pair_in_order() {
+        let mut d = HttpDetector::new();
+        let mut c = Conn::open(&mut d);
+        c.client(
+            &mut d,
+            b"GET /a HTTP/1.1\r\nHost: web\r\n\r\nGET /b?x=1 HTTP/1.1\r\nHost: web\r\n\r\n",
+            1_000,
+        );
+        c.server(
+            &mut d,
+            b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhel",
+            21_000,
+        );
+        c.server(&mut d, b"lo", 31_000);
+        c.server(
+            &mut d,
+            b"HTTP/1.1 503 Busy\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nerr\r\n0\r\n\r\n",
+            41_000,
+        );
+        let out = d.finalize();
+
+        assert_eq!(out["transactions_total"], 2);
+        let t0 = &out["transactions"][0];
+        assert_eq!(t0["uri"], "/a");
+        assert_eq!(t0["status"], 200);
+        assert_eq!(t0["ttfb_ms"], 20.0);
+        assert_eq!(t0["response_ms"], 30.0);
+        assert_eq!(t0["response_body_bytes"], 5);
+        let t1 = &out["transactions"][1];
+        assert_eq!(t1["status"], 503);
+        assert_eq!(t1["response_body_bytes"], 3);
+
+        let host = &out["top_by_severity"][0];
+        assert_eq!(host["host"], "web");
+        assert_eq!(host["rate_5xx"], 0.5);
+        assert_eq!(host["slowest_endpoints"][0]["endpoint"], "GET /b");
+    }
+
+    #[test]
+    fn keep_alive_with_request_body_and_unanswered_request() {
+        let mut d = HttpDetector::new();
+        let mut c = Conn::open(&mut d);
+        c.client(
+            &mut d,
+            b"POST /api HTTP/1.1\r\nHost: api\r\nContent-Length: 4\r\n\r\nbody",
+            1_000,
+        );
+        c.server(
+            &mut d,
+            b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n",
+            5_000,
+        );
+        c.client(&mut d, b"GET /slow HTTP/1.1\r\nHost: api\r\n\r\n", 6_000);
+        let out = d.finalize();
+
+        assert_eq!(out["transactions"][0]["request_body_bytes"], 4);
+        assert_eq!(out["transactions"][0]["status"], 201);
+        assert_eq!(out["transactions"][1]["status"], Value::Null);
+        let host = &out["top_by_severity"][0];
+        assert_eq!(host["unanswered"], 1);
+        assert_eq!(host["score"]["level"], "MEDIA");
+    }
+
+    #[test]
+    fn non_http_streams_are_ignored() {
+        let mut d = HttpDetector::new();
+        let mut c = Conn::open(&mut d);
+        c.client(&mut d, b"\x16\x03\x01\x00\x05hello", 1_000);
+        c.server(&mut d, b"HTTP/1.1 200 OK\r\n\r\n", 2_000);
+        let out = d.finalize();
+        assert_eq!(out["transactions_total"], 0);
+        assert_eq!(out["connections_total"], 0);
+    }
+}
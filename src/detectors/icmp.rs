@@ -0,0 +1,469 @@
+use crate::detectors::finding::level;
+use crate::detectors::latency::LatencyStats;
+use crate::engine::Detector;
+use crate::network::flow::{Flow, PROTO_ICMP, PROTO_ICMPV6, PROTO_TCP, PROTO_UDP};
+use crate::network::flow_table::{FlowTable, FlowTableConfig};
+use crate::network::format::{endpoint_label, rate};
+use crate::network::icmp::{self, IcmpError, IcmpMessage};
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+// Flujos afectados con detalle (el resto solo cuenta en los agregados)
+const MAX_AFFECTED_FLOWS: usize = 10_000;
+const MAX_FLOWS_REPORTED: usize = 1000;
+const MAX_REPORTERS: usize = 10;
+// Ecos pendientes de respuesta y pares origen/destino con estadísticas
+const MAX_PENDING_ECHOES: usize = 100_000;
+const MAX_ECHO_PAIRS: usize = 10_000;
+
+/// Errores recibidos por un flujo (en el sentido del paquete citado)
+#[derive(Debug, Default)]
+struct AffectedFlow {
+    protocol: u8,
+    // Hubo tráfico TCP/UDP de este flujo en la captura
+    seen: bool,
+    errors: BTreeMap<&'static str, u64>,
+    reporters: BTreeSet<IpAddr>,
+    min_mtu: Option<u32>,
+    gateways: BTreeSet<IpAddr>,
+    first_us: u64,
+    last_us: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct EchoKey {
+    src: IpAddr,
+    dst: IpAddr,
+    id: u16,
+    seq: u16,
+}
+
+#[derive(Debug, Default)]
+struct EchoPair {
+    requests: u64,
+    replies: u64,
+    rtt: LatencyStats,
+}
+
+/// Detector ICMP/ICMPv6: errores correlacionados con el flujo que los provocó
+/// y latencia/pérdida de echo request/reply
+pub struct IcmpDetector {
+    // Flujos TCP/UDP vistos, para saber si el error cita tráfico real de la captura
+    seen: FlowTable<u64>,
+    affected: HashMap<(Flow, u8), AffectedFlow>,
+    affected_truncated: u64,
+    messages_total: u64,
+    errors_total: u64,
+    errors_by_type: BTreeMap<&'static str, u64>,
+    correlated: u64,
+    unparsed_quotes: u64,
+    pending: HashMap<EchoKey, u64>,
+    echo_pairs: BTreeMap<(IpAddr, IpAddr), EchoPair>,
+    echo_rtt: LatencyStats,
+    echo_requests: u64,
+    echo_replies: u64,
+    unmatched_replies: u64,
+    untracked_echoes: u64,
+}
+
+impl Default for IcmpDetector {
+    fn default() -> Self {
+        Self::with_flow_table(FlowTableConfig::default())
+    }
+}
+
+fn protocol_name(protocol: u8) -> String {
+    match protocol {
+        PROTO_TCP => "TCP".to_string(),
+        PROTO_UDP => "UDP".to_string(),
+        PROTO_ICMP => "ICMP".to_string(),
+        PROTO_ICMPV6 => "ICMPv6".to_string(),
+        p => format!("IP/{p}"),
+    }
+}
+
+/// Sentido del paquete citado: su emisor es quien recibe el error
+fn flow_label(flow: &Flow, protocol: u8) -> String {
+    let proto = protocol_name(protocol);
+    if matches!(protocol, PROTO_TCP | PROTO_UDP) {
+        let src = endpoint_label(flow.source_ip, flow.source_port);
+        let dst = endpoint_label(flow.destination_ip, flow.destination_port);
+        format!("{src} -> {dst}/{proto}")
+    } else {
+        format!("{} -> {}/{proto}", flow.source_ip, flow.destination_ip)
+    }
+}
+
+/// Puntos de severidad de cada tipo de error (una vez por tipo y flujo)
+fn error_score(name: &str) -> u32 {
+    match name {
+        "fragmentation_needed" | "packet_too_big" | "redirect" => 20,
+        "port_unreachable"
+        | "ttl_exceeded"
+        | "hop_limit_exceeded"
+        | "reassembly_time_exceeded"
+        | "parameter_problem" => 40,
+        // Red/host inalcanzable, sin ruta o prohibido por política
+        _ => 60,
+    }
+}
+
+impl IcmpDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_flow_table(flow_table: FlowTableConfig) -> Self {
+        IcmpDetector {
+            seen: FlowTable::new(flow_table),
+            affected: HashMap::new(),
+            affected_truncated: 0,
+            messages_total: 0,
+            errors_total: 0,
+            errors_by_type: BTreeMap::new(),
+            correlated: 0,
+            unparsed_quotes: 0,
+            pending: HashMap::new(),
+            echo_pairs: BTreeMap::new(),
+            echo_rtt: LatencyStats::default(),
+            echo_requests: 0,
+            echo_replies: 0,
+            unmatched_replies: 0,
+            untracked_echoes: 0,
+        }
+    }
+
+    fn on_error(&mut self, reporter: IpAddr, e: IcmpError, ts_us: u64) {
+        self.errors_total += 1;
+        *self.errors_by_type.entry(e.name).or_default() += 1;
+
+        let Some(q) = icmp::quoted_flow(e.quoted) else {
+            self.unparsed_quotes += 1;
+            return;
+        };
+        let seen =
+            matches!(q.protocol, PROTO_TCP | PROTO_UDP) && self.seen.get_mut(&q.flow).is_some();
+        if seen {
+            self.correlated += 1;
+        }
+        let key = (q.flow, q.protocol);
+        if !self.affected.contains_key(&key) && self.affected.len() >= MAX_AFFECTED_FLOWS {
+            self.affected_truncated += 1;
+            return;
+        }
+        let f = self.affected.entry(key).or_insert_with(|| AffectedFlow {
+            protocol: q.protocol,
+            first_us: ts_us,
+            ..AffectedFlow::default()
+        });
+        f.seen |= seen;
+        f.last_us = ts_us;
+        *f.errors.entry(e.name).or_default() += 1;
+        if f.reporters.len() < MAX_REPORTERS {
+            f.reporters.insert(reporter);
+        }
+        if let Some(mtu) = e.mtu {
+            f.min_mtu = Some(f.min_mtu.map_or(mtu, |m| m.min(mtu)));
+        }
+        if let Some(gw) = e.gateway {
+            f.gateways.insert(gw);
+        }
+    }
+
+    fn on_echo(&mut self, src: IpAddr, dst: IpAddr, request: bool, id: u16, seq: u16, ts: u64) {
+        if request {
+            self.echo_requests += 1;
+            let key = EchoKey { src, dst, id, seq };
+            let tracked = self.pending.len() < MAX_PENDING_ECHOES
+                && (self.echo_pairs.contains_key(&(src, dst))
+                    || self.echo_pairs.len() < MAX_ECHO_PAIRS);
+            if !tracked {
+                self.untracked_echoes += 1;
+                return;
+            }
+            self.echo_pairs.entry((src, dst)).or_default().requests += 1;
+            self.pending.insert(key, ts);
+            return;
+        }
+        self.echo_replies += 1;
+        // La respuesta viaja en sentido contrario a la petición
+        let key = EchoKey {
+            src: dst,
+            dst: src,
+            id,
+            seq,
+        };
+        let Some(sent) = self.pending.remove(&key) else {
+            self.unmatched_replies += 1;
+            return;
+        };
+        let rtt = ts.saturating_sub(sent);
+        self.echo_rtt.add_sample(rtt);
+        if let Some(pair) = self.echo_pairs.get_mut(&(dst, src)) {
+            pair.replies += 1;
+            pair.rtt.add_sample(rtt);
+        }
+    }
+
+    fn affected_json(&self) -> Vec<Value> {
+        let mut out: Vec<(u32, String, Value)> = self
+            .affected
+            .iter()
+            .map(|((flow, _), f)| {
+                let label = flow_label(flow, f.protocol);
+                let mut score = 0;
+                let mut reasons = Vec::new();
+                for (name, count) in &f.errors {
+                    score += error_score(name);
+                    reasons.push(format!("ICMP {name} ({count})"));
+                }
+                if let Some(mtu) = f.min_mtu {
+                    reasons.push(format!("MTU anunciada {mtu}"));
+                }
+                if !f.gateways.is_empty() {
+                    let gws: Vec<String> = f.gateways.iter().map(|g| g.to_string()).collect();
+                    reasons.push(format!("redirigido a {}", gws.join(", ")));
+                }
+                let level = level(score);
+                let value = json!({
+                    "flow": label,
+                    "protocol": protocol_name(f.protocol),
+                    "flow_seen": f.seen,
+                    "score": { "value": score, "level": level },
+                    "reasons": reasons,
+                    "errors": f.errors,
+                    "reporters": f.reporters.iter().map(|r| r.to_string()).collect::<Vec<_>>(),
+                    "min_mtu": f.min_mtu,
+                    "first_ts_us": f.first_us,
+                    "last_ts_us": f.last_us
+                });
+                (score, label, value)
+            })
+            .collect();
+        // Orden estable: score desc, luego flow asc
+        out.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        out.into_iter()
+            .take(MAX_FLOWS_REPORTED)
+            .map(|(_, _, v)| v)
+            .collect()
+    }
+
+    fn echo_json(&self) -> Value {
+        let mut pairs: Vec<(&(IpAddr, IpAddr), &EchoPair)> = self.echo_pairs.iter().collect();
+        pairs.sort_by_key(|(_, p)| std::cmp::Reverse(p.requests));
+        let pairs: Vec<Value> = pairs
+            .into_iter()
+            .take(MAX_FLOWS_REPORTED)
+            .map(|((src, dst), p)| {
+                let lost = p.requests.saturating_sub(p.replies);
+                json!({
+                    "source": src.to_string(),
+                    "destination": dst.to_string(),
+                    "requests": p.requests,
+                    "replies": p.replies,
+                    "lost": lost,
+                    "loss_rate": rate(lost, p.requests),
+                    "rtt_ms": p.rtt.to_json()
+                })
+            })
+            .collect();
+        let lost = self.pending.len() as u64;
+        let tracked = self.echo_requests - self.untracked_echoes;
+        json!({
+            "requests": self.echo_requests,
+            "replies": self.echo_replies,
+            "lost": lost,
+            "loss_rate": rate(lost, tracked),
+            "unmatched_replies": self.unmatched_replies,
+            "untracked_requests": self.untracked_echoes,
+            "rtt_ms": self.echo_rtt.to_json(),
+            "pairs": pairs
+        })
+    }
+}
+
+impl Detector for IcmpDetector {
+    fn name(&self) -> &'static str {
+        "icmp"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        // Solo se necesita saber qué flujos siguen vivos
+        self.seen.evict(ts_micros);
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        let (ports, message) = match &sliced.transport {
+            Some(TransportSlice::Tcp(tcp)) => {
+                (Some((tcp.source_port(), tcp.destination_port())), None)
+            }
+            Some(TransportSlice::Udp(udp)) => {
+                (Some((udp.source_port(), udp.destination_port())), None)
+            }
+            Some(TransportSlice::Icmpv4(m)) => (
+                None,
+                icmp::classify(false, m.type_u8(), m.code_u8(), m.bytes5to8(), m.payload()),
+            ),
+            Some(TransportSlice::Icmpv6(m)) => (
+                None,
+                icmp::classify(true, m.type_u8(), m.code_u8(), m.bytes5to8(), m.payload()),
+            ),
+            None => return,
+        };
+        if let Some((source_port, destination_port)) = ports {
+            let flow = Flow {
+                source_ip: src,
+                source_port,
+                destination_ip: dst,
+                destination_port,
+            };
+            *self.seen.get_or_insert(flow, ts_micros).1 += 1;
+            return;
+        }
+
+        self.messages_total += 1;
+        match message {
+            Some(IcmpMessage::Error(e)) => self.on_error(src, e, ts_micros),
+            Some(IcmpMessage::Echo { request, id, seq }) => {
+                self.on_echo(src, dst, request, id, seq, ts_micros)
+            }
+            None => {}
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        json!({
+            "messages_total": self.messages_total,
+            "errors_total": self.errors_total,
+            "errors_by_type": self.errors_by_type,
+            "correlated_errors": self.correlated,
+            "unparsed_quotes": self.unparsed_quotes,
+            "affected_flows": self.affected_json(),
+            "affected_flows_total": self.affected.len(),
+            "affected_flows_truncated": self.affected_truncated,
+            "echo": self.echo_json()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etherparse::{IcmpEchoHeader, Icmpv4Type, PacketBuilder};
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+    const ROUTER: [u8; 4] = [10, 0, 0, 254];
+
+    fn ip_udp(src: [u8; 4], dst: [u8; 4], sp: u16, dp: u16) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ipv4(src, dst, 64)
+            .udp(sp, dp)
+            .write(&mut out, b"datos")
+            .unwrap();
+        out
+    }
+
+    fn frame(ip: &[u8]) -> Vec<u8> {
+        let mut out = vec![2; 6];
+        out.extend([1; 6]);
+        out.extend([0x08, 0x00]);
+        out.extend_from_slice(ip);
+        out
+    }
+
+    fn icmp(src: [u8; 4], dst: [u8; 4], t: Icmpv4Type, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(src, dst, 64)
+            .icmpv4(t)
+            .write(&mut out, payload)
+            .unwrap();
+        out
+    }
+
+    fn echo(src: [u8; 4], dst: [u8; 4], request: bool, seq: u16) -> Vec<u8> {
+        let h = IcmpEchoHeader { id: 7, seq };
+        let t = if request {
+            Icmpv4Type::EchoRequest(h)
+        } else {
+            Icmpv4Type::EchoReply(h)
+        };
+        icmp(src, dst, t, b"ping")
+    }
+
+    #[test]
+    fn errors_are_correlated_to_the_quoted_flow() {
+        let mut d = IcmpDetector::new();
+        let probe = ip_udp(CLIENT, SERVER, 40000, 161);
+        d.on_packet(&frame(&probe), 0);
+        // Puerto cerrado en el servidor, citado dos veces
+        let unreachable =
+            Icmpv4Type::DestinationUnreachable(etherparse::icmpv4::DestUnreachableHeader::Port);
+        d.on_packet(
+            &icmp(SERVER, CLIENT, unreachable.clone(), &probe[..28]),
+            1_000,
+        );
+        d.on_packet(&icmp(SERVER, CLIENT, unreachable, &probe[..28]), 2_000);
+        // TTL agotado de un flujo que no aparece en la captura
+        let other = ip_udp(CLIENT, [8, 8, 8, 8], 33434, 33434);
+        let ttl =
+            Icmpv4Type::TimeExceeded(etherparse::icmpv4::TimeExceededCode::TtlExceededInTransit);
+        d.on_packet(&icmp(ROUTER, CLIENT, ttl, &other[..28]), 3_000);
+
+        let out = d.finalize();
+        assert_eq!(out["errors_total"], 3);
+        assert_eq!(out["errors_by_type"]["port_unreachable"], 2);
+        assert_eq!(out["correlated_errors"], 2);
+        let flows = out["affected_flows"].as_array().unwrap();
+        assert_eq!(flows.len(), 2);
+        let closed = flows
+            .iter()
+            .find(|f| f["errors"]["port_unreachable"] == 2)
+            .unwrap();
+        assert_eq!(closed["flow"], "10.0.0.1:40000 -> 10.0.0.2:161/UDP");
+        assert_eq!(closed["flow_seen"], true);
+        assert_eq!(closed["reporters"][0], "10.0.0.2");
+        assert_eq!(closed["score"]["value"], 40);
+        let traced = flows.iter().find(|f| f["flow_seen"] == false).unwrap();
+        assert_eq!(traced["errors"]["ttl_exceeded"], 1);
+    }
+
+    #[test]
+    fn echo_latency_and_loss() {
+        let mut d = IcmpDetector::new();
+        d.on_packet(&echo(CLIENT, SERVER, true, 1), 0);
+        d.on_packet(&echo(SERVER, CLIENT, false, 1), 12_000);
+        d.on_packet(&echo(CLIENT, SERVER, true, 2), 1_000_000);
+        d.on_packet(&echo(CLIENT, SERVER, true, 3), 2_000_000);
+        d.on_packet(&echo(SERVER, CLIENT, false, 3), 2_020_000);
+        // Respuesta sin petición capturada
+        d.on_packet(&echo(SERVER, CLIENT, false, 9), 3_000_000);
+
+        let out = d.finalize();
+        let e = &out["echo"];
+        assert_eq!(e["requests"], 3);
+        assert_eq!(e["replies"], 3);
+        assert_eq!(e["lost"], 1);
+        assert_eq!(e["unmatched_replies"], 1);
+        assert_eq!(e["rtt_ms"]["max"], 20.0);
+        let pair = &e["pairs"][0];
+        assert_eq!(pair["source"], "10.0.0.1");
+        assert_eq!(pair["lost"], 1);
+        assert!((pair["loss_rate"].as_f64().unwrap() - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,43 @@
+use serde_json::{json, Value};
+
+/// Muestras de latencia (µs) con tope de memoria, para percentiles por detector
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LatencyStats {
+    samples: Vec<u64>, // µs (cap)
+    count: u64,
+    max_us: u64,
+}
+
+impl LatencyStats {
+    const CAP: usize = 4096;
+
+    pub(crate) fn add_sample(&mut self, us: u64) {
+        self.count = self.count.saturating_add(1);
+        if self.samples.len() < Self::CAP {
+            self.samples.push(us);
+        }
+        self.max_us = self.max_us.max(us);
+    }
+
+    /// Percentil `q` (0.0..=1.0) en ms; 0.0 sin muestras
+    pub(crate) fn percentile_ms(&self, q: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut v = self.samples.clone();
+        v.sort_unstable();
+        let pos = ((v.len() - 1) as f64 * q).round() as usize;
+        v[pos] as f64 / 1000.0
+    }
+
+    /// `{p50, p95, p99, max, samples}` en ms
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "p50": self.percentile_ms(0.50),
+            "p95": self.percentile_ms(0.95),
+            "p99": self.percentile_ms(0.99),
+            "max": self.max_us as f64 / 1000.0,
+            "samples": self.count
+        })
+    }
+}
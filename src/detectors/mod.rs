@@ -1 +1,23 @@
+pub mod beacon;
+pub mod credentials;
+pub mod dhcp;
+pub mod dns;
+pub(crate) mod dns_threats;
+pub(crate) mod finding;
+pub mod hierarchy;
+pub mod http;
+pub mod icmp;
+pub(crate) mod latency;
+pub mod neighbor;
+pub mod pmtu;
+pub mod quic;
+pub mod rtp;
+pub mod scan;
+pub mod sip;
+pub mod synflood;
+pub mod talkers;
 pub mod tcp_health;
+#[cfg(test)]
+mod test_frames;
+pub mod tls;
+pub mod udp;
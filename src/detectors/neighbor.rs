@@ -0,0 +1,998 @@
+use crate::detectors::finding::{level, Finding};
+use crate::engine::Detector;
+use crate::network::arp::{self, format_mac, ArpPacket, OP_REPLY, OP_REQUEST};
+use crate::network::ndp::{self, NdpMessage, RouterAdvert, NDP_HOP_LIMIT};
+use etherparse::{EtherType, InternetSlice, LinkSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+// Tabla IP→MAC y estado auxiliar acotados (el resto solo cuenta en los agregados)
+const MAX_BINDINGS: usize = 100_000;
+const MAX_MACS_PER_IP: usize = 16;
+const MAX_HISTORY: usize = 20;
+const MAX_PENDING: usize = 100_000;
+const MAX_ROUTERS: usize = 256;
+const MAX_IPS_PER_MAC: usize = 16;
+const MAX_REPORTED: usize = 1000;
+// Una petición ARP/NS sin respuesta en este plazo cuenta como no contestada
+const REQUEST_TIMEOUT_US: u64 = 3_000_000;
+// Tras un sondeo DAD, cualquier otro equipo que reclame la IP en este plazo es un conflicto
+const DAD_WINDOW_US: u64 = 2_000_000;
+// Inundación de ARP gratuito: anuncios de una misma MAC por segundo
+const GARP_WINDOW_US: u64 = 1_000_000;
+const GARP_FLOOD_THRESHOLD: u32 = 10;
+// Peticiones sin respuesta hacia un mismo destino antes de reportarlo
+const UNANSWERED_MIN: u64 = 3;
+const UNANSWERED_HIGH: u64 = 20;
+
+/// Configuración del detector de vecinos
+#[derive(Debug, Clone, Default)]
+pub struct NeighborConfig {
+    /// Routers IPv6 autorizados (IP o MAC). Vacío: se toma como legítimo el primero visto
+    pub trusted_routers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MacChange {
+    ts_us: u64,
+    from: [u8; 6],
+    to: [u8; 6],
+}
+
+/// Asociación IP→MAC a lo largo de la captura
+#[derive(Debug)]
+struct Binding {
+    mac: [u8; 6],
+    macs: BTreeSet<[u8; 6]>,
+    changes: u64,
+    // Vuelta a una MAC ya vista: alternancia típica de suplantación o conflicto
+    flaps: u64,
+    history: Vec<MacChange>,
+    source: &'static str,
+    first_us: u64,
+    last_us: u64,
+}
+
+#[derive(Debug, Default)]
+struct GarpStats {
+    total: u64,
+    window_start: u64,
+    window_count: u32,
+    peak_per_window: u32,
+    flood_windows: u64,
+    ips: BTreeSet<IpAddr>,
+}
+
+#[derive(Debug, Default)]
+struct Unanswered {
+    count: u64,
+    requesters: BTreeSet<IpAddr>,
+}
+
+#[derive(Debug)]
+struct DadProbe {
+    ts_us: u64,
+    mac: [u8; 6],
+}
+
+#[derive(Debug)]
+struct DadConflict {
+    prober: [u8; 6],
+    defender: [u8; 6],
+    count: u64,
+    first_us: u64,
+}
+
+#[derive(Debug)]
+struct Router {
+    mac: Option<[u8; 6]>,
+    adverts: u64,
+    last: RouterAdvert,
+    prefixes: BTreeSet<String>,
+    first_us: u64,
+    last_us: u64,
+}
+
+#[derive(Debug, Default)]
+struct ArpCounters {
+    packets: u64,
+    requests: u64,
+    replies: u64,
+    gratuitous: u64,
+    probes: u64,
+    unanswered: u64,
+    malformed: u64,
+}
+
+#[derive(Debug, Default)]
+struct NdpCounters {
+    router_solicitations: u64,
+    router_advertisements: u64,
+    neighbor_solicitations: u64,
+    neighbor_advertisements: u64,
+    dad_probes: u64,
+    unanswered: u64,
+    invalid_hop_limit: u64,
+}
+
+/// Detector ARP / NDP: tabla IP→MAC, cambios de MAC, inundaciones de ARP gratuito,
+/// peticiones sin respuesta, conflictos DAD y Router Advertisements no autorizados
+pub struct NeighborDetector {
+    trusted_routers: BTreeSet<String>,
+    bindings: BTreeMap<IpAddr, Binding>,
+    bindings_truncated: u64,
+    garp: BTreeMap<[u8; 6], GarpStats>,
+    // (solicitante, destino) → instante de la petición
+    pending: HashMap<(IpAddr, IpAddr), u64>,
+    unanswered: BTreeMap<IpAddr, Unanswered>,
+    dad_pending: HashMap<IpAddr, DadProbe>,
+    dad_conflicts: BTreeMap<IpAddr, DadConflict>,
+    routers: BTreeMap<IpAddr, Router>,
+    routers_truncated: u64,
+    bad_hop_limit: BTreeMap<IpAddr, u64>,
+    arp: ArpCounters,
+    ndp: NdpCounters,
+    last_sweep_us: u64,
+    last_ts_us: u64,
+}
+
+impl Default for NeighborDetector {
+    fn default() -> Self {
+        Self::with_config(NeighborConfig::default())
+    }
+}
+
+fn macs_json(macs: &BTreeSet<[u8; 6]>) -> Vec<String> {
+    macs.iter().map(format_mac).collect()
+}
+
+impl NeighborDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: NeighborConfig) -> Self {
+        NeighborDetector {
+            trusted_routers: config
+                .trusted_routers
+                .iter()
+                .map(|r| r.trim())
+                .filter(|r| !r.is_empty())
+                // Forma canónica: así "FE80:0::1" coincide con "fe80::1"
+                .map(|r| match r.parse::<IpAddr>() {
+                    Ok(ip) => ip.to_string(),
+                    Err(_) => r.to_ascii_lowercase(),
+                })
+                .collect(),
+            bindings: BTreeMap::new(),
+            bindings_truncated: 0,
+            garp: BTreeMap::new(),
+            pending: HashMap::new(),
+            unanswered: BTreeMap::new(),
+            dad_pending: HashMap::new(),
+            dad_conflicts: BTreeMap::new(),
+            routers: BTreeMap::new(),
+            routers_truncated: 0,
+            bad_hop_limit: BTreeMap::new(),
+            arp: ArpCounters::default(),
+            ndp: NdpCounters::default(),
+            last_sweep_us: 0,
+            last_ts_us: 0,
+        }
+    }
+
+    /// Registra que `ip` se anuncia con `mac`
+    fn learn(&mut self, ip: IpAddr, mac: [u8; 6], source: &'static str, ts: u64) {
+        if ip.is_unspecified() || mac == [0; 6] || mac == arp::BROADCAST_MAC {
+            return;
+        }
+        self.check_dad(ip, mac, ts);
+        if !self.bindings.contains_key(&ip) && self.bindings.len() >= MAX_BINDINGS {
+            self.bindings_truncated += 1;
+            return;
+        }
+        let b = self.bindings.entry(ip).or_insert_with(|| Binding {
+            mac,
+            macs: BTreeSet::from([mac]),
+            changes: 0,
+            flaps: 0,
+            history: Vec::new(),
+            source,
+            first_us: ts,
+            last_us: ts,
+        });
+        b.last_us = ts;
+        if b.mac == mac {
+            return;
+        }
+        b.changes += 1;
+        if b.macs.contains(&mac) {
+            b.flaps += 1;
+        } else if b.macs.len() < MAX_MACS_PER_IP {
+            b.macs.insert(mac);
+        }
+        if b.history.len() < MAX_HISTORY {
+            b.history.push(MacChange {
+                ts_us: ts,
+                from: b.mac,
+                to: mac,
+            });
+        }
+        b.mac = mac;
+    }
+
+    /// Otro equipo reclama una IP que acaba de sondearse con DAD
+    fn check_dad(&mut self, ip: IpAddr, mac: [u8; 6], ts: u64) {
+        let Some(probe) = self.dad_pending.get(&ip) else {
+            return;
+        };
+        if probe.mac == mac || ts.saturating_sub(probe.ts_us) > DAD_WINDOW_US {
+            return;
+        }
+        let prober = probe.mac;
+        let c = self.dad_conflicts.entry(ip).or_insert(DadConflict {
+            prober,
+            defender: mac,
+            count: 0,
+            first_us: ts,
+        });
+        c.count += 1;
+        self.dad_pending.remove(&ip);
+    }
+
+    fn dad_probe(&mut self, ip: IpAddr, mac: [u8; 6], ts: u64) {
+        if self.dad_pending.len() < MAX_PENDING || self.dad_pending.contains_key(&ip) {
+            self.dad_pending.insert(ip, DadProbe { ts_us: ts, mac });
+        }
+    }
+
+    fn request(&mut self, requester: IpAddr, target: IpAddr, ts: u64) {
+        if self.pending.len() >= MAX_PENDING && !self.pending.contains_key(&(requester, target)) {
+            return;
+        }
+        // Un reintento implica que la petición anterior no obtuvo respuesta
+        if self.pending.insert((requester, target), ts).is_some() {
+            self.count_unanswered(requester, target);
+        }
+    }
+
+    fn answer(&mut self, requester: IpAddr, target: IpAddr) {
+        self.pending.remove(&(requester, target));
+    }
+
+    fn count_unanswered(&mut self, requester: IpAddr, target: IpAddr) {
+        if target.is_ipv4() {
+            self.arp.unanswered += 1;
+        } else {
+            self.ndp.unanswered += 1;
+        }
+        if !self.unanswered.contains_key(&target) && self.unanswered.len() >= MAX_BINDINGS {
+            return;
+        }
+        let u = self.unanswered.entry(target).or_default();
+        u.count += 1;
+        if u.requesters.len() < MAX_IPS_PER_MAC {
+            u.requesters.insert(requester);
+        }
+    }
+
+    fn sweep(&mut self, now: u64) {
+        if now.saturating_sub(self.last_sweep_us) < 1_000_000 {
+            return;
+        }
+        self.last_sweep_us = now;
+        self.expire(now);
+    }
+
+    /// Peticiones caducadas y sondeos DAD cerrados
+    fn expire(&mut self, now: u64) {
+        let mut expired: Vec<(IpAddr, IpAddr)> = self
+            .pending
+            .iter()
+            .filter(|(_, &ts)| now.saturating_sub(ts) >= REQUEST_TIMEOUT_US)
+            .map(|(k, _)| *k)
+            .collect();
+        expired.sort();
+        for (requester, target) in expired {
+            self.pending.remove(&(requester, target));
+            self.count_unanswered(requester, target);
+        }
+        self.dad_pending
+            .retain(|_, p| now.saturating_sub(p.ts_us) <= DAD_WINDOW_US);
+    }
+
+    fn on_garp(&mut self, mac: [u8; 6], ip: IpAddr, ts: u64) {
+        self.arp.gratuitous += 1;
+        if !self.garp.contains_key(&mac) && self.garp.len() >= MAX_BINDINGS {
+            return;
+        }
+        let g = self.garp.entry(mac).or_default();
+        g.total += 1;
+        if g.total == 1 || ts.saturating_sub(g.window_start) >= GARP_WINDOW_US {
+            g.window_start = ts;
+            g.window_count = 0;
+        }
+        g.window_count += 1;
+        g.peak_per_window = g.peak_per_window.max(g.window_count);
+        if g.window_count == GARP_FLOOD_THRESHOLD {
+            g.flood_windows += 1;
+        }
+        if g.ips.len() < MAX_IPS_PER_MAC {
+            g.ips.insert(ip);
+        }
+    }
+
+    fn on_arp(&mut self, a: ArpPacket, ts: u64) {
+        self.arp.packets += 1;
+        let sender = IpAddr::V4(a.sender_ip);
+        let target = IpAddr::V4(a.target_ip);
+        if a.is_probe() {
+            self.arp.probes += 1;
+            self.dad_probe(target, a.sender_mac, ts);
+            return;
+        }
+        match a.op {
+            OP_REQUEST => self.arp.requests += 1,
+            OP_REPLY => self.arp.replies += 1,
+            _ => {
+                self.arp.malformed += 1;
+                return;
+            }
+        }
+        if a.is_gratuitous() {
+            self.on_garp(a.sender_mac, sender, ts);
+        } else if a.op == OP_REQUEST {
+            self.request(sender, target, ts);
+        } else {
+            self.answer(target, sender);
+        }
+        self.learn(sender, a.sender_mac, "arp", ts);
+    }
+
+    fn on_router_advert(&mut self, src: IpAddr, mac: [u8; 6], ra: RouterAdvert, ts: u64) {
+        self.ndp.router_advertisements += 1;
+        if !self.routers.contains_key(&src) && self.routers.len() >= MAX_ROUTERS {
+            self.routers_truncated += 1;
+            return;
+        }
+        let r = self.routers.entry(src).or_insert_with(|| Router {
+            mac: None,
+            adverts: 0,
+            last: ra.clone(),
+            prefixes: BTreeSet::new(),
+            first_us: ts,
+            last_us: ts,
+        });
+        r.adverts += 1;
+        r.last_us = ts;
+        r.mac = Some(ra.source_mac.unwrap_or(mac));
+        for (prefix, len) in &ra.prefixes {
+            r.prefixes.insert(format!("{prefix}/{len}"));
+        }
+        r.last = ra;
+    }
+
+    fn on_ndp(
+        &mut self,
+        src: IpAddr,
+        dst: IpAddr,
+        hop_limit: u8,
+        eth_src: [u8; 6],
+        m: NdpMessage,
+        ts: u64,
+    ) {
+        // Los hosts descartan NDP con hop limit != 255: llegó desde fuera del enlace
+        if hop_limit != NDP_HOP_LIMIT {
+            self.ndp.invalid_hop_limit += 1;
+            if self.bad_hop_limit.contains_key(&src) || self.bad_hop_limit.len() < MAX_ROUTERS {
+                *self.bad_hop_limit.entry(src).or_default() += 1;
+            }
+            return;
+        }
+        match m {
+            NdpMessage::RouterSolicitation { source_mac } => {
+                self.ndp.router_solicitations += 1;
+                if let Some(mac) = source_mac {
+                    self.learn(src, mac, "ndp", ts);
+                }
+            }
+            NdpMessage::RouterAdvertisement(ra) => {
+                self.learn(src, ra.source_mac.unwrap_or(eth_src), "ndp", ts);
+                self.on_router_advert(src, eth_src, ra, ts);
+            }
+            NdpMessage::NeighborSolicitation { target, source_mac } => {
+                self.ndp.neighbor_solicitations += 1;
+                let target = IpAddr::V6(target);
+                if src.is_unspecified() {
+                    self.ndp.dad_probes += 1;
+                    self.dad_probe(target, eth_src, ts);
+                    return;
+                }
+                // Las verificaciones unicast de alcanzabilidad también esperan respuesta
+                self.request(src, target, ts);
+                if let Some(mac) = source_mac {
+                    self.learn(src, mac, "ndp", ts);
+                }
+            }
+            NdpMessage::NeighborAdvertisement {
+                target,
+                target_mac,
+                solicited,
+                ..
+            } => {
+                self.ndp.neighbor_advertisements += 1;
+                let target = IpAddr::V6(target);
+                if solicited {
+                    self.answer(dst, target);
+                }
+                self.learn(target, target_mac.unwrap_or(eth_src), "ndp", ts);
+            }
+        }
+    }
+
+    fn is_trusted(&self, ip: &IpAddr, r: &Router) -> bool {
+        self.trusted_routers.contains(&ip.to_string())
+            || r.mac
+                .is_some_and(|m| self.trusted_routers.contains(&format_mac(&m)))
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        let mut out = Vec::new();
+
+        for (ip, b) in self.bindings.iter().filter(|(_, b)| b.changes > 0) {
+            let macs = macs_json(&b.macs);
+            let (code, mut score, mut reason) = if b.flaps > 0 || b.macs.len() >= 3 {
+                (
+                    "MAC_FLAPPING",
+                    100,
+                    format!(
+                        "la IP alterna entre {} MACs ({} cambios): posible suplantación ARP/NDP o IP duplicada",
+                        b.macs.len(),
+                        b.changes
+                    ),
+                )
+            } else {
+                (
+                    "MAC_CHANGE",
+                    40,
+                    format!("la IP cambió de MAC ({})", macs.join(" -> ")),
+                )
+            };
+            if self.routers.contains_key(ip) {
+                score += 40;
+                reason.push_str("; la IP es un router IPv6");
+            }
+            out.push(Finding {
+                code,
+                subject: ip.to_string(),
+                score,
+                level: level(score),
+                reason,
+                count: b.changes,
+                examples: None,
+                detail: json!({ "macs": macs, "current_mac": format_mac(&b.mac) }),
+            });
+        }
+
+        for (mac, g) in self.garp.iter().filter(|(_, g)| g.flood_windows > 0) {
+            let score = if g.peak_per_window >= GARP_FLOOD_THRESHOLD * 10 {
+                100
+            } else {
+                60
+            };
+            out.push(Finding {
+                code: "GARP_FLOOD",
+                subject: format_mac(mac),
+                score,
+                level: level(score),
+                reason: format!(
+                    "inundación de ARP gratuito (pico {}/s en {} ventanas)",
+                    g.peak_per_window, g.flood_windows
+                ),
+                count: g.total,
+                examples: None,
+                detail: json!({
+                    "peak_per_second": g.peak_per_window,
+                    "flood_windows": g.flood_windows,
+                    "ips": g.ips.iter().map(|ip| ip.to_string()).collect::<Vec<_>>()
+                }),
+            });
+        }
+
+        for (target, u) in self
+            .unanswered
+            .iter()
+            .filter(|(_, u)| u.count >= UNANSWERED_MIN)
+        {
+            let (code, kind) = if target.is_ipv4() {
+                ("UNANSWERED_ARP", "ARP")
+            } else {
+                ("UNANSWERED_NS", "Neighbor Solicitation")
+            };
+            let score = if u.count >= UNANSWERED_HIGH { 40 } else { 20 };
+            out.push(Finding {
+                code,
+                subject: target.to_string(),
+                score,
+                level: level(score),
+                reason: format!(
+                    "peticiones {kind} sin respuesta ({}): host caído o respuesta no visible en la captura",
+                    u.count
+                ),
+                count: u.count,
+                examples: None,
+                detail: json!({
+                    "requesters": u.requesters.iter().map(|ip| ip.to_string()).collect::<Vec<_>>()
+                }),
+            });
+        }
+
+        for (ip, c) in &self.dad_conflicts {
+            out.push(Finding {
+                code: "DAD_CONFLICT",
+                subject: ip.to_string(),
+                score: 100,
+                level: "ALTA",
+                reason: format!(
+                    "dirección duplicada: el sondeo de {} fue contestado por {}",
+                    format_mac(&c.prober),
+                    format_mac(&c.defender)
+                ),
+                count: c.count,
+                examples: None,
+                detail: json!({
+                    "prober_mac": format_mac(&c.prober),
+                    "defender_mac": format_mac(&c.defender),
+                    "first_ts_us": c.first_us
+                }),
+            });
+        }
+
+        // Sin lista de confianza, el primer router visto se toma como legítimo
+        let first_router = self
+            .routers
+            .iter()
+            .min_by_key(|(ip, r)| (r.first_us, **ip))
+            .map(|(ip, _)| *ip);
+        for (ip, r) in &self.routers {
+            let (mut score, mut reason) = if !self.trusted_routers.is_empty() {
+                if self.is_trusted(ip, r) {
+                    continue;
+                }
+                (
+                    100,
+                    "Router Advertisement de un router no autorizado".to_string(),
+                )
+            } else if Some(*ip) != first_router {
+                (
+                    60,
+                    format!(
+                        "router IPv6 adicional (el primero visto fue {})",
+                        first_router.map(|f| f.to_string()).unwrap_or_default()
+                    ),
+                )
+            } else {
+                continue;
+            };
+            if r.last.preference == "high" {
+                score += 20;
+                reason.push_str("; anuncia preferencia alta");
+            }
+            out.push(Finding {
+                code: "ROGUE_RA",
+                subject: ip.to_string(),
+                score,
+                level: level(score),
+                reason,
+                count: r.adverts,
+                examples: None,
+                detail: json!({
+                    "mac": r.mac.as_ref().map(format_mac),
+                    "prefixes": r.prefixes,
+                    "router_lifetime_s": r.last.router_lifetime_s
+                }),
+            });
+        }
+
+        for (src, count) in &self.bad_hop_limit {
+            out.push(Finding {
+                code: "NDP_BAD_HOP_LIMIT",
+                subject: src.to_string(),
+                score: 40,
+                level: "MEDIA",
+                reason: format!(
+                    "mensajes NDP con hop limit distinto de 255 ({count}): origen fuera del enlace"
+                ),
+                count: *count,
+                examples: None,
+                detail: json!({}),
+            });
+        }
+
+        out.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.code.cmp(b.code))
+                .then_with(|| a.subject.cmp(&b.subject))
+        });
+        out
+    }
+
+    fn conflicting_bindings_json(&self) -> Vec<Value> {
+        let mut changed: Vec<(&IpAddr, &Binding)> = self
+            .bindings
+            .iter()
+            .filter(|(_, b)| b.changes > 0)
+            .collect();
+        changed.sort_by_key(|(_, b)| std::cmp::Reverse(b.changes));
+        changed
+            .into_iter()
+            .take(MAX_REPORTED)
+            .map(|(ip, b)| {
+                json!({
+                    "ip": ip.to_string(),
+                    "mac": format_mac(&b.mac),
+                    "macs": macs_json(&b.macs),
+                    "changes": b.changes,
+                    "source": b.source,
+                    "first_ts_us": b.first_us,
+                    "last_ts_us": b.last_us,
+                    "history": b.history.iter().map(|c| json!({
+                        "ts_us": c.ts_us,
+                        "from": format_mac(&c.from),
+                        "to": format_mac(&c.to)
+                    })).collect::<Vec<_>>()
+                })
+            })
+            .collect()
+    }
+
+    fn routers_json(&self) -> Vec<Value> {
+        self.routers
+            .iter()
+            .map(|(ip, r)| {
+                json!({
+                    "ip": ip.to_string(),
+                    "mac": r.mac.as_ref().map(format_mac),
+                    "advertisements": r.adverts,
+                    "router_lifetime_s": r.last.router_lifetime_s,
+                    "preference": r.last.preference,
+                    "managed": r.last.managed,
+                    "other_config": r.last.other_config,
+                    "prefixes": r.prefixes,
+                    "mtu": r.last.mtu,
+                    "trusted": self.is_trusted(ip, r),
+                    "first_ts_us": r.first_us,
+                    "last_ts_us": r.last_us
+                })
+            })
+            .collect()
+    }
+}
+
+impl Detector for NeighborDetector {
+    fn name(&self) -> &'static str {
+        "neighbor"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        self.last_ts_us = self.last_ts_us.max(ts_micros);
+        self.sweep(ts_micros);
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let eth_src = match &sliced.link {
+            Some(LinkSlice::Ethernet2(eth)) => eth.source(),
+            _ => return,
+        };
+        let ip = match &sliced.net {
+            Some(InternetSlice::Ipv6(ip)) => ip,
+            Some(InternetSlice::Ipv4(_)) => return,
+            None => {
+                // etherparse no interpreta ARP: se lee el payload Ethernet/VLAN
+                let payload = match (&sliced.vlan, &sliced.link) {
+                    (Some(vlan), _) => vlan.payload(),
+                    (None, Some(link)) => link.payload(),
+                    (None, None) => return,
+                };
+                if payload.ether_type == EtherType::ARP {
+                    match arp::parse(payload.payload) {
+                        Some(a) => self.on_arp(a, ts_micros),
+                        None => self.arp.malformed += 1,
+                    }
+                }
+                return;
+            }
+        };
+        let Some(TransportSlice::Icmpv6(m)) = &sliced.transport else {
+            return;
+        };
+        let Some(msg) = ndp::parse(m.type_u8(), m.bytes5to8(), m.payload()) else {
+            return;
+        };
+        let src = IpAddr::V6(ip.header().source_addr());
+        let dst = IpAddr::V6(ip.header().destination_addr());
+        let hop_limit = ip.header().hop_limit();
+        self.on_ndp(src, dst, hop_limit, eth_src, msg, ts_micros);
+    }
+
+    fn finalize(&mut self) -> Value {
+        // Lo que queda pendiente y ya caducó respecto al último paquete visto
+        self.expire(self.last_ts_us);
+        let pending_at_end = self.pending.len();
+
+        let findings = self.findings();
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for f in &findings {
+            *counts.entry(f.code).or_default() += 1;
+        }
+        json!({
+            "arp": {
+                "packets": self.arp.packets,
+                "requests": self.arp.requests,
+                "replies": self.arp.replies,
+                "gratuitous": self.arp.gratuitous,
+                "probes": self.arp.probes,
+                "unanswered": self.arp.unanswered,
+                "malformed": self.arp.malformed
+            },
+            "ndp": {
+                "router_solicitations": self.ndp.router_solicitations,
+                "router_advertisements": self.ndp.router_advertisements,
+                "neighbor_solicitations": self.ndp.neighbor_solicitations,
+                "neighbor_advertisements": self.ndp.neighbor_advertisements,
+                "dad_probes": self.ndp.dad_probes,
+                "unanswered": self.ndp.unanswered,
+                "invalid_hop_limit": self.ndp.invalid_hop_limit
+            },
+            "pending_at_end": pending_at_end,
+            "bindings_total": self.bindings.len(),
+            "bindings_truncated": self.bindings_truncated,
+            "conflicting_bindings": self.conflicting_bindings_json(),
+            "routers": self.routers_json(),
+            "routers_truncated": self.routers_truncated,
+            "dad_conflicts": self.dad_conflicts.len(),
+            "findings": findings.iter().take(MAX_REPORTED).map(Finding::to_json).collect::<Vec<_>>(),
+            "counts_by_code": counts
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::arp::tests::arp_body;
+    use crate::network::ndp::tests::{neighbor_body, router_advert_body};
+    use crate::network::ndp::{
+        ICMPV6_NEIGHBOR_ADVERTISEMENT, ICMPV6_NEIGHBOR_SOLICITATION, ICMPV6_ROUTER_ADVERTISEMENT,
+    };
+    use etherparse::{Icmpv6Type, PacketBuilder};
+    use std::net::Ipv6Addr;
+
+    const GATEWAY_MAC: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const ATTACKER_MAC: [u8; 6] = [0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb];
+    const HOST_MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x01];
+
+    fn arp_frame(src_mac: [u8; 6], body: Vec<u8>) -> Vec<u8> {
+        let mut out = arp::BROADCAST_MAC.to_vec();
+        out.extend(src_mac);
+        out.extend([0x08, 0x06]);
+        out.extend(body);
+        out
+    }
+
+    fn ndp_frame(
+        src_mac: [u8; 6],
+        src: Ipv6Addr,
+        hop_limit: u8,
+        t: u8,
+        b5to8: [u8; 4],
+        body: &[u8],
+    ) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2(src_mac, [0x33, 0x33, 0, 0, 0, 1])
+            .ipv6(
+                src.octets(),
+                "ff02::1".parse::<Ipv6Addr>().unwrap().octets(),
+                hop_limit,
+            )
+            .icmpv6(Icmpv6Type::Unknown {
+                type_u8: t,
+                code_u8: 0,
+                bytes5to8: b5to8,
+            })
+            .write(&mut out, body)
+            .unwrap();
+        out
+    }
+
+    fn codes(report: &Value) -> Vec<(String, String, String)> {
+        report["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| {
+                (
+                    f["code"].as_str().unwrap().to_string(),
+                    f["subject"].as_str().unwrap().to_string(),
+                    f["score"]["level"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn arp_spoofing_flood_unanswered_and_dad_conflict() {
+        let mut d = NeighborDetector::new();
+        let gw = [10, 0, 0, 1];
+        let host = [10, 0, 0, 20];
+        let mut ts = 1_000_000;
+        // Petición/respuesta normal: el host aprende la MAC del gateway
+        d.on_packet(
+            &arp_frame(HOST_MAC, arp_body(OP_REQUEST, HOST_MAC, host, [0; 6], gw)),
+            ts,
+        );
+        d.on_packet(
+            &arp_frame(
+                GATEWAY_MAC,
+                arp_body(OP_REPLY, GATEWAY_MAC, gw, HOST_MAC, host),
+            ),
+            ts + 500,
+        );
+        // El atacante inunda con ARP gratuito suplantando al gateway y este se defiende
+        for i in 0..12 {
+            ts += 10_000;
+            d.on_packet(
+                &arp_frame(
+                    ATTACKER_MAC,
+                    arp_body(OP_REPLY, ATTACKER_MAC, gw, arp::BROADCAST_MAC, gw),
+                ),
+                ts,
+            );
+            if i == 5 {
+                d.on_packet(
+                    &arp_frame(
+                        GATEWAY_MAC,
+                        arp_body(OP_REPLY, GATEWAY_MAC, gw, HOST_MAC, host),
+                    ),
+                    ts + 1,
+                );
+            }
+        }
+        // Peticiones a un host apagado: tres reintentos sin respuesta
+        for _ in 0..4 {
+            ts += 1_000_000;
+            d.on_packet(
+                &arp_frame(
+                    HOST_MAC,
+                    arp_body(OP_REQUEST, HOST_MAC, host, [0; 6], [10, 0, 0, 99]),
+                ),
+                ts,
+            );
+        }
+        // Sondeo DAD para 10.0.0.50 contestado por otro equipo
+        ts += 1_000_000;
+        d.on_packet(
+            &arp_frame(
+                HOST_MAC,
+                arp_body(OP_REQUEST, HOST_MAC, [0; 4], [0; 6], [10, 0, 0, 50]),
+            ),
+            ts,
+        );
+        d.on_packet(
+            &arp_frame(
+                ATTACKER_MAC,
+                arp_body(OP_REPLY, ATTACKER_MAC, [10, 0, 0, 50], HOST_MAC, [0; 4]),
+            ),
+            ts + 100,
+        );
+        ts += 10_000_000;
+        d.on_packet(
+            &arp_frame(HOST_MAC, arp_body(OP_REQUEST, HOST_MAC, host, [0; 6], gw)),
+            ts,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["arp"]["gratuitous"], 12);
+        assert_eq!(r["arp"]["probes"], 1);
+        assert_eq!(r["arp"]["unanswered"], 4);
+        let found = codes(&r);
+        let has = |code: &str, subject: &str, lvl: &str| {
+            found.contains(&(code.to_string(), subject.to_string(), lvl.to_string()))
+        };
+        assert!(has("MAC_FLAPPING", "10.0.0.1", "ALTA"), "{found:?}");
+        assert!(has("GARP_FLOOD", "66:77:88:99:aa:bb", "MEDIA"), "{found:?}");
+        assert!(has("UNANSWERED_ARP", "10.0.0.99", "BAJA"), "{found:?}");
+        assert!(has("DAD_CONFLICT", "10.0.0.50", "ALTA"), "{found:?}");
+        let binding = &r["conflicting_bindings"][0];
+        assert_eq!(binding["ip"], "10.0.0.1");
+        assert_eq!(binding["macs"].as_array().unwrap().len(), 2);
+        // El gateway contestó al host: su petición no cuenta como perdida
+        assert!(!has("UNANSWERED_ARP", "10.0.0.1", "BAJA"));
+    }
+
+    #[test]
+    fn ndp_rogue_router_advertisement_and_duplicate_address() {
+        let router: Ipv6Addr = "fe80::1".parse().unwrap();
+        let rogue: Ipv6Addr = "fe80::bad".parse().unwrap();
+        let prefix: Ipv6Addr = "2001:db8::".parse().unwrap();
+        let mut d = NeighborDetector::with_config(NeighborConfig {
+            trusted_routers: vec!["FE80:0::1".to_string()],
+        });
+        let ra = |mac, src, hop, ts, d: &mut NeighborDetector| {
+            d.on_packet(
+                &ndp_frame(
+                    mac,
+                    src,
+                    hop,
+                    ICMPV6_ROUTER_ADVERTISEMENT,
+                    [64, 0x08, 0x07, 0x08],
+                    &router_advert_body(mac, prefix),
+                ),
+                ts,
+            )
+        };
+        ra(GATEWAY_MAC, router, 255, 1_000_000, &mut d);
+        ra(ATTACKER_MAC, rogue, 255, 2_000_000, &mut d);
+        // Hop limit 64: no puede venir del propio enlace, se descarta
+        ra(ATTACKER_MAC, rogue, 64, 2_100_000, &mut d);
+
+        // DAD: NS desde :: y NA de otro equipo reclamando la misma dirección
+        let addr: Ipv6Addr = "2001:db8::20".parse().unwrap();
+        d.on_packet(
+            &ndp_frame(
+                HOST_MAC,
+                Ipv6Addr::UNSPECIFIED,
+                255,
+                ICMPV6_NEIGHBOR_SOLICITATION,
+                [0; 4],
+                &neighbor_body(addr, 1, None),
+            ),
+            3_000_000,
+        );
+        d.on_packet(
+            &ndp_frame(
+                ATTACKER_MAC,
+                addr,
+                255,
+                ICMPV6_NEIGHBOR_ADVERTISEMENT,
+                [0x20, 0, 0, 0],
+                &neighbor_body(addr, 2, Some(ATTACKER_MAC)),
+            ),
+            3_200_000,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["ndp"]["router_advertisements"], 2);
+        assert_eq!(r["ndp"]["invalid_hop_limit"], 1);
+        assert_eq!(r["ndp"]["dad_probes"], 1);
+        assert_eq!(r["routers"].as_array().unwrap().len(), 2);
+        assert_eq!(r["routers"][0]["trusted"], true);
+        assert_eq!(r["routers"][0]["prefixes"][0], "2001:db8::/64");
+        assert_eq!(r["routers"][0]["preference"], "high");
+        let found = codes(&r);
+        assert_eq!(
+            found[0],
+            (
+                "ROGUE_RA".to_string(),
+                "fe80::bad".to_string(),
+                "ALTA".to_string()
+            )
+        );
+        assert!(found.contains(&(
+            "DAD_CONFLICT".to_string(),
+            addr.to_string(),
+            "ALTA".to_string()
+        )));
+        assert!(found.contains(&(
+            "NDP_BAD_HOP_LIMIT".to_string(),
+            "fe80::bad".to_string(),
+            "MEDIA".to_string()
+        )));
+        assert!(!found
+            .iter()
+            .any(|(c, s, _)| c == "ROGUE_RA" && s == "fe80::1"));
+    }
+}
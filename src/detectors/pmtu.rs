@@ -0,0 +1,800 @@
+use crate::detectors::tcp_health::{seq_lte, syn_mss};
+use crate::engine::Detector;
+use crate::network::flow::{Flow, PROTO_TCP, PROTO_UDP};
+use crate::network::flow_table::{FlowArchive, FlowTable, FlowTableConfig};
+use crate::network::format::flow_label;
+use crate::network::icmp;
+use etherparse::icmpv4::DestUnreachableHeader;
+use etherparse::{Icmpv4Type, Icmpv6Type, InternetSlice, SlicedPacket, TcpSlice, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+
+// Tiempo máximo de reensamblado (ipfrag_time de Linux)
+const FRAG_TIMEOUT_US: u64 = 30 * 1_000_000;
+const FRAG_MAX_GROUPS: usize = 10_000;
+// Fragmentos por datagrama: 64 KiB en fragmentos de 1280 B son ~50; más es anómalo
+const FRAG_MAX_PER_GROUP: u32 = 256;
+// Segmento "grande": candidato a superar la MTU de un túnel/VPN
+const LARGE_SEGMENT_BYTES: usize = 1200;
+const LARGE_TRACKED: usize = 64;
+// Retransmisiones de segmentos grandes sin ningún ACK para sospechar agujero negro
+const BLACK_HOLE_MIN_RETX: u32 = 3;
+// Resúmenes de flujos expulsados con hallazgo que se conservan
+const MAX_ARCHIVED: usize = 50_000;
+
+#[derive(Default, Debug)]
+struct PmtuStream {
+    packets: u32,
+    df_packets: u32,
+    syn_mss: Option<u16>,
+    max_payload: u32,
+    oversize_segments: u32, // payload > MSS anunciado por el receptor
+
+    // Segmentos grandes (≥ LARGE_SEGMENT_BYTES)
+    large_segments: u32,
+    large_retransmissions: u32,
+    large_acked: u32,
+    recent_large: VecDeque<u32>, // seq de segmentos grandes recientes
+    outstanding_large: VecDeque<u32>, // seq_end de segmentos grandes sin ACK
+
+    // ACKs del otro lado sobre los datos de este sentido
+    highest_ack: Option<u32>,
+    ack_advances: u32,
+
+    // ICMP "fragmentation needed" / Packet-Too-Big que citan este sentido
+    ptb_received: u32,
+    ptb_min_mtu: Option<u32>,
+}
+
+#[derive(Default, Debug)]
+struct PmtuFlowState {
+    flow: Flow,
+    protocol: u8,
+    c2s: PmtuStream,
+    s2c: PmtuStream,
+    fragments: u32,
+    datagrams_reassembled: u32,
+    datagrams_incomplete: u32,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct FragKey {
+    src: IpAddr,
+    dst: IpAddr,
+    protocol: u8,
+    id: u32,
+}
+
+#[derive(Debug)]
+struct FragGroup {
+    first_ts_us: u64,
+    fragments: u32,
+    ranges: Vec<(u32, u32)>, // [inicio, fin) en bytes, ordenados y fusionados
+    total_len: Option<u32>,  // conocido al ver el último fragmento (MF=0)
+    overlapping: bool,
+    flow: Option<Flow>, // puertos del primer fragmento
+}
+
+impl FragGroup {
+    fn add(&mut self, offset: u32, len: u32, more_fragments: bool) {
+        let end = offset + len;
+        // Se fusiona con los intervalos que solapa o con los que es contiguo
+        let first = self.ranges.partition_point(|&(_, e)| e < offset);
+        let mut last = first;
+        let (mut start, mut stop) = (offset, end);
+        while let Some(&(s, e)) = self.ranges.get(last).filter(|(s, _)| *s <= end) {
+            if offset < e && s < end {
+                self.overlapping = true;
+            }
+            start = start.min(s);
+            stop = stop.max(e);
+            last += 1;
+        }
+        self.ranges.splice(first..last, [(start, stop)]);
+        self.fragments += 1;
+        if !more_fragments {
+            self.total_len = Some(end);
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        let Some(total) = self.total_len else {
+            return false;
+        };
+        matches!(self.ranges[..], [(0, e)] if e >= total)
+    }
+}
+
+#[derive(Debug, Default)]
+struct FragStats {
+    ipv4_fragments: u64,
+    ipv6_fragments: u64,
+    datagrams: u64,
+    reassembled: u64,
+    incomplete: u64,
+    overlapping: u64,
+    over_limit: u64,
+}
+
+#[derive(Debug, Default)]
+struct PtbStats {
+    total: u64,
+    correlated: u64,
+    min_mtu: Option<u32>,
+}
+
+/// Flujo finalizado con algún hallazgo de PMTU/fragmentación
+struct FlowSummary {
+    flow: String,
+    score: u32,
+    fragments: u32,
+    black_hole: bool,
+    json: Value,
+}
+
+/// Diagnóstico de Path MTU y fragmentación IP por conversación
+pub struct PmtuDetector {
+    flows: FlowTable<PmtuFlowState>,
+    archived: FlowArchive<FlowSummary>,
+    black_holes: u64, // también los de resúmenes podados
+    frag_groups: HashMap<FragKey, FragGroup>,
+    last_frag_sweep_us: u64,
+    last_ts_us: u64,
+    frag_stats: FragStats,
+    ptb_stats: PtbStats,
+}
+
+impl Default for PmtuDetector {
+    fn default() -> Self {
+        Self::with_flow_table(FlowTableConfig::default())
+    }
+}
+
+impl PmtuDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_flow_table(config: FlowTableConfig) -> Self {
+        PmtuDetector {
+            flows: FlowTable::new(config),
+            // Poda: mitad superior por severidad y mitad superior por fragmentos
+            archived: FlowArchive::new(MAX_ARCHIVED, cmp_by_severity, cmp_by_fragments),
+            black_holes: 0,
+            frag_groups: HashMap::new(),
+            last_frag_sweep_us: 0,
+            last_ts_us: 0,
+            frag_stats: FragStats::default(),
+            ptb_stats: PtbStats::default(),
+        }
+    }
+
+    fn on_tcp(&mut self, src: IpAddr, dst: IpAddr, df: bool, tcp: &TcpSlice, ts_us: u64) {
+        let flow = Flow {
+            source_ip: src,
+            source_port: tcp.source_port(),
+            destination_ip: dst,
+            destination_port: tcp.destination_port(),
+        };
+        let (key, st, forward) = self.flows.get_or_insert(flow, ts_us);
+        st.flow = key;
+        st.protocol = PROTO_TCP;
+        let (fwd, rev) = if forward {
+            (&mut st.c2s, &mut st.s2c)
+        } else {
+            (&mut st.s2c, &mut st.c2s)
+        };
+
+        fwd.packets += 1;
+        if df {
+            fwd.df_packets += 1;
+        }
+        if tcp.syn() {
+            if let Some(mss) = syn_mss(tcp) {
+                fwd.syn_mss = Some(mss);
+            }
+        }
+
+        let payload_len = tcp.payload().len();
+        let seq = tcp.sequence_number();
+        fwd.max_payload = fwd.max_payload.max(payload_len as u32);
+        // El límite lo fija el MSS que anunció el receptor en su SYN
+        if rev.syn_mss.is_some_and(|mss| payload_len > mss as usize) {
+            fwd.oversize_segments += 1;
+        }
+
+        if payload_len >= LARGE_SEGMENT_BYTES && !tcp.syn() && !tcp.rst() {
+            if fwd.recent_large.contains(&seq) {
+                fwd.large_retransmissions += 1;
+            } else {
+                fwd.large_segments += 1;
+                if fwd.recent_large.len() >= LARGE_TRACKED {
+                    fwd.recent_large.pop_front();
+                }
+                fwd.recent_large.push_back(seq);
+                if fwd.outstanding_large.len() >= LARGE_TRACKED {
+                    fwd.outstanding_large.pop_front();
+                }
+                fwd.outstanding_large
+                    .push_back(seq.wrapping_add(payload_len as u32));
+            }
+        }
+
+        if tcp.ack() && !tcp.rst() {
+            Self::on_ack(rev, tcp.acknowledgment_number());
+        }
+    }
+
+    /// ACK recibido sobre los datos de `stream`
+    fn on_ack(stream: &mut PmtuStream, ack: u32) {
+        let advanced = match stream.highest_ack {
+            Some(prev) => prev != ack && seq_lte(prev, ack),
+            None => true,
+        };
+        if advanced {
+            stream.highest_ack = Some(ack);
+            stream.ack_advances += 1;
+        }
+        while let Some(&end) = stream.outstanding_large.front() {
+            if seq_lte(end, ack) {
+                stream.outstanding_large.pop_front();
+                stream.large_acked += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// ICMP "fragmentation needed" (v4) o Packet-Too-Big (v6)
+    fn on_packet_too_big(&mut self, quoted: &[u8], mtu: u32) {
+        self.ptb_stats.total += 1;
+        self.ptb_stats.min_mtu = Some(self.ptb_stats.min_mtu.map_or(mtu, |m| m.min(mtu)));
+
+        let Some(q) = icmp::quoted_flow(quoted) else {
+            return;
+        };
+        // El paquete citado lo envió el host que recibe el error
+        if let Some((_, st, forward)) = self.flows.get_mut(&q.flow) {
+            let stream = if forward { &mut st.c2s } else { &mut st.s2c };
+            stream.ptb_received += 1;
+            stream.ptb_min_mtu = Some(stream.ptb_min_mtu.map_or(mtu, |m| m.min(mtu)));
+            self.ptb_stats.correlated += 1;
+        }
+    }
+
+    fn on_fragment(
+        &mut self,
+        key: FragKey,
+        offset: u32,
+        payload: &[u8],
+        more_fragments: bool,
+        ipv6: bool,
+        ts_us: u64,
+    ) {
+        if ipv6 {
+            self.frag_stats.ipv6_fragments += 1;
+        } else {
+            self.frag_stats.ipv4_fragments += 1;
+        }
+
+        let group = self.frag_groups.entry(key).or_insert_with(|| FragGroup {
+            first_ts_us: ts_us,
+            fragments: 0,
+            ranges: Vec::new(),
+            total_len: None,
+            overlapping: false,
+            flow: None,
+        });
+        group.add(offset, payload.len() as u32, more_fragments);
+
+        // El primer fragmento lleva los puertos de transporte
+        if offset == 0 && matches!(key.protocol, PROTO_TCP | PROTO_UDP) && payload.len() >= 4 {
+            group.flow = Some(Flow {
+                source_ip: key.src,
+                source_port: u16::from_be_bytes([payload[0], payload[1]]),
+                destination_ip: key.dst,
+                destination_port: u16::from_be_bytes([payload[2], payload[3]]),
+            });
+        }
+
+        if group.is_complete() {
+            let group = self.frag_groups.remove(&key).expect("grupo existente");
+            self.close_group(key, group, true, ts_us);
+        } else if group.fragments >= FRAG_MAX_PER_GROUP {
+            // Demasiados fragmentos para un datagrama: se da por perdido
+            let group = self.frag_groups.remove(&key).expect("grupo existente");
+            self.frag_stats.over_limit += 1;
+            self.close_group(key, group, false, ts_us);
+        }
+    }
+
+    /// Atribuye un datagrama (reensamblado o no) a su flujo
+    fn close_group(&mut self, key: FragKey, group: FragGroup, complete: bool, ts_us: u64) {
+        self.frag_stats.datagrams += 1;
+        if complete {
+            self.frag_stats.reassembled += 1;
+        } else {
+            self.frag_stats.incomplete += 1;
+        }
+        if group.overlapping {
+            self.frag_stats.overlapping += 1;
+        }
+
+        // Sin primer fragmento no hay puertos: se agrupa por par de hosts
+        let flow = group.flow.unwrap_or(Flow {
+            source_ip: key.src,
+            source_port: 0,
+            destination_ip: key.dst,
+            destination_port: 0,
+        });
+        let (k, st, _) = self.flows.get_or_insert(flow, ts_us);
+        st.flow = k;
+        st.protocol = key.protocol;
+        st.fragments += group.fragments;
+        if complete {
+            st.datagrams_reassembled += 1;
+        } else {
+            st.datagrams_incomplete += 1;
+        }
+    }
+
+    /// Cierra como incompletos los grupos que superan el tiempo de reensamblado
+    fn expire_fragments(&mut self, now_us: u64, force: bool) {
+        let over_cap = self.frag_groups.len() > FRAG_MAX_GROUPS;
+        if !force && !over_cap && now_us.saturating_sub(self.last_frag_sweep_us) < 1_000_000 {
+            return;
+        }
+        self.last_frag_sweep_us = now_us;
+
+        let mut expired: Vec<(FragKey, u64)> = self
+            .frag_groups
+            .iter()
+            .filter(|(_, g)| force || now_us.saturating_sub(g.first_ts_us) > FRAG_TIMEOUT_US)
+            .map(|(k, g)| (*k, g.first_ts_us))
+            .collect();
+
+        if over_cap && expired.is_empty() {
+            // Demasiados datagramas a medias: se cierran los más antiguos
+            let mut by_age: Vec<(FragKey, u64)> = self
+                .frag_groups
+                .iter()
+                .map(|(k, g)| (*k, g.first_ts_us))
+                .collect();
+            by_age.sort_unstable_by_key(|(_, ts)| *ts);
+            by_age.truncate(FRAG_MAX_GROUPS / 10);
+            expired = by_age;
+        }
+
+        // Orden determinista
+        expired.sort_unstable_by(|a, b| {
+            a.1.cmp(&b.1)
+                .then_with(|| frag_key_str(&a.0).cmp(&frag_key_str(&b.0)))
+        });
+        for (k, _) in expired {
+            if let Some(g) = self.frag_groups.remove(&k) {
+                self.close_group(k, g, false, now_us);
+            }
+        }
+    }
+
+    fn archive(&mut self, evicted: Vec<(Flow, PmtuFlowState)>) {
+        let summaries: Vec<FlowSummary> =
+            evicted.iter().filter_map(|(_, st)| summarize(st)).collect();
+        self.black_holes += summaries.iter().filter(|f| f.black_hole).count() as u64;
+        self.archived.extend(summaries);
+    }
+}
+
+fn frag_key_str(k: &FragKey) -> String {
+    format!("{}>{}/{}#{}", k.src, k.dst, k.protocol, k.id)
+}
+
+/// Agujero negro PMTUD: los segmentos grandes se retransmiten sin ser confirmados
+/// mientras el otro lado sí confirma datos (handshake o segmentos pequeños).
+fn black_hole_suspected(s: &PmtuStream) -> bool {
+    s.large_retransmissions >= BLACK_HOLE_MIN_RETX && s.large_acked == 0 && s.ack_advances >= 1
+}
+
+fn compute_severity(st: &PmtuFlowState) -> (u32, &'static str, Vec<String>) {
+    let mut reasons = Vec::<String>::new();
+    let mut score = 0u32;
+    let mut level = "BAJA";
+
+    for s in [&st.c2s, &st.s2c] {
+        if black_hole_suspected(s) {
+            score += 100;
+            level = "ALTA";
+            if s.ptb_received == 0 {
+                reasons.push(format!(
+                    "posible agujero negro PMTUD: segmentos ≥{LARGE_SEGMENT_BYTES} B retransmitidos sin ACK y sin ICMP Packet-Too-Big ({})",
+                    s.large_retransmissions
+                ));
+            } else {
+                reasons.push(format!(
+                    "segmentos grandes siguen retransmitiéndose pese a ICMP Packet-Too-Big (MTU {}) ({})",
+                    s.ptb_min_mtu.unwrap_or(0),
+                    s.large_retransmissions
+                ));
+            }
+        }
+    }
+
+    let ptb = st.c2s.ptb_received + st.s2c.ptb_received;
+    if ptb >= 1 {
+        score += 20;
+        if level == "BAJA" {
+            level = "MEDIA";
+        }
+        let mtu = [st.c2s.ptb_min_mtu, st.s2c.ptb_min_mtu]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(0);
+        reasons.push(format!(
+            "ICMP Packet-Too-Big recibido, MTU mínima {mtu} ({ptb})"
+        ));
+    }
+
+    if st.datagrams_incomplete >= 1 {
+        score += 30;
+        if level == "BAJA" {
+            level = "MEDIA";
+        }
+        reasons.push(format!(
+            "reensamblado fallido ({} datagramas incompletos)",
+            st.datagrams_incomplete
+        ));
+    }
+
+    let oversize = st.c2s.oversize_segments + st.s2c.oversize_segments;
+    if oversize >= 1 {
+        score += 10;
+        reasons.push(format!(
+            "segmentos mayores que el MSS negociado, posible TSO/GRO en el host de captura ({oversize})"
+        ));
+    }
+
+    if st.fragments >= 1 {
+        score += 5;
+        reasons.push(format!("flujo fragmentado ({} fragmentos)", st.fragments));
+    }
+
+    (score, level, reasons)
+}
+
+fn stream_json(s: &PmtuStream) -> Value {
+    json!({
+        "packets": s.packets,
+        "df_packets": s.df_packets,
+        "syn_mss": s.syn_mss,
+        "max_payload": s.max_payload,
+        "oversize_segments": s.oversize_segments,
+        "large_segments": s.large_segments,
+        "large_retransmissions": s.large_retransmissions,
+        "large_acked": s.large_acked,
+        "icmp_ptb": { "count": s.ptb_received, "min_mtu": s.ptb_min_mtu },
+        "black_hole_suspected": black_hole_suspected(s)
+    })
+}
+
+/// Solo se reportan los flujos con algún hallazgo de PMTU/fragmentación
+fn summarize(st: &PmtuFlowState) -> Option<FlowSummary> {
+    let (score, level, reasons) = compute_severity(st);
+    if reasons.is_empty() {
+        return None;
+    }
+    let proto = match st.protocol {
+        PROTO_TCP => "TCP".to_string(),
+        PROTO_UDP => "UDP".to_string(),
+        p => format!("IP{p}"),
+    };
+    let flow = flow_label(&st.flow, &proto);
+    let json = json!({
+        "flow": flow,
+        "score": { "value": score, "level": level },
+        "reasons": reasons,
+        "fragments": st.fragments,
+        "datagrams": {
+            "reassembled": st.datagrams_reassembled,
+            "incomplete": st.datagrams_incomplete
+        },
+        "c2s": stream_json(&st.c2s),
+        "s2c": stream_json(&st.s2c)
+    });
+    Some(FlowSummary {
+        flow,
+        score,
+        fragments: st.fragments,
+        black_hole: black_hole_suspected(&st.c2s) || black_hole_suspected(&st.s2c),
+        json,
+    })
+}
+
+/// Orden estable: score desc, luego flow asc
+fn cmp_by_severity(a: &FlowSummary, b: &FlowSummary) -> std::cmp::Ordering {
+    b.score.cmp(&a.score).then_with(|| a.flow.cmp(&b.flow))
+}
+
+fn cmp_by_fragments(a: &FlowSummary, b: &FlowSummary) -> std::cmp::Ordering {
+    b.fragments
+        .cmp(&a.fragments)
+        .then_with(|| a.flow.cmp(&b.flow))
+}
+
+impl Detector for PmtuDetector {
+    fn name(&self) -> &'static str {
+        "pmtu"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+
+        self.last_ts_us = self.last_ts_us.max(ts_micros);
+        let evicted = self.flows.evict(ts_micros);
+        self.archive(evicted);
+        self.expire_fragments(ts_micros, false);
+
+        // (src, dst, DF, protocolo, fragmento: (id, offset, MF), payload IP)
+        let (src, dst, df, ipv6, protocol, fragment, payload) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => {
+                let h = ip.header();
+                let frag = ip.is_payload_fragmented().then(|| {
+                    (
+                        h.identification() as u32,
+                        h.fragments_offset().value() as u32 * 8,
+                        h.more_fragments(),
+                    )
+                });
+                (
+                    IpAddr::V4(h.source_addr()),
+                    IpAddr::V4(h.destination_addr()),
+                    h.dont_fragment(),
+                    false,
+                    ip.payload().ip_number.0,
+                    frag,
+                    ip.payload().payload,
+                )
+            }
+            Some(InternetSlice::Ipv6(ip)) => {
+                let h = ip.header();
+                let frag = icmp::ipv6_fragment(h.next_header().0, ip.extensions().slice())
+                    .map(|f| (f.identification, f.offset, f.more_fragments));
+                // IPv6: los routers nunca fragmentan (equivale a DF siempre activo)
+                (
+                    IpAddr::V6(h.source_addr()),
+                    IpAddr::V6(h.destination_addr()),
+                    true,
+                    true,
+                    ip.payload().ip_number.0,
+                    frag,
+                    ip.payload().payload,
+                )
+            }
+            None => return,
+        };
+
+        if let Some((id, offset, more)) = fragment {
+            let key = FragKey {
+                src,
+                dst,
+                protocol,
+                id,
+            };
+            self.on_fragment(key, offset, payload, more, ipv6, ts_micros);
+            return;
+        }
+
+        match &sliced.transport {
+            Some(TransportSlice::Tcp(tcp)) => self.on_tcp(src, dst, df, tcp, ts_micros),
+            Some(TransportSlice::Icmpv4(icmp)) => {
+                if let Icmpv4Type::DestinationUnreachable(
+                    DestUnreachableHeader::FragmentationNeeded { next_hop_mtu },
+                ) = icmp.icmp_type()
+                {
+                    self.on_packet_too_big(icmp.payload(), next_hop_mtu as u32);
+                }
+            }
+            Some(TransportSlice::Icmpv6(icmp)) => {
+                if let Icmpv6Type::PacketTooBig { mtu } = icmp.icmp_type() {
+                    self.on_packet_too_big(icmp.payload(), mtu);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        // Lo que quede a medias al final de la captura no se pudo reensamblar
+        self.expire_fragments(self.last_ts_us, true);
+
+        let live = self.flows.drain();
+        self.archive(live);
+        let mut summaries = self.archived.take();
+        summaries.sort_by(cmp_by_severity);
+        let total = summaries.len() as u64 + self.archived.pruned();
+        let entries: Vec<Value> = summaries.into_iter().map(|f| f.json).collect();
+
+        json!({
+            "fragments": {
+                "ipv4": self.frag_stats.ipv4_fragments,
+                "ipv6": self.frag_stats.ipv6_fragments,
+                "datagrams": self.frag_stats.datagrams,
+                "reassembled": self.frag_stats.reassembled,
+                "incomplete": self.frag_stats.incomplete,
+                "overlapping": self.frag_stats.overlapping,
+                "over_limit": self.frag_stats.over_limit
+            },
+            "icmp_packet_too_big": {
+                "total": self.ptb_stats.total,
+                "correlated": self.ptb_stats.correlated,
+                "min_mtu": self.ptb_stats.min_mtu
+            },
+            "black_holes_suspected": self.black_holes,
+            "flows_total": total,
+            "summaries_pruned": self.archived.pruned(),
+            "top_by_severity": entries
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etherparse::{
+        EtherType, Ethernet2Header, IpFragOffset, IpNumber, Ipv4Header, PacketBuilder,
+    };
+
+    const A: [u8; 4] = [10, 0, 0, 1];
+    const B: [u8; 4] = [10, 0, 0, 2];
+
+    fn ipv4_fragment(id: u16, offset_bytes: u16, more: bool, payload: &[u8]) -> Vec<u8> {
+        let mut ip = Ipv4Header::new(payload.len() as u16, 64, IpNumber::UDP, A, B).unwrap();
+        ip.identification = id;
+        ip.more_fragments = more;
+        ip.fragment_offset = IpFragOffset::try_new(offset_bytes / 8).unwrap();
+        ip.header_checksum = ip.calc_header_checksum();
+        let eth = Ethernet2Header {
+            source: [1; 6],
+            destination: [2; 6],
+            ether_type: EtherType::IPV4,
+        };
+        let mut out = Vec::new();
+        eth.write(&mut out).unwrap();
+        ip.write(&mut out).unwrap();
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn tcp(from_a: bool, seq: u32, ack: u32, payload_len: usize) -> Vec<u8> {
+        let (s, d, sp, dp) = if from_a {
+            (A, B, 40000, 443)
+        } else {
+            (B, A, 443, 40000)
+        };
+        let b = PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(s, d, 64)
+            .tcp(sp, dp, seq, 65535)
+            .ack(ack);
+        let payload = vec![0u8; payload_len];
+        let mut out = Vec::new();
+        b.write(&mut out, &payload).unwrap();
+        out
+    }
+
+    #[test]
+    fn complete_and_incomplete_datagrams() {
+        let mut d = PmtuDetector::new();
+        // Datagrama 1: dos fragmentos completos (UDP 5000 -> 53)
+        let mut first = vec![0x13, 0x88, 0x00, 0x35];
+        first.extend_from_slice(&[0u8; 12]);
+        d.on_packet(&ipv4_fragment(1, 0, true, &first), 0);
+        d.on_packet(&ipv4_fragment(1, 16, false, &[0u8; 8]), 10);
+        // Datagrama 2: falta el último fragmento
+        d.on_packet(&ipv4_fragment(2, 0, true, &first), 20);
+        let out = d.finalize();
+        assert_eq!(out["fragments"]["ipv4"], 3);
+        assert_eq!(out["fragments"]["reassembled"], 1);
+        assert_eq!(out["fragments"]["incomplete"], 1);
+        let flow = &out["top_by_severity"][0];
+        assert_eq!(flow["flow"], "10.0.0.1:5000 <-> 10.0.0.2:53/UDP");
+        assert_eq!(flow["score"]["level"], "MEDIA");
+        assert_eq!(flow["datagrams"]["incomplete"], 1);
+    }
+
+    #[test]
+    fn overlapping_fragments_are_counted() {
+        let mut g = FragGroup {
+            first_ts_us: 0,
+            fragments: 0,
+            ranges: Vec::new(),
+            total_len: None,
+            overlapping: false,
+            flow: None,
+        };
+        g.add(0, 16, true);
+        g.add(8, 16, false);
+        assert!(g.overlapping);
+        assert!(g.is_complete());
+    }
+
+    #[test]
+    fn tiny_fragments_are_merged_in_order_and_capped() {
+        let mut d = PmtuDetector::new();
+        // Fragmentos de 8 B en orden inverso: sin solapes y un único intervalo al final
+        for i in (1..FRAG_MAX_PER_GROUP as u16 - 1).rev() {
+            d.on_packet(&ipv4_fragment(7, i * 8, true, &[0u8; 8]), 0);
+        }
+        let g = d.frag_groups.values().next().unwrap();
+        assert_eq!(g.ranges, vec![(8, (FRAG_MAX_PER_GROUP - 1) * 8)]);
+        assert!(!g.overlapping);
+        // Un datagrama que no termina nunca se corta al llegar al tope
+        d.on_packet(&ipv4_fragment(7, 2040, true, &[0u8; 8]), 1);
+        d.on_packet(&ipv4_fragment(7, 2048, true, &[0u8; 8]), 2);
+        assert!(d.frag_groups.is_empty());
+        let out = d.finalize();
+        assert_eq!(out["fragments"]["over_limit"], 1);
+        assert_eq!(out["fragments"]["incomplete"], 1);
+        assert_eq!(out["fragments"]["overlapping"], 0);
+    }
+
+    #[test]
+    fn packet_too_big_is_correlated_to_conversation() {
+        let mut d = PmtuDetector::new();
+        let big = tcp(true, 1000, 1, 1400);
+        d.on_packet(&big, 0);
+        // ICMP frag-needed desde un router hacia A citando el segmento grande
+        let icmp = PacketBuilder::ethernet2([3; 6], [1; 6])
+            .ipv4([192, 168, 1, 1], A, 64)
+            .icmpv4(Icmpv4Type::DestinationUnreachable(
+                DestUnreachableHeader::FragmentationNeeded { next_hop_mtu: 1400 },
+            ));
+        let quoted = &big[14..14 + 28];
+        let mut frame = Vec::new();
+        icmp.write(&mut frame, quoted).unwrap();
+        d.on_packet(&frame, 10);
+
+        let out = d.finalize();
+        assert_eq!(out["icmp_packet_too_big"]["correlated"], 1);
+        assert_eq!(out["icmp_packet_too_big"]["min_mtu"], 1400);
+        let flow = &out["top_by_severity"][0];
+        assert_eq!(flow["c2s"]["icmp_ptb"]["count"], 1);
+        assert_eq!(flow["score"]["level"], "MEDIA");
+    }
+
+    #[test]
+    fn black_hole_when_large_segments_never_acked() {
+        let mut d = PmtuDetector::new();
+        // Datos pequeños confirmados
+        d.on_packet(&tcp(true, 1000, 1, 100), 0);
+        d.on_packet(&tcp(false, 1, 1100, 0), 10);
+        // Segmento grande retransmitido 3 veces, el ACK no avanza
+        for i in 0..4 {
+            d.on_packet(&tcp(true, 1100, 1, 1400), 100 + i * 1_000_000);
+            d.on_packet(&tcp(false, 1, 1100, 0), 200 + i * 1_000_000);
+        }
+        let out = d.finalize();
+        assert_eq!(out["black_holes_suspected"], 1);
+        let flow = &out["top_by_severity"][0];
+        assert_eq!(flow["score"]["level"], "ALTA");
+        assert!(flow["reasons"][0]
+            .as_str()
+            .unwrap()
+            .contains("agujero negro PMTUD"));
+    }
+
+    #[test]
+    fn healthy_large_transfer_is_not_reported() {
+        let mut d = PmtuDetector::new();
+        let mut seq = 1000u32;
+        for i in 0..10u64 {
+            d.on_packet(&tcp(true, seq, 1, 1400), i * 10);
+            seq += 1400;
+            d.on_packet(&tcp(false, 1, seq, 0), i * 10 + 5);
+        }
+        let out = d.finalize();
+        assert_eq!(out["flows_total"], 0);
+    }
+}
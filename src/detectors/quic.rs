@@ -0,0 +1,798 @@
+use crate::detectors::finding::level;
+use crate::detectors::latency::LatencyStats;
+use crate::engine::Detector;
+use crate::network::flow::Flow;
+use crate::network::flow_table::FlowTableConfig;
+use crate::network::format::{endpoint_label, hex};
+use crate::network::quic::{
+    self, CryptoStream, InitialKeys, LongHeader, LongType, Packet, VERSION_1, VERSION_2,
+};
+use crate::network::tls::{self, ClientHello, ServerHello, HS_CLIENT_HELLO, HS_SERVER_HELLO};
+use crate::network::tls_fingerprint::{self, Ja4Transport};
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+// Conexiones con detalle en el reporte (el resto solo cuenta en los agregados)
+const MAX_CONVERSATIONS_REPORTED: usize = 1000;
+// Handshakes por encima de estos umbrales se señalan como lentos
+const SLOW_HANDSHAKE_MS: f64 = 1000.0;
+const VERY_SLOW_HANDSHAKE_MS: f64 = 3000.0;
+// Cada cuánto se buscan conexiones inactivas
+const SWEEP_INTERVAL_US: u64 = 1_000_000;
+
+/// Un sentido de la conexión: contadores y espacio de paquetes Initial
+#[derive(Debug, Default)]
+struct Direction {
+    packets: u64,
+    bytes: u64,
+    keys: Option<InitialKeys>,
+    initial_packets: u64,
+    largest_initial_pn: Option<u64>,
+    initial_pn_gaps: u64,
+    crypto: CryptoStream,
+}
+
+impl Direction {
+    fn on_initial_pn(&mut self, pn: u64) {
+        self.initial_packets += 1;
+        match self.largest_initial_pn {
+            Some(largest) if pn <= largest => {}
+            Some(largest) => {
+                self.initial_pn_gaps += pn - largest - 1;
+                self.largest_initial_pn = Some(pn);
+            }
+            None => {
+                // El primer PN de cada espacio es 0: lo anterior se perdió
+                self.initial_pn_gaps += pn;
+                self.largest_initial_pn = Some(pn);
+            }
+        }
+    }
+}
+
+/// Conexión QUIC identificada por sus connection IDs, no por el 5-tupla
+#[derive(Debug)]
+struct QuicConn {
+    version: u32,
+    // DCID del que salen las claves Initial (el original o el de Retry)
+    key_dcid: Vec<u8>,
+    client_cid: Vec<u8>,
+    server_cid: Option<Vec<u8>>,
+    first_path: Flow,
+    // Ruta actual cliente -> servidor
+    client_path: Flow,
+    paths: Vec<Flow>,
+    migrations: u64,
+    client: Direction,
+    server: Direction,
+    client_hello: Option<ClientHello>,
+    server_hello: Option<ServerHello>,
+    first_us: u64,
+    server_first_us: Option<u64>,
+    done_us: Option<u64>,
+    last_us: u64,
+    retry: bool,
+    version_negotiation: bool,
+    // (lo envió el cliente, código de error)
+    close: Option<(bool, u64)>,
+    decrypt_errors: u64,
+    parse_errors: u64,
+    // CIDs indexados, para poder retirarlos al cerrar
+    cids: Vec<Vec<u8>>,
+}
+
+impl QuicConn {
+    fn rekey(&mut self) {
+        self.client.keys = InitialKeys::derive(self.version, &self.key_dcid, false);
+        self.server.keys = InitialKeys::derive(self.version, &self.key_dcid, true);
+    }
+
+    fn side(&mut self, from_client: bool) -> &mut Direction {
+        if from_client {
+            &mut self.client
+        } else {
+            &mut self.server
+        }
+    }
+}
+
+/// A qué conexión apunta un CID y si lo eligió el cliente
+#[derive(Debug, Clone, Copy)]
+struct CidOwner {
+    conn: u64,
+    client_side: bool,
+}
+
+/// Detector de conexiones QUIC/HTTP3: versión, SNI y ALPN del Initial,
+/// tiempos de handshake, migraciones y huecos en los números de paquete
+pub struct QuicDetector {
+    config: FlowTableConfig,
+    conns: HashMap<u64, QuicConn>,
+    next_id: u64,
+    cid_index: HashMap<Vec<u8>, CidOwner>,
+    cid_lengths: BTreeSet<usize>,
+    // Ruta cliente -> servidor, para CIDs de longitud cero
+    path_index: HashMap<Flow, u64>,
+    last_sweep_us: Option<u64>,
+    conversations: Vec<(u32, Value)>,
+    connections_total: u64,
+    completed: u64,
+    http3: u64,
+    retries: u64,
+    version_negotiations: u64,
+    migrations_total: u64,
+    initial_pn_gaps: u64,
+    decrypt_errors: u64,
+    parse_errors: u64,
+    evicted_capacity: u64,
+    versions: BTreeMap<String, u64>,
+    unsupported_versions: BTreeMap<String, u64>,
+    ciphers: BTreeMap<String, u64>,
+    alpn_offered: BTreeMap<String, u64>,
+    close_errors: BTreeMap<String, u64>,
+    initial_rtt: LatencyStats,
+    handshake_latency: LatencyStats,
+}
+
+impl Default for QuicDetector {
+    fn default() -> Self {
+        Self::with_flow_table(FlowTableConfig::default())
+    }
+}
+
+fn path_label(flow: &Flow) -> String {
+    let client = endpoint_label(flow.source_ip, flow.source_port);
+    let server = endpoint_label(flow.destination_ip, flow.destination_port);
+    format!("{client} <-> {server}/UDP")
+}
+
+fn is_h3(alpn: &str) -> bool {
+    alpn == "h3" || alpn.starts_with("h3-")
+}
+
+fn ms(from: u64, to: Option<u64>) -> Option<f64> {
+    to.map(|t| t.saturating_sub(from) as f64 / 1000.0)
+}
+
+impl QuicDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_flow_table(config: FlowTableConfig) -> Self {
+        QuicDetector {
+            config,
+            conns: HashMap::new(),
+            next_id: 0,
+            cid_index: HashMap::new(),
+            cid_lengths: BTreeSet::new(),
+            path_index: HashMap::new(),
+            last_sweep_us: None,
+            conversations: Vec::new(),
+            connections_total: 0,
+            completed: 0,
+            http3: 0,
+            retries: 0,
+            version_negotiations: 0,
+            migrations_total: 0,
+            initial_pn_gaps: 0,
+            decrypt_errors: 0,
+            parse_errors: 0,
+            evicted_capacity: 0,
+            versions: BTreeMap::new(),
+            unsupported_versions: BTreeMap::new(),
+            ciphers: BTreeMap::new(),
+            alpn_offered: BTreeMap::new(),
+            close_errors: BTreeMap::new(),
+            initial_rtt: LatencyStats::default(),
+            handshake_latency: LatencyStats::default(),
+        }
+    }
+
+    fn index_cid(&mut self, cid: &[u8], owner: CidOwner) {
+        // Los CIDs vacíos no identifican nada: se recurre a la ruta
+        if cid.is_empty() || self.cid_index.contains_key(cid) {
+            return;
+        }
+        self.cid_index.insert(cid.to_vec(), owner);
+        self.cid_lengths.insert(cid.len());
+        if let Some(c) = self.conns.get_mut(&owner.conn) {
+            c.cids.push(cid.to_vec());
+        }
+    }
+
+    /// Conexión y sentido de un paquete a partir de su DCID o, si no, de la ruta
+    fn lookup(&self, dcid: Option<&[u8]>, path: &Flow) -> Option<(u64, bool)> {
+        if let Some(owner) = dcid.and_then(|d| self.cid_index.get(d)) {
+            // Un DCID elegido por el cliente va hacia el cliente
+            return Some((owner.conn, !owner.client_side));
+        }
+        if let Some(id) = self.path_index.get(path) {
+            return Some((*id, true));
+        }
+        self.path_index.get(&path.reverse()).map(|id| (*id, false))
+    }
+
+    /// Cabecera corta: el DCID no lleva longitud, se prueban las conocidas
+    fn lookup_short(&self, bytes: &[u8], path: &Flow) -> Option<(u64, bool)> {
+        for len in &self.cid_lengths {
+            if let Some(owner) = bytes.get(1..1 + len).and_then(|c| self.cid_index.get(c)) {
+                return Some((owner.conn, !owner.client_side));
+            }
+        }
+        self.lookup(None, path)
+    }
+
+    fn on_udp(&mut self, path: Flow, payload: &[u8], ts_us: u64) {
+        let mut accounted = false;
+        for packet in quic::parse_datagram(payload) {
+            let hit = match packet {
+                Packet::Long(h) => self.on_long(path, &h, ts_us),
+                Packet::Short(bytes) => self.on_short(path, bytes, ts_us),
+                Packet::VersionNegotiation { dcid, .. } => {
+                    let hit = self.lookup(Some(dcid), &path);
+                    if let Some(c) = hit.and_then(|(id, _)| self.conns.get_mut(&id)) {
+                        c.version_negotiation = true;
+                    }
+                    hit
+                }
+            };
+            // Un datagrama (con sus paquetes coalescidos) cuenta una vez
+            if let (Some((id, from_client)), false) = (hit, accounted) {
+                accounted = true;
+                if let Some(c) = self.conns.get_mut(&id) {
+                    c.last_us = ts_us;
+                    let d = c.side(from_client);
+                    d.packets += 1;
+                    d.bytes += payload.len() as u64;
+                    if !from_client && c.server_first_us.is_none() {
+                        c.server_first_us = Some(ts_us);
+                    }
+                }
+            }
+        }
+    }
+
+    fn on_long(&mut self, path: Flow, h: &LongHeader, ts_us: u64) -> Option<(u64, bool)> {
+        let known = h.version == VERSION_1 || h.version == VERSION_2;
+        let hit = self.lookup(Some(h.dcid), &path);
+        let Some((id, from_client)) = hit else {
+            if h.kind != LongType::Initial {
+                return None;
+            }
+            if !known {
+                *self
+                    .unsupported_versions
+                    .entry(quic::version_name(h.version))
+                    .or_default() += 1;
+                return None;
+            }
+            return self.open(path, h, ts_us);
+        };
+
+        match h.kind {
+            LongType::Retry if !from_client => {
+                let c = self.conns.get_mut(&id)?;
+                if !c.retry {
+                    // El cliente repite el Initial con claves derivadas del SCID del Retry
+                    c.retry = true;
+                    c.key_dcid = h.scid.to_vec();
+                    c.rekey();
+                    let owner = CidOwner {
+                        conn: id,
+                        client_side: false,
+                    };
+                    self.index_cid(h.scid, owner);
+                }
+            }
+            LongType::Initial if known => {
+                let c = self.conns.get_mut(&id)?;
+                if from_client && h.version != c.version {
+                    // Nuevo intento tras Version Negotiation
+                    c.version = h.version;
+                    c.rekey();
+                }
+                self.on_initial(id, from_client, h);
+            }
+            _ => {}
+        }
+        if from_client {
+            self.check_path(id, path);
+        } else if !h.scid.is_empty() {
+            let c = self.conns.get_mut(&id)?;
+            if c.server_cid.is_none() {
+                c.server_cid = Some(h.scid.to_vec());
+            }
+            let owner = CidOwner {
+                conn: id,
+                client_side: false,
+            };
+            self.index_cid(h.scid, owner);
+        }
+        Some((id, from_client))
+    }
+
+    /// Solo un Initial del cliente que descifra abre una conexión
+    fn open(&mut self, path: Flow, h: &LongHeader, ts_us: u64) -> Option<(u64, bool)> {
+        let keys = InitialKeys::derive(h.version, h.dcid, false)?;
+        quic::decrypt_initial(h, &keys, None)?;
+
+        if self.config.max_flows > 0 && self.conns.len() >= self.config.max_flows {
+            self.evict_oldest();
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut conn = QuicConn {
+            version: h.version,
+            key_dcid: h.dcid.to_vec(),
+            client_cid: h.scid.to_vec(),
+            server_cid: None,
+            first_path: path,
+            client_path: path,
+            paths: vec![path],
+            migrations: 0,
+            client: Direction::default(),
+            server: Direction::default(),
+            client_hello: None,
+            server_hello: None,
+            first_us: ts_us,
+            server_first_us: None,
+            done_us: None,
+            last_us: ts_us,
+            retry: false,
+            version_negotiation: false,
+            close: None,
+            decrypt_errors: 0,
+            parse_errors: 0,
+            cids: Vec::new(),
+        };
+        conn.rekey();
+        self.conns.insert(id, conn);
+        self.path_index.insert(path, id);
+        let server_side = CidOwner {
+            conn: id,
+            client_side: false,
+        };
+        let client_side = CidOwner {
+            conn: id,
+            client_side: true,
+        };
+        self.index_cid(h.dcid, server_side);
+        self.index_cid(h.scid, client_side);
+        self.on_initial(id, true, h);
+        Some((id, true))
+    }
+
+    fn on_initial(&mut self, id: u64, from_client: bool, h: &LongHeader) {
+        let Some(c) = self.conns.get_mut(&id) else {
+            return;
+        };
+        let d = if from_client {
+            &mut c.client
+        } else {
+            &mut c.server
+        };
+        let Some(keys) = d.keys.as_ref() else {
+            return;
+        };
+        let Some((pn, plain)) = quic::decrypt_initial(h, keys, d.largest_initial_pn) else {
+            c.decrypt_errors += 1;
+            return;
+        };
+        d.on_initial_pn(pn);
+        let Some(frames) = quic::parse_initial_frames(&plain) else {
+            c.parse_errors += 1;
+            return;
+        };
+        for (offset, data) in &frames.crypto {
+            d.crypto.insert(*offset, data);
+        }
+        let first = d.crypto.first_message();
+        if from_client && c.client_hello.is_none() {
+            if let Some((HS_CLIENT_HELLO, body)) = first {
+                c.client_hello = tls::parse_client_hello(body);
+            }
+        } else if !from_client && c.server_hello.is_none() {
+            if let Some((HS_SERVER_HELLO, body)) = first {
+                c.server_hello = tls::parse_server_hello(body);
+            }
+        }
+        if let Some(code) = frames.close_code {
+            c.close.get_or_insert((from_client, code));
+        }
+    }
+
+    fn on_short(&mut self, path: Flow, bytes: &[u8], ts_us: u64) -> Option<(u64, bool)> {
+        let (id, from_client) = self.lookup_short(bytes, &path)?;
+        if from_client {
+            let c = self.conns.get_mut(&id)?;
+            // El primer paquete 1-RTT del cliente marca el fin del handshake
+            c.done_us.get_or_insert(ts_us);
+            self.check_path(id, path);
+        }
+        Some((id, from_client))
+    }
+
+    /// Un CID conocido llegando desde otra dirección del cliente es una migración
+    fn check_path(&mut self, id: u64, path: Flow) {
+        let Some(c) = self.conns.get_mut(&id) else {
+            return;
+        };
+        if path == c.client_path {
+            return;
+        }
+        c.client_path = path;
+        if c.done_us.is_some() {
+            c.migrations += 1;
+        }
+        if !c.paths.contains(&path) {
+            c.paths.push(path);
+        }
+        self.path_index.insert(path, id);
+    }
+
+    fn sweep(&mut self, now_us: u64) {
+        let idle = self.config.idle_timeout_us;
+        if idle == 0 {
+            return;
+        }
+        if let Some(last) = self.last_sweep_us {
+            if now_us.saturating_sub(last) < SWEEP_INTERVAL_US {
+                return;
+            }
+        }
+        self.last_sweep_us = Some(now_us);
+        let mut expired: Vec<u64> = self
+            .conns
+            .iter()
+            .filter(|(_, c)| now_us.saturating_sub(c.last_us) > idle)
+            .map(|(id, _)| *id)
+            .collect();
+        expired.sort_unstable();
+        for id in expired {
+            self.close_conn(id);
+        }
+    }
+
+    /// Libera la décima parte menos reciente de la tabla al llegar al límite
+    fn evict_oldest(&mut self) {
+        let mut by_age: Vec<(u64, u64)> =
+            self.conns.iter().map(|(id, c)| (c.last_us, *id)).collect();
+        by_age.sort_unstable();
+        let n = (self.config.max_flows / 10).max(1);
+        for (_, id) in by_age.into_iter().take(n) {
+            self.evicted_capacity += 1;
+            self.close_conn(id);
+        }
+    }
+
+    fn close_conn(&mut self, id: u64) {
+        let Some(c) = self.conns.remove(&id) else {
+            return;
+        };
+        for cid in &c.cids {
+            self.cid_index.remove(cid);
+        }
+        for p in &c.paths {
+            if self.path_index.get(p) == Some(&id) {
+                self.path_index.remove(p);
+            }
+        }
+        self.finish_conn(c);
+    }
+
+    fn finish_conn(&mut self, c: QuicConn) {
+        self.connections_total += 1;
+        self.migrations_total += c.migrations;
+        self.decrypt_errors += c.decrypt_errors;
+        self.parse_errors += c.parse_errors;
+        let gaps = c.client.initial_pn_gaps + c.server.initial_pn_gaps;
+        self.initial_pn_gaps += gaps;
+        *self
+            .versions
+            .entry(quic::version_name(c.version))
+            .or_default() += 1;
+        if c.retry {
+            self.retries += 1;
+        }
+        if c.version_negotiation {
+            self.version_negotiations += 1;
+        }
+
+        let ch = c.client_hello.as_ref();
+        let sni = ch.and_then(|h| h.sni.clone());
+        let offered: Vec<String> = ch.map(|h| h.alpn.clone()).unwrap_or_default();
+        for p in &offered {
+            *self.alpn_offered.entry(p.clone()).or_default() += 1;
+        }
+        // El ALPN elegido viaja cifrado (EncryptedExtensions): se usa el ofrecido
+        let http3 = offered.iter().any(|p| is_h3(p));
+        if http3 {
+            self.http3 += 1;
+        }
+        let cipher = c.server_hello.as_ref().map(|sh| sh.cipher_suite);
+        if let Some(cs) = cipher {
+            *self.ciphers.entry(tls::cipher_name(cs)).or_default() += 1;
+        }
+        let ja4 = ch.map(|h| tls_fingerprint::ja4(h, Ja4Transport::Quic).hash);
+
+        let initial_rtt_ms = ms(c.first_us, c.server_first_us);
+        let handshake_ms = ms(c.first_us, c.done_us);
+        if let Some(t) = c.server_first_us {
+            self.initial_rtt.add_sample(t.saturating_sub(c.first_us));
+        }
+        if let Some(t) = c.done_us {
+            self.completed += 1;
+            self.handshake_latency
+                .add_sample(t.saturating_sub(c.first_us));
+        }
+        let received = c.client.initial_packets + c.server.initial_packets;
+        let loss = if gaps > 0 {
+            gaps as f64 / (gaps + received) as f64
+        } else {
+            0.0
+        };
+
+        let mut score = 0u32;
+        let mut reasons = Vec::new();
+        if c.server.packets == 0 {
+            score += 60;
+            reasons.push("sin respuesta del servidor".to_string());
+        } else if c.done_us.is_none() {
+            score += 20;
+            reasons.push("handshake incompleto".to_string());
+        }
+        if let Some(t) = handshake_ms {
+            if t > VERY_SLOW_HANDSHAKE_MS {
+                score += 40;
+            } else if t > SLOW_HANDSHAKE_MS {
+                score += 20;
+            }
+            if t > SLOW_HANDSHAKE_MS {
+                reasons.push(format!("handshake lento {t:.1} ms"));
+            }
+        }
+        let close = c.close.map(|(from_client, code)| {
+            let name = quic::error_name(code);
+            *self.close_errors.entry(name.clone()).or_default() += 1;
+            let side = if from_client { "cliente" } else { "servidor" };
+            if c.done_us.is_none() {
+                score += 40;
+                reasons.push(format!(
+                    "conexión cerrada por el {side} en el handshake: {name}"
+                ));
+            }
+            json!({
+                "from": if from_client { "client" } else { "server" },
+                "code": code,
+                "error": name
+            })
+        });
+        if gaps > 0 {
+            score += 10;
+            reasons.push(format!("{gaps} paquetes Initial perdidos o no capturados"));
+        }
+        let level = level(score);
+
+        let server = endpoint_label(c.first_path.destination_ip, c.first_path.destination_port);
+        self.conversations.push((
+            score,
+            json!({
+                "flow": path_label(&c.first_path),
+                "server": server,
+                "sni": sni,
+                "score": { "value": score, "level": level },
+                "reasons": reasons,
+                "version": quic::version_name(c.version),
+                "client_cid": hex(&c.client_cid),
+                "server_cid": c.server_cid.as_deref().map(hex),
+                "retry": c.retry,
+                "version_negotiation": c.version_negotiation,
+                "alpn_offered": offered,
+                "http3": http3,
+                "cipher": cipher.map(tls::cipher_name),
+                "ja4": ja4,
+                "initial_rtt_ms": initial_rtt_ms,
+                "handshake_ms": handshake_ms,
+                "complete": c.done_us.is_some(),
+                "migrations": c.migrations,
+                "client_paths": c.paths.iter().map(path_label).collect::<Vec<_>>(),
+                "initial_pn_gaps": {
+                    "client": c.client.initial_pn_gaps,
+                    "server": c.server.initial_pn_gaps
+                },
+                "initial_loss_estimate": loss,
+                "close": close,
+                "packets": { "client": c.client.packets, "server": c.server.packets },
+                "bytes": { "client": c.client.bytes, "server": c.server.bytes }
+            }),
+        ));
+    }
+}
+
+impl Detector for QuicDetector {
+    fn name(&self) -> &'static str {
+        "quic"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        self.sweep(ts_micros);
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        if let Some(TransportSlice::Udp(udp)) = &sliced.transport {
+            let path = Flow {
+                source_ip: src,
+                source_port: udp.source_port(),
+                destination_ip: dst,
+                destination_port: udp.destination_port(),
+            };
+            self.on_udp(path, udp.payload(), ts_micros);
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        let mut live: Vec<u64> = self.conns.keys().copied().collect();
+        live.sort_unstable();
+        for id in live {
+            self.close_conn(id);
+        }
+
+        let mut conversations = std::mem::take(&mut self.conversations);
+        // Orden estable: score desc, luego orden de cierre
+        conversations.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        let truncated = conversations
+            .len()
+            .saturating_sub(MAX_CONVERSATIONS_REPORTED);
+        conversations.truncate(MAX_CONVERSATIONS_REPORTED);
+
+        json!({
+            "connections_total": self.connections_total,
+            "handshakes_completed": self.completed,
+            "http3": self.http3,
+            "versions": self.versions,
+            "unsupported_versions": self.unsupported_versions,
+            "ciphers": self.ciphers,
+            "alpn_offered": self.alpn_offered,
+            "retries": self.retries,
+            "version_negotiations": self.version_negotiations,
+            "migrations_total": self.migrations_total,
+            "initial_pn_gaps": self.initial_pn_gaps,
+            "close_errors": self.close_errors,
+            "decrypt_errors": self.decrypt_errors,
+            "parse_errors": self.parse_errors,
+            "evicted_capacity": self.evicted_capacity,
+            "initial_rtt_ms": self.initial_rtt.to_json(),
+            "handshake_ms": self.handshake_latency.to_json(),
+            "top_by_severity": conversations.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+            "connections_truncated": truncated
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::quic::tests::{crypto_frame, initial_packet};
+    use crate::network::tls::tests::{client_hello_record, server_hello_record};
+    use etherparse::PacketBuilder;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+    const DCID: &[u8] = &[0x83, 0x94, 0xc8, 0xf0, 0x3e, 0x51, 0x57, 0x08];
+    const CCID: &[u8] = b"client01";
+    const SCID: &[u8] = b"server01";
+
+    fn udp(from_client: bool, client: ([u8; 4], u16), payload: &[u8]) -> Vec<u8> {
+        let (s, d, sp, dp) = if from_client {
+            (client.0, SERVER, client.1, 443)
+        } else {
+            (SERVER, client.0, 443, client.1)
+        };
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(s, d, 64)
+            .udp(sp, dp)
+            .write(&mut out, payload)
+            .unwrap();
+        out
+    }
+
+    fn client_initial(pn: u32) -> Vec<u8> {
+        let hello = client_hello_record("h3.example", &[0x1301, 0x1302], &["h3"]);
+        let mut frames = crypto_frame(0, &hello);
+        frames.resize(1162, 0); // relleno hasta un datagrama de 1200
+        initial_packet(DCID, CCID, DCID, false, pn, &frames)
+    }
+
+    fn short(dcid: &[u8]) -> Vec<u8> {
+        let mut p = vec![0x41];
+        p.extend_from_slice(dcid);
+        p.extend_from_slice(&[0u8; 24]);
+        p
+    }
+
+    #[test]
+    fn handshake_with_migration_and_lost_initial() {
+        let mut d = QuicDetector::new();
+        let home = (CLIENT, 50000);
+        d.on_packet(&udp(true, home, &client_initial(0)), 0);
+        let sh = server_hello_record(0x1301, true, None);
+        let server_initial = initial_packet(CCID, SCID, DCID, true, 0, &crypto_frame(0, &sh));
+        d.on_packet(&udp(false, home, &server_initial), 30_000);
+        // El segundo Initial del cliente (PN 1) no se capturó
+        let ack = initial_packet(SCID, CCID, DCID, false, 2, &[0x02, 0, 0, 0, 0]);
+        d.on_packet(&udp(true, home, &ack), 60_000);
+        d.on_packet(&udp(true, home, &short(SCID)), 61_000);
+        d.on_packet(&udp(false, home, &short(CCID)), 80_000);
+        // El cliente cambia de red y mantiene el CID
+        let roamed = ([192, 168, 1, 7], 40000);
+        d.on_packet(&udp(true, roamed, &short(SCID)), 500_000);
+        d.on_packet(&udp(false, roamed, &short(CCID)), 520_000);
+        // Ruido UDP que no es QUIC
+        d.on_packet(&udp(true, (CLIENT, 53), b"\x12\x34not quic"), 600_000);
+
+        let out = d.finalize();
+        assert_eq!(out["connections_total"], 1);
+        assert_eq!(out["handshakes_completed"], 1);
+        assert_eq!(out["http3"], 1);
+        assert_eq!(out["versions"]["QUICv1"], 1);
+        assert_eq!(out["migrations_total"], 1);
+        let c = &out["top_by_severity"][0];
+        assert_eq!(c["sni"], "h3.example");
+        assert_eq!(c["cipher"], "TLS_AES_128_GCM_SHA256");
+        assert_eq!(c["initial_rtt_ms"], 30.0);
+        assert_eq!(c["handshake_ms"], 61.0);
+        assert_eq!(c["client_cid"], hex(CCID));
+        assert_eq!(c["server_cid"], hex(SCID));
+        assert_eq!(c["initial_pn_gaps"]["client"], 1);
+        assert_eq!(c["client_paths"].as_array().unwrap().len(), 2);
+        assert_eq!(c["packets"]["client"], 4);
+        assert_eq!(c["packets"]["server"], 3);
+        assert!(c["ja4"].as_str().unwrap().starts_with("q13d"));
+        assert_eq!(c["score"]["level"], "BAJA");
+    }
+
+    #[test]
+    fn unanswered_and_refused_connections() {
+        let mut d = QuicDetector::new();
+        d.on_packet(&udp(true, (CLIENT, 50001), &client_initial(0)), 0);
+
+        // Otro cliente rechazado por el servidor en el propio Initial
+        let other = ([10, 0, 0, 9], 50002);
+        let dcid = b"otherdcid";
+        let hello = client_hello_record("refused.example", &[0x1301], &["h3"]);
+        let mut frames = crypto_frame(0, &hello);
+        frames.resize(1162, 0);
+        let initial = initial_packet(dcid, b"c2", dcid, false, 0, &frames);
+        d.on_packet(&udp(true, other, &initial), 1_000);
+        // CONNECTION_CLOSE 0x1c con CONNECTION_REFUSED (0x2), sin frase
+        let close = initial_packet(b"c2", b"s2", dcid, true, 0, &[0x1c, 0x02, 0x00, 0x00]);
+        d.on_packet(&udp(false, other, &close), 5_000);
+
+        let out = d.finalize();
+        assert_eq!(out["connections_total"], 2);
+        assert_eq!(out["handshakes_completed"], 0);
+        assert_eq!(out["close_errors"]["CONNECTION_REFUSED"], 1);
+        let top = out["top_by_severity"].as_array().unwrap();
+        assert_eq!(top[0]["sni"], "h3.example");
+        assert_eq!(top[0]["score"]["value"], 60);
+        assert_eq!(top[1]["sni"], "refused.example");
+        assert_eq!(top[1]["close"]["from"], "server");
+        assert_eq!(top[1]["score"]["value"], 60);
+    }
+}
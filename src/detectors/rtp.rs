@@ -0,0 +1,913 @@
+use crate::engine::Detector;
+use crate::network::flow::Flow;
+use crate::network::format::{endpoint_label, round2};
+use crate::network::rtp::{self, RtcpPacket, RtpHeader};
+use crate::network::sdp::{self, SdpMedia};
+use crate::network::sip;
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+
+// RFC 3550 A.1
+const RTP_SEQ_MOD: u32 = 1 << 16;
+const MAX_DROPOUT: u16 = 3000;
+const MAX_MISORDER: u16 = 100;
+// Paquetes consecutivos para aceptar un flujo RTP sin SDP que lo anuncie
+const MIN_SEQUENTIAL: u32 = 4;
+// Estado acotado
+const MAX_STREAMS: usize = 10_000;
+const MAX_ENDPOINTS: usize = 10_000;
+const MAX_SENDER_REPORTS: usize = 10_000;
+// Candidatos heurísticos sin confirmar y SR sin contestar se olvidan tras este tiempo
+const CANDIDATE_IDLE_US: u64 = 5_000_000;
+const SENDER_REPORT_TTL_US: u64 = 60_000_000;
+// Puertos UDP altos con protocolos conocidos que no deben tomarse por RTP
+const NON_RTP_PORTS: [u16; 6] = [1900, 3478, 4500, 5060, 5353, 5355];
+// Relojes habituales para estimar el de un payload type dinámico sin SDP
+const COMMON_CLOCKS: [u32; 6] = [8000, 16000, 32000, 44100, 48000, 90000];
+
+/// Medio anunciado en un SDP (endpoint receptor)
+#[derive(Debug, Clone)]
+struct MediaHint {
+    media: SdpMedia,
+    call_id: Option<String>,
+    rtcp: bool,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+struct StreamKey {
+    flow: Flow,
+    ssrc: u32,
+}
+
+/// Vista de un flujo a partir de los RTCP del receptor
+#[derive(Debug, Default, Clone)]
+struct RtcpView {
+    reports: u64,
+    fraction_lost: f64,
+    cumulative_lost: i32,
+    jitter_units: u32,
+    rtt_us: Option<u64>,
+}
+
+#[derive(Debug)]
+struct RtpStream {
+    flow: Flow,
+    ssrc: u32,
+    identified_by: &'static str,
+    call_id: Option<String>,
+    media: Option<String>,
+    hint: Option<SdpMedia>,
+    confirmed: bool,
+    probation: u32,
+    payload_type: u8,
+    payload_type_changes: u64,
+    clock_hz: Option<u32>,
+    clock_estimated: bool,
+    packets: u64,
+    payload_bytes: u64,
+    markers: u64,
+    // Secuencia (RFC 3550 A.1)
+    base_seq: u16,
+    max_seq: u16,
+    cycles: u32,
+    bad_seq: u32,
+    received: u64,
+    // Acumulado de antes de cada reinicio de secuencia
+    expected_prior: u64,
+    received_prior: u64,
+    out_of_order: u64,
+    duplicates: u64,
+    seq_errors: u64,
+    bursts: u64,
+    burst_max: u64,
+    burst_lost: u64,
+    // Jitter entre llegadas (RFC 3550 A.8), en unidades del reloj RTP
+    last_arrival: Option<(u64, u32)>,
+    jitter: f64,
+    jitter_max: f64,
+    first_arrival: (u64, u32),
+    first_us: u64,
+    last_us: u64,
+    bye: bool,
+}
+
+impl RtpStream {
+    fn new(key: StreamKey, h: &RtpHeader, hint: Option<&MediaHint>, ts: u64) -> Self {
+        let (codec_clock, media) = match hint {
+            Some(hint) => (
+                hint.media.codec(h.payload_type).map(|(_, hz)| hz),
+                Some(hint.media.kind.clone()),
+            ),
+            None => (rtp::static_payload(h.payload_type).map(|(_, hz)| hz), None),
+        };
+        RtpStream {
+            flow: key.flow,
+            ssrc: key.ssrc,
+            identified_by: if hint.is_some() { "sdp" } else { "heuristic" },
+            call_id: hint.and_then(|h| h.call_id.clone()),
+            media,
+            hint: hint.map(|h| h.media.clone()),
+            confirmed: hint.is_some(),
+            probation: if hint.is_some() {
+                0
+            } else {
+                MIN_SEQUENTIAL - 1
+            },
+            payload_type: h.payload_type,
+            payload_type_changes: 0,
+            clock_hz: codec_clock,
+            clock_estimated: false,
+            packets: 1,
+            payload_bytes: h.payload_len as u64,
+            markers: h.marker as u64,
+            base_seq: h.sequence,
+            max_seq: h.sequence,
+            cycles: 0,
+            bad_seq: RTP_SEQ_MOD + 1,
+            received: 1,
+            expected_prior: 0,
+            received_prior: 0,
+            out_of_order: 0,
+            duplicates: 0,
+            seq_errors: 0,
+            bursts: 0,
+            burst_max: 0,
+            burst_lost: 0,
+            last_arrival: Some((ts, h.timestamp)),
+            jitter: 0.0,
+            jitter_max: 0.0,
+            first_arrival: (ts, h.timestamp),
+            first_us: ts,
+            last_us: ts,
+            bye: false,
+        }
+    }
+
+    /// Período de prueba de un candidato heurístico: números de secuencia consecutivos
+    fn probe(&mut self, h: &RtpHeader, ts: u64) {
+        if h.sequence == self.max_seq.wrapping_add(1) && h.payload_type == self.payload_type {
+            self.probation -= 1;
+            self.max_seq = h.sequence;
+            self.packets += 1;
+            self.received += 1;
+            self.payload_bytes += h.payload_len as u64;
+            self.update_jitter(h, ts);
+            if self.probation == 0 {
+                self.confirmed = true;
+            }
+        } else {
+            let first = RtpStream::new(
+                StreamKey {
+                    flow: self.flow,
+                    ssrc: self.ssrc,
+                },
+                h,
+                None,
+                ts,
+            );
+            *self = first;
+        }
+        self.last_us = ts;
+    }
+
+    fn on_packet(&mut self, h: &RtpHeader, ts: u64) {
+        if !self.confirmed {
+            self.probe(h, ts);
+            return;
+        }
+        self.last_us = ts;
+        self.packets += 1;
+        self.payload_bytes += h.payload_len as u64;
+        self.markers += h.marker as u64;
+        if h.payload_type != self.payload_type {
+            // DTMF (telephone-event) o CN intercalados no cambian el reloj del flujo
+            self.payload_type_changes += 1;
+        }
+
+        let udelta = h.sequence.wrapping_sub(self.max_seq);
+        if udelta == 0 {
+            self.duplicates += 1;
+            return;
+        } else if udelta < MAX_DROPOUT {
+            if h.sequence < self.max_seq {
+                self.cycles += 1;
+            }
+            if udelta > 1 {
+                let gap = (udelta - 1) as u64;
+                self.bursts += 1;
+                self.burst_lost += gap;
+                self.burst_max = self.burst_max.max(gap);
+            }
+            self.max_seq = h.sequence;
+            if h.payload_type == self.payload_type {
+                self.update_jitter(h, ts);
+            }
+        } else if udelta as u32 <= RTP_SEQ_MOD - MAX_MISORDER as u32 {
+            // Salto grande: dos paquetes consecutivos tras él indican que el emisor reinició
+            self.seq_errors += 1;
+            if h.sequence as u32 != self.bad_seq {
+                self.bad_seq = (h.sequence as u32 + 1) & (RTP_SEQ_MOD - 1);
+                return;
+            }
+            self.expected_prior += self.expected_current();
+            self.received_prior += self.received;
+            self.base_seq = h.sequence;
+            self.max_seq = h.sequence;
+            self.cycles = 0;
+            self.received = 0;
+            self.last_arrival = Some((ts, h.timestamp));
+        } else {
+            self.out_of_order += 1;
+        }
+        self.received += 1;
+    }
+
+    fn update_jitter(&mut self, h: &RtpHeader, ts: u64) {
+        if self.clock_hz.is_none() {
+            self.estimate_clock(h.timestamp, ts);
+        }
+        if let (Some((last_us, last_ts)), Some(clock)) = (self.last_arrival, self.clock_hz) {
+            let arrival = ts.saturating_sub(last_us) as f64 * clock as f64 / 1e6;
+            let sent = h.timestamp.wrapping_sub(last_ts) as i32 as f64;
+            let d = (arrival - sent).abs();
+            self.jitter += (d - self.jitter) / 16.0;
+            self.jitter_max = self.jitter_max.max(self.jitter);
+        }
+        self.last_arrival = Some((ts, h.timestamp));
+    }
+
+    /// Reloj de un payload type dinámico sin SDP: avance del timestamp RTP frente al tiempo real
+    fn estimate_clock(&mut self, rtp_ts: u32, ts: u64) {
+        let elapsed_us = ts.saturating_sub(self.first_arrival.0);
+        if elapsed_us < 1_000_000 {
+            return;
+        }
+        let rate = rtp_ts.wrapping_sub(self.first_arrival.1) as f64 * 1e6 / elapsed_us as f64;
+        self.clock_hz = COMMON_CLOCKS
+            .iter()
+            .min_by(|a, b| {
+                let da = (rate / **a as f64).ln().abs();
+                let db = (rate / **b as f64).ln().abs();
+                da.total_cmp(&db)
+            })
+            .copied();
+        self.clock_estimated = true;
+    }
+
+    fn expected_current(&self) -> u64 {
+        let extended_max = self.cycles as u64 * RTP_SEQ_MOD as u64 + self.max_seq as u64;
+        extended_max.saturating_sub(self.base_seq as u64) + 1
+    }
+
+    fn expected(&self) -> u64 {
+        self.expected_prior + self.expected_current()
+    }
+
+    fn lost(&self) -> u64 {
+        self.expected()
+            .saturating_sub(self.received_prior + self.received)
+    }
+
+    fn codec(&self) -> Option<String> {
+        match &self.hint {
+            Some(m) => m.codec(self.payload_type).map(|(c, _)| c),
+            None => rtp::static_payload(self.payload_type).map(|(c, _)| c.to_string()),
+        }
+    }
+
+    fn media(&self) -> &str {
+        match (&self.media, self.clock_hz) {
+            (Some(m), _) => m,
+            (None, Some(90000)) => "video",
+            _ => "audio",
+        }
+    }
+
+    fn ms(&self, units: f64) -> Option<f64> {
+        self.clock_hz.map(|hz| units / hz as f64 * 1000.0)
+    }
+}
+
+/// Parámetros del E-model por códec (ITU-T G.113 Apéndice I): (Ie, Bpl)
+fn codec_impairment(codec: Option<&str>) -> (f64, f64) {
+    match codec.map(|c| c.to_ascii_uppercase()).as_deref() {
+        Some("G729") => (11.0, 19.0),
+        Some("G723") => (15.0, 16.1),
+        Some("GSM") => (20.0, 10.0),
+        // G.711 con ocultación de pérdidas; también para códecs modernos sin valores publicados
+        _ => (0.0, 25.1),
+    }
+}
+
+/// R-factor del E-model simplificado (G.107) y su MOS estimado
+fn r_factor(
+    loss_pct: f64,
+    burst_ratio: f64,
+    jitter_ms: f64,
+    one_way_ms: f64,
+    codec: Option<&str>,
+) -> (f64, f64) {
+    let effective_ms = one_way_ms + 2.0 * jitter_ms + 10.0;
+    let id = if effective_ms < 160.0 {
+        effective_ms / 40.0
+    } else {
+        (effective_ms - 120.0) / 10.0
+    };
+    let (ie, bpl) = codec_impairment(codec);
+    let ie_eff = ie + (95.0 - ie) * loss_pct / (loss_pct / burst_ratio + bpl);
+    let r = (93.2 - id - ie_eff).clamp(0.0, 100.0);
+    let mos = 1.0 + 0.035 * r + 7e-6 * r * (r - 60.0) * (100.0 - r);
+    (r, mos.clamp(1.0, 4.5))
+}
+
+struct StreamSummary {
+    label: String,
+    score: u32,
+    level: &'static str,
+    reasons: Vec<String>,
+    json: Value,
+}
+
+fn compute_severity(
+    loss_pct: f64,
+    jitter_ms: Option<f64>,
+    mos: Option<f64>,
+    s: &RtpStream,
+) -> (u32, &'static str, Vec<String>) {
+    let jitter = jitter_ms.unwrap_or(0.0);
+    let ooo_pct = if s.packets > 0 {
+        s.out_of_order as f64 / s.packets as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    let score_f = 10.0 * loss_pct
+        + jitter
+        + mos.map(|m| (4.4 - m).max(0.0) * 40.0).unwrap_or(0.0)
+        + 5.0 * s.seq_errors as f64
+        + 2.0 * ooo_pct;
+    let score = score_f.round() as u32;
+
+    let mut reasons = Vec::new();
+    if loss_pct >= 1.0 {
+        reasons.push(format!("pérdida {loss_pct:.1}% ({} paquetes)", s.lost()));
+    }
+    if s.burst_max >= 5 {
+        reasons.push(format!(
+            "ráfagas de pérdida de hasta {} paquetes",
+            s.burst_max
+        ));
+    }
+    if jitter >= 30.0 {
+        reasons.push(format!("jitter {jitter:.1} ms"));
+    }
+    if let Some(m) = mos.filter(|m| *m < 3.6) {
+        reasons.push(format!("MOS estimado {m:.2}"));
+    }
+    if s.seq_errors > 0 {
+        reasons.push(format!("saltos de secuencia ({})", s.seq_errors));
+    }
+    if ooo_pct > 2.0 {
+        reasons.push(format!("fuera de orden {ooo_pct:.1}% ({})", s.out_of_order));
+    }
+
+    let mos = mos.unwrap_or(4.5);
+    let level = if mos < 3.1 || loss_pct >= 5.0 || jitter >= 50.0 {
+        "ALTA"
+    } else if mos < 3.6 || loss_pct >= 1.0 || jitter >= 30.0 || s.seq_errors > 0 || ooo_pct > 2.0 {
+        "MEDIA"
+    } else {
+        "BAJA"
+    };
+    (score, level, reasons)
+}
+
+#[derive(Debug, Default)]
+struct RtcpCounters {
+    packets: u64,
+    sender_reports: u64,
+    receiver_reports: u64,
+    bye: u64,
+    other: u64,
+}
+
+/// Calidad de voz y vídeo sobre RTP: flujos por SSRC (vía SDP o heurística) con
+/// jitter y pérdida según RFC 3550, informes RTCP y MOS/R-factor estimado
+#[derive(Default)]
+pub struct RtpDetector {
+    endpoints: HashMap<(IpAddr, u16), MediaHint>,
+    endpoints_truncated: u64,
+    streams: HashMap<StreamKey, RtpStream>,
+    streams_truncated: u64,
+    rtcp_views: HashMap<u32, RtcpView>,
+    // (SSRC emisor, NTP central) → instante de captura del SR, para el RTT vía LSR/DLSR
+    sender_reports: HashMap<(u32, u32), u64>,
+    rtcp: RtcpCounters,
+    sdp_sessions: u64,
+    last_sweep_us: u64,
+}
+
+impl RtpDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn on_sip(&mut self, payload: &[u8]) {
+        let Some(msg) = sip::parse(payload) else {
+            return;
+        };
+        let Some(media) = msg.sdp().and_then(sdp::parse) else {
+            return;
+        };
+        self.sdp_sessions += 1;
+        let call_id = msg.call_id().map(str::to_string);
+        for m in media.into_iter().filter(|m| m.port != 0) {
+            let Some(addr) = m.addr else {
+                continue;
+            };
+            for (port, rtcp) in [(m.port, false), (m.rtcp_port(), true)] {
+                if self.endpoints.len() >= MAX_ENDPOINTS
+                    && !self.endpoints.contains_key(&(addr, port))
+                {
+                    self.endpoints_truncated += 1;
+                    continue;
+                }
+                self.endpoints.insert(
+                    (addr, port),
+                    MediaHint {
+                        media: m.clone(),
+                        call_id: call_id.clone(),
+                        rtcp,
+                    },
+                );
+            }
+        }
+    }
+
+    fn hint(&self, src: (IpAddr, u16), dst: (IpAddr, u16)) -> Option<&MediaHint> {
+        // El SDP anuncia dónde recibe cada extremo; con RTP simétrico también es su origen
+        self.endpoints
+            .get(&dst)
+            .or_else(|| self.endpoints.get(&src))
+    }
+
+    fn on_rtp(&mut self, flow: Flow, h: RtpHeader, sdp: bool, ts: u64) {
+        let key = StreamKey { flow, ssrc: h.ssrc };
+        if let Some(s) = self.streams.get_mut(&key) {
+            s.on_packet(&h, ts);
+            return;
+        }
+        if self.streams.len() >= MAX_STREAMS {
+            self.streams_truncated += 1;
+            return;
+        }
+        let hint = if sdp {
+            self.hint(
+                (flow.source_ip, flow.source_port),
+                (flow.destination_ip, flow.destination_port),
+            )
+        } else {
+            None
+        };
+        let stream = RtpStream::new(key, &h, hint, ts);
+        self.streams.insert(key, stream);
+    }
+
+    fn on_rtcp(&mut self, packets: Vec<RtcpPacket>, ts: u64) {
+        self.rtcp.packets += 1;
+        for p in packets {
+            match p {
+                RtcpPacket::SenderReport {
+                    ssrc,
+                    ntp_middle,
+                    reports,
+                    ..
+                } => {
+                    self.rtcp.sender_reports += 1;
+                    if self.sender_reports.len() < MAX_SENDER_REPORTS {
+                        self.sender_reports.insert((ssrc, ntp_middle), ts);
+                    }
+                    self.on_report_blocks(&reports, ts);
+                }
+                RtcpPacket::ReceiverReport { reports, .. } => {
+                    self.rtcp.receiver_reports += 1;
+                    self.on_report_blocks(&reports, ts);
+                }
+                RtcpPacket::Bye { ssrcs } => {
+                    self.rtcp.bye += 1;
+                    for s in self.streams.values_mut() {
+                        if ssrcs.contains(&s.ssrc) {
+                            s.bye = true;
+                        }
+                    }
+                }
+                RtcpPacket::Other { .. } => self.rtcp.other += 1,
+            }
+        }
+    }
+
+    fn on_report_blocks(&mut self, blocks: &[rtp::ReportBlock], ts: u64) {
+        for b in blocks {
+            if !self.rtcp_views.contains_key(&b.ssrc) && self.rtcp_views.len() >= MAX_STREAMS {
+                continue;
+            }
+            // RTT visto desde el punto de captura: llegada del RR - SR citado - DLSR
+            let rtt_us = (b.lsr != 0)
+                .then(|| self.sender_reports.get(&(b.ssrc, b.lsr)))
+                .flatten()
+                .and_then(|sr_ts| {
+                    let dlsr_us = b.dlsr as u64 * 1_000_000 / 65536;
+                    ts.checked_sub(*sr_ts)?.checked_sub(dlsr_us)
+                });
+            let v = self.rtcp_views.entry(b.ssrc).or_default();
+            v.reports += 1;
+            v.fraction_lost = b.fraction_lost as f64 / 256.0;
+            v.cumulative_lost = b.cumulative_lost;
+            v.jitter_units = b.jitter;
+            if rtt_us.is_some() {
+                v.rtt_us = rtt_us;
+            }
+        }
+    }
+
+    fn sweep(&mut self, now: u64) {
+        if now.saturating_sub(self.last_sweep_us) < 1_000_000 {
+            return;
+        }
+        self.last_sweep_us = now;
+        self.streams
+            .retain(|_, s| s.confirmed || now.saturating_sub(s.last_us) < CANDIDATE_IDLE_US);
+        self.sender_reports
+            .retain(|_, sr_ts| now.saturating_sub(*sr_ts) < SENDER_REPORT_TTL_US);
+    }
+
+    fn summarize(&self, s: &RtpStream) -> StreamSummary {
+        let expected = s.expected();
+        let lost = s.lost();
+        let loss_pct = if expected > 0 {
+            lost as f64 / expected as f64 * 100.0
+        } else {
+            0.0
+        };
+        let jitter_ms = s.ms(s.jitter);
+        let rtcp = self.rtcp_views.get(&s.ssrc);
+        let codec = s.codec();
+
+        let quality = (s.media() == "audio").then(|| {
+            let p = loss_pct / 100.0;
+            // Longitud media de ráfaga frente a la esperada con pérdida aleatoria (1/(1-p))
+            let burst_ratio = if s.bursts > 0 && p < 1.0 {
+                (s.burst_lost as f64 / s.bursts as f64 * (1.0 - p)).max(1.0)
+            } else {
+                1.0
+            };
+            let one_way_ms = rtcp
+                .and_then(|v| v.rtt_us)
+                .map(|rtt| rtt as f64 / 2000.0)
+                .unwrap_or(0.0);
+            r_factor(
+                loss_pct,
+                burst_ratio,
+                jitter_ms.unwrap_or(0.0),
+                one_way_ms,
+                codec.as_deref(),
+            )
+        });
+        let (score, level, reasons) =
+            compute_severity(loss_pct, jitter_ms, quality.map(|q| q.1), s);
+
+        let label = format!(
+            "{} -> {}/RTP",
+            endpoint_label(s.flow.source_ip, s.flow.source_port),
+            endpoint_label(s.flow.destination_ip, s.flow.destination_port)
+        );
+        let json = json!({
+            "flow": label,
+            "ssrc": format!("0x{:08x}", s.ssrc),
+            "identified_by": s.identified_by,
+            "call_id": s.call_id,
+            "media": s.media(),
+            "payload_type": s.payload_type,
+            "codec": codec,
+            "clock_hz": s.clock_hz,
+            "clock_estimated": s.clock_estimated,
+            "packets": s.packets,
+            "payload_bytes": s.payload_bytes,
+            "duration_ms": s.last_us.saturating_sub(s.first_us) as f64 / 1000.0,
+            "expected": expected,
+            "lost": lost,
+            "loss_pct": round2(loss_pct),
+            "out_of_order": s.out_of_order,
+            "duplicates": s.duplicates,
+            "seq_errors": s.seq_errors,
+            "payload_type_changes": s.payload_type_changes,
+            "burst_loss": {
+                "bursts": s.bursts,
+                "max_len": s.burst_max,
+                "mean_len": if s.bursts > 0 { round2(s.burst_lost as f64 / s.bursts as f64) } else { 0.0 }
+            },
+            "jitter_ms": jitter_ms.map(round2),
+            "max_jitter_ms": s.ms(s.jitter_max).map(round2),
+            "rtcp": rtcp.map(|v| json!({
+                "reports": v.reports,
+                "fraction_lost_pct": round2(v.fraction_lost * 100.0),
+                "cumulative_lost": v.cumulative_lost,
+                "jitter_ms": s.ms(v.jitter_units as f64).map(round2),
+                "rtt_ms": v.rtt_us.map(|us| us as f64 / 1000.0)
+            })),
+            "bye": s.bye,
+            "quality": quality.map(|(r, mos)| json!({ "r_factor": round2(r), "mos": round2(mos) })),
+            "score": { "value": score, "level": level },
+            "reasons": reasons
+        });
+        StreamSummary {
+            label,
+            score,
+            level,
+            reasons,
+            json,
+        }
+    }
+}
+
+impl Detector for RtpDetector {
+    fn name(&self) -> &'static str {
+        "rtp"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        self.sweep(ts_micros);
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        let udp = match &sliced.transport {
+            Some(TransportSlice::Udp(udp)) => udp,
+            // SIP sobre TCP: solo mensajes contenidos en un segmento
+            Some(TransportSlice::Tcp(tcp)) => {
+                if sip::looks_like_sip(tcp.payload()) {
+                    self.on_sip(tcp.payload());
+                }
+                return;
+            }
+            _ => return,
+        };
+        let payload = udp.payload();
+        let flow = Flow {
+            source_ip: src,
+            source_port: udp.source_port(),
+            destination_ip: dst,
+            destination_port: udp.destination_port(),
+        };
+        if sip::looks_like_sip(payload) {
+            self.on_sip(payload);
+            return;
+        }
+
+        let hint = self.hint((src, flow.source_port), (dst, flow.destination_port));
+        let rtcp_endpoint = hint.map(|h| h.rtcp);
+        let known = self.streams.contains_key(&StreamKey {
+            flow,
+            ssrc: rtp::parse_rtp(payload).map(|h| h.ssrc).unwrap_or_default(),
+        });
+        let heuristic_ok = [flow.source_port, flow.destination_port]
+            .iter()
+            .all(|p| *p >= 1024 && !NON_RTP_PORTS.contains(p));
+        if rtcp_endpoint.is_none() && !heuristic_ok && !known {
+            return;
+        }
+
+        // Con rtcp-mux RTP y RTCP comparten puerto: el tipo decide (RFC 5761)
+        if let Some(packets) = rtp::parse_rtcp(payload).filter(|p| {
+            matches!(
+                p.first(),
+                Some(RtcpPacket::SenderReport { .. } | RtcpPacket::ReceiverReport { .. })
+            )
+        }) {
+            self.on_rtcp(packets, ts_micros);
+            return;
+        }
+        if rtcp_endpoint == Some(true) {
+            return;
+        }
+        let Some(h) = rtp::parse_rtp(payload) else {
+            return;
+        };
+        let sdp = rtcp_endpoint == Some(false);
+        // Sin SDP solo se aceptan payload types estáticos conocidos o dinámicos
+        if !sdp && !known && rtp::static_payload(h.payload_type).is_none() && h.payload_type < 96 {
+            return;
+        }
+        self.on_rtp(flow, h, sdp, ts_micros);
+    }
+
+    fn finalize(&mut self) -> Value {
+        let mut summaries: Vec<StreamSummary> = self
+            .streams
+            .values()
+            .filter(|s| s.confirmed)
+            .map(|s| self.summarize(s))
+            .collect();
+        summaries.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.label.cmp(&b.label)));
+
+        let mut by_level: BTreeMap<&str, u64> = BTreeMap::new();
+        let mut by_identification: BTreeMap<&str, u64> = BTreeMap::new();
+        for s in self.streams.values().filter(|s| s.confirmed) {
+            *by_identification.entry(s.identified_by).or_default() += 1;
+        }
+        for s in &summaries {
+            *by_level.entry(s.level).or_default() += 1;
+        }
+        let with_reasons = summaries.iter().filter(|s| !s.reasons.is_empty()).count();
+
+        json!({
+            "streams_total": summaries.len(),
+            "streams_truncated": self.streams_truncated,
+            "streams_with_issues": with_reasons,
+            "by_identification": by_identification,
+            "counts_by_level": by_level,
+            "sdp": {
+                "sessions": self.sdp_sessions,
+                "endpoints": self.endpoints.len(),
+                "endpoints_truncated": self.endpoints_truncated
+            },
+            "rtcp": {
+                "packets": self.rtcp.packets,
+                "sender_reports": self.rtcp.sender_reports,
+                "receiver_reports": self.rtcp.receiver_reports,
+                "bye": self.rtcp.bye,
+                "other": self.rtcp.other
+            },
+            "top_by_severity": summaries.into_iter().map(|s| s.json).collect::<Vec<_>>()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::rtp::tests::{rtcp_sr_rr, rtp_packet};
+    use crate::network::sip::tests::invite;
+    use etherparse::PacketBuilder;
+
+    const ALICE: [u8; 4] = [10, 0, 0, 1];
+    const BOB: [u8; 4] = [10, 0, 0, 2];
+
+    fn udp(src: ([u8; 4], u16), dst: ([u8; 4], u16), payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(src.0, dst.0, 64)
+            .udp(src.1, dst.1)
+            .write(&mut out, payload)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn sdp_stream_with_loss_jitter_rtcp_and_mos() {
+        let mut d = RtpDetector::new();
+        // Bob anuncia dónde recibe el audio
+        d.on_packet(
+            &udp(
+                (BOB, 5060),
+                (ALICE, 5060),
+                &invite("call-1", "10.0.0.2", 30000),
+            ),
+            0,
+        );
+        // 100 paquetes PCMU cada 20 ms; se pierden el 10, 50-54 y el 80 llega tarde
+        let mut ts = 1_000_000;
+        for seq in 0u16..100 {
+            ts += 20_000;
+            if seq == 10 || (50..55).contains(&seq) || seq == 80 {
+                continue;
+            }
+            // El paquete 30 llega 40 ms tarde respecto a su instante nominal
+            let arrival = if seq == 30 { ts + 40_000 } else { ts };
+            d.on_packet(
+                &udp(
+                    (ALICE, 40000),
+                    (BOB, 30000),
+                    &rtp_packet(0, seq, seq as u32 * 160, 0xabc, 160),
+                ),
+                arrival,
+            );
+            if seq == 85 {
+                d.on_packet(
+                    &udp(
+                        (ALICE, 40000),
+                        (BOB, 30000),
+                        &rtp_packet(0, 80, 80 * 160, 0xabc, 160),
+                    ),
+                    ts + 1,
+                );
+            }
+        }
+        // SR de Alice y RR de Bob que lo cita 50 ms después (DLSR 10 ms)
+        d.on_packet(
+            &udp(
+                (ALICE, 40001),
+                (BOB, 30001),
+                &rtcp_sr_rr(0xabc, 0x1111_0000, 0xdef, [0, 0, 0, 0, 0]),
+            ),
+            ts,
+        );
+        d.on_packet(
+            &udp(
+                (BOB, 30001),
+                (ALICE, 40001),
+                &rtcp_sr_rr(
+                    0xdef,
+                    0x2222_0000,
+                    0xabc,
+                    [0x0d00_0006, 99, 24, 0x1111_0000, 655],
+                ),
+            ),
+            ts + 50_000,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["streams_total"], 1);
+        assert_eq!(r["by_identification"]["sdp"], 1);
+        assert_eq!(r["rtcp"]["sender_reports"], 2);
+        let s = &r["top_by_severity"][0];
+        assert_eq!(s["flow"], "10.0.0.1:40000 -> 10.0.0.2:30000/RTP");
+        assert_eq!(s["call_id"], "call-1");
+        assert_eq!(s["codec"], "PCMU");
+        assert_eq!(s["expected"], 100);
+        assert_eq!(s["lost"], 6);
+        assert_eq!(s["out_of_order"], 1);
+        assert_eq!(s["burst_loss"]["bursts"], 3);
+        assert_eq!(s["burst_loss"]["max_len"], 5);
+        assert!(s["jitter_ms"].as_f64().unwrap() > 0.0);
+        assert_eq!(s["rtcp"]["cumulative_lost"], 6);
+        assert_eq!(s["rtcp"]["jitter_ms"], 3.0);
+        // 50 ms desde el SR menos 10 ms de DLSR (655/65536 s)
+        assert_eq!(s["rtcp"]["rtt_ms"], 40.006);
+        assert_eq!(s["score"]["level"], "ALTA");
+        let mos = s["quality"]["mos"].as_f64().unwrap();
+        assert!(mos < 4.0, "{mos}");
+        assert!(s["quality"]["r_factor"].as_f64().unwrap() < 80.0);
+        assert!(s["reasons"][0]
+            .as_str()
+            .unwrap()
+            .starts_with("pérdida 6.0%"));
+    }
+
+    #[test]
+    fn heuristic_detection_needs_sequential_packets_and_clean_stream_is_low() {
+        let mut d = RtpDetector::new();
+        // Tráfico UDP que parece RTP por casualidad: secuencias sin orden
+        for (i, seq) in [7u16, 900, 3, 4000].iter().enumerate() {
+            d.on_packet(
+                &udp(
+                    (ALICE, 50000),
+                    (BOB, 50002),
+                    &rtp_packet(96, *seq, 0, 1, 50),
+                ),
+                i as u64 * 1000,
+            );
+        }
+        // Opus dinámico sin SDP: reloj estimado a 48 kHz
+        for seq in 0u16..200 {
+            d.on_packet(
+                &udp(
+                    (ALICE, 16384),
+                    (BOB, 16386),
+                    &rtp_packet(111, seq, seq as u32 * 960, 2, 80),
+                ),
+                10_000_000 + seq as u64 * 20_000,
+            );
+        }
+        // DNS no se toma por RTP aunque los bytes encajen
+        d.on_packet(
+            &udp((ALICE, 53), (BOB, 53000), &rtp_packet(0, 1, 1, 3, 20)),
+            1,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["streams_total"], 1);
+        let s = &r["top_by_severity"][0];
+        assert_eq!(s["identified_by"], "heuristic");
+        assert_eq!(s["clock_hz"], 48000);
+        assert_eq!(s["clock_estimated"], true);
+        assert_eq!(s["packets"], 200);
+        assert_eq!(s["lost"], 0);
+        assert_eq!(s["jitter_ms"], 0.0);
+        assert_eq!(s["score"]["level"], "BAJA");
+        assert!(s["quality"]["mos"].as_f64().unwrap() > 4.3);
+    }
+}
@@ -0,0 +1,676 @@
+use crate::detectors::finding::{level, Finding};
+use crate::engine::Detector;
+use crate::network::flow::{PROTO_TCP, PROTO_UDP};
+use crate::network::format::endpoint_label;
+use crate::network::icmp::{self, IcmpMessage};
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+
+// Sondeos distintos en memoria (scanner, destino, puerto); el resto solo se cuenta
+const MAX_PROBES: usize = 500_000;
+const MAX_REPORTED: usize = 1000;
+const MAX_TARGETS_LISTED: usize = 20;
+const MAX_OPEN_LISTED: usize = 50;
+const MAX_RANGES: usize = 64;
+// Ventana de los contadores de distintos
+const WINDOW_US: u64 = 60_000_000;
+const VERTICAL_MIN_PORTS: usize = 20;
+const HORIZONTAL_MIN_HOSTS: usize = 20;
+// A partir de aquí el barrido escala a ALTA
+const WIDE_SCAN: usize = 100;
+const STEALTH_MIN_PROBES: u64 = 5;
+// Un cliente legítimo obtiene respuesta en la mayoría de sus intentos
+const MIN_FAILED_RATIO: f64 = 0.5;
+// Los sondeos UDP suelen ir vacíos o con payloads mínimos
+const UDP_PROBE_MAX_PAYLOAD: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum ProbeKind {
+    Syn,
+    Fin,
+    Null,
+    Xmas,
+    Udp,
+}
+
+impl ProbeKind {
+    fn name(self) -> &'static str {
+        match self {
+            ProbeKind::Syn => "syn",
+            ProbeKind::Fin => "fin",
+            ProbeKind::Null => "null",
+            ProbeKind::Xmas => "xmas",
+            ProbeKind::Udp => "udp",
+        }
+    }
+
+    /// Combinaciones de flags que ninguna pila envía fuera de un escaneo
+    fn stealth(self) -> bool {
+        matches!(self, ProbeKind::Fin | ProbeKind::Null | ProbeKind::Xmas)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Response {
+    Unanswered,
+    Open,
+    Closed,
+    Filtered,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ProbeKey {
+    scanner: IpAddr,
+    target: IpAddr,
+    port: u16,
+    udp: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Probe {
+    first_us: u64,
+    kind: ProbeKind,
+    response: Response,
+}
+
+/// Paquetes de sondeo enviados por un origen (repeticiones incluidas)
+#[derive(Debug, Default)]
+struct Activity {
+    packets: u64,
+    first_us: u64,
+    last_us: u64,
+}
+
+/// Puertos ordenados como rangos compactos: `1-1024,3306,8080-8081`
+fn port_ranges(ports: &BTreeSet<u16>) -> String {
+    let mut ranges: Vec<(u16, u16)> = Vec::new();
+    for &p in ports {
+        match ranges.last_mut() {
+            Some((_, end)) if end.checked_add(1) == Some(p) => *end = p,
+            _ => ranges.push((p, p)),
+        }
+    }
+    let mut parts: Vec<String> = ranges
+        .iter()
+        .take(MAX_RANGES)
+        .map(|(a, b)| {
+            if a == b {
+                a.to_string()
+            } else {
+                format!("{a}-{b}")
+            }
+        })
+        .collect();
+    if ranges.len() > MAX_RANGES {
+        parts.push("…".to_string());
+    }
+    parts.join(",")
+}
+
+/// Máximo de puertos distintos en un host y de hosts distintos en un puerto
+/// dentro de cualquier ventana de `WINDOW_US`
+struct WindowPeaks {
+    ports_per_host: usize,
+    host: Option<IpAddr>,
+    hosts_per_port: usize,
+    port: Option<(u16, bool)>,
+}
+
+fn window_peaks(probes: &[(ProbeKey, Probe)]) -> WindowPeaks {
+    let mut peaks = WindowPeaks {
+        ports_per_host: 0,
+        host: None,
+        hosts_per_port: 0,
+        port: None,
+    };
+    let mut per_host: HashMap<IpAddr, usize> = HashMap::new();
+    let mut per_port: HashMap<(u16, bool), usize> = HashMap::new();
+    let mut start = 0;
+    for (k, p) in probes {
+        // Cada clave es un sondeo distinto: entra una vez en cada contador
+        *per_host.entry(k.target).or_default() += 1;
+        *per_port.entry((k.port, k.udp)).or_default() += 1;
+        while probes[start].1.first_us + WINDOW_US < p.first_us {
+            let old = probes[start].0;
+            *per_host.entry(old.target).or_default() -= 1;
+            *per_port.entry((old.port, old.udp)).or_default() -= 1;
+            start += 1;
+        }
+        let ports = per_host[&k.target];
+        if ports > peaks.ports_per_host {
+            peaks.ports_per_host = ports;
+            peaks.host = Some(k.target);
+        }
+        let hosts = per_port[&(k.port, k.udp)];
+        if hosts > peaks.hosts_per_port {
+            peaks.hosts_per_port = hosts;
+            peaks.port = Some((k.port, k.udp));
+        }
+    }
+    peaks
+}
+
+fn port_label((port, udp): (u16, bool)) -> String {
+    format!("{port}/{}", if udp { "udp" } else { "tcp" })
+}
+
+/// Escaneos de puertos: verticales (muchos puertos de un host), horizontales
+/// (un puerto en muchos hosts) y sondeos SYN/FIN/NULL/Xmas/UDP, con el perfil
+/// de respuestas (abierto/cerrado/filtrado) de cada scanner
+#[derive(Default)]
+pub struct ScanDetector {
+    probes: HashMap<ProbeKey, Probe>,
+    probes_truncated: u64,
+    activity: HashMap<IpAddr, Activity>,
+}
+
+impl ScanDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn on_probe(&mut self, key: ProbeKey, kind: ProbeKind, ts: u64) {
+        let a = self
+            .activity
+            .entry(key.scanner)
+            .or_insert_with(|| Activity {
+                first_us: ts,
+                ..Activity::default()
+            });
+        a.packets += 1;
+        a.last_us = ts;
+        if let Some(p) = self.probes.get_mut(&key) {
+            // Un sondeo FIN/NULL/Xmas prevalece sobre un SYN previo al mismo puerto
+            if p.kind == ProbeKind::Syn && kind.stealth() {
+                p.kind = kind;
+            }
+            return;
+        }
+        if self.probes.len() >= MAX_PROBES {
+            self.probes_truncated += 1;
+            return;
+        }
+        self.probes.insert(
+            key,
+            Probe {
+                first_us: ts,
+                kind,
+                response: Response::Unanswered,
+            },
+        );
+    }
+
+    /// Respuesta del destino: solo la primera cuenta
+    fn on_response(&mut self, key: ProbeKey, response: Response) -> bool {
+        match self.probes.get_mut(&key) {
+            Some(p) => {
+                if p.response == Response::Unanswered {
+                    p.response = response;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn on_tcp(&mut self, src: IpAddr, dst: IpAddr, tcp: &etherparse::TcpSlice, ts: u64) {
+        let reply = ProbeKey {
+            scanner: dst,
+            target: src,
+            port: tcp.source_port(),
+            udp: false,
+        };
+        if tcp.syn() && tcp.ack() {
+            self.on_response(reply, Response::Open);
+            return;
+        }
+        if tcp.rst() {
+            self.on_response(reply, Response::Closed);
+            return;
+        }
+        let kind = if tcp.syn() && !tcp.ack() && !tcp.fin() {
+            ProbeKind::Syn
+        } else if tcp.ack() || tcp.syn() || tcp.rst() {
+            return;
+        } else if tcp.fin() && tcp.psh() && tcp.urg() {
+            ProbeKind::Xmas
+        } else if tcp.fin() {
+            ProbeKind::Fin
+        } else if !tcp.psh() && !tcp.urg() && !tcp.ece() && !tcp.cwr() {
+            ProbeKind::Null
+        } else {
+            return;
+        };
+        let key = ProbeKey {
+            scanner: src,
+            target: dst,
+            port: tcp.destination_port(),
+            udp: false,
+        };
+        self.on_probe(key, kind, ts);
+    }
+
+    fn on_udp(&mut self, src: IpAddr, dst: IpAddr, udp: &etherparse::UdpSlice, ts: u64) {
+        let reply = ProbeKey {
+            scanner: dst,
+            target: src,
+            port: udp.source_port(),
+            udp: true,
+        };
+        if self.on_response(reply, Response::Open) || udp.payload().len() > UDP_PROBE_MAX_PAYLOAD {
+            return;
+        }
+        let key = ProbeKey {
+            scanner: src,
+            target: dst,
+            port: udp.destination_port(),
+            udp: true,
+        };
+        self.on_probe(key, ProbeKind::Udp, ts);
+    }
+
+    fn on_icmp(&mut self, message: Option<IcmpMessage>) {
+        let Some(IcmpMessage::Error(e)) = message else {
+            return;
+        };
+        let Some(q) = icmp::quoted_flow(e.quoted) else {
+            return;
+        };
+        if q.protocol != PROTO_TCP && q.protocol != PROTO_UDP {
+            return;
+        }
+        let udp = q.protocol == PROTO_UDP;
+        let response = match e.name {
+            // El host contestó que no hay nadie escuchando
+            "port_unreachable" | "protocol_unreachable" => Response::Closed,
+            "admin_prohibited" | "host_unreachable" | "net_unreachable" | "no_route" => {
+                Response::Filtered
+            }
+            _ => return,
+        };
+        let key = ProbeKey {
+            scanner: q.flow.source_ip,
+            target: q.flow.destination_ip,
+            port: q.flow.destination_port,
+            udp,
+        };
+        self.on_response(key, response);
+    }
+
+    /// Evaluación de un origen; `None` si su actividad no parece un escaneo
+    fn evaluate(
+        &self,
+        scanner: IpAddr,
+        mut probes: Vec<(ProbeKey, Probe)>,
+    ) -> Option<(Value, Vec<Finding>)> {
+        probes.sort_by(|a, b| {
+            a.1.first_us
+                .cmp(&b.1.first_us)
+                .then_with(|| a.0.target.cmp(&b.0.target))
+                .then_with(|| (a.0.port, a.0.udp).cmp(&(b.0.port, b.0.udp)))
+        });
+        let peaks = window_peaks(&probes);
+
+        let mut kinds: BTreeMap<ProbeKind, u64> = BTreeMap::new();
+        let (mut open, mut closed, mut filtered) = (0u64, 0u64, 0u64);
+        let mut targets: BTreeSet<IpAddr> = BTreeSet::new();
+        let mut tcp_ports: BTreeSet<u16> = BTreeSet::new();
+        let mut udp_ports: BTreeSet<u16> = BTreeSet::new();
+        let mut open_ports: Vec<String> = Vec::new();
+        for (k, p) in &probes {
+            *kinds.entry(p.kind).or_default() += 1;
+            targets.insert(k.target);
+            if k.udp {
+                udp_ports.insert(k.port);
+            } else {
+                tcp_ports.insert(k.port);
+            }
+            match p.response {
+                Response::Open => {
+                    open += 1;
+                    if open_ports.len() < MAX_OPEN_LISTED {
+                        open_ports.push(format!(
+                            "{}/{}",
+                            endpoint_label(k.target, k.port),
+                            if k.udp { "udp" } else { "tcp" }
+                        ));
+                    }
+                }
+                Response::Closed => closed += 1,
+                Response::Filtered | Response::Unanswered => filtered += 1,
+            }
+        }
+        let total = probes.len() as u64;
+        let failed_ratio = (closed + filtered) as f64 / total.max(1) as f64;
+        let stealth: u64 = kinds
+            .iter()
+            .filter(|(k, _)| k.stealth())
+            .map(|(_, n)| *n)
+            .sum();
+
+        let vertical =
+            peaks.ports_per_host >= VERTICAL_MIN_PORTS && failed_ratio >= MIN_FAILED_RATIO;
+        let horizontal =
+            peaks.hosts_per_port >= HORIZONTAL_MIN_HOSTS && failed_ratio >= MIN_FAILED_RATIO;
+        let stealthy = stealth >= STEALTH_MIN_PROBES;
+        if !vertical && !horizontal && !stealthy {
+            return None;
+        }
+
+        let a = self.activity.get(&scanner)?;
+        let duration_us = a.last_us.saturating_sub(a.first_us);
+        let rate_pps = if duration_us > 0 {
+            a.packets as f64 / (duration_us as f64 / 1e6)
+        } else {
+            a.packets as f64
+        };
+        let subject = scanner.to_string();
+        let kinds_text: Vec<&str> = kinds.keys().map(|k| k.name()).collect();
+        let mut findings = Vec::new();
+        if vertical {
+            let score = if peaks.ports_per_host >= WIDE_SCAN {
+                100
+            } else {
+                60
+            };
+            let host = peaks.host.map(|h| h.to_string()).unwrap_or_default();
+            findings.push(Finding {
+                code: "PORT_SCAN_VERTICAL",
+                subject: subject.clone(),
+                score,
+                level: level(score),
+                reason: format!(
+                    "{} puertos distintos de {host} en {} s ({})",
+                    peaks.ports_per_host,
+                    WINDOW_US / 1_000_000,
+                    kinds_text.join("/")
+                ),
+                count: peaks.ports_per_host as u64,
+                examples: None,
+                detail: json!({ "target": host }),
+            });
+        }
+        if horizontal {
+            let score = if peaks.hosts_per_port >= WIDE_SCAN {
+                100
+            } else {
+                60
+            };
+            let port = peaks.port.map(port_label).unwrap_or_default();
+            findings.push(Finding {
+                code: "PORT_SCAN_HORIZONTAL",
+                subject: subject.clone(),
+                score,
+                level: level(score),
+                reason: format!(
+                    "{} hosts distintos en el puerto {port} en {} s",
+                    peaks.hosts_per_port,
+                    WINDOW_US / 1_000_000
+                ),
+                count: peaks.hosts_per_port as u64,
+                examples: None,
+                detail: json!({ "port": port }),
+            });
+        }
+        if stealthy {
+            findings.push(Finding {
+                code: "STEALTH_SCAN",
+                subject: subject.clone(),
+                score: 100,
+                level: "ALTA",
+                reason: format!("sondeos con flags FIN/NULL/Xmas ({stealth})"),
+                count: stealth,
+                examples: None,
+                detail: json!({
+                    "fin": kinds.get(&ProbeKind::Fin).copied().unwrap_or(0),
+                    "null": kinds.get(&ProbeKind::Null).copied().unwrap_or(0),
+                    "xmas": kinds.get(&ProbeKind::Xmas).copied().unwrap_or(0)
+                }),
+            });
+        }
+
+        let pattern = match (vertical, horizontal) {
+            (true, true) => "block",
+            (true, false) => "vertical",
+            (false, true) => "horizontal",
+            (false, false) => "stealth",
+        };
+        let score = findings.iter().map(|f| f.score).max().unwrap_or(0);
+        let report = json!({
+            "scanner": subject,
+            "pattern": pattern,
+            "score": { "value": score, "level": level(score) },
+            "reasons": findings.iter().map(|f| f.reason.clone()).collect::<Vec<_>>(),
+            "kinds": kinds.iter().map(|(k, n)| (k.name().to_string(), json!(n))).collect::<serde_json::Map<_, _>>(),
+            "probes": total,
+            "packets": a.packets,
+            "targets_total": targets.len(),
+            "targets": targets.iter().take(MAX_TARGETS_LISTED).map(|t| t.to_string()).collect::<Vec<_>>(),
+            "ports_total": tcp_ports.len() + udp_ports.len(),
+            "port_ranges": { "tcp": port_ranges(&tcp_ports), "udp": port_ranges(&udp_ports) },
+            "peak_ports_per_host": peaks.ports_per_host,
+            "peak_hosts_per_port": peaks.hosts_per_port,
+            "window_s": WINDOW_US / 1_000_000,
+            "first_ts_us": a.first_us,
+            "last_ts_us": a.last_us,
+            "duration_s": duration_us as f64 / 1e6,
+            "rate_pps": rate_pps,
+            "responses": { "open": open, "closed": closed, "filtered": filtered },
+            "open_ports": open_ports
+        });
+        Some((report, findings))
+    }
+}
+
+impl Detector for ScanDetector {
+    fn name(&self) -> &'static str {
+        "scan"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        match &sliced.transport {
+            Some(TransportSlice::Tcp(tcp)) => self.on_tcp(src, dst, tcp, ts_micros),
+            Some(TransportSlice::Udp(udp)) => self.on_udp(src, dst, udp, ts_micros),
+            Some(TransportSlice::Icmpv4(m)) => self.on_icmp(icmp::classify(
+                false,
+                m.type_u8(),
+                m.code_u8(),
+                m.bytes5to8(),
+                m.payload(),
+            )),
+            Some(TransportSlice::Icmpv6(m)) => self.on_icmp(icmp::classify(
+                true,
+                m.type_u8(),
+                m.code_u8(),
+                m.bytes5to8(),
+                m.payload(),
+            )),
+            _ => {}
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        let mut by_scanner: BTreeMap<IpAddr, Vec<(ProbeKey, Probe)>> = BTreeMap::new();
+        for (k, p) in &self.probes {
+            by_scanner.entry(k.scanner).or_default().push((*k, *p));
+        }
+
+        let mut scanners: Vec<(u32, Value)> = Vec::new();
+        let mut findings: Vec<Finding> = Vec::new();
+        for (scanner, probes) in by_scanner {
+            if let Some((report, mut f)) = self.evaluate(scanner, probes) {
+                let score = f.iter().map(|f| f.score).max().unwrap_or(0);
+                scanners.push((score, report));
+                findings.append(&mut f);
+            }
+        }
+        // Orden estable: score desc, luego IP (BTreeMap ya ordenado)
+        scanners.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        findings.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.code.cmp(b.code))
+                .then_with(|| a.subject.cmp(&b.subject))
+        });
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for f in &findings {
+            *counts.entry(f.code).or_default() += 1;
+        }
+
+        json!({
+            "scanners_total": scanners.len(),
+            "probes_tracked": self.probes.len(),
+            "probes_truncated": self.probes_truncated,
+            "scanners": scanners.into_iter().take(MAX_REPORTED).map(|(_, v)| v).collect::<Vec<_>>(),
+            "findings": findings.iter().take(MAX_REPORTED).map(Finding::to_json).collect::<Vec<_>>(),
+            "counts_by_code": counts
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::test_frames::{tcp, udp};
+    use etherparse::PacketBuilder;
+
+    const SCANNER: [u8; 4] = [10, 0, 0, 66];
+    const TARGET: [u8; 4] = [10, 0, 0, 2];
+
+    /// Port unreachable citando la cabecera IP + UDP del sondeo
+    fn port_unreachable(probe: &[u8]) -> Vec<u8> {
+        let quoted = &probe[14..14 + 28];
+        let ip = &probe[14..34];
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([2; 6], [1; 6])
+            .ipv4(
+                [ip[16], ip[17], ip[18], ip[19]],
+                [ip[12], ip[13], ip[14], ip[15]],
+                64,
+            )
+            .icmpv4(etherparse::Icmpv4Type::DestinationUnreachable(
+                etherparse::icmpv4::DestUnreachableHeader::Port,
+            ))
+            .write(&mut out, quoted)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn vertical_syn_scan_with_response_profile_and_stealth_probes() {
+        let mut d = ScanDetector::new();
+        let mut ts = 0;
+        for port in 1..=120u16 {
+            d.on_packet(&tcp(SCANNER, TARGET, 64, 40000, port, "S", &[]), ts);
+            match port {
+                22 | 80 => d.on_packet(&tcp(TARGET, SCANNER, 64, port, 40000, "SA", &[]), ts + 100),
+                p if p % 2 == 0 => {
+                    d.on_packet(&tcp(TARGET, SCANNER, 64, port, 40000, "RA", &[]), ts + 100)
+                }
+                // Impares: sin respuesta (filtrados)
+                _ => {}
+            }
+            ts += 1_000;
+        }
+        // Xmas y NULL contra unos cuantos puertos más
+        for port in 200..203u16 {
+            d.on_packet(&tcp(SCANNER, TARGET, 64, 40001, port, "FPU", &[]), ts);
+            d.on_packet(&tcp(SCANNER, TARGET, 64, 40001, port + 100, "", &[]), ts);
+        }
+
+        let r = d.finalize();
+        assert_eq!(r["scanners_total"], 1);
+        let s = &r["scanners"][0];
+        assert_eq!(s["scanner"], "10.0.0.66");
+        assert_eq!(s["pattern"], "vertical");
+        assert_eq!(s["kinds"]["syn"], 120);
+        assert_eq!(s["kinds"]["xmas"], 3);
+        assert_eq!(s["kinds"]["null"], 3);
+        assert_eq!(s["peak_ports_per_host"], 126);
+        assert_eq!(s["port_ranges"]["tcp"], "1-120,200-202,300-302");
+        assert_eq!(s["responses"]["open"], 2);
+        assert_eq!(s["responses"]["closed"], 58);
+        assert_eq!(s["responses"]["filtered"], 66);
+        assert_eq!(
+            s["open_ports"],
+            json!(["10.0.0.2:22/tcp", "10.0.0.2:80/tcp"])
+        );
+        assert_eq!(r["counts_by_code"]["PORT_SCAN_VERTICAL"], 1);
+        assert_eq!(r["counts_by_code"]["STEALTH_SCAN"], 1);
+        assert_eq!(r["findings"][0]["score"]["level"], "ALTA");
+    }
+
+    #[test]
+    fn horizontal_udp_sweep_but_not_answered_clients() {
+        let mut d = ScanDetector::new();
+        // Barrido SNMP: 30 hosts, todos responden port unreachable salvo uno
+        for host in 1..=30u8 {
+            let probe = udp(SCANNER, [10, 0, 1, host], 50000, 161, b"");
+            d.on_packet(&probe, host as u64 * 10_000);
+            if host == 7 {
+                d.on_packet(
+                    &udp([10, 0, 1, host], SCANNER, 161, 50000, b"resp"),
+                    host as u64 * 10_000 + 50,
+                );
+            } else {
+                d.on_packet(&port_unreachable(&probe), host as u64 * 10_000 + 50);
+            }
+        }
+        // Cliente web normal: 30 servidores, todos contestan
+        let client = [10, 0, 0, 9];
+        for host in 1..=30u8 {
+            d.on_packet(
+                &tcp(
+                    client,
+                    [192, 0, 2, host],
+                    64,
+                    50000 + host as u16,
+                    443,
+                    "S",
+                    &[],
+                ),
+                0,
+            );
+            d.on_packet(
+                &tcp(
+                    [192, 0, 2, host],
+                    client,
+                    64,
+                    443,
+                    50000 + host as u16,
+                    "SA",
+                    &[],
+                ),
+                100,
+            );
+        }
+
+        let r = d.finalize();
+        assert_eq!(r["scanners_total"], 1);
+        let s = &r["scanners"][0];
+        assert_eq!(s["pattern"], "horizontal");
+        assert_eq!(s["kinds"]["udp"], 30);
+        assert_eq!(s["targets_total"], 30);
+        assert_eq!(s["port_ranges"]["udp"], "161");
+        assert_eq!(s["responses"]["closed"], 29);
+        assert_eq!(s["open_ports"], json!(["10.0.1.7:161/udp"]));
+        assert_eq!(r["findings"][0]["code"], "PORT_SCAN_HORIZONTAL");
+        assert_eq!(r["findings"][0]["score"]["level"], "MEDIA");
+    }
+}
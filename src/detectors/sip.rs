@@ -0,0 +1,781 @@
+use crate::detectors::finding::{level, Finding};
+use crate::detectors::latency::LatencyStats;
+use crate::engine::Detector;
+use crate::network::rtp;
+use crate::network::sdp;
+use crate::network::sip::{self, SipMessage, StartLine};
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+
+// Llamadas, registros y endpoints de media seguidos como mucho
+const MAX_CALLS: usize = 10_000;
+const MAX_REGISTRATIONS: usize = 10_000;
+const MAX_SEEN: usize = 200_000;
+const MAX_MEDIA_ENDPOINTS: usize = 20_000;
+const MAX_STREAMS_PER_CALL: usize = 16;
+const MAX_REPORTED: usize = 1000;
+// Post-dial delay por encima de esto se reporta como lento
+const SLOW_PDD_US: u64 = 5_000_000;
+// Reenvíos de una misma petición por UDP antes de reportarlos
+const RETRANSMISSIONS_MIN: u64 = 2;
+
+/// Diálogo INVITE identificado por Call-ID
+#[derive(Debug, Default)]
+struct Call {
+    from: String,
+    to: String,
+    caller: Option<IpAddr>,
+    callee: Option<IpAddr>,
+    invite_us: u64,
+    // CSeq del INVITE en curso: tras un 401/407 el cliente repite con otro CSeq
+    invite_cseq: u32,
+    challenged: bool,
+    progress_us: Option<u64>,
+    final_code: Option<u16>,
+    final_reason: String,
+    final_us: Option<u64>,
+    cancelled: bool,
+    bye_us: Option<u64>,
+    bye_by_caller: Option<bool>,
+    last_us: u64,
+    request_retransmissions: u64,
+    response_retransmissions: u64,
+    media: BTreeSet<(IpAddr, u16)>,
+    // (endpoint destino, SSRC) → paquetes RTP vistos
+    rtp: BTreeMap<((IpAddr, u16), u32), u64>,
+}
+
+impl Call {
+    fn answered(&self) -> bool {
+        self.final_code.is_some_and(|c| (200..300).contains(&c))
+    }
+
+    fn outcome(&self) -> &'static str {
+        match self.final_code {
+            _ if self.cancelled || self.final_code == Some(487) => "abandoned",
+            Some(200..=299) => "answered",
+            Some(300..=399) => "redirected",
+            Some(_) => "failed",
+            None => "no_final_response",
+        }
+    }
+
+    /// Hasta el primer 180/183 o, si no lo hubo, la respuesta final
+    fn post_dial_delay_us(&self) -> Option<u64> {
+        self.progress_us
+            .or(self.final_us)
+            .map(|t| t.saturating_sub(self.invite_us))
+    }
+
+    fn setup_us(&self) -> Option<u64> {
+        self.final_us
+            .filter(|_| self.answered())
+            .map(|t| t.saturating_sub(self.invite_us))
+    }
+
+    /// Endpoints de medios que recibieron RTP
+    fn rtp_targets(&self) -> BTreeSet<(IpAddr, u16)> {
+        self.rtp.keys().map(|(endpoint, _)| *endpoint).collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registration {
+    registrar: Option<IpAddr>,
+    attempts: u64,
+    succeeded: u64,
+    challenged: u64,
+    failed: u64,
+    last_code: Option<u16>,
+    codes: BTreeMap<u16, u64>,
+    retransmissions: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: BTreeMap<String, u64>,
+    responses: u64,
+    request_retransmissions: u64,
+    response_retransmissions: u64,
+    malformed: u64,
+}
+
+/// URI de una cabecera From/To sin nombre visible ni parámetros (`tag`, ...)
+fn header_uri(value: &str) -> String {
+    let uri = match (value.find('<'), value.find('>')) {
+        (Some(a), Some(b)) if a < b => &value[a + 1..b],
+        _ => value.split(';').next().unwrap_or(value),
+    };
+    uri.trim().to_string()
+}
+
+fn endpoint(&(ip, port): &(IpAddr, u16)) -> String {
+    SocketAddr::from((ip, port)).to_string()
+}
+
+fn ms(us: u64) -> f64 {
+    us as f64 / 1000.0
+}
+
+/// Diálogos SIP: INVITE → respuesta final → BYE con post-dial delay, códigos
+/// finales, llamadas fallidas/abandonadas, retransmisiones UDP, registros fallidos
+/// y los flujos RTP de cada llamada según su SDP
+#[derive(Default)]
+pub struct SipDetector {
+    calls: BTreeMap<String, Call>,
+    calls_truncated: u64,
+    registrations: BTreeMap<String, Registration>,
+    registrations_truncated: u64,
+    // (Call-ID, CSeq, método, código) ya vistos: una repetición por UDP es una retransmisión
+    seen: HashSet<(String, u32, String, u16)>,
+    media_endpoints: HashMap<(IpAddr, u16), String>,
+    final_responses: BTreeMap<u16, u64>,
+    post_dial_delay: LatencyStats,
+    setup_time: LatencyStats,
+    counters: Counters,
+}
+
+impl SipDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn on_message(&mut self, msg: SipMessage, src: IpAddr, dst: IpAddr, udp: bool, ts: u64) {
+        let (Some(call_id), Some((cseq, cseq_method))) = (msg.call_id(), msg.cseq()) else {
+            self.counters.malformed += 1;
+            return;
+        };
+        let call_id = call_id.to_string();
+        let cseq_method = cseq_method.to_ascii_uppercase();
+        let code = match &msg.start {
+            StartLine::Request { method, .. } => {
+                *self.counters.requests.entry(method.clone()).or_default() += 1;
+                0
+            }
+            StartLine::Response { code, .. } => {
+                self.counters.responses += 1;
+                *code
+            }
+        };
+
+        // Solo UDP retransmite en la capa SIP; por TCP una repetición es otra cosa
+        let key = (call_id.clone(), cseq, cseq_method.clone(), code);
+        let retransmission = udp && self.seen.contains(&key);
+        if !retransmission && self.seen.len() < MAX_SEEN {
+            self.seen.insert(key);
+        }
+        if retransmission {
+            if code == 0 {
+                self.counters.request_retransmissions += 1;
+            } else {
+                self.counters.response_retransmissions += 1;
+            }
+        }
+
+        if cseq_method == "REGISTER" {
+            self.on_register(&msg, code, dst, retransmission);
+            return;
+        }
+        if !self.calls.contains_key(&call_id) {
+            // Solo un INVITE abre una llamada; el resto de métodos fuera de diálogo se cuentan
+            if code != 0 || cseq_method != "INVITE" {
+                return;
+            }
+            if self.calls.len() >= MAX_CALLS {
+                self.calls_truncated += 1;
+                return;
+            }
+            self.calls.insert(
+                call_id.clone(),
+                Call {
+                    from: msg.header("from").map(header_uri).unwrap_or_default(),
+                    to: msg.header("to").map(header_uri).unwrap_or_default(),
+                    caller: Some(src),
+                    callee: Some(dst),
+                    invite_us: ts,
+                    invite_cseq: cseq,
+                    ..Call::default()
+                },
+            );
+        }
+        if let Some(media) = msg.sdp().and_then(sdp::parse) {
+            self.link_media(&call_id, media);
+        }
+        let Some(call) = self.calls.get_mut(&call_id) else {
+            return;
+        };
+        call.last_us = ts;
+        if retransmission {
+            if code == 0 {
+                call.request_retransmissions += 1;
+            } else {
+                call.response_retransmissions += 1;
+            }
+            return;
+        }
+
+        match (&msg.start, cseq_method.as_str()) {
+            (StartLine::Request { method, .. }, _) => match method.as_str() {
+                // Reintento tras desafío de autenticación, antes de contestar
+                "INVITE" if call.final_us.is_none() => call.invite_cseq = cseq,
+                "CANCEL" if call.final_us.is_none() => call.cancelled = true,
+                "BYE" if call.bye_us.is_none() => {
+                    call.bye_us = Some(ts);
+                    call.bye_by_caller = Some(call.caller == Some(src));
+                }
+                _ => {}
+            },
+            (StartLine::Response { code, reason }, "INVITE") if cseq == call.invite_cseq => {
+                match code {
+                    180 | 183 => {
+                        call.progress_us.get_or_insert(ts);
+                    }
+                    401 | 407 => call.challenged = true,
+                    c if *c >= 200 && call.final_code.is_none() => {
+                        call.final_code = Some(*c);
+                        call.final_reason = reason.clone();
+                        call.final_us = Some(ts);
+                        *self.final_responses.entry(*c).or_default() += 1;
+                        if let Some(pdd) = call.post_dial_delay_us() {
+                            self.post_dial_delay.add_sample(pdd);
+                        }
+                        if let Some(setup) = call.setup_us() {
+                            self.setup_time.add_sample(setup);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_register(&mut self, msg: &SipMessage, code: u16, dst: IpAddr, retransmission: bool) {
+        let aor = msg.header("to").map(header_uri).unwrap_or_default();
+        if !self.registrations.contains_key(&aor) && self.registrations.len() >= MAX_REGISTRATIONS {
+            self.registrations_truncated += 1;
+            return;
+        }
+        let r = self.registrations.entry(aor).or_default();
+        if retransmission {
+            r.retransmissions += (code == 0) as u64;
+            return;
+        }
+        match code {
+            0 => {
+                r.attempts += 1;
+                r.registrar = Some(dst);
+            }
+            100..=199 => {}
+            200..=299 => r.succeeded += 1,
+            401 | 407 => r.challenged += 1,
+            _ => r.failed += 1,
+        }
+        if code >= 200 {
+            r.last_code = Some(code);
+            *r.codes.entry(code).or_default() += 1;
+        }
+    }
+
+    fn link_media(&mut self, call_id: &str, media: Vec<sdp::SdpMedia>) {
+        let Some(call) = self.calls.get_mut(call_id) else {
+            return;
+        };
+        for m in media.into_iter().filter(|m| m.port != 0) {
+            let Some(addr) = m.addr else {
+                continue;
+            };
+            call.media.insert((addr, m.port));
+            if self.media_endpoints.len() < MAX_MEDIA_ENDPOINTS
+                || self.media_endpoints.contains_key(&(addr, m.port))
+            {
+                self.media_endpoints
+                    .insert((addr, m.port), call_id.to_string());
+            }
+        }
+    }
+
+    /// Paquete UDP hacia un endpoint anunciado en el SDP de una llamada
+    fn on_media(&mut self, dst: (IpAddr, u16), payload: &[u8]) {
+        let Some(call_id) = self.media_endpoints.get(&dst) else {
+            return;
+        };
+        let (Some(call), Some(h)) = (self.calls.get_mut(call_id), rtp::parse_rtp(payload)) else {
+            return;
+        };
+        let key = (dst, h.ssrc);
+        if call.rtp.contains_key(&key) || call.rtp.len() < MAX_STREAMS_PER_CALL {
+            *call.rtp.entry(key).or_default() += 1;
+        }
+    }
+
+    fn findings(&self) -> Vec<Finding> {
+        let mut out = Vec::new();
+
+        for (id, c) in &self.calls {
+            let code = c.final_code.unwrap_or(0);
+            let failure = match c.outcome() {
+                // Ocupado / rechazado por el usuario: comportamiento normal
+                "failed" if matches!(code, 486 | 600 | 603) => Some(20),
+                "failed" if code >= 500 || code == 408 => Some(60),
+                "failed" => Some(40),
+                "no_final_response" => Some(60),
+                _ => None,
+            };
+            if let Some(score) = failure {
+                let reason = if code > 0 {
+                    format!("la llamada falló con {code} {}", c.final_reason)
+                } else {
+                    "el INVITE nunca recibió respuesta final".to_string()
+                };
+                out.push(Finding {
+                    code: "SIP_CALL_FAILED",
+                    subject: id.clone(),
+                    score,
+                    level: level(score),
+                    reason,
+                    count: 1,
+                    examples: None,
+                    detail: json!({ "from": c.from, "to": c.to, "final_code": c.final_code }),
+                });
+            }
+
+            if let Some(pdd) = c.post_dial_delay_us().filter(|p| *p >= SLOW_PDD_US) {
+                out.push(Finding {
+                    code: "SIP_SLOW_POST_DIAL_DELAY",
+                    subject: id.clone(),
+                    score: 40,
+                    level: "MEDIA",
+                    reason: format!("post-dial delay de {:.1} s", pdd as f64 / 1e6),
+                    count: 1,
+                    examples: None,
+                    detail: json!({ "post_dial_delay_ms": ms(pdd) }),
+                });
+            }
+
+            if c.request_retransmissions >= RETRANSMISSIONS_MIN {
+                out.push(Finding {
+                    code: "SIP_RETRANSMISSIONS",
+                    subject: id.clone(),
+                    score: 40,
+                    level: "MEDIA",
+                    reason: format!(
+                        "peticiones retransmitidas por UDP ({}): pérdida o respuesta lenta",
+                        c.request_retransmissions
+                    ),
+                    count: c.request_retransmissions,
+                    examples: None,
+                    detail: json!({ "response_retransmissions": c.response_retransmissions }),
+                });
+            }
+
+            if c.answered() && !c.media.is_empty() {
+                let targets = c.rtp_targets();
+                let (code, score, reason) = if targets.is_empty() {
+                    (
+                        "SIP_NO_RTP",
+                        40,
+                        "llamada contestada sin RTP visible hacia el SDP negociado",
+                    )
+                } else if c.media.len() >= 2 && targets.len() == 1 {
+                    (
+                        "SIP_ONE_WAY_AUDIO",
+                        60,
+                        "RTP en un solo sentido: audio unidireccional (NAT o firewall)",
+                    )
+                } else {
+                    continue;
+                };
+                out.push(Finding {
+                    code,
+                    subject: id.clone(),
+                    score,
+                    level: level(score),
+                    reason: reason.to_string(),
+                    count: c.rtp.values().sum(),
+                    examples: None,
+                    detail: json!({
+                        "media": c.media.iter().map(endpoint).collect::<Vec<_>>(),
+                        "rtp_to": targets.iter().map(endpoint).collect::<Vec<_>>()
+                    }),
+                });
+            }
+        }
+
+        for (aor, r) in self.registrations.iter().filter(|(_, r)| r.failed > 0) {
+            let score = if r.succeeded == 0 { 60 } else { 20 };
+            out.push(Finding {
+                code: "SIP_REGISTRATION_FAILED",
+                subject: aor.clone(),
+                score,
+                level: level(score),
+                reason: format!(
+                    "registros rechazados ({}, último código {})",
+                    r.failed,
+                    r.last_code.unwrap_or(0)
+                ),
+                count: r.failed,
+                examples: None,
+                detail: json!({
+                    "registrar": r.registrar.map(|ip| ip.to_string()),
+                    "succeeded": r.succeeded,
+                    "codes": r.codes
+                }),
+            });
+        }
+
+        out.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.code.cmp(b.code))
+                .then_with(|| a.subject.cmp(&b.subject))
+        });
+        out
+    }
+
+    fn call_json(id: &str, c: &Call) -> Value {
+        json!({
+            "call_id": id,
+            "from": c.from,
+            "to": c.to,
+            "caller": c.caller.map(|ip| ip.to_string()),
+            "callee": c.callee.map(|ip| ip.to_string()),
+            "outcome": c.outcome(),
+            "final_code": c.final_code,
+            "final_reason": c.final_reason,
+            "challenged": c.challenged,
+            "post_dial_delay_ms": c.post_dial_delay_us().map(ms),
+            "setup_ms": c.setup_us().map(ms),
+            "duration_ms": match (c.final_us.filter(|_| c.answered()), c.bye_us) {
+                (Some(start), Some(end)) => Some(ms(end.saturating_sub(start))),
+                _ => None,
+            },
+            "ended_by": c.bye_by_caller.map(|caller| if caller { "caller" } else { "callee" }),
+            "retransmissions": {
+                "requests": c.request_retransmissions,
+                "responses": c.response_retransmissions
+            },
+            "media": c.media.iter().map(endpoint).collect::<Vec<_>>(),
+            "rtp_streams": c.rtp.iter().map(|((to, ssrc), packets)| json!({
+                "to": endpoint(to),
+                "ssrc": format!("0x{ssrc:08x}"),
+                "packets": packets
+            })).collect::<Vec<_>>(),
+            "first_ts_us": c.invite_us,
+            "last_ts_us": c.last_us
+        })
+    }
+}
+
+impl Detector for SipDetector {
+    fn name(&self) -> &'static str {
+        "sip"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        let (payload, dport, udp) = match &sliced.transport {
+            Some(TransportSlice::Udp(u)) => (u.payload(), u.destination_port(), true),
+            // SIP sobre TCP: solo mensajes contenidos en un segmento
+            Some(TransportSlice::Tcp(t)) => (t.payload(), t.destination_port(), false),
+            _ => return,
+        };
+        if sip::looks_like_sip(payload) {
+            match sip::parse(payload) {
+                Some(msg) => self.on_message(msg, src, dst, udp, ts_micros),
+                None => self.counters.malformed += 1,
+            }
+        } else if udp {
+            self.on_media((dst, dport), payload);
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        let findings = self.findings();
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for f in &findings {
+            *counts.entry(f.code).or_default() += 1;
+        }
+        let mut by_outcome: BTreeMap<&str, u64> = BTreeMap::new();
+        for c in self.calls.values() {
+            *by_outcome.entry(c.outcome()).or_default() += 1;
+        }
+        let mut calls: Vec<(&String, &Call)> = self.calls.iter().collect();
+        calls.sort_by(|a, b| a.1.invite_us.cmp(&b.1.invite_us).then_with(|| a.0.cmp(b.0)));
+
+        let reg_total =
+            |f: fn(&Registration) -> u64| self.registrations.values().map(f).sum::<u64>();
+        json!({
+            "calls_total": self.calls.len(),
+            "calls_truncated": self.calls_truncated,
+            "by_outcome": by_outcome,
+            "final_responses": self.final_responses,
+            "post_dial_delay_ms": self.post_dial_delay.to_json(),
+            "setup_time_ms": self.setup_time.to_json(),
+            "messages": {
+                "requests": self.counters.requests,
+                "responses": self.counters.responses,
+                "malformed": self.counters.malformed
+            },
+            "retransmissions": {
+                "requests": self.counters.request_retransmissions,
+                "responses": self.counters.response_retransmissions
+            },
+            "registrations": {
+                "aors": self.registrations.len(),
+                "attempts": reg_total(|r| r.attempts),
+                "succeeded": reg_total(|r| r.succeeded),
+                "challenged": reg_total(|r| r.challenged),
+                "failed": reg_total(|r| r.failed),
+                "truncated": self.registrations_truncated
+            },
+            "calls": calls
+                .into_iter()
+                .take(MAX_REPORTED)
+                .map(|(id, c)| Self::call_json(id, c))
+                .collect::<Vec<_>>(),
+            "findings": findings.iter().take(MAX_REPORTED).map(Finding::to_json).collect::<Vec<_>>(),
+            "counts_by_code": counts
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::rtp::tests::rtp_packet;
+    use crate::network::sip::tests::invite;
+    use etherparse::PacketBuilder;
+
+    const ALICE: [u8; 4] = [10, 0, 0, 1];
+    const PROXY: [u8; 4] = [10, 0, 0, 5];
+    const BOB: [u8; 4] = [10, 0, 0, 2];
+
+    fn udp(src: ([u8; 4], u16), dst: ([u8; 4], u16), payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(src.0, dst.0, 64)
+            .udp(src.1, dst.1)
+            .write(&mut out, payload)
+            .unwrap();
+        out
+    }
+
+    fn response(
+        call_id: &str,
+        cseq: &str,
+        code: u16,
+        reason: &str,
+        sdp: Option<(&str, u16)>,
+    ) -> Vec<u8> {
+        let body = sdp
+            .map(|(addr, port)| format!("v=0\r\nc=IN IP4 {addr}\r\nm=audio {port} RTP/AVP 0\r\n"))
+            .unwrap_or_default();
+        let ct = if body.is_empty() {
+            ""
+        } else {
+            "Content-Type: application/sdp\r\n"
+        };
+        format!(
+            "SIP/2.0 {code} {reason}\r\nFrom: <sip:alice@example.com>;tag=a1\r\n\
+             To: <sip:bob@example.com>;tag=b1\r\nCall-ID: {call_id}\r\nCSeq: {cseq}\r\n{ct}\
+             Content-Length: {}\r\n\r\n{body}",
+            body.len()
+        )
+        .into_bytes()
+    }
+
+    fn request(method: &str, call_id: &str, cseq: u32, to: &str) -> Vec<u8> {
+        format!(
+            "{method} sip:{to} SIP/2.0\r\nFrom: <sip:{to}>;tag=x\r\nTo: <sip:{to}>\r\n\
+             Call-ID: {call_id}\r\nCSeq: {cseq} {method}\r\nContent-Length: 0\r\n\r\n"
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn answered_call_with_pdd_rtp_link_and_one_way_audio() {
+        let mut d = SipDetector::new();
+        let a = (ALICE, 5060);
+        let p = (PROXY, 5060);
+        // INVITE retransmitido dos veces antes del 100 Trying
+        d.on_packet(&udp(a, p, &invite("c1", "10.0.0.1", 40000)), 0);
+        d.on_packet(&udp(a, p, &invite("c1", "10.0.0.1", 40000)), 500_000);
+        d.on_packet(&udp(a, p, &invite("c1", "10.0.0.1", 40000)), 1_500_000);
+        d.on_packet(
+            &udp(p, a, &response("c1", "1 INVITE", 100, "Trying", None)),
+            1_510_000,
+        );
+        d.on_packet(
+            &udp(p, a, &response("c1", "1 INVITE", 180, "Ringing", None)),
+            2_000_000,
+        );
+        d.on_packet(
+            &udp(
+                p,
+                a,
+                &response("c1", "1 INVITE", 200, "OK", Some(("10.0.0.2", 30000))),
+            ),
+            6_000_000,
+        );
+        // El 200 OK se retransmite hasta recibir el ACK
+        d.on_packet(
+            &udp(
+                p,
+                a,
+                &response("c1", "1 INVITE", 200, "OK", Some(("10.0.0.2", 30000))),
+            ),
+            6_500_000,
+        );
+        // Solo Alice envía audio: Bob nunca llega a 10.0.0.1:40000
+        for seq in 0..10u16 {
+            d.on_packet(
+                &udp(
+                    (ALICE, 40000),
+                    (BOB, 30000),
+                    &rtp_packet(0, seq, seq as u32 * 160, 7, 160),
+                ),
+                6_600_000 + seq as u64 * 20_000,
+            );
+        }
+        d.on_packet(
+            &udp(p, a, &request("BYE", "c1", 2, "alice@example.com")),
+            66_000_000,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["calls_total"], 1);
+        assert_eq!(r["by_outcome"]["answered"], 1);
+        assert_eq!(r["retransmissions"]["requests"], 2);
+        assert_eq!(r["retransmissions"]["responses"], 1);
+        let c = &r["calls"][0];
+        assert_eq!(c["from"], "sip:alice@example.com");
+        assert_eq!(c["to"], "sip:bob@example.com");
+        assert_eq!(c["post_dial_delay_ms"], 2000.0);
+        assert_eq!(c["setup_ms"], 6000.0);
+        assert_eq!(c["duration_ms"], 60000.0);
+        assert_eq!(c["ended_by"], "callee");
+        assert_eq!(c["media"].as_array().unwrap().len(), 2);
+        assert_eq!(c["rtp_streams"][0]["to"], "10.0.0.2:30000");
+        assert_eq!(c["rtp_streams"][0]["packets"], 10);
+
+        let codes: Vec<&str> = r["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["code"].as_str().unwrap())
+            .collect();
+        assert_eq!(codes, ["SIP_ONE_WAY_AUDIO", "SIP_RETRANSMISSIONS"]);
+    }
+
+    #[test]
+    fn failed_abandoned_challenged_calls_and_registration_failure() {
+        let mut d = SipDetector::new();
+        let a = (ALICE, 5060);
+        let p = (PROXY, 5060);
+        // Desafío 407 y reintento con CSeq 2 que termina en 503
+        d.on_packet(&udp(a, p, &invite("c2", "10.0.0.1", 40002)), 0);
+        d.on_packet(
+            &udp(
+                p,
+                a,
+                &response("c2", "1 INVITE", 407, "Proxy Authentication Required", None),
+            ),
+            1_000,
+        );
+        d.on_packet(
+            &udp(a, p, &request("INVITE", "c2", 2, "bob@example.com")),
+            2_000,
+        );
+        d.on_packet(
+            &udp(
+                p,
+                a,
+                &response("c2", "2 INVITE", 503, "Service Unavailable", None),
+            ),
+            300_000,
+        );
+        // Llamada cancelada mientras sonaba
+        d.on_packet(&udp(a, p, &invite("c3", "10.0.0.1", 40004)), 1_000_000);
+        d.on_packet(
+            &udp(p, a, &response("c3", "1 INVITE", 180, "Ringing", None)),
+            1_100_000,
+        );
+        d.on_packet(
+            &udp(a, p, &request("CANCEL", "c3", 1, "bob@example.com")),
+            4_000_000,
+        );
+        d.on_packet(
+            &udp(
+                p,
+                a,
+                &response("c3", "1 INVITE", 487, "Request Terminated", None),
+            ),
+            4_010_000,
+        );
+        // Registro: desafío y luego 403
+        d.on_packet(
+            &udp(a, p, &request("REGISTER", "r1", 1, "bob@example.com")),
+            5_000_000,
+        );
+        d.on_packet(
+            &udp(
+                p,
+                a,
+                &response("r1", "1 REGISTER", 401, "Unauthorized", None),
+            ),
+            5_001_000,
+        );
+        d.on_packet(
+            &udp(a, p, &request("REGISTER", "r1", 2, "bob@example.com")),
+            5_002_000,
+        );
+        d.on_packet(
+            &udp(p, a, &response("r1", "2 REGISTER", 403, "Forbidden", None)),
+            5_003_000,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["by_outcome"]["failed"], 1);
+        assert_eq!(r["by_outcome"]["abandoned"], 1);
+        assert_eq!(r["final_responses"]["503"], 1);
+        assert_eq!(r["calls"][0]["challenged"], true);
+        assert_eq!(r["calls"][0]["post_dial_delay_ms"], 300.0);
+        assert_eq!(r["registrations"]["attempts"], 2);
+        assert_eq!(r["registrations"]["challenged"], 1);
+        assert_eq!(r["registrations"]["failed"], 1);
+        let found: Vec<(&str, &str, &str)> = r["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| {
+                (
+                    f["code"].as_str().unwrap(),
+                    f["subject"].as_str().unwrap(),
+                    f["score"]["level"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert!(
+            found.contains(&("SIP_CALL_FAILED", "c2", "MEDIA")),
+            "{found:?}"
+        );
+        assert!(found.contains(&("SIP_REGISTRATION_FAILED", "sip:bob@example.com", "MEDIA")));
+        assert!(!found.iter().any(|(_, s, _)| *s == "c3"));
+    }
+}
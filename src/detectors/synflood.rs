@@ -0,0 +1,681 @@
+use crate::detectors::finding::{level, Finding};
+use crate::engine::Detector;
+use crate::network::format::{endpoint_label, rate};
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+
+// Topes de servicios, handshakes pendientes y orígenes muestreados por ataque
+const MAX_SERVICES: usize = 10_000;
+const MAX_PENDING: usize = 500_000;
+const MAX_SAMPLES_PER_SECOND: usize = 1024;
+const MAX_SOURCES_PER_ATTACK: usize = 100_000;
+const MAX_REPORTED: usize = 1000;
+// Un SYN sin completar se da por perdido tras este tiempo (SYN_RECV del servidor)
+const HALF_OPEN_TIMEOUT_US: u64 = 30_000_000;
+// Ventana deslizante para el ratio de handshakes completados
+const WINDOW_S: usize = 10;
+// Segundo "caliente": al menos estos SYN y casi ningún handshake completado en la ventana
+const FLOOD_MIN_SYN_RATE: u64 = 100;
+const MAX_COMPLETION_RATIO: f64 = 0.2;
+// Segundos sin actividad de ataque antes de cerrar la ventana
+const GAP_TOLERANCE_S: u64 = 5;
+const SEVERE_SYN_RATE: u64 = 1000;
+const HALF_OPEN_MIN: u64 = 256;
+// Diversidad de orígenes a partir de la cual se sospecha de direcciones falsificadas
+const SPOOF_MIN_SOURCES: usize = 50;
+const SPOOF_MIN_DIVERSITY: f64 = 0.5;
+// Misma distancia en saltos para miles de orígenes: probablemente una sola máquina
+const SPOOF_MAX_TTL_SPREAD: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Service {
+    ip: IpAddr,
+    port: u16,
+}
+
+impl Service {
+    fn label(&self) -> String {
+        endpoint_label(self.ip, self.port)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct HandshakeKey {
+    client: IpAddr,
+    client_port: u16,
+    service: Service,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    syn_us: u64,
+    synack: bool,
+}
+
+/// Un segundo de actividad de un servicio
+#[derive(Debug, Default)]
+struct Bucket {
+    sec: u64,
+    syn: u64,
+    synack: u64,
+    completed: u64,
+    rst: u64,
+    half_open_peak: u64,
+    // Muestra de (origen, TTL) de los SYN del segundo
+    samples: Vec<(IpAddr, u8)>,
+}
+
+#[derive(Debug)]
+struct Attack {
+    start_sec: u64,
+    last_hot_sec: u64,
+    syn: u64,
+    synack: u64,
+    completed: u64,
+    rst: u64,
+    peak_syn_rate: u64,
+    peak_half_open: u64,
+    sources: HashSet<IpAddr>,
+    sources_capped: bool,
+    ttls: BTreeMap<u8, u64>,
+    samples: u64,
+    // Segundos tranquilos tras el último caliente: solo cuentan si el ataque sigue
+    tail: Vec<Bucket>,
+}
+
+impl Attack {
+    fn new(b: Bucket) -> Self {
+        let mut a = Attack {
+            start_sec: b.sec,
+            last_hot_sec: b.sec,
+            syn: 0,
+            synack: 0,
+            completed: 0,
+            rst: 0,
+            peak_syn_rate: 0,
+            peak_half_open: 0,
+            sources: HashSet::new(),
+            sources_capped: false,
+            ttls: BTreeMap::new(),
+            samples: 0,
+            tail: Vec::new(),
+        };
+        a.absorb(b);
+        a
+    }
+
+    fn absorb(&mut self, b: Bucket) {
+        self.syn += b.syn;
+        self.synack += b.synack;
+        self.completed += b.completed;
+        self.rst += b.rst;
+        self.peak_syn_rate = self.peak_syn_rate.max(b.syn);
+        self.peak_half_open = self.peak_half_open.max(b.half_open_peak);
+        for (src, ttl) in b.samples {
+            self.samples += 1;
+            *self.ttls.entry(ttl).or_default() += 1;
+            if self.sources.len() < MAX_SOURCES_PER_ATTACK {
+                self.sources.insert(src);
+            } else {
+                self.sources_capped = true;
+            }
+        }
+    }
+
+    fn hot(&mut self, b: Bucket) {
+        for t in std::mem::take(&mut self.tail) {
+            self.absorb(t);
+        }
+        self.last_hot_sec = b.sec;
+        self.absorb(b);
+    }
+}
+
+/// Estimación de falsificación de orígenes a partir de la muestra de SYN
+fn spoofing(a: &Attack) -> (&'static str, Value) {
+    let distinct = a.sources.len();
+    let diversity = distinct as f64 / a.samples.max(1) as f64;
+    let ttl_min = a.ttls.keys().next().copied().unwrap_or(0);
+    let ttl_max = a.ttls.keys().next_back().copied().unwrap_or(0);
+    let spread = ttl_max - ttl_min;
+    let verdict = if distinct >= SPOOF_MIN_SOURCES && diversity >= SPOOF_MIN_DIVERSITY {
+        if spread <= SPOOF_MAX_TTL_SPREAD {
+            "probable"
+        } else {
+            // Orígenes dispersos de verdad (botnet) o TTL aleatorizado
+            "posible"
+        }
+    } else {
+        "improbable"
+    };
+    let detail = json!({
+        "verdict": verdict,
+        "distinct_sources": distinct,
+        "sources_capped": a.sources_capped,
+        "source_diversity": diversity,
+        "ttl_min": ttl_min,
+        "ttl_max": ttl_max,
+        "ttl_spread": spread,
+        "distinct_ttls": a.ttls.len(),
+        "sampled_syns": a.samples
+    });
+    (verdict, detail)
+}
+
+#[derive(Debug, Default)]
+struct ServiceState {
+    syn: u64,
+    synack: u64,
+    completed: u64,
+    half_open: u64,
+    half_open_peak: u64,
+    expired: u64,
+    current: Option<Bucket>,
+    // (syn, completados) de los últimos segundos cerrados; los que no llegan al umbral de
+    // SYN cuentan como vacíos para que el tráfico sano previo no diluya el inicio del ataque
+    window: VecDeque<(u64, u64)>,
+    attack: Option<Attack>,
+    attacks: Vec<Attack>,
+}
+
+impl ServiceState {
+    /// Segundo en curso; un timestamp que va hacia atrás cuenta en él en lugar de reabrir
+    /// segundos ya cerrados, así la ventana y los ataques solo avanzan
+    fn bucket(&mut self, sec: u64) -> &mut Bucket {
+        if self.current.as_ref().is_some_and(|b| sec > b.sec) {
+            self.close_until(sec);
+        }
+        self.current.get_or_insert_with(|| Bucket {
+            sec,
+            ..Bucket::default()
+        })
+    }
+
+    /// Cierra el segundo en curso y los vacíos hasta `sec` (sin recorrer huecos largos)
+    fn close_until(&mut self, sec: u64) {
+        let Some(b) = self.current.take() else {
+            return;
+        };
+        let mut next = b.sec + 1;
+        // Pasados estos segundos vacíos la ventana está a cero y el ataque cerrado
+        let limit = next + WINDOW_S as u64 + GAP_TOLERANCE_S + 1;
+        self.close(b);
+        while next < sec.min(limit) {
+            self.close(Bucket {
+                sec: next,
+                ..Bucket::default()
+            });
+            next += 1;
+        }
+    }
+
+    fn close(&mut self, b: Bucket) {
+        let busy = b.syn >= FLOOD_MIN_SYN_RATE;
+        self.window
+            .push_back(if busy { (b.syn, b.completed) } else { (0, 0) });
+        if self.window.len() > WINDOW_S {
+            self.window.pop_front();
+        }
+        let (syn, completed) = self
+            .window
+            .iter()
+            .fold((0, 0), |(s, c), (bs, bc)| (s + bs, c + bc));
+        let ratio = completed as f64 / syn.max(1) as f64;
+        let hot = busy && ratio < MAX_COMPLETION_RATIO;
+
+        match self.attack.as_mut() {
+            Some(a) if hot => a.hot(b),
+            Some(a) if b.sec.saturating_sub(a.last_hot_sec) > GAP_TOLERANCE_S => {
+                if let Some(a) = self.attack.take() {
+                    self.attacks.push(a);
+                }
+            }
+            Some(a) => a.tail.push(b),
+            None if hot => self.attack = Some(Attack::new(b)),
+            None => {}
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(b) = self.current.take() {
+            self.close(b);
+        }
+        if let Some(a) = self.attack.take() {
+            self.attacks.push(a);
+        }
+    }
+}
+
+/// SYN floods y agotamiento de conexiones: SYN frente a SYN-ACK / ACK completados por
+/// servicio destino en ventanas deslizantes, conexiones half-open y estimación de
+/// orígenes falsificados (diversidad de direcciones y dispersión de TTL)
+#[derive(Default)]
+pub struct SynFloodDetector {
+    services: HashMap<Service, ServiceState>,
+    services_truncated: u64,
+    pending: HashMap<HandshakeKey, Pending>,
+    // Orden de llegada de los SYN para expirar sin recorrer todo el mapa
+    pending_order: VecDeque<(u64, HandshakeKey)>,
+    pending_truncated: u64,
+    last_sweep_us: u64,
+}
+
+impl SynFloodDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn service(&mut self, service: Service) -> Option<&mut ServiceState> {
+        if !self.services.contains_key(&service) && self.services.len() >= MAX_SERVICES {
+            self.services_truncated += 1;
+            return None;
+        }
+        Some(self.services.entry(service).or_default())
+    }
+
+    fn sweep(&mut self, now: u64) {
+        while let Some(&(ts, key)) = self.pending_order.front() {
+            if ts + HALF_OPEN_TIMEOUT_US > now {
+                break;
+            }
+            self.pending_order.pop_front();
+            // La entrada puede haberse completado (o renovado) desde entonces
+            if self.pending.get(&key).is_some_and(|p| p.syn_us == ts) {
+                self.pending.remove(&key);
+                if let Some(s) = self.services.get_mut(&key.service) {
+                    s.half_open = s.half_open.saturating_sub(1);
+                    s.expired += 1;
+                }
+            }
+        }
+    }
+
+    fn on_syn(&mut self, key: HandshakeKey, ttl: u8, ts: u64) {
+        let sec = ts / 1_000_000;
+        let new = !self.pending.contains_key(&key);
+        let tracked = new && self.pending.len() < MAX_PENDING;
+        if new && !tracked {
+            self.pending_truncated += 1;
+        }
+        if tracked {
+            self.pending.insert(
+                key,
+                Pending {
+                    syn_us: ts,
+                    synack: false,
+                },
+            );
+            self.pending_order.push_back((ts, key));
+        }
+        let Some(s) = self.service(key.service) else {
+            return;
+        };
+        s.syn += 1;
+        if tracked {
+            s.half_open += 1;
+            s.half_open_peak = s.half_open_peak.max(s.half_open);
+        }
+        let half_open = s.half_open;
+        let b = s.bucket(sec);
+        b.syn += 1;
+        b.half_open_peak = b.half_open_peak.max(half_open);
+        if b.samples.len() < MAX_SAMPLES_PER_SECOND {
+            b.samples.push((key.client, ttl));
+        }
+    }
+
+    fn on_tcp(&mut self, src: IpAddr, dst: IpAddr, ttl: u8, tcp: &etherparse::TcpSlice, ts: u64) {
+        let sec = ts / 1_000_000;
+        if tcp.syn() && !tcp.ack() {
+            let key = HandshakeKey {
+                client: src,
+                client_port: tcp.source_port(),
+                service: Service {
+                    ip: dst,
+                    port: tcp.destination_port(),
+                },
+            };
+            self.on_syn(key, ttl, ts);
+            return;
+        }
+
+        // Respuestas del servidor: SYN-ACK o RST a un SYN pendiente
+        let reply = HandshakeKey {
+            client: dst,
+            client_port: tcp.destination_port(),
+            service: Service {
+                ip: src,
+                port: tcp.source_port(),
+            },
+        };
+        if tcp.syn() && tcp.ack() {
+            if let Some(p) = self.pending.get_mut(&reply) {
+                p.synack = true;
+                if let Some(s) = self.services.get_mut(&reply.service) {
+                    s.synack += 1;
+                    s.bucket(sec).synack += 1;
+                }
+            }
+            return;
+        }
+
+        // ACK del cliente que completa el handshake, o RST de cualquiera de los dos
+        let forward = HandshakeKey {
+            client: src,
+            client_port: tcp.source_port(),
+            service: Service {
+                ip: dst,
+                port: tcp.destination_port(),
+            },
+        };
+        let (key, completed) = if tcp.rst() {
+            let key = if self.pending.contains_key(&forward) {
+                forward
+            } else {
+                reply
+            };
+            (key, false)
+        } else if tcp.ack() && self.pending.get(&forward).is_some_and(|p| p.synack) {
+            (forward, true)
+        } else {
+            return;
+        };
+        if self.pending.remove(&key).is_none() {
+            return;
+        }
+        if let Some(s) = self.services.get_mut(&key.service) {
+            s.half_open = s.half_open.saturating_sub(1);
+            let b = s.bucket(sec);
+            if completed {
+                b.completed += 1;
+            } else {
+                b.rst += 1;
+            }
+            s.completed += completed as u64;
+        }
+    }
+
+    fn attack_json(service: &Service, a: &Attack) -> (Value, Finding) {
+        let end_sec = a.last_hot_sec + 1;
+        let duration = end_sec.saturating_sub(a.start_sec);
+        let (verdict, spoof) = spoofing(a);
+        let synack_ratio = rate(a.synack, a.syn);
+        let completion_ratio = rate(a.completed, a.syn);
+
+        let mut reasons = vec![format!(
+            "{} SYN en {duration} s (pico {} SYN/s), {:.1}% completados",
+            a.syn,
+            a.peak_syn_rate,
+            completion_ratio * 100.0
+        )];
+        if synack_ratio < 0.5 {
+            reasons.push(format!(
+                "el servidor solo contestó {:.1}% de los SYN (saturado o con SYN cookies descartando)",
+                synack_ratio * 100.0
+            ));
+        }
+        if a.peak_half_open >= HALF_OPEN_MIN {
+            reasons.push(format!("pico de {} conexiones half-open", a.peak_half_open));
+        }
+        if verdict != "improbable" {
+            reasons.push(format!("orígenes falsificados: {verdict}"));
+        }
+        let score = if a.peak_syn_rate >= SEVERE_SYN_RATE || synack_ratio < 0.5 {
+            100
+        } else {
+            60
+        };
+        let report = json!({
+            "service": service.label(),
+            "start_ts_us": a.start_sec * 1_000_000,
+            "end_ts_us": end_sec * 1_000_000,
+            "duration_s": duration,
+            "syn": a.syn,
+            "synack": a.synack,
+            "completed": a.completed,
+            "rst": a.rst,
+            "synack_ratio": synack_ratio,
+            "completion_ratio": completion_ratio,
+            "peak_syn_rate": a.peak_syn_rate,
+            "avg_syn_rate": rate(a.syn, duration),
+            "peak_half_open": a.peak_half_open,
+            "spoofing": spoof,
+            "score": { "value": score, "level": level(score) },
+            "reasons": reasons
+        });
+        let finding = Finding {
+            code: "SYN_FLOOD",
+            subject: service.label(),
+            score,
+            level: level(score),
+            reason: reasons.join("; "),
+            count: a.syn,
+            examples: None,
+            detail: json!({
+                "start_ts_us": a.start_sec * 1_000_000,
+                "end_ts_us": end_sec * 1_000_000,
+                "peak_syn_rate": a.peak_syn_rate,
+                "spoofing": verdict
+            }),
+        };
+        (report, finding)
+    }
+}
+
+impl Detector for SynFloodDetector {
+    fn name(&self) -> &'static str {
+        "synflood"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        if ts_micros >= self.last_sweep_us + 1_000_000 {
+            self.sweep(ts_micros);
+            self.last_sweep_us = ts_micros;
+        }
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst, ttl) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+                ip.header().ttl(),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+                ip.header().hop_limit(),
+            ),
+            None => return,
+        };
+        if let Some(TransportSlice::Tcp(tcp)) = &sliced.transport {
+            self.on_tcp(src, dst, ttl, tcp, ts_micros);
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        let mut services: Vec<(&Service, &mut ServiceState)> = self.services.iter_mut().collect();
+        services.sort_by_key(|(s, _)| **s);
+
+        let mut attacks: Vec<(u32, u64, Value)> = Vec::new();
+        let mut findings: Vec<Finding> = Vec::new();
+        let mut busiest: Vec<(u64, Value)> = Vec::new();
+        for (service, st) in services {
+            st.finish();
+            for a in &st.attacks {
+                let (report, finding) = Self::attack_json(service, a);
+                attacks.push((finding.score, a.start_sec, report));
+                findings.push(finding);
+            }
+            if st.half_open_peak >= HALF_OPEN_MIN && st.attacks.is_empty() {
+                findings.push(Finding {
+                    code: "HALF_OPEN_BUILDUP",
+                    subject: service.label(),
+                    score: 40,
+                    level: "MEDIA",
+                    reason: format!(
+                        "pico de {} conexiones half-open ({} expiradas sin completar)",
+                        st.half_open_peak, st.expired
+                    ),
+                    count: st.half_open_peak,
+                    examples: None,
+                    detail: json!({ "syn": st.syn, "completed": st.completed }),
+                });
+            }
+            busiest.push((
+                st.syn,
+                json!({
+                    "service": service.label(),
+                    "syn": st.syn,
+                    "synack": st.synack,
+                    "completed": st.completed,
+                    "completion_ratio": rate(st.completed, st.syn),
+                    "half_open_peak": st.half_open_peak,
+                    "half_open_expired": st.expired,
+                    "attacks": st.attacks.len()
+                }),
+            ));
+        }
+
+        attacks.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        busiest.sort_by_key(|(syn, _)| std::cmp::Reverse(*syn));
+        findings.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.code.cmp(b.code))
+                .then_with(|| a.subject.cmp(&b.subject))
+        });
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for f in &findings {
+            *counts.entry(f.code).or_default() += 1;
+        }
+
+        json!({
+            "services_total": self.services.len(),
+            "services_truncated": self.services_truncated,
+            "pending_truncated": self.pending_truncated,
+            "attacks_total": attacks.len(),
+            "attacks": attacks.into_iter().take(MAX_REPORTED).map(|(_, _, v)| v).collect::<Vec<_>>(),
+            "top_services": busiest.into_iter().take(MAX_REPORTED).map(|(_, v)| v).collect::<Vec<_>>(),
+            "findings": findings.iter().take(MAX_REPORTED).map(Finding::to_json).collect::<Vec<_>>(),
+            "counts_by_code": counts
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detectors::test_frames::tcp;
+
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    #[test]
+    fn spoofed_flood_window_with_peak_rate_and_half_open_buildup() {
+        let mut d = SynFloodDetector::new();
+        // Tráfico normal antes del ataque: 20 conexiones completas por segundo
+        for sec in 0..5u64 {
+            for i in 0..20u16 {
+                let client = [192, 168, 1, i as u8 + 1];
+                let ts = sec * 1_000_000 + i as u64 * 1_000;
+                d.on_packet(&tcp(client, SERVER, 64, 30000 + i, 80, "S", &[]), ts);
+                d.on_packet(&tcp(SERVER, client, 64, 80, 30000 + i, "SA", &[]), ts + 100);
+                d.on_packet(&tcp(client, SERVER, 64, 30000 + i, 80, "A", &[]), ts + 200);
+            }
+        }
+        // 5 s de flood (300 SYN/s) desde orígenes aleatorios con el mismo TTL;
+        // el servidor solo contesta uno de cada tres SYN
+        let mut n = 0u32;
+        for sec in 10..15u64 {
+            for i in 0..300u32 {
+                n += 1;
+                let src = (0xc000_0000u32 + n * 7919).to_be_bytes();
+                let ts = sec * 1_000_000 + i as u64 * 3_000;
+                d.on_packet(
+                    &tcp(src, SERVER, 50, 1024 + (n % 60000) as u16, 80, "S", &[]),
+                    ts,
+                );
+                if i % 3 == 0 {
+                    d.on_packet(
+                        &tcp(SERVER, src, 64, 80, 1024 + (n % 60000) as u16, "SA", &[]),
+                        ts + 100,
+                    );
+                }
+            }
+        }
+        // Fin de la captura mucho después: las half-open expiran
+        d.on_packet(
+            &tcp([192, 168, 1, 1], SERVER, 64, 40000, 80, "S", &[]),
+            60_000_000,
+        );
+
+        let r = d.finalize();
+        assert_eq!(r["attacks_total"], 1);
+        let a = &r["attacks"][0];
+        assert_eq!(a["service"], "10.0.0.2:80");
+        assert_eq!(a["start_ts_us"], 10_000_000);
+        assert_eq!(a["end_ts_us"], 15_000_000);
+        assert_eq!(a["syn"], 1500);
+        assert_eq!(a["peak_syn_rate"], 300);
+        assert_eq!(a["completed"], 0);
+        assert_eq!(a["peak_half_open"], 1500);
+        assert_eq!(a["spoofing"]["verdict"], "probable");
+        assert_eq!(a["spoofing"]["ttl_spread"], 0);
+        assert_eq!(a["score"]["level"], "ALTA");
+        assert_eq!(r["top_services"][0]["half_open_expired"], 1500);
+        assert_eq!(r["counts_by_code"]["SYN_FLOOD"], 1);
+    }
+
+    #[test]
+    fn busy_but_healthy_service_is_not_a_flood() {
+        let mut d = SynFloodDetector::new();
+        // 200 conexiones/s durante 3 s, todas completas, desde pocos clientes
+        for sec in 0..3u64 {
+            for i in 0..200u16 {
+                let client = [192, 168, 1, (i % 10) as u8 + 1];
+                let port = 20000 + sec as u16 * 1000 + i;
+                let ts = sec * 1_000_000 + i as u64 * 4_000;
+                d.on_packet(&tcp(client, SERVER, 64, port, 443, "S", &[]), ts);
+                d.on_packet(&tcp(SERVER, client, 64, 443, port, "SA", &[]), ts + 100);
+                d.on_packet(&tcp(client, SERVER, 64, port, 443, "A", &[]), ts + 200);
+            }
+        }
+        let r = d.finalize();
+        assert_eq!(r["attacks_total"], 0);
+        assert_eq!(r["top_services"][0]["completed"], 600);
+        assert_eq!(r["top_services"][0]["half_open_peak"], 1);
+        assert!(r["findings"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn backwards_timestamp_during_flood_counts_in_current_second() {
+        let mut d = SynFloodDetector::new();
+        let mut n = 0u32;
+        for sec in 10..15u64 {
+            for i in 0..300u32 {
+                n += 1;
+                let src = (0xc000_0000u32 + n * 7919).to_be_bytes();
+                // Un SYN con el reloj 9 s atrás en mitad del ataque
+                let ts = if sec == 12 && i == 150 {
+                    3_000_000
+                } else {
+                    sec * 1_000_000 + i as u64 * 3_000
+                };
+                d.on_packet(
+                    &tcp(src, SERVER, 50, 1024 + (n % 60000) as u16, 80, "S", &[]),
+                    ts,
+                );
+            }
+        }
+        let r = d.finalize();
+        assert_eq!(r["attacks_total"], 1);
+        let a = &r["attacks"][0];
+        assert_eq!(a["start_ts_us"], 10_000_000);
+        assert_eq!(a["end_ts_us"], 15_000_000);
+        assert_eq!(a["syn"], 1500);
+        assert_eq!(a["peak_syn_rate"], 300);
+    }
+}
@@ -0,0 +1,452 @@
+use crate::engine::Detector;
+use crate::network::flow::{PROTO_ICMP, PROTO_ICMPV6, PROTO_TCP, PROTO_UDP};
+use crate::network::format::percent;
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+
+// Hosts, pares y servicios con detalle; el resto se agrega en `*_truncated`
+const MAX_HOSTS: usize = 100_000;
+const MAX_PAIRS: usize = 200_000;
+const MAX_SERVICES: usize = 10_000;
+const MAX_REPORTED: usize = 1000;
+// Hosts (los de más bytes) que entran en la matriz emisor × receptor
+const MATRIX_HOSTS: usize = 20;
+
+/// Nombre legible de un protocolo IP (número IANA)
+pub(crate) fn ip_protocol_name(protocol: u8) -> String {
+    match protocol {
+        PROTO_ICMP => "ICMP".to_string(),
+        2 => "IGMP".to_string(),
+        4 => "IPIP".to_string(),
+        PROTO_TCP => "TCP".to_string(),
+        PROTO_UDP => "UDP".to_string(),
+        41 => "IPv6".to_string(),
+        47 => "GRE".to_string(),
+        50 => "ESP".to_string(),
+        51 => "AH".to_string(),
+        PROTO_ICMPV6 => "ICMPv6".to_string(),
+        89 => "OSPF".to_string(),
+        103 => "PIM".to_string(),
+        112 => "VRRP".to_string(),
+        132 => "SCTP".to_string(),
+        p => format!("IP/{p}"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Counter {
+    packets: u64,
+    bytes: u64,
+}
+
+impl Counter {
+    fn add(&mut self, bytes: u64) {
+        self.packets += 1;
+        self.bytes += bytes;
+    }
+
+    fn to_json(self) -> Value {
+        json!({ "packets": self.packets, "bytes": self.bytes })
+    }
+}
+
+#[derive(Debug, Default)]
+struct HostStats {
+    sent: Counter,
+    received: Counter,
+}
+
+/// Par de hosts sin orden: `a` es siempre la dirección menor
+#[derive(Debug, Default)]
+struct PairStats {
+    a_to_b: Counter,
+    b_to_a: Counter,
+    by_protocol: BTreeMap<u8, Counter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct ServiceKey {
+    protocol: u8,
+    // Solo TCP/UDP llevan puerto de servicio
+    port: Option<u16>,
+}
+
+impl ServiceKey {
+    fn label(&self) -> String {
+        let proto = ip_protocol_name(self.protocol);
+        match self.port {
+            Some(port) => format!("{proto}/{port}"),
+            None => proto,
+        }
+    }
+}
+
+/// Top talkers y matriz de conversaciones: paquetes y bytes de trama por host, por par de
+/// hosts y por servicio (protocolo/puerto), para cualquier protocolo L3
+#[derive(Default)]
+pub struct TalkersDetector {
+    total: Counter,
+    non_ip: Counter,
+    non_ip_by_ethertype: BTreeMap<u16, Counter>,
+    hosts: HashMap<IpAddr, HostStats>,
+    hosts_truncated: Counter,
+    pairs: HashMap<(IpAddr, IpAddr), PairStats>,
+    pairs_truncated: Counter,
+    services: HashMap<ServiceKey, Counter>,
+    services_truncated: Counter,
+}
+
+impl TalkersDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn host(&mut self, ip: IpAddr, bytes: u64) -> Option<&mut HostStats> {
+        if !self.hosts.contains_key(&ip) && self.hosts.len() >= MAX_HOSTS {
+            self.hosts_truncated.add(bytes);
+            return None;
+        }
+        Some(self.hosts.entry(ip).or_default())
+    }
+
+    fn on_ip(&mut self, src: IpAddr, dst: IpAddr, protocol: u8, port: Option<u16>, bytes: u64) {
+        if let Some(h) = self.host(src, bytes) {
+            h.sent.add(bytes);
+        }
+        if let Some(h) = self.host(dst, bytes) {
+            h.received.add(bytes);
+        }
+
+        let key = (src.min(dst), src.max(dst));
+        if !self.pairs.contains_key(&key) && self.pairs.len() >= MAX_PAIRS {
+            self.pairs_truncated.add(bytes);
+        } else {
+            let pair = self.pairs.entry(key).or_default();
+            if src == key.0 {
+                pair.a_to_b.add(bytes);
+            } else {
+                pair.b_to_a.add(bytes);
+            }
+            pair.by_protocol.entry(protocol).or_default().add(bytes);
+        }
+
+        let service = ServiceKey { protocol, port };
+        if !self.services.contains_key(&service) && self.services.len() >= MAX_SERVICES {
+            self.services_truncated.add(bytes);
+        } else {
+            self.services.entry(service).or_default().add(bytes);
+        }
+    }
+
+    fn matrix(&self, hosts: &[IpAddr]) -> Value {
+        let cell = |src: &IpAddr, dst: &IpAddr| {
+            let key = (*src.min(dst), *src.max(dst));
+            self.pairs.get(&key).map_or(Counter::default(), |p| {
+                if *src == key.0 {
+                    p.a_to_b
+                } else {
+                    p.b_to_a
+                }
+            })
+        };
+        let grid = |f: fn(Counter) -> u64| -> Vec<Vec<u64>> {
+            hosts
+                .iter()
+                .map(|src| hosts.iter().map(|dst| f(cell(src, dst))).collect())
+                .collect()
+        };
+        json!({
+            "hosts": hosts.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "bytes": grid(|c| c.bytes),
+            "packets": grid(|c| c.packets)
+        })
+    }
+}
+
+/// Puerto de servicio de un flujo TCP/UDP: el menor de los dos, porque el cliente usa
+/// un puerto efímero alto
+fn service_port(sport: u16, dport: u16) -> u16 {
+    sport.min(dport)
+}
+
+/// Vistas top por bytes y por paquetes; desempate estable por la clave
+fn top_views<K: Ord + Copy>(items: &[(K, Counter, Value)]) -> (Vec<Value>, Vec<Value>) {
+    let mut by_bytes: Vec<&(K, Counter, Value)> = items.iter().collect();
+    by_bytes.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+    let mut by_packets = by_bytes.clone();
+    by_packets.sort_by(|a, b| b.1.packets.cmp(&a.1.packets).then_with(|| a.0.cmp(&b.0)));
+    let take = |v: Vec<&(K, Counter, Value)>| {
+        v.into_iter()
+            .take(MAX_REPORTED)
+            .map(|(_, _, j)| j.clone())
+            .collect()
+    };
+    (take(by_bytes), take(by_packets))
+}
+
+impl Detector for TalkersDetector {
+    fn name(&self) -> &'static str {
+        "talkers"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], _ts_micros: u64) {
+        let bytes = packet_data.len() as u64;
+        self.total.add(bytes);
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            self.non_ip.add(bytes);
+            return;
+        };
+        let (src, dst, protocol) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+                ip.payload().ip_number.0,
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+                ip.payload().ip_number.0,
+            ),
+            None => {
+                self.non_ip.add(bytes);
+                let payload = match (&sliced.vlan, &sliced.link) {
+                    (Some(vlan), _) => Some(vlan.payload()),
+                    (None, Some(link)) => Some(link.payload()),
+                    (None, None) => None,
+                };
+                if let Some(p) = payload {
+                    self.non_ip_by_ethertype
+                        .entry(p.ether_type.0)
+                        .or_default()
+                        .add(bytes);
+                }
+                return;
+            }
+        };
+        // Fragmentos no iniciales: sin cabecera de transporte, cuentan como el protocolo sin puerto
+        let port = match &sliced.transport {
+            Some(TransportSlice::Tcp(tcp)) => {
+                Some(service_port(tcp.source_port(), tcp.destination_port()))
+            }
+            Some(TransportSlice::Udp(udp)) => {
+                Some(service_port(udp.source_port(), udp.destination_port()))
+            }
+            _ => None,
+        };
+        self.on_ip(src, dst, protocol, port, bytes);
+    }
+
+    fn finalize(&mut self) -> Value {
+        let total_bytes = self.total.bytes;
+
+        let mut peers: HashMap<IpAddr, u64> = HashMap::new();
+        for (a, b) in self.pairs.keys() {
+            *peers.entry(*a).or_default() += 1;
+            *peers.entry(*b).or_default() += 1;
+        }
+        let hosts: Vec<(IpAddr, Counter, Value)> = self
+            .hosts
+            .iter()
+            .map(|(ip, h)| {
+                let both = Counter {
+                    packets: h.sent.packets + h.received.packets,
+                    bytes: h.sent.bytes + h.received.bytes,
+                };
+                let v = json!({
+                    "host": ip.to_string(),
+                    "packets": both.packets,
+                    "bytes": both.bytes,
+                    "sent": h.sent.to_json(),
+                    "received": h.received.to_json(),
+                    "peers": peers.get(ip).copied().unwrap_or(0),
+                    "bytes_share_pct": percent(both.bytes, total_bytes)
+                });
+                (*ip, both, v)
+            })
+            .collect();
+        let (hosts_by_bytes, hosts_by_packets) = top_views(&hosts);
+
+        let pairs: Vec<((IpAddr, IpAddr), Counter, Value)> = self
+            .pairs
+            .iter()
+            .map(|((a, b), p)| {
+                let both = Counter {
+                    packets: p.a_to_b.packets + p.b_to_a.packets,
+                    bytes: p.a_to_b.bytes + p.b_to_a.bytes,
+                };
+                let protocols: BTreeMap<String, Value> = p
+                    .by_protocol
+                    .iter()
+                    .map(|(proto, c)| (ip_protocol_name(*proto), c.to_json()))
+                    .collect();
+                let v = json!({
+                    "a": a.to_string(),
+                    "b": b.to_string(),
+                    "packets": both.packets,
+                    "bytes": both.bytes,
+                    "a_to_b": p.a_to_b.to_json(),
+                    "b_to_a": p.b_to_a.to_json(),
+                    "protocols": protocols,
+                    "bytes_share_pct": percent(both.bytes, total_bytes)
+                });
+                ((*a, *b), both, v)
+            })
+            .collect();
+        let (pairs_by_bytes, pairs_by_packets) = top_views(&pairs);
+
+        let services: Vec<(ServiceKey, Counter, Value)> = self
+            .services
+            .iter()
+            .map(|(k, c)| {
+                let v = json!({
+                    "service": k.label(),
+                    "protocol": ip_protocol_name(k.protocol),
+                    "port": k.port,
+                    "packets": c.packets,
+                    "bytes": c.bytes,
+                    "bytes_share_pct": percent(c.bytes, total_bytes)
+                });
+                (*k, *c, v)
+            })
+            .collect();
+        let (services_by_bytes, services_by_packets) = top_views(&services);
+
+        let mut ranked: Vec<&(IpAddr, Counter, Value)> = hosts.iter().collect();
+        ranked.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+        let matrix_hosts: Vec<IpAddr> = ranked.iter().take(MATRIX_HOSTS).map(|h| h.0).collect();
+
+        let by_ethertype: BTreeMap<String, Value> = self
+            .non_ip_by_ethertype
+            .iter()
+            .map(|(t, c)| (format!("0x{t:04x}"), c.to_json()))
+            .collect();
+
+        json!({
+            "packets_total": self.total.packets,
+            "bytes_total": total_bytes,
+            "non_ip": {
+                "packets": self.non_ip.packets,
+                "bytes": self.non_ip.bytes,
+                "by_ethertype": by_ethertype
+            },
+            "hosts_total": self.hosts.len(),
+            "hosts_truncated": self.hosts_truncated.to_json(),
+            "pairs_total": self.pairs.len(),
+            "pairs_truncated": self.pairs_truncated.to_json(),
+            "services_total": self.services.len(),
+            "services_truncated": self.services_truncated.to_json(),
+            "top_hosts_by_bytes": hosts_by_bytes,
+            "top_hosts_by_packets": hosts_by_packets,
+            "top_pairs_by_bytes": pairs_by_bytes,
+            "top_pairs_by_packets": pairs_by_packets,
+            "top_services_by_bytes": services_by_bytes,
+            "top_services_by_packets": services_by_packets,
+            "matrix": self.matrix(&matrix_hosts)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etherparse::{EtherType, Ethernet2Header, IpNumber, Ipv4Header, PacketBuilder};
+
+    const A: [u8; 4] = [10, 0, 0, 1];
+    const B: [u8; 4] = [10, 0, 0, 2];
+    const C: [u8; 4] = [10, 0, 0, 3];
+
+    fn tcp(src: [u8; 4], dst: [u8; 4], sport: u16, dport: u16, len: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(src, dst, 64)
+            .tcp(sport, dport, 1, 1024)
+            .write(&mut out, &vec![0; len])
+            .unwrap();
+        out
+    }
+
+    /// Trama IPv4 con un protocolo sin cabecera de transporte conocida (GRE)
+    fn gre(src: [u8; 4], dst: [u8; 4], len: usize) -> Vec<u8> {
+        let ip = Ipv4Header::new(len as u16, 64, IpNumber(47), src, dst).unwrap();
+        let mut out = Vec::new();
+        Ethernet2Header {
+            source: [1; 6],
+            destination: [2; 6],
+            ether_type: EtherType::IPV4,
+        }
+        .write(&mut out)
+        .unwrap();
+        ip.write(&mut out).unwrap();
+        out.extend(vec![0; len]);
+        out
+    }
+
+    #[test]
+    fn aggregates_hosts_pairs_and_services_across_protocols() {
+        let mut d = TalkersDetector::new();
+        for _ in 0..3 {
+            d.on_packet(&tcp(A, B, 50000, 443, 1000), 0);
+            d.on_packet(&tcp(B, A, 443, 50000, 100), 0);
+        }
+        d.on_packet(&gre(A, C, 200), 0);
+        d.on_packet(&gre(C, A, 200), 0);
+
+        let r = d.finalize();
+        assert_eq!(r["packets_total"], 8);
+        assert_eq!(r["hosts_total"], 3);
+        assert_eq!(r["pairs_total"], 2);
+
+        let top = &r["top_hosts_by_bytes"][0];
+        assert_eq!(top["host"], "10.0.0.1");
+        assert_eq!(top["sent"]["packets"], 4);
+        assert_eq!(top["received"]["packets"], 4);
+        assert_eq!(top["peers"], 2);
+
+        let pair = &r["top_pairs_by_bytes"][0];
+        assert_eq!(
+            (pair["a"].as_str(), pair["b"].as_str()),
+            (Some("10.0.0.1"), Some("10.0.0.2"))
+        );
+        assert_eq!(pair["a_to_b"]["bytes"], 3 * 1054);
+        assert_eq!(pair["b_to_a"]["bytes"], 3 * 154);
+        assert_eq!(pair["protocols"]["TCP"]["packets"], 6);
+
+        let services: Vec<&str> = r["top_services_by_bytes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|s| s["service"].as_str())
+            .collect();
+        assert_eq!(services, ["TCP/443", "GRE"]);
+    }
+
+    #[test]
+    fn matrix_is_directional_and_non_ip_counted_by_ethertype() {
+        let mut d = TalkersDetector::new();
+        d.on_packet(&tcp(A, B, 40000, 22, 500), 0);
+        d.on_packet(&tcp(A, B, 40000, 22, 500), 0);
+        d.on_packet(&tcp(B, A, 22, 40000, 0), 0);
+        // Trama ARP: sin IP, cuenta solo por EtherType
+        let mut arp = Vec::new();
+        Ethernet2Header {
+            source: [1; 6],
+            destination: [0xff; 6],
+            ether_type: EtherType::ARP,
+        }
+        .write(&mut arp)
+        .unwrap();
+        arp.extend([0; 28]);
+        d.on_packet(&arp, 0);
+
+        let r = d.finalize();
+        let m = &r["matrix"];
+        assert_eq!(m["hosts"], json!(["10.0.0.1", "10.0.0.2"]));
+        assert_eq!(m["packets"], json!([[0, 2], [1, 0]]));
+        assert_eq!(m["bytes"], json!([[0, 2 * 554], [54, 0]]));
+        assert_eq!(r["non_ip"]["packets"], 1);
+        assert_eq!(r["non_ip"]["by_ethertype"]["0x0806"]["bytes"], 42);
+        assert_eq!(r["packets_total"], 4);
+    }
+}
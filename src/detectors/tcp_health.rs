@@ -1,9 +1,18 @@
 use crate::engine::Detector;
-use crate::network::flow::Flow;
-use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use crate::network::checksum::{self, ChecksumStatus};
+use crate::network::flow::{Flow, PROTO_TCP};
+use crate::network::flow_table::{FlowArchive, FlowTable, FlowTableConfig};
+use crate::network::format::flow_label;
+use crate::network::icmp::{self, IcmpMessage};
+use etherparse::err::packet::SliceError;
+use etherparse::err::Layer;
+use etherparse::{
+    EtherType, InternetSlice, IpNumber, LaxNetSlice, LaxSlicedPacket, LenSource, SlicedPacket,
+    TcpOptionElement, TcpSlice, TransportSlice,
+};
 use serde_json::{json, Value};
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::net::{IpAddr, Ipv4Addr};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::net::IpAddr;
 
 #[derive(Default, Debug)]
 pub struct TcpStreamState {
@@ -16,6 +25,7 @@ pub struct TcpStreamState {
     // Heurísticas
     pub seen_seq_numbers: HashSet<u32>, // retransmisión si repite seq con payload
     pub seen_seq_queue: VecDeque<u32>,
+    seen_seq_cap: usize, // 0 = SEEN_MAX_TRACKED (fijado por el detector según max_flows)
     pub highest_seq_end: Option<u32>, // mayor (seq + len) observado
 
     // DupACK
@@ -25,6 +35,30 @@ pub struct TcpStreamState {
 
     pub last_window_seen: Option<u16>,
 
+    // Middlebox (TTL / IP-ID / MSS / ISN)
+    pub syn_mss: Option<u16>, // MSS anunciado en el SYN (o SYN-ACK) de este lado
+    pub isn: Option<u32>,     // número de secuencia inicial (SYN)
+    pub last_ttl: Option<u8>, // último TTL visto en paquetes que no son RST
+    pub last_ip_id: Option<u16>, // último IP-ID visto en paquetes que no son RST
+    pub ttl_changes: u32,     // cambios de TTL a mitad de stream
+    pub ip_id_samples: u32,   // pares consecutivos de IP-ID comparados
+    pub ip_id_shifts: u32,    // saltos de IP-ID fuera de la progresión esperada
+    pub suspected_injected_rst: u32, // RST cuyo TTL/IP-ID no encaja con el stream
+    pub seq_rewrite_events: u32, // SYN-ACK que no confirma ISN+1 de este lado
+    pub mss_exceeded: u32,    // segmentos por encima del MSS del otro lado (pero no del propio)
+
+    // Límites de memoria
+    pub tracking_truncations: u32, // descartes por límite (seq vistos / pendientes de RTT)
+
+    // Validación
+    pub checksum_bad: u32,          // IPv4 o TCP con checksum incorrecto
+    pub checksum_offload_like: u32, // ...con patrón de offload (0, parcial o todo el sentido)
+    pub checksum_excluded: u32,     // ...excluidos de las métricas
+    pub illegal_flag_segments: u32, // SYN+FIN, NULL o Xmas
+
+    // Errores ICMP que citan segmentos de este sentido (evidencia de fallo)
+    pub icmp_errors: BTreeMap<&'static str, u32>,
+
     // RTT (campos internos; no exponer tipos privados)
     outstanding: VecDeque<OutstandingSegment>, // segmentos enviados pendientes de ACK
     rtt: RttStats,                             // stats de RTT en µs (cap de muestras)
@@ -37,9 +71,196 @@ pub struct TcpConversationState {
     pub s2c: TcpStreamState,
 }
 
-#[derive(Default)]
+/// Límites de memoria del detector
+#[derive(Debug, Clone, Copy)]
+pub struct TcpHealthConfig {
+    pub flow_table: FlowTableConfig,
+    /// Máximo de resúmenes de conversaciones expulsadas que se conservan (0 = sin límite)
+    pub max_archived: usize,
+    pub checksum_mode: ChecksumMode,
+}
+
+impl Default for TcpHealthConfig {
+    fn default() -> Self {
+        TcpHealthConfig {
+            flow_table: FlowTableConfig::default(),
+            max_archived: 50_000,
+            checksum_mode: ChecksumMode::OffloadAware,
+        }
+    }
+}
+
+/// Cómo tratar los paquetes con checksum IPv4/TCP incorrecto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumMode {
+    /// Sin verificación (todos los paquetes cuentan en las métricas)
+    Off,
+    /// Todo paquete con checksum incorrecto se excluye de las métricas
+    Strict,
+    /// Los que parecen artefactos de offload de la NIC se mantienen; el resto se excluye
+    OffloadAware,
+}
+
+impl ChecksumMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChecksumMode::Off => "off",
+            ChecksumMode::Strict => "strict",
+            ChecksumMode::OffloadAware => "offload_aware",
+        }
+    }
+}
+
 pub struct TcpHealthDetector {
-    conversations: HashMap<Flow, TcpConversationState>,
+    conversations: FlowTable<TcpConversationState>,
+    // Conversaciones expulsadas de la tabla, ya finalizadas
+    archived: FlowArchive<ConversationSummary>,
+    // Agregado de todas las conversaciones finalizadas: no se poda con el archivo
+    totals: ConversationTotals,
+    seen_seq_cap: usize,
+    checksum_mode: ChecksumMode,
+    validation: ValidationStats,
+}
+
+const TCP_MIN_HEADER_LEN: usize = 20;
+// Segmentos previos, todos con checksum incorrecto, para atribuir un sentido a offload
+// en lugar de a un paquete corrupto aislado
+const OFFLOAD_MIN_SEGMENTS: u32 = 3;
+
+/// Contadores globales de segmentos malformados y checksums
+#[derive(Debug, Default)]
+struct ValidationStats {
+    truncated_headers: u64,
+    truncated_segments: u64,
+    invalid_data_offset: u64,
+    syn_fin: u64,
+    null_flags: u64,
+    xmas: u64,
+    ipv4_checksum_bad: u64,
+    tcp_checksum_bad: u64,
+    offload_like: u64,
+    excluded: u64,
+}
+
+impl ValidationStats {
+    /// Clasifica por qué etherparse no pudo cortar el frame
+    fn record_slice_error(&mut self, err: &SliceError) {
+        match err {
+            SliceError::Tcp(_) => self.invalid_data_offset += 1,
+            SliceError::Len(len) => match (len.layer, len.len_source) {
+                // Menos de 20 bytes: cabecera TCP truncada; más: el data offset
+                // apunta fuera del segmento
+                (Layer::TcpHeader, _) if len.required_len > TCP_MIN_HEADER_LEN => {
+                    self.invalid_data_offset += 1
+                }
+                (Layer::TcpHeader, _)
+                | (Layer::Ipv4Header, _)
+                | (Layer::Ipv6Header, _)
+                | (Layer::IpHeader, _) => self.truncated_headers += 1,
+                (Layer::Ipv4Packet, LenSource::Slice) | (Layer::Ipv6Packet, LenSource::Slice) => {
+                    self.truncated_segments += 1
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn findings(&self) -> Vec<String> {
+        let mut findings = Vec::<String>::new();
+        let bad = self.ipv4_checksum_bad + self.tcp_checksum_bad;
+        if bad > 0 {
+            if self.offload_like.saturating_mul(10) >= bad.saturating_mul(9) {
+                findings.push(format!(
+                    "checksums inválidos con patrón de offload de la NIC (captura en el host emisor) ({bad})"
+                ));
+            } else {
+                findings.push(format!(
+                    "checksums inválidos sin patrón de offload: posible corrupción real ({bad}, {} excluidos de métricas)",
+                    self.excluded
+                ));
+            }
+        }
+        if self.truncated_headers > 0 {
+            findings.push(format!("cabeceras truncadas ({})", self.truncated_headers));
+        }
+        if self.invalid_data_offset > 0 {
+            findings.push(format!(
+                "data offset TCP inválido ({})",
+                self.invalid_data_offset
+            ));
+        }
+        let illegal = self.syn_fin + self.null_flags + self.xmas;
+        if illegal > 0 {
+            findings.push(format!(
+                "combinaciones de flags ilegales: SYN+FIN {}, NULL {}, Xmas {}",
+                self.syn_fin, self.null_flags, self.xmas
+            ));
+        }
+        findings
+    }
+}
+
+impl Default for TcpHealthDetector {
+    fn default() -> Self {
+        Self::with_config(TcpHealthConfig::default())
+    }
+}
+
+/// Contadores de todas las conversaciones finalizadas, incluidas las podadas del archivo
+#[derive(Debug, Default)]
+struct ConversationTotals {
+    conversations: u64,
+    packets: u64,
+    retransmissions: u64,
+    out_of_order: u64,
+    duplicate_ack_events: u64,
+    zero_window_events: u64,
+    middlebox_suspects: u64,
+    truncated_flows: u64,
+    by_level: BTreeMap<&'static str, u64>,
+}
+
+impl ConversationTotals {
+    fn add(&mut self, st: &TcpConversationState, summary: &ConversationSummary) {
+        self.conversations += 1;
+        for s in [&st.c2s, &st.s2c] {
+            self.packets += s.packet_count as u64;
+            self.retransmissions += s.retransmission_count as u64;
+            self.out_of_order += s.out_of_order_count as u64;
+            self.duplicate_ack_events += s.duplicate_ack_events as u64;
+            self.zero_window_events += s.zero_window_events as u64;
+        }
+        self.middlebox_suspects += u64::from(summary.middlebox_suspect);
+        self.truncated_flows += u64::from(summary.truncated);
+        *self.by_level.entry(summary.level).or_default() += 1;
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "conversations": self.conversations,
+            "packets": self.packets,
+            "retransmissions": self.retransmissions,
+            "out_of_order": self.out_of_order,
+            "duplicate_ack_events": self.duplicate_ack_events,
+            "zero_window_events": self.zero_window_events,
+            "by_level": self.by_level
+        })
+    }
+}
+
+/// Resumen compacto de una conversación finalizada (sin estado de seguimiento)
+struct ConversationSummary {
+    flow: String,
+    sort_key: (String, u16, String, u16),
+    total_packets: u32,
+    score: u32,
+    level: &'static str,
+    reasons: Vec<String>,
+    middlebox_suspect: bool,
+    truncated: bool,
+    c2s: Value,
+    s2c: Value,
 }
 
 // ---- RTT support ----
@@ -93,7 +314,7 @@ fn quantile_us(sorted_us: &[u64], q: f64) -> u64 {
 
 // Comparación modular de secuencias TCP
 #[inline]
-fn seq_lte(a: u32, b: u32) -> bool {
+pub(crate) fn seq_lte(a: u32, b: u32) -> bool {
     b.wrapping_sub(a) as i32 >= 0
 }
 #[inline]
@@ -101,10 +322,120 @@ fn seq_lt(a: u32, b: u32) -> bool {
     b.wrapping_sub(a) as i32 > 0
 }
 #[inline]
-fn seq_gt(a: u32, b: u32) -> bool {
+pub(crate) fn seq_gt(a: u32, b: u32) -> bool {
     a.wrapping_sub(b) as i32 > 0
 }
 
+// ---- Middlebox support ----
+
+/// Tolerancia de TTL entre paquetes del mismo sentido (ECMP / cambios de ruta menores)
+const TTL_TOLERANCE: u8 = 1;
+/// Avance máximo de IP-ID considerado "progresión normal" entre dos paquetes del mismo sentido
+const IP_ID_WINDOW: u16 = 2048;
+
+#[derive(Clone, Copy)]
+struct IpMeta {
+    ttl: u8,
+    ip_id: u16,
+}
+
+#[inline]
+fn ip_id_in_window(last: u16, current: u16) -> bool {
+    current.wrapping_sub(last) <= IP_ID_WINDOW
+}
+
+/// El IP-ID solo sirve como evidencia si el stack lo incrementa de forma secuencial
+/// (OpenBSD/macOS y Linux con DF pueden usar IP-ID aleatorio o 0).
+fn ip_id_is_sequential(st: &TcpStreamState) -> bool {
+    st.ip_id_samples >= 2 && st.ip_id_shifts.saturating_mul(10) <= st.ip_id_samples
+}
+
+/// MSS anunciado en las opciones de un segmento SYN (si existe)
+pub(crate) fn syn_mss(tcp: &TcpSlice) -> Option<u16> {
+    tcp.options_iterator().find_map(|opt| match opt {
+        Ok(TcpOptionElement::MaximumSegmentSize(mss)) => Some(mss),
+        _ => None,
+    })
+}
+
+/// Hallazgos de interferencia de middleboxes (RST inyectados, MSS clamping, reescritura de seq)
+fn middlebox_findings(c2s: &TcpStreamState, s2c: &TcpStreamState) -> Vec<String> {
+    let mut findings = Vec::<String>::new();
+
+    let rst = c2s.suspected_injected_rst + s2c.suspected_injected_rst;
+    if rst >= 1 {
+        findings.push(format!(
+            "posible RST inyectado (TTL/IP-ID no coinciden) ({rst})"
+        ));
+    }
+
+    let rewrites = c2s.seq_rewrite_events + s2c.seq_rewrite_events;
+    if rewrites >= 1 {
+        findings.push(format!(
+            "SYN-ACK no confirma ISN+1: posible reescritura de secuencia ({rewrites})"
+        ));
+    }
+
+    // Cada lado anuncia su propio MSS: que difieran es normal. Solo cuenta como clamping
+    // si un emisor supera el MSS que aquí se ve del otro lado (vio otro valor) o si el
+    // flujo ya muestra otras señales de middlebox.
+    if let (Some(c), Some(s)) = (c2s.syn_mss, s2c.syn_mss) {
+        let exceeded = c2s.mss_exceeded + s2c.mss_exceeded;
+        let ttl_changes = c2s.ttl_changes + s2c.ttl_changes;
+        if c != s && (exceeded >= 1 || ttl_changes >= 1 || !findings.is_empty()) {
+            findings.push(format!(
+                "MSS distinto entre SYN ({c}) y SYN-ACK ({s}) con indicios de middlebox: posible MSS clamping"
+            ));
+        }
+    }
+
+    findings
+}
+
+/// Hallazgos de checksums y flags ilegales por conversación
+fn validation_findings(c2s: &TcpStreamState, s2c: &TcpStreamState) -> Vec<String> {
+    let mut findings = Vec::<String>::new();
+
+    let bad = c2s.checksum_bad + s2c.checksum_bad;
+    let offload = c2s.checksum_offload_like + s2c.checksum_offload_like;
+    let excluded = c2s.checksum_excluded + s2c.checksum_excluded;
+    if bad >= 1 {
+        if offload == bad {
+            findings.push(format!(
+                "checksums inválidos, probable offload de la NIC ({bad})"
+            ));
+        } else {
+            findings.push(format!(
+                "checksums inválidos, posible corrupción ({bad}, {excluded} excluidos)"
+            ));
+        }
+    }
+
+    let illegal = c2s.illegal_flag_segments + s2c.illegal_flag_segments;
+    if illegal >= 1 {
+        findings.push(format!("flags ilegales SYN+FIN/NULL/Xmas ({illegal})"));
+    }
+
+    findings
+}
+
+/// Errores ICMP recibidos por cada sentido (puerto cerrado, TTL agotado, ...)
+fn icmp_findings(c2s: &TcpStreamState, s2c: &TcpStreamState) -> Vec<String> {
+    let mut findings = Vec::<String>::new();
+    for (side, st) in [("C->S", c2s), ("S->C", s2c)] {
+        if st.icmp_errors.is_empty() {
+            continue;
+        }
+        let list: Vec<String> = st
+            .icmp_errors
+            .iter()
+            .map(|(name, n)| format!("{name} ({n})"))
+            .collect();
+        findings.push(format!("errores ICMP sobre {side}: {}", list.join(", ")));
+    }
+    findings
+}
+
 // ----- Scoring de severidad (extraído para test) -----
 fn compute_severity(
     c2s: &TcpStreamState,
@@ -176,7 +507,12 @@ fn compute_severity(
 }
 
 const SEEN_WINDOW_BYTES: u64 = 16 * 1024 * 1024; // 16 MiB
-const SEEN_MAX_TRACKED: usize = 200_000;
+                                                 // Seq vistos entre todos los streams activos (~16 B por entrada entre set y cola):
+                                                 // con la tabla llena, max_flows × 2 × cap no pasa de este presupuesto
+const SEEN_TRACKED_BUDGET: usize = 16_000_000;
+const SEEN_MAX_TRACKED: usize = 16_384; // una ventana de 16 MiB en segmentos de ~1 KiB
+const SEEN_MIN_TRACKED: usize = 64;
+const OUTSTANDING_MAX_TRACKED: usize = 16_384; // sin ACKs visibles (captura unidireccional) no se vacía
 
 #[inline]
 fn seq_distance_forward(from: u32, to: u32) -> u64 {
@@ -198,7 +534,12 @@ fn maintain_seen_window(stream: &mut TcpStreamState) {
             }
         }
     }
-    if stream.seen_seq_numbers.len() > SEEN_MAX_TRACKED {
+    let cap = match stream.seen_seq_cap {
+        0 => SEEN_MAX_TRACKED,
+        cap => cap,
+    };
+    if stream.seen_seq_numbers.len() > cap {
+        stream.tracking_truncations += 1;
         let drop = stream.seen_seq_numbers.len() / 4;
         for _ in 0..drop {
             if let Some(x) = stream.seen_seq_queue.pop_front() {
@@ -208,25 +549,83 @@ fn maintain_seen_window(stream: &mut TcpStreamState) {
     }
 }
 
+/// Seq vistos por stream para que la tabla llena respete `SEEN_TRACKED_BUDGET`
+fn seen_seq_cap(max_flows: usize) -> usize {
+    if max_flows == 0 {
+        return SEEN_MAX_TRACKED;
+    }
+    (SEEN_TRACKED_BUDGET / (2 * max_flows)).clamp(SEEN_MIN_TRACKED, SEEN_MAX_TRACKED)
+}
+
 impl TcpHealthDetector {
     pub fn new() -> Self {
         Self::default()
     }
 
-    fn get_conv_mut(
-        conversations: &mut HashMap<Flow, TcpConversationState>,
-        current_flow: Flow,
-    ) -> &mut TcpConversationState {
-        if conversations.contains_key(&current_flow) {
-            return conversations.get_mut(&current_flow).unwrap();
+    pub fn with_config(config: TcpHealthConfig) -> Self {
+        TcpHealthDetector {
+            conversations: FlowTable::new(config.flow_table),
+            // Conserva la mitad superior por severidad y la mitad superior por volumen
+            archived: FlowArchive::new(config.max_archived, cmp_by_severity, cmp_by_packets),
+            totals: ConversationTotals::default(),
+            seen_seq_cap: seen_seq_cap(config.flow_table.max_flows),
+            checksum_mode: config.checksum_mode,
+            validation: ValidationStats::default(),
+        }
+    }
+
+    /// Decide si un segmento con checksum incorrecto entra en las métricas.
+    /// Un sentido en el que todos los paquetes fallan es el patrón típico de TX offload.
+    fn accept_bad_checksum(
+        mode: ChecksumMode,
+        stream: &mut TcpStreamState,
+        status: ChecksumStatus,
+    ) -> bool {
+        // Los excluidos no suman en packet_count pero también son segmentos vistos
+        let seen = stream.packet_count + stream.checksum_excluded;
+        let consistent = seen >= OFFLOAD_MIN_SEGMENTS && stream.checksum_bad >= seen;
+        stream.checksum_bad += 1;
+        let offload_like = status.is_offload_signature() || consistent;
+        if offload_like {
+            stream.checksum_offload_like += 1;
         }
-        let reverse = current_flow.reverse();
-        if conversations.contains_key(&reverse) {
-            return conversations.get_mut(&reverse).unwrap();
+        let accept = mode == ChecksumMode::OffloadAware && offload_like;
+        if !accept {
+            stream.checksum_excluded += 1;
+        }
+        accept
+    }
+
+    /// Un error ICMP que cita un segmento de una conversación conocida queda como evidencia
+    fn on_icmp(&mut self, msg: Option<IcmpMessage>) {
+        let Some(IcmpMessage::Error(e)) = msg else {
+            return;
+        };
+        let Some(q) = icmp::quoted_flow(e.quoted).filter(|q| q.protocol == PROTO_TCP) else {
+            return;
+        };
+        // El paquete citado lo envió el host que recibe el error
+        if let Some((_, conv, forward)) = self.conversations.get_mut(&q.flow) {
+            let stream = if forward {
+                &mut conv.c2s
+            } else {
+                &mut conv.s2c
+            };
+            *stream.icmp_errors.entry(e.name).or_default() += 1;
         }
-        let entry = conversations.entry(current_flow).or_default();
-        entry.flow = current_flow;
-        entry
+    }
+
+    /// Finaliza conversaciones expulsadas de la tabla en resúmenes compactos
+    fn archive(&mut self, evicted: Vec<(Flow, TcpConversationState)>) {
+        if evicted.is_empty() {
+            return;
+        }
+        let totals = &mut self.totals;
+        self.archived.extend(evicted.iter().map(|(_, st)| {
+            let summary = summarize(st);
+            totals.add(st, &summary);
+            summary
+        }));
     }
 
     #[inline]
@@ -271,6 +670,10 @@ impl TcpHealthDetector {
             }
 
             // Registrar pendiente para RTT
+            if stream.outstanding.len() >= OUTSTANDING_MAX_TRACKED {
+                stream.outstanding.pop_front();
+                stream.tracking_truncations += 1;
+            }
             stream.outstanding.push_back(OutstandingSegment {
                 seq_end: seg_end,
                 ts_us,
@@ -295,6 +698,66 @@ impl TcpHealthDetector {
         }
     }
 
+    /// Sigue TTL/IP-ID/MSS/ISN por sentido para detectar interferencia de middleboxes
+    fn track_middlebox(
+        fwd: &mut TcpStreamState,
+        rev: &mut TcpStreamState,
+        ip: IpMeta,
+        seq_num: u32,
+        ack_num: u32,
+        flags: TcpFlags,
+        mss: Option<u16>,
+    ) {
+        if flags.syn {
+            fwd.isn = Some(seq_num);
+            if mss.is_some() {
+                fwd.syn_mss = mss;
+            }
+            // SYN-ACK: debe confirmar exactamente el ISN+1 del otro lado
+            if flags.ack {
+                if let Some(peer_isn) = rev.isn {
+                    if ack_num != peer_isn.wrapping_add(1) {
+                        rev.seq_rewrite_events += 1;
+                    }
+                }
+            }
+        }
+
+        if flags.rst {
+            // Un RST legítimo sale del mismo stack que el resto del stream:
+            // mismo TTL y un IP-ID cercano al último visto (Linux puede usar IP-ID 0).
+            let ttl_mismatch = fwd
+                .last_ttl
+                .is_some_and(|t| t.abs_diff(ip.ttl) > TTL_TOLERANCE);
+            let ip_id_mismatch = ip.ip_id != 0
+                && ip_id_is_sequential(fwd)
+                && fwd
+                    .last_ip_id
+                    .is_some_and(|last| !ip_id_in_window(last, ip.ip_id));
+            if ttl_mismatch || ip_id_mismatch {
+                fwd.suspected_injected_rst += 1;
+            }
+            return;
+        }
+
+        if let Some(last) = fwd.last_ttl {
+            if last != ip.ttl {
+                fwd.ttl_changes += 1;
+            }
+        }
+        fwd.last_ttl = Some(ip.ttl);
+
+        if let Some(last) = fwd.last_ip_id {
+            if last != 0 && ip.ip_id != 0 {
+                fwd.ip_id_samples += 1;
+                if !ip_id_in_window(last, ip.ip_id) {
+                    fwd.ip_id_shifts += 1;
+                }
+            }
+        }
+        fwd.last_ip_id = Some(ip.ip_id);
+    }
+
     fn update_stream(
         stream: &mut TcpStreamState,
         seq_num: u32,
@@ -355,171 +818,251 @@ impl Detector for TcpHealthDetector {
     }
 
     fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
-        if let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) {
-            if let (Some(InternetSlice::Ipv4(ip)), Some(TransportSlice::Tcp(tcp))) =
-                (sliced.net, sliced.transport)
-            {
+        let sliced = match SlicedPacket::from_ethernet(packet_data) {
+            Ok(sliced) => sliced,
+            Err(e) => {
+                if carries_tcp(packet_data) {
+                    self.validation.record_slice_error(&e);
+                }
+                return;
+            }
+        };
+        let tcp = match &sliced.transport {
+            Some(TransportSlice::Tcp(tcp)) => tcp,
+            Some(TransportSlice::Icmpv4(m)) => {
+                let msg =
+                    icmp::classify(false, m.type_u8(), m.code_u8(), m.bytes5to8(), m.payload());
+                self.on_icmp(msg);
+                return;
+            }
+            Some(TransportSlice::Icmpv6(m)) => {
+                let msg =
+                    icmp::classify(true, m.type_u8(), m.code_u8(), m.bytes5to8(), m.payload());
+                self.on_icmp(msg);
+                return;
+            }
+            _ => return,
+        };
+
+        // Dirección + metadatos IP (TTL/IP-ID) + checksum de cabecera IPv4
+        let (src, dst, ip_meta, ip_status) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => {
                 let h = ip.header();
-                let flow = Flow {
-                    source_ip: IpAddr::V4(Ipv4Addr::from(h.source())),
-                    source_port: tcp.source_port(),
-                    destination_ip: IpAddr::V4(Ipv4Addr::from(h.destination())),
-                    destination_port: tcp.destination_port(),
+                let status = if checksum::ipv4_header_valid(h.slice()) {
+                    ChecksumStatus::Valid
+                } else if h.header_checksum() == 0 {
+                    ChecksumStatus::Zero
+                } else {
+                    ChecksumStatus::Invalid
+                };
+                let meta = IpMeta {
+                    ttl: h.ttl(),
+                    ip_id: h.identification(),
                 };
+                (
+                    IpAddr::V4(h.source_addr()),
+                    IpAddr::V4(h.destination_addr()),
+                    meta,
+                    status,
+                )
+            }
+            Some(InternetSlice::Ipv6(ip)) => {
+                let h = ip.header();
+                // IPv6 no tiene IP-ID: 0 = "sin evidencia" para las heurísticas de middlebox
+                let meta = IpMeta {
+                    ttl: h.hop_limit(),
+                    ip_id: 0,
+                };
+                (
+                    IpAddr::V6(h.source_addr()),
+                    IpAddr::V6(h.destination_addr()),
+                    meta,
+                    ChecksumStatus::Valid,
+                )
+            }
+            None => return,
+        };
+
+        let flow = Flow {
+            source_ip: src,
+            source_port: tcp.source_port(),
+            destination_ip: dst,
+            destination_port: tcp.destination_port(),
+        };
+
+        let seq = tcp.sequence_number();
+        let ack = tcp.acknowledgment_number();
+        let win = tcp.window_size();
+        let flags = TcpFlags {
+            syn: tcp.syn(),
+            fin: tcp.fin(),
+            rst: tcp.rst(),
+            ack: tcp.ack(),
+        };
+        let payload_len = tcp.payload().len(); // datos de aplicación
+
+        // Combinaciones de flags ilegales (típicas de escaneos)
+        let no_flags =
+            !tcp.fin() && !tcp.syn() && !tcp.rst() && !tcp.psh() && !tcp.ack() && !tcp.urg();
+        let illegal_flags = if tcp.syn() && tcp.fin() {
+            self.validation.syn_fin += 1;
+            true
+        } else if no_flags {
+            self.validation.null_flags += 1;
+            true
+        } else if tcp.fin() && tcp.psh() && tcp.urg() && !tcp.ack() {
+            self.validation.xmas += 1;
+            true
+        } else {
+            false
+        };
 
-                let conv = Self::get_conv_mut(&mut self.conversations, flow);
+        // Checksums: el peor de IPv4 y TCP
+        let tcp_status = if self.checksum_mode == ChecksumMode::Off {
+            ChecksumStatus::Valid
+        } else {
+            checksum::transport_status(src, dst, 6, tcp.slice(), tcp.checksum())
+        };
+        let ip_status = if self.checksum_mode == ChecksumMode::Off {
+            ChecksumStatus::Valid
+        } else {
+            ip_status
+        };
+        if ip_status != ChecksumStatus::Valid {
+            self.validation.ipv4_checksum_bad += 1;
+        }
+        if tcp_status != ChecksumStatus::Valid {
+            self.validation.tcp_checksum_bad += 1;
+        }
+        let status = match (ip_status, tcp_status) {
+            (ChecksumStatus::Invalid, _) | (_, ChecksumStatus::Invalid) => ChecksumStatus::Invalid,
+            (ChecksumStatus::Valid, other) => other,
+            (other, _) => other,
+        };
+
+        let evicted = self.conversations.evict(ts_micros);
+        self.archive(evicted);
+
+        let mode = self.checksum_mode;
+        let seen_cap = self.seen_seq_cap;
+        let (key, conv, forward) = self.conversations.get_or_insert(flow, ts_micros);
+        conv.flow = key;
+        conv.c2s.seen_seq_cap = seen_cap;
+        conv.s2c.seen_seq_cap = seen_cap;
+
+        let (fwd, rev) = if forward {
+            (&mut conv.c2s, &mut conv.s2c)
+        } else {
+            (&mut conv.s2c, &mut conv.c2s)
+        };
 
-                let (fwd, rev) = if flow == conv.flow {
-                    (&mut conv.c2s, &mut conv.s2c)
-                } else {
-                    (&mut conv.s2c, &mut conv.c2s)
-                };
+        if illegal_flags {
+            fwd.illegal_flag_segments += 1;
+        }
 
-                let seq = tcp.sequence_number();
-                let ack = tcp.acknowledgment_number();
-                let win = tcp.window_size();
-                let flags = TcpFlags {
-                    syn: tcp.syn(),
-                    fin: tcp.fin(),
-                    rst: tcp.rst(),
-                    ack: tcp.ack(),
-                };
-                let payload_len = tcp.payload().len(); // datos de aplicación
+        if status != ChecksumStatus::Valid {
+            let offload_before = fwd.checksum_offload_like;
+            let accepted = Self::accept_bad_checksum(mode, fwd, status);
+            if fwd.checksum_offload_like > offload_before {
+                self.validation.offload_like += 1;
+            }
+            if !accepted {
+                self.validation.excluded += 1;
+                return;
+            }
+        }
 
-                // Actualiza lado emisor del segmento (métricas + outstanding)
-                Self::update_stream(fwd, seq, ack, win, payload_len, flags, ts_micros);
+        let mss = if flags.syn { syn_mss(tcp) } else { None };
 
-                // **ACK piggyback**: usa cualquier ACK válido (con o sin payload) para RTT
-                if flags.ack && !flags.syn && !flags.fin && !flags.rst {
-                    Self::on_ack(rev, ack, ts_micros);
-                }
+        // Middlebox: TTL/IP-ID/MSS/ISN por sentido (antes de actualizar métricas)
+        Self::track_middlebox(fwd, rev, ip_meta, seq, ack, flags, mss);
+        // Cabe en el MSS propio pero no en el anunciado por el receptor (TSO supera ambos)
+        if let (Some(own), Some(peer)) = (fwd.syn_mss, rev.syn_mss) {
+            if payload_len > peer as usize && payload_len <= own as usize {
+                fwd.mss_exceeded += 1;
             }
         }
+
+        // Actualiza lado emisor del segmento (métricas + outstanding)
+        Self::update_stream(fwd, seq, ack, win, payload_len, flags, ts_micros);
+
+        // **ACK piggyback**: usa cualquier ACK válido (con o sin payload) para RTT
+        if flags.ack && !flags.syn && !flags.fin && !flags.rst {
+            Self::on_ack(rev, ack, ts_micros);
+        }
     }
 
     fn finalize(&mut self) -> Value {
-        // ---- construir vistas ----
-        let convs: Vec<_> = self.conversations.values().collect();
+        // Las conversaciones aún activas se finalizan igual que las expulsadas
+        let stats = self.conversations.stats();
+        let table_cfg = self.conversations.config();
+        let live = self.conversations.drain();
+        let mut summaries: Vec<ConversationSummary> = self.archived.take();
+        for (_, st) in &live {
+            let summary = summarize(st);
+            self.totals.add(st, &summary);
+            summaries.push(summary);
+        }
 
-        // Top por severidad
-        let mut by_severity: Vec<Value> = convs
+        // Top por severidad. Orden estable: score desc, luego flow asc (desempate)
+        summaries.sort_by(cmp_by_severity);
+        let by_severity: Vec<Value> = summaries
             .iter()
-            .map(|st| {
-                let (score, level, reasons) = compute_severity(&st.c2s, &st.s2c);
-
-                // RTT percentiles (en ms)
-                let (c2s_p50, c2s_p95) = st.c2s.rtt.percentiles_ms();
-                let (s2c_p50, s2c_p95) = st.s2c.rtt.percentiles_ms();
-
-                let src_ip = st.flow.source_ip;
-                let src_port = st.flow.source_port;
-                let dst_ip = st.flow.destination_ip;
-                let dst_port = st.flow.destination_port;
-                let flow_str = format!("{src_ip}:{src_port} <-> {dst_ip}:{dst_port}/TCP");
-
+            .map(|c| {
                 json!({
-                    "flow": flow_str,
-                    "score": { "value": score, "level": level },
-                    "reasons": reasons,
-                    "c2s": {
-                        "packets": st.c2s.packet_count,
-                        "retransmissions": st.c2s.retransmission_count,
-                        "out_of_order": st.c2s.out_of_order_count,
-                        "zero_window_events": st.c2s.zero_window_events,
-                        "duplicate_ack_events": st.c2s.duplicate_ack_events,
-                        "rtt_ms": {
-                            "p50": c2s_p50,
-                            "p95": c2s_p95,
-                            "samples": st.c2s.rtt.count
-                        }
-                    },
-                    "s2c": {
-                        "packets": st.s2c.packet_count,
-                        "retransmissions": st.s2c.retransmission_count,
-                        "out_of_order": st.s2c.out_of_order_count,
-                        "zero_window_events": st.s2c.zero_window_events,
-                        "duplicate_ack_events": st.s2c.duplicate_ack_events,
-                        "rtt_ms": {
-                            "p50": s2c_p50,
-                            "p95": s2c_p95,
-                            "samples": st.s2c.rtt.count
-                        }
-                    }
+                    "flow": c.flow,
+                    "score": { "value": c.score, "level": c.level },
+                    "reasons": c.reasons,
+                    "c2s": c.c2s,
+                    "s2c": c.s2c
                 })
             })
             .collect();
 
-        // Orden estable: score desc, luego flow asc (desempate)
-        by_severity.sort_by(|a, b| {
-            let sa = a["score"]["value"].as_i64().unwrap_or(0);
-            let sb = b["score"]["value"].as_i64().unwrap_or(0);
-            sb.cmp(&sa).then_with(|| {
-                let fa = a["flow"].as_str().unwrap_or("");
-                let fb = b["flow"].as_str().unwrap_or("");
-                fa.cmp(fb)
-            })
-        });
-
         // Top por volumen (paquetes)
-        let mut by_packets: Vec<&TcpConversationState> = convs.clone();
-        by_packets.sort_by(|x, y| {
-            let sx = x.c2s.packet_count.saturating_add(x.s2c.packet_count);
-            let sy = y.c2s.packet_count.saturating_add(y.s2c.packet_count);
-            sy.cmp(&sx).then_with(|| {
-                // Desempate lexicográfico estable por 4-tupla del flow
-                let kx = (
-                    x.flow.source_ip.to_string(),
-                    x.flow.source_port,
-                    x.flow.destination_ip.to_string(),
-                    x.flow.destination_port,
-                );
-                let ky = (
-                    y.flow.source_ip.to_string(),
-                    y.flow.source_port,
-                    y.flow.destination_ip.to_string(),
-                    y.flow.destination_port,
-                );
-                kx.cmp(&ky)
-            })
-        });
-
-        let by_packets_json: Vec<Value> = by_packets
-            .into_iter()
-            .map(|st| {
-                let (c2s_p50, c2s_p95) = st.c2s.rtt.percentiles_ms();
-                let (s2c_p50, s2c_p95) = st.s2c.rtt.percentiles_ms();
-
-                let src_ip = st.flow.source_ip;
-                let src_port = st.flow.source_port;
-                let dst_ip = st.flow.destination_ip;
-                let dst_port = st.flow.destination_port;
-                let flow_str = format!("{src_ip}:{src_port} <-> {dst_ip}:{dst_port}/TCP");
-
+        summaries.sort_by(cmp_by_packets);
+        let by_packets_json: Vec<Value> = summaries
+            .iter()
+            .map(|c| {
                 json!({
-                    "flow": flow_str,
-                    "total_packets": st.c2s.packet_count.saturating_add(st.s2c.packet_count),
-                    "c2s": {
-                        "packets": st.c2s.packet_count,
-                        "retransmissions": st.c2s.retransmission_count,
-                        "out_of_order": st.c2s.out_of_order_count,
-                        "zero_window_events": st.c2s.zero_window_events,
-                        "duplicate_ack_events": st.c2s.duplicate_ack_events,
-                        "rtt_ms": { "p50": c2s_p50, "p95": c2s_p95, "samples": st.c2s.rtt.count }
-                    },
-                    "s2c": {
-                        "packets": st.s2c.packet_count,
-                        "retransmissions": st.s2c.retransmission_count,
-                        "out_of_order": st.s2c.out_of_order_count,
-                        "zero_window_events": st.s2c.zero_window_events,
-                        "duplicate_ack_events": st.s2c.duplicate_ack_events,
-                        "rtt_ms": { "p50": s2c_p50, "p95": s2c_p95, "samples": st.s2c.rtt.count }
-                    }
+                    "flow": c.flow,
+                    "total_packets": c.total_packets,
+                    "c2s": c.c2s,
+                    "s2c": c.s2c
                 })
             })
             .collect();
 
         json!({
-            "conversations_total": self.conversations.len(),
+            "conversations_total": self.totals.conversations,
+            "middlebox_suspects": self.totals.middlebox_suspects,
+            "totals": self.totals.to_json(),
+            "flow_table": {
+                "max_flows": table_cfg.max_flows,
+                "idle_timeout_ms": table_cfg.idle_timeout_us / 1_000,
+                "peak_active_flows": stats.peak_flows,
+                "evicted_idle": stats.evicted_idle,
+                "evicted_capacity": stats.evicted_capacity,
+                "seen_seq_cap": self.seen_seq_cap,
+                "truncated_flows": self.totals.truncated_flows,
+                "summaries_pruned": self.archived.pruned()
+            },
+            "validation": {
+                "checksum_mode": self.checksum_mode.as_str(),
+                "ipv4_checksum_bad": self.validation.ipv4_checksum_bad,
+                "tcp_checksum_bad": self.validation.tcp_checksum_bad,
+                "offload_like": self.validation.offload_like,
+                "excluded_from_metrics": self.validation.excluded,
+                "truncated_headers": self.validation.truncated_headers,
+                "truncated_segments": self.validation.truncated_segments,
+                "invalid_data_offset": self.validation.invalid_data_offset,
+                "illegal_flags": {
+                    "syn_fin": self.validation.syn_fin,
+                    "null": self.validation.null_flags,
+                    "xmas": self.validation.xmas
+                },
+                "findings": self.validation.findings()
+            },
             "top_by_severity": by_severity,
             "top_by_packets": by_packets_json,
             // alias por compatibilidad
@@ -528,6 +1071,98 @@ impl Detector for TcpHealthDetector {
     }
 }
 
+fn summarize(st: &TcpConversationState) -> ConversationSummary {
+    let (score, level, mut reasons) = compute_severity(&st.c2s, &st.s2c);
+    let findings = middlebox_findings(&st.c2s, &st.s2c);
+    let middlebox_suspect = !findings.is_empty();
+    reasons.extend(findings);
+    reasons.extend(validation_findings(&st.c2s, &st.s2c));
+    reasons.extend(icmp_findings(&st.c2s, &st.s2c));
+
+    ConversationSummary {
+        flow: flow_label(&st.flow, "TCP"),
+        sort_key: (
+            st.flow.source_ip.to_string(),
+            st.flow.source_port,
+            st.flow.destination_ip.to_string(),
+            st.flow.destination_port,
+        ),
+        total_packets: st.c2s.packet_count.saturating_add(st.s2c.packet_count),
+        score,
+        level,
+        reasons,
+        middlebox_suspect,
+        truncated: st.c2s.tracking_truncations > 0 || st.s2c.tracking_truncations > 0,
+        c2s: stream_json(&st.c2s),
+        s2c: stream_json(&st.s2c),
+    }
+}
+
+/// Si un frame que no se pudo cortar lleva TCP; con la cabecera IP truncada se mira el
+/// byte de protocolo directamente si llegó a capturarse
+fn carries_tcp(packet_data: &[u8]) -> bool {
+    let Ok(lax) = LaxSlicedPacket::from_ethernet(packet_data) else {
+        return false;
+    };
+    match &lax.net {
+        Some(LaxNetSlice::Ipv4(ip)) => return ip.payload().ip_number == IpNumber::TCP,
+        Some(LaxNetSlice::Ipv6(ip)) => return ip.payload().ip_number == IpNumber::TCP,
+        None => {}
+    }
+    let ether = match (&lax.vlan, &lax.link) {
+        (Some(vlan), _) => vlan.payload(),
+        (None, Some(link)) => link.payload(),
+        (None, None) => return false,
+    };
+    match ether.ether_type {
+        EtherType::IPV4 => ether.payload.get(9) == Some(&IpNumber::TCP.0),
+        EtherType::IPV6 => ether.payload.get(6) == Some(&IpNumber::TCP.0),
+        _ => false,
+    }
+}
+
+fn cmp_by_severity(a: &ConversationSummary, b: &ConversationSummary) -> std::cmp::Ordering {
+    b.score.cmp(&a.score).then_with(|| a.flow.cmp(&b.flow))
+}
+
+fn cmp_by_packets(a: &ConversationSummary, b: &ConversationSummary) -> std::cmp::Ordering {
+    // Desempate lexicográfico estable por 4-tupla del flow
+    b.total_packets
+        .cmp(&a.total_packets)
+        .then_with(|| a.sort_key.cmp(&b.sort_key))
+}
+
+/// Vista JSON de un sentido de la conversación (compartida por todas las vistas)
+fn stream_json(st: &TcpStreamState) -> Value {
+    let (p50, p95) = st.rtt.percentiles_ms();
+    json!({
+        "packets": st.packet_count,
+        "retransmissions": st.retransmission_count,
+        "out_of_order": st.out_of_order_count,
+        "zero_window_events": st.zero_window_events,
+        "duplicate_ack_events": st.duplicate_ack_events,
+        "rtt_ms": { "p50": p50, "p95": p95, "samples": st.rtt.count },
+        "middlebox": {
+            "ttl": st.last_ttl,
+            "ttl_changes": st.ttl_changes,
+            "ip_id_shifts": st.ip_id_shifts,
+            "ip_id_sequential": ip_id_is_sequential(st),
+            "syn_mss": st.syn_mss,
+            "suspected_injected_rst": st.suspected_injected_rst,
+            "seq_rewrite_events": st.seq_rewrite_events,
+            "mss_exceeded": st.mss_exceeded
+        },
+        "tracking_truncations": st.tracking_truncations,
+        "icmp_errors": st.icmp_errors,
+        "validation": {
+            "checksum_bad": st.checksum_bad,
+            "checksum_offload_like": st.checksum_offload_like,
+            "checksum_excluded": st.checksum_excluded,
+            "illegal_flag_segments": st.illegal_flag_segments
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -596,6 +1231,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::manual_range_contains)]
     fn rtt_is_measured_on_ack_of_data() {
         // Simula DATA C->S seguido de ACK S->C
         let mut conv = TcpConversationState::default();
@@ -731,6 +1367,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::field_reassign_with_default)]
     fn severity_ooo_exactly_2pct_is_baja_and_above_is_media() {
         // 2.0% exacto => BAJA; >2.0% => MEDIA
         let mut a = TcpStreamState::default();
@@ -743,4 +1380,402 @@ mod tests {
         let (_s2, l2, _r2) = super::compute_severity(&a, &TcpStreamState::default());
         assert_eq!(l2, "MEDIA");
     }
+
+    fn f_syn() -> TcpFlags {
+        TcpFlags {
+            syn: true,
+            fin: false,
+            rst: false,
+            ack: false,
+        }
+    }
+
+    fn f_syn_ack() -> TcpFlags {
+        TcpFlags {
+            syn: true,
+            fin: false,
+            rst: false,
+            ack: true,
+        }
+    }
+
+    fn ip(ttl: u8, ip_id: u16) -> IpMeta {
+        IpMeta { ttl, ip_id }
+    }
+
+    #[test]
+    fn injected_rst_when_ttl_differs_from_stream() {
+        let mut c2s = TcpStreamState::default();
+        let mut s2c = TcpStreamState::default();
+        let f = f_ack();
+        TcpHealthDetector::track_middlebox(&mut s2c, &mut c2s, ip(52, 100), 1, 1, f, None);
+        TcpHealthDetector::track_middlebox(&mut s2c, &mut c2s, ip(52, 101), 1, 1, f, None);
+        // RST con TTL muy distinto => sospechoso
+        TcpHealthDetector::track_middlebox(
+            &mut s2c,
+            &mut c2s,
+            ip(64, 102),
+            1,
+            1,
+            f_ack_rst(),
+            None,
+        );
+        assert_eq!(s2c.suspected_injected_rst, 1);
+        // El RST no altera la línea base del stream
+        assert_eq!(s2c.last_ttl, Some(52));
+        let findings = super::middlebox_findings(&c2s, &s2c);
+        assert!(findings.iter().any(|r| r.contains("RST inyectado")));
+    }
+
+    #[test]
+    fn rst_matching_ttl_and_ip_id_is_not_flagged() {
+        let mut c2s = TcpStreamState::default();
+        let mut s2c = TcpStreamState::default();
+        TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(64, 500), 1, 1, f_ack(), None);
+        TcpHealthDetector::track_middlebox(
+            &mut c2s,
+            &mut s2c,
+            ip(64, 501),
+            1,
+            1,
+            f_ack_rst(),
+            None,
+        );
+        // Linux puede enviar RST con IP-ID 0: tampoco es sospechoso
+        TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(64, 0), 1, 1, f_ack_rst(), None);
+        assert_eq!(c2s.suspected_injected_rst, 0);
+    }
+
+    #[test]
+    fn injected_rst_when_ip_id_out_of_window() {
+        let mut c2s = TcpStreamState::default();
+        let mut s2c = TcpStreamState::default();
+        for id in 10_000..10_003 {
+            TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(64, id), 1, 1, f_ack(), None);
+        }
+        TcpHealthDetector::track_middlebox(
+            &mut c2s,
+            &mut s2c,
+            ip(64, 40_000),
+            1,
+            1,
+            f_ack_rst(),
+            None,
+        );
+        assert_eq!(c2s.suspected_injected_rst, 1);
+    }
+
+    #[test]
+    fn random_ip_id_stack_is_not_evidence_for_injected_rst() {
+        let mut c2s = TcpStreamState::default();
+        let mut s2c = TcpStreamState::default();
+        for id in [4_000, 51_000, 17_000, 33_000] {
+            TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(64, id), 1, 1, f_ack(), None);
+        }
+        assert!(!super::ip_id_is_sequential(&c2s));
+        TcpHealthDetector::track_middlebox(
+            &mut c2s,
+            &mut s2c,
+            ip(64, 60_000),
+            1,
+            1,
+            f_ack_rst(),
+            None,
+        );
+        assert_eq!(c2s.suspected_injected_rst, 0);
+    }
+
+    #[test]
+    fn ttl_changes_and_ip_id_shifts_are_counted() {
+        let mut c2s = TcpStreamState::default();
+        let mut s2c = TcpStreamState::default();
+        let f = f_ack();
+        TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(64, 1), 1, 1, f, None);
+        TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(63, 2), 1, 1, f, None);
+        TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(63, 30_000), 1, 1, f, None);
+        assert_eq!(c2s.ttl_changes, 1);
+        assert_eq!(c2s.ip_id_shifts, 1);
+    }
+
+    #[test]
+    fn mss_mismatch_between_syn_and_syn_ack() {
+        let mut c2s = TcpStreamState::default();
+        let mut s2c = TcpStreamState::default();
+        TcpHealthDetector::track_middlebox(
+            &mut c2s,
+            &mut s2c,
+            ip(64, 1),
+            1000,
+            0,
+            f_syn(),
+            Some(1460),
+        );
+        TcpHealthDetector::track_middlebox(
+            &mut s2c,
+            &mut c2s,
+            ip(60, 1),
+            9000,
+            1001,
+            f_syn_ack(),
+            Some(1380),
+        );
+        // MSS distintos por sí solos son normales
+        assert!(super::middlebox_findings(&c2s, &s2c).is_empty());
+        // El cliente envía más de lo que el servidor anunció aquí: vio otro MSS
+        c2s.mss_exceeded = 2;
+        let findings = super::middlebox_findings(&c2s, &s2c);
+        assert!(findings.iter().any(|r| r.contains("MSS clamping")));
+        assert!(!findings.iter().any(|r| r.contains("reescritura")));
+    }
+
+    #[test]
+    fn seq_rewrite_when_syn_ack_does_not_ack_isn_plus_one() {
+        let mut c2s = TcpStreamState::default();
+        let mut s2c = TcpStreamState::default();
+        TcpHealthDetector::track_middlebox(&mut c2s, &mut s2c, ip(64, 1), 1000, 0, f_syn(), None);
+        TcpHealthDetector::track_middlebox(
+            &mut s2c,
+            &mut c2s,
+            ip(60, 1),
+            9000,
+            777_001,
+            f_syn_ack(),
+            None,
+        );
+        assert_eq!(c2s.seq_rewrite_events, 1);
+        let findings = super::middlebox_findings(&c2s, &s2c);
+        assert!(findings
+            .iter()
+            .any(|r| r.contains("reescritura de secuencia")));
+    }
+
+    fn tcp_frame(src_port: u16, seq: u32, payload: &[u8]) -> Vec<u8> {
+        let builder = etherparse::PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .tcp(src_port, 80, seq, 65535)
+            .ack(1);
+        let mut out = Vec::with_capacity(builder.size(payload.len()));
+        builder.write(&mut out, payload).unwrap();
+        out
+    }
+
+    #[test]
+    fn evicted_conversations_are_reported_not_dropped() {
+        let mut d = TcpHealthDetector::with_config(TcpHealthConfig {
+            flow_table: FlowTableConfig {
+                max_flows: 0,
+                idle_timeout_us: 1_000_000,
+            },
+            max_archived: 0,
+            ..TcpHealthConfig::default()
+        });
+        d.on_packet(&tcp_frame(1000, 1, b"hola"), 0);
+        d.on_packet(&tcp_frame(2000, 1, b"hola"), 100);
+        // 5 s después: ambos flujos inactivos => expulsados al llegar el siguiente paquete
+        d.on_packet(&tcp_frame(3000, 1, b"hola"), 5_000_000);
+        let out = d.finalize();
+        assert_eq!(out["conversations_total"], 3);
+        assert_eq!(out["flow_table"]["evicted_idle"], 2);
+        assert_eq!(out["top_by_packets"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn icmp_errors_are_attached_to_the_quoted_conversation() {
+        let mut d = TcpHealthDetector::new();
+        let syn = tcp_frame(1000, 1, b"hola");
+        d.on_packet(&syn, 0);
+        let mut icmp = Vec::new();
+        etherparse::PacketBuilder::ethernet2([3; 6], [1; 6])
+            .ipv4([10, 0, 0, 254], [10, 0, 0, 1], 64)
+            .icmpv4(etherparse::Icmpv4Type::DestinationUnreachable(
+                etherparse::icmpv4::DestUnreachableHeader::Host,
+            ))
+            .write(&mut icmp, &syn[14..14 + 28])
+            .unwrap();
+        d.on_packet(&icmp, 1_000);
+        let out = d.finalize();
+        let conv = &out["top_by_packets"][0];
+        assert_eq!(conv["c2s"]["icmp_errors"]["host_unreachable"], 1);
+        assert_eq!(conv["c2s"]["packets"], 1);
+        assert!(out["top_by_severity"][0]["reasons"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|r| r == "errores ICMP sobre C->S: host_unreachable (1)"));
+    }
+
+    #[test]
+    fn archive_is_pruned_to_cap_keeping_top_entries() {
+        let mut d = TcpHealthDetector::with_config(TcpHealthConfig {
+            flow_table: FlowTableConfig {
+                max_flows: 1,
+                idle_timeout_us: 0,
+            },
+            max_archived: 2,
+            ..TcpHealthConfig::default()
+        });
+        for (i, port) in [1000u16, 2000, 3000, 4000].iter().enumerate() {
+            d.on_packet(&tcp_frame(*port, 1, b"x"), i as u64);
+        }
+        let out = d.finalize();
+        assert_eq!(out["conversations_total"], 4);
+        assert!(out["flow_table"]["summaries_pruned"].as_u64().unwrap() >= 1);
+        // Lo podado del top sigue contando en el agregado
+        assert_eq!(out["totals"]["packets"], 4);
+        assert_eq!(out["totals"]["by_level"]["BAJA"], 4);
+        assert_eq!(out["flow_table"]["evicted_capacity"], 3);
+    }
+
+    fn corrupt_tcp_checksum(frame: &mut [u8], value: u16) {
+        // Ethernet (14) + IPv4 sin opciones (20) + offset del checksum TCP (16)
+        frame[14 + 20 + 16..14 + 20 + 18].copy_from_slice(&value.to_be_bytes());
+    }
+
+    #[test]
+    fn isolated_bad_checksum_is_excluded_as_corruption() {
+        let mut d = TcpHealthDetector::new();
+        d.on_packet(&tcp_frame(1000, 1, b"hola"), 0);
+        let mut bad = tcp_frame(1000, 5, b"hola");
+        corrupt_tcp_checksum(&mut bad, 0x1234);
+        d.on_packet(&bad, 10);
+        let out = d.finalize();
+        let v = &out["validation"];
+        assert_eq!(v["tcp_checksum_bad"], 1);
+        assert_eq!(v["excluded_from_metrics"], 1);
+        assert_eq!(out["top_by_packets"][0]["c2s"]["packets"], 1);
+        assert!(v["findings"][0].as_str().unwrap().contains("corrupción"));
+    }
+
+    #[test]
+    fn offload_signature_is_kept_in_metrics() {
+        let mut d = TcpHealthDetector::new();
+        d.on_packet(&tcp_frame(1000, 1, b"hola"), 0);
+        let mut zero = tcp_frame(1000, 5, b"hola");
+        corrupt_tcp_checksum(&mut zero, 0);
+        d.on_packet(&zero, 10);
+        let out = d.finalize();
+        assert_eq!(out["validation"]["excluded_from_metrics"], 0);
+        assert_eq!(out["validation"]["offload_like"], 1);
+        assert_eq!(out["top_by_packets"][0]["c2s"]["packets"], 2);
+        assert!(out["validation"]["findings"][0]
+            .as_str()
+            .unwrap()
+            .contains("offload"));
+    }
+
+    #[test]
+    fn strict_mode_excludes_every_bad_checksum() {
+        let mut d = TcpHealthDetector::with_config(TcpHealthConfig {
+            checksum_mode: ChecksumMode::Strict,
+            ..TcpHealthConfig::default()
+        });
+        let mut zero = tcp_frame(1000, 1, b"hola");
+        corrupt_tcp_checksum(&mut zero, 0);
+        d.on_packet(&zero, 0);
+        let out = d.finalize();
+        assert_eq!(out["validation"]["excluded_from_metrics"], 1);
+        assert_eq!(out["top_by_packets"][0]["c2s"]["packets"], 0);
+    }
+
+    #[test]
+    fn illegal_flag_combinations_are_counted() {
+        let mut d = TcpHealthDetector::new();
+        let frame = |syn: bool, fin: bool, psh: bool, urg: bool| {
+            let mut b = etherparse::PacketBuilder::ethernet2([1; 6], [2; 6])
+                .ipv4([10, 0, 0, 9], [10, 0, 0, 2], 64)
+                .tcp(4444, 22, 1, 1024);
+            if syn {
+                b = b.syn();
+            }
+            if fin {
+                b = b.fin();
+            }
+            if psh {
+                b = b.psh();
+            }
+            if urg {
+                b = b.urg(0);
+            }
+            let mut out = Vec::new();
+            b.write(&mut out, &[]).unwrap();
+            out
+        };
+        d.on_packet(&frame(true, true, false, false), 0); // SYN+FIN
+        d.on_packet(&frame(false, false, false, false), 1); // NULL
+        d.on_packet(&frame(false, true, true, true), 2); // Xmas
+        let out = d.finalize();
+        let f = &out["validation"]["illegal_flags"];
+        assert_eq!(
+            (
+                f["syn_fin"].as_u64(),
+                f["null"].as_u64(),
+                f["xmas"].as_u64()
+            ),
+            (Some(1), Some(1), Some(1))
+        );
+        let reasons = out["top_by_severity"][0]["reasons"].as_array().unwrap();
+        assert!(reasons
+            .iter()
+            .any(|r| r.as_str().unwrap().contains("flags ilegales")));
+    }
+
+    #[test]
+    fn truncated_and_bad_offset_headers_are_classified() {
+        let mut d = TcpHealthDetector::new();
+        let full = tcp_frame(1000, 1, b"hola");
+        // Cabecera TCP de 10 bytes (total_len IPv4 ajustado para que el paquete IP sea coherente)
+        let mut short = full[..14 + 20 + 10].to_vec();
+        short[16..18].copy_from_slice(&30u16.to_be_bytes());
+        d.on_packet(&short, 0);
+        // data offset = 3 (< 5)
+        let mut bad_off = full.clone();
+        bad_off[14 + 20 + 12] = 0x30;
+        d.on_packet(&bad_off, 1);
+        let out = d.finalize();
+        assert_eq!(out["validation"]["truncated_headers"], 1);
+        assert_eq!(out["validation"]["invalid_data_offset"], 1);
+        assert_eq!(out["conversations_total"], 0);
+    }
+
+    #[test]
+    fn single_corrupt_packet_is_not_taken_for_offload() {
+        // Único paquete del sentido con checksum incorrecto: corrupción, no offload
+        let mut d = TcpHealthDetector::new();
+        let mut bad = tcp_frame(1000, 1, b"hola");
+        corrupt_tcp_checksum(&mut bad, 0x1234);
+        d.on_packet(&bad, 0);
+        let out = d.finalize();
+        assert_eq!(out["validation"]["offload_like"], 0);
+        assert_eq!(out["validation"]["excluded_from_metrics"], 1);
+
+        // Un sentido que falla siempre sí se atribuye a offload a partir del mínimo
+        let mut d = TcpHealthDetector::new();
+        for i in 0..5u32 {
+            let mut bad = tcp_frame(1000, 1 + i * 4, b"hola");
+            corrupt_tcp_checksum(&mut bad, 0x1234);
+            d.on_packet(&bad, i as u64);
+        }
+        let out = d.finalize();
+        assert_eq!(out["validation"]["excluded_from_metrics"], 3);
+        assert_eq!(out["validation"]["offload_like"], 2);
+    }
+
+    #[test]
+    fn slice_errors_of_non_tcp_packets_are_ignored() {
+        let mut d = TcpHealthDetector::new();
+        let mut udp = Vec::new();
+        etherparse::PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .udp(5000, 53)
+            .write(&mut udp, b"consulta")
+            .unwrap();
+        // Datagrama UDP truncado y cabecera IPv4 truncada tras el byte de protocolo
+        d.on_packet(&udp[..udp.len() - 3], 0);
+        d.on_packet(&udp[..14 + 12], 1);
+        // Cabecera IPv4 truncada de un segmento TCP: sí cuenta
+        d.on_packet(&tcp_frame(1000, 1, b"hola")[..14 + 12], 2);
+        let out = d.finalize();
+        assert_eq!(out["validation"]["truncated_headers"], 1);
+        assert_eq!(out["validation"]["truncated_segments"], 0);
+    }
 }
@@ -0,0 +1,42 @@
+//! Tramas Ethernet/IPv4 sintéticas para los tests de los detectores.
+
+use etherparse::PacketBuilder;
+
+/// Segmento TCP con las flags indicadas por letra: S(YN), A(CK), F(IN), R(ST), P(SH), U(RG)
+pub(crate) fn tcp(
+    src: [u8; 4],
+    dst: [u8; 4],
+    ttl: u8,
+    sport: u16,
+    dport: u16,
+    flags: &str,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut b = PacketBuilder::ethernet2([1; 6], [2; 6])
+        .ipv4(src, dst, ttl)
+        .tcp(sport, dport, 1, 1024);
+    for f in flags.chars() {
+        b = match f {
+            'S' => b.syn(),
+            'A' => b.ack(2),
+            'F' => b.fin(),
+            'R' => b.rst(),
+            'P' => b.psh(),
+            'U' => b.urg(0),
+            _ => b,
+        };
+    }
+    let mut out = Vec::new();
+    b.write(&mut out, payload).unwrap();
+    out
+}
+
+pub(crate) fn udp(src: [u8; 4], dst: [u8; 4], sport: u16, dport: u16, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    PacketBuilder::ethernet2([1; 6], [2; 6])
+        .ipv4(src, dst, 64)
+        .udp(sport, dport)
+        .write(&mut out, payload)
+        .unwrap();
+    out
+}
@@ -0,0 +1,1188 @@
+use crate::detectors::latency::LatencyStats;
+use crate::engine::Detector;
+use crate::network::flow::Flow;
+use crate::network::flow_table::{FlowTable, FlowTableConfig};
+use crate::network::format::{endpoint_label, flow_label};
+use crate::network::reassembly::StreamReassembler;
+use crate::network::tls::{
+    self, CipherWeakness, ClientHello, ServerHello, TlsEvent, TlsReader, VERSION_TLS13,
+};
+use crate::network::tls_fingerprint::{
+    self, Fingerprint, FingerprintList, Ja4Transport, ListVerdict,
+};
+use crate::network::x509::{self, Certificate};
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+
+// Conversaciones con detalle en el reporte (el resto solo cuenta en los agregados)
+const MAX_CONVERSATIONS_REPORTED: usize = 1000;
+const MAX_EXAMPLES: usize = 5;
+const MAX_CERTIFICATE_SERVERS: usize = 1000;
+// Huellas distintas en memoria y clientes/servidores contados por huella
+const MAX_FINGERPRINTS_TRACKED: usize = 10_000;
+const MAX_FINGERPRINT_PEERS: usize = 1000;
+// Handshakes por encima de estos umbrales se señalan como lentos
+const SLOW_HANDSHAKE_MS: f64 = 1000.0;
+const VERY_SLOW_HANDSHAKE_MS: f64 = 3000.0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AlertSeen {
+    from_client: bool,
+    level: u8,
+    description: u8,
+}
+
+/// Estado del handshake de una conexión, alimentado por ambos sentidos
+#[derive(Debug, Default)]
+struct TlsSession {
+    client: TlsReader,
+    server: TlsReader,
+    client_hello: Option<ClientHello>,
+    server_hello: Option<ServerHello>,
+    // Primer byte del cliente: el ClientHello puede ocupar varios segmentos
+    client_first_us: Option<u64>,
+    client_hello_us: Option<u64>,
+    done_us: Option<u64>,
+    server_ccs: bool,
+    server_certificate: bool,
+    certificates: Vec<Vec<u8>>,
+    certificate_us: Option<u64>,
+    alerts: Vec<AlertSeen>,
+    encrypted_alerts: u64,
+    parse_errors: u64,
+}
+
+impl TlsSession {
+    fn is_tls13(&self) -> bool {
+        self.server_hello
+            .as_ref()
+            .is_some_and(|sh| sh.version() == VERSION_TLS13)
+    }
+
+    /// El handshake terminó o ya no se puede seguir: no hace falta más tráfico
+    fn finished(&self) -> bool {
+        self.done_us.is_some() || (self.client.is_invalid() && self.server.is_invalid())
+    }
+
+    fn on_bytes(&mut self, from_client: bool, data: &[u8], ts_us: u64) {
+        let mut events = Vec::new();
+        if from_client {
+            self.client_first_us.get_or_insert(ts_us);
+        }
+        let reader = if from_client {
+            &mut self.client
+        } else {
+            &mut self.server
+        };
+        let was_invalid = reader.is_invalid();
+        reader.feed(data, &mut events);
+        if reader.is_invalid() && !was_invalid {
+            self.parse_errors += 1;
+        }
+        for ev in events {
+            self.on_event(from_client, ev, ts_us);
+        }
+    }
+
+    fn on_event(&mut self, from_client: bool, ev: TlsEvent, ts_us: u64) {
+        match ev {
+            TlsEvent::Handshake(tls::HS_CLIENT_HELLO, body)
+                if from_client && self.client_hello.is_none() =>
+            {
+                match tls::parse_client_hello(&body) {
+                    Some(ch) => {
+                        self.client_hello = Some(ch);
+                        self.client_hello_us = self.client_first_us.or(Some(ts_us));
+                    }
+                    None => self.parse_errors += 1,
+                }
+            }
+            TlsEvent::Handshake(tls::HS_SERVER_HELLO, body)
+                if !from_client && self.server_hello.is_none() =>
+            {
+                match tls::parse_server_hello(&body) {
+                    Some(sh) => self.server_hello = Some(sh),
+                    None => self.parse_errors += 1,
+                }
+            }
+            TlsEvent::Handshake(tls::HS_CERTIFICATE, body) if !from_client => {
+                self.server_certificate = true;
+                match tls::parse_certificate_chain(&body) {
+                    Some(chain) => {
+                        self.certificates = chain;
+                        self.certificate_us = Some(ts_us);
+                    }
+                    None => self.parse_errors += 1,
+                }
+            }
+            TlsEvent::Handshake(..) => {}
+            TlsEvent::ChangeCipherSpec => {
+                if !from_client {
+                    self.server_ccs = true;
+                }
+            }
+            TlsEvent::Alert(level, description) => self.alerts.push(AlertSeen {
+                from_client,
+                level,
+                description,
+            }),
+            TlsEvent::EncryptedAlert => self.encrypted_alerts += 1,
+            TlsEvent::ApplicationData => {
+                if self.server_hello.is_none() || self.done_us.is_some() {
+                    return;
+                }
+                // TLS 1.3: el primer registro cifrado del cliente es su Finished.
+                // TLS ≤ 1.2: el Finished del servidor llega tras su ChangeCipherSpec.
+                let done = if self.is_tls13() {
+                    from_client
+                } else {
+                    !from_client && self.server_ccs
+                };
+                if done {
+                    self.done_us = Some(ts_us);
+                }
+            }
+        }
+    }
+
+    /// Tipo de reanudación, si la hubo
+    fn resumption(&self) -> Option<&'static str> {
+        let sh = self.server_hello.as_ref()?;
+        if sh.psk_accepted {
+            return Some("psk");
+        }
+        if self.is_tls13() {
+            return None;
+        }
+        let ch = self.client_hello.as_ref()?;
+        // Handshake abreviado: el servidor pasa a cifrado sin enviar certificado
+        if !self.server_ccs || self.server_certificate {
+            return None;
+        }
+        if !sh.session_id.is_empty() && sh.session_id == ch.session_id {
+            Some("session_id")
+        } else {
+            Some("ticket")
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TlsConnState {
+    flow: Flow,
+    client_forward: Option<bool>,
+    not_tls: bool,
+    fwd: StreamReassembler,
+    rev: StreamReassembler,
+    session: TlsSession,
+}
+
+#[derive(Debug)]
+struct FindingStats {
+    score: u32,
+    level: &'static str,
+    reason: String,
+    count: u64,
+    examples: BTreeSet<String>,
+}
+
+/// Severidad de un hallazgo y la conversación que lo produjo
+struct Severity<'a> {
+    score: u32,
+    level: &'static str,
+    reason: String,
+    flow: &'a str,
+}
+
+#[derive(Debug, Default)]
+struct Verdict {
+    score: u32,
+    high: bool,
+    reasons: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct FingerprintStats {
+    raw: String,
+    conversations: u64,
+    clients: BTreeSet<IpAddr>,
+    servers: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+struct CertificateServer {
+    server: String,
+    handshakes: u64,
+    chain: Vec<Value>,
+}
+
+fn certificate_json(c: &Certificate) -> Value {
+    json!({
+        "subject": c.subject,
+        "issuer": c.issuer,
+        "serial": c.serial,
+        "sans": c.sans,
+        "not_before": c.not_before.iso,
+        "not_after": c.not_after.iso,
+        "key_type": c.key_type,
+        "key_bits": c.key_bits,
+        "signature_algorithm": c.signature_algorithm,
+        "self_signed": c.self_signed
+    })
+}
+
+/// Detector de handshakes TLS sobre TCP reensamblado
+pub struct TlsDetector {
+    conns: FlowTable<TlsConnState>,
+    conversations: Vec<(u32, Value)>,
+    handshakes_total: u64,
+    completed: u64,
+    resumed: u64,
+    fatal_alerts: u64,
+    parse_errors: u64,
+    stream_gaps: u64,
+    versions: BTreeMap<String, u64>,
+    ciphers: BTreeMap<String, u64>,
+    alpn: BTreeMap<String, u64>,
+    handshake_latency: LatencyStats,
+    // (código, servidor, detalle) -> ocurrencias
+    findings: BTreeMap<(&'static str, String, String), FindingStats>,
+    // (servidor, emisor/serie del certificado hoja) -> cadena
+    certificates: BTreeMap<(String, String), CertificateServer>,
+    certificates_truncated: u64,
+    certificate_errors: u64,
+    fingerprint_list: FingerprintList,
+    // (tipo, huella) -> uso
+    fingerprints: BTreeMap<(&'static str, String), FingerprintStats>,
+}
+
+/// Configuración del detector TLS
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    pub flow_table: FlowTableConfig,
+    /// Lista local de huellas JA3/JA3S/JA4 permitidas o bloqueadas
+    pub fingerprints: FingerprintList,
+}
+
+impl Default for TlsDetector {
+    fn default() -> Self {
+        Self::with_config(TlsConfig::default())
+    }
+}
+
+fn alert_level_name(level: u8) -> &'static str {
+    match level {
+        1 => "warning",
+        2 => "fatal",
+        _ => "unknown",
+    }
+}
+
+impl TlsDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_flow_table(flow_table: FlowTableConfig) -> Self {
+        Self::with_config(TlsConfig {
+            flow_table,
+            ..TlsConfig::default()
+        })
+    }
+
+    pub fn with_config(config: TlsConfig) -> Self {
+        TlsDetector {
+            conns: FlowTable::new(config.flow_table),
+            fingerprint_list: config.fingerprints,
+            fingerprints: BTreeMap::new(),
+            conversations: Vec::new(),
+            handshakes_total: 0,
+            completed: 0,
+            resumed: 0,
+            fatal_alerts: 0,
+            parse_errors: 0,
+            stream_gaps: 0,
+            versions: BTreeMap::new(),
+            ciphers: BTreeMap::new(),
+            alpn: BTreeMap::new(),
+            handshake_latency: LatencyStats::default(),
+            findings: BTreeMap::new(),
+            certificates: BTreeMap::new(),
+            certificates_truncated: 0,
+            certificate_errors: 0,
+        }
+    }
+
+    fn on_tcp(&mut self, flow: Flow, tcp: &etherparse::TcpSlice, ts_us: u64) {
+        let (key, st, forward) = self.conns.get_or_insert(flow, ts_us);
+        st.flow = key;
+        if st.not_tls || st.session.finished() {
+            return;
+        }
+
+        let seq = tcp.sequence_number();
+        let payload = tcp.payload();
+        if tcp.syn() {
+            let r = if forward { &mut st.fwd } else { &mut st.rev };
+            r.on_syn(seq);
+            if !tcp.ack() && st.client_forward.is_none() {
+                st.client_forward = Some(forward);
+            }
+        }
+        if payload.is_empty() {
+            return;
+        }
+
+        // Sin SYN visto, el ClientHello decide quién es el cliente
+        if st.client_forward.is_none() {
+            if tls::looks_like_client_hello(payload) {
+                st.client_forward = Some(forward);
+            } else {
+                st.not_tls = true;
+                return;
+            }
+        }
+        let from_client = st.client_forward == Some(forward);
+
+        let TlsConnState {
+            fwd, rev, session, ..
+        } = st;
+        let reasm = if forward { fwd } else { rev };
+        let mut first_bytes = reasm.delivered_bytes() == 0;
+        let mut not_tls = false;
+        reasm.push(seq, payload, ts_us, &mut |data, ts, gap| {
+            // El cliente debe empezar con un ClientHello
+            if first_bytes && from_client && !tls::looks_like_client_hello(data) {
+                not_tls = true;
+            }
+            first_bytes = false;
+            // Tras un hueco no hay forma fiable de recuperar el límite de registro
+            if not_tls || gap {
+                return;
+            }
+            session.on_bytes(from_client, data, ts);
+        });
+        if not_tls {
+            st.not_tls = true;
+        }
+    }
+
+    fn finish_conn(&mut self, st: TlsConnState) {
+        if st.not_tls {
+            return;
+        }
+        let s = &st.session;
+        let Some(ch) = s.client_hello.as_ref() else {
+            return;
+        };
+        self.handshakes_total += 1;
+        self.parse_errors += s.parse_errors;
+        self.stream_gaps += (st.fwd.gaps() + st.rev.gaps()) as u64;
+
+        let client_forward = st.client_forward.unwrap_or(true);
+        let server = if client_forward {
+            endpoint_label(st.flow.destination_ip, st.flow.destination_port)
+        } else {
+            endpoint_label(st.flow.source_ip, st.flow.source_port)
+        };
+        let client_ip = if client_forward {
+            st.flow.source_ip
+        } else {
+            st.flow.destination_ip
+        };
+        let subject = ch.sni.clone().unwrap_or_else(|| server.clone());
+        let label = flow_label(&st.flow, "TCP");
+
+        let version = s.server_hello.as_ref().map(|sh| sh.version());
+        let cipher = s.server_hello.as_ref().map(|sh| sh.cipher_suite);
+        let alpn = s.server_hello.as_ref().and_then(|sh| sh.alpn.clone());
+        let handshake_ms = match (s.client_hello_us, s.done_us) {
+            (Some(start), Some(end)) => Some(end.saturating_sub(start) as f64 / 1000.0),
+            _ => None,
+        };
+        let resumption = s.resumption();
+
+        if let Some(v) = version {
+            *self.versions.entry(tls::version_name(v)).or_default() += 1;
+        }
+        if let Some(c) = cipher {
+            *self.ciphers.entry(tls::cipher_name(c)).or_default() += 1;
+        }
+        if let Some(p) = alpn.as_ref() {
+            *self.alpn.entry(p.clone()).or_default() += 1;
+        }
+        if s.done_us.is_some() {
+            self.completed += 1;
+        }
+        if let (Some(start), Some(end)) = (s.client_hello_us, s.done_us) {
+            self.handshake_latency.add_sample(end.saturating_sub(start));
+        }
+        if resumption.is_some() {
+            self.resumed += 1;
+        }
+
+        let mut v = Verdict::default();
+        if let Some(ver @ 0x0300..=0x0302) = version {
+            let name = tls::version_name(ver);
+            let (score, level) = if ver == 0x0300 {
+                (100, "ALTA")
+            } else {
+                (50, "MEDIA")
+            };
+            let sev = Severity {
+                score,
+                level,
+                reason: format!("versión obsoleta {name} negociada"),
+                flow: &label,
+            };
+            self.flag(&mut v, "DEPRECATED_VERSION", &subject, &name, sev);
+        }
+        if let Some(c) = cipher {
+            let name = tls::cipher_name(c);
+            let weak = match tls::cipher_weakness(c) {
+                Some(CipherWeakness::Broken) => Some((100, "ALTA", "inseguro")),
+                Some(CipherWeakness::Weak) => Some((60, "MEDIA", "débil")),
+                None => None,
+            };
+            if let Some((score, level, kind)) = weak {
+                let sev = Severity {
+                    score,
+                    level,
+                    reason: format!("cipher suite {kind} {name}"),
+                    flow: &label,
+                };
+                self.flag(&mut v, "WEAK_CIPHER", &subject, &name, sev);
+            }
+        }
+
+        let fatal: Vec<&AlertSeen> = s.alerts.iter().filter(|a| a.level == 2).collect();
+        if let Some(a) = fatal.first() {
+            self.fatal_alerts += fatal.len() as u64;
+            let name = tls::alert_name(a.description);
+            let side = if a.from_client { "cliente" } else { "servidor" };
+            let sev = Severity {
+                score: 60,
+                level: "MEDIA",
+                reason: format!("alerta fatal {name} del {side}"),
+                flow: &label,
+            };
+            self.flag(&mut v, "FATAL_ALERT", &subject, &name, sev);
+        } else if s.server_hello.is_none() {
+            v.score += 40;
+            v.reasons.push("handshake sin ServerHello".to_string());
+        } else if s.done_us.is_none() {
+            v.score += 20;
+            v.reasons.push("handshake incompleto".to_string());
+        }
+
+        let mut chain = Vec::new();
+        for der in &s.certificates {
+            match x509::parse_certificate(der) {
+                Some(c) => chain.push(c),
+                None => self.certificate_errors += 1,
+            }
+        }
+        if !chain.is_empty() {
+            let seen_us = s.certificate_us.unwrap_or(0);
+            self.check_chain(&mut v, &subject, ch.sni.as_deref(), &chain, seen_us, &label);
+            self.record_chain(&subject, &server, &chain);
+        }
+
+        let ja3 = tls_fingerprint::ja3(ch);
+        let ja4 = tls_fingerprint::ja4(ch, Ja4Transport::Tcp);
+        let ja3s = s.server_hello.as_ref().map(tls_fingerprint::ja3s);
+        let mut prints = vec![("JA3", &ja3), ("JA4", &ja4)];
+        if let Some(fp) = ja3s.as_ref() {
+            prints.push(("JA3S", fp));
+        }
+        for (kind, fp) in prints {
+            self.record_fingerprint(kind, fp, client_ip, &subject);
+            let Some(entry) = self.fingerprint_list.lookup(&fp.hash) else {
+                continue;
+            };
+            if entry.verdict == ListVerdict::Deny {
+                let mut reason = format!("huella {kind} en la lista de bloqueo");
+                if !entry.comment.is_empty() {
+                    reason = format!("{reason}: {}", entry.comment);
+                }
+                let sev = Severity {
+                    score: 100,
+                    level: "ALTA",
+                    reason,
+                    flow: &label,
+                };
+                let what = format!("{kind} {}", fp.hash);
+                self.flag(&mut v, "FINGERPRINT_DENYLISTED", &subject, &what, sev);
+            }
+        }
+
+        if let Some(ms) = handshake_ms {
+            if ms > VERY_SLOW_HANDSHAKE_MS {
+                v.score += 40;
+            } else if ms > SLOW_HANDSHAKE_MS {
+                v.score += 20;
+            }
+            if ms > SLOW_HANDSHAKE_MS {
+                v.reasons.push(format!("handshake lento {ms:.1} ms"));
+            }
+        }
+
+        let Verdict {
+            score,
+            high,
+            reasons,
+        } = v;
+        let level = if high || score >= 100 {
+            "ALTA"
+        } else if score >= 40 {
+            "MEDIA"
+        } else {
+            "BAJA"
+        };
+
+        let alerts: Vec<Value> = s
+            .alerts
+            .iter()
+            .map(|a| {
+                json!({
+                    "from": if a.from_client { "client" } else { "server" },
+                    "level": alert_level_name(a.level),
+                    "description": tls::alert_name(a.description)
+                })
+            })
+            .collect();
+        let offered: Vec<&String> = ch.alpn.iter().collect();
+        self.conversations.push((
+            score,
+            json!({
+                "flow": label,
+                "server": server,
+                "sni": ch.sni,
+                "score": { "value": score, "level": level },
+                "reasons": reasons,
+                "offered_version": tls::version_name(ch.max_version()),
+                "version": version.map(tls::version_name),
+                "cipher": cipher.map(tls::cipher_name),
+                "alpn_offered": offered,
+                "alpn": alpn,
+                "handshake_ms": handshake_ms,
+                "complete": s.done_us.is_some(),
+                "resumed": resumption.is_some(),
+                "resumption": resumption,
+                "certificates": s.certificates.len(),
+                "certificate_subject": chain.first().map(|c| c.subject.clone()),
+                "ja3": ja3.hash,
+                "ja3s": ja3s.map(|f| f.hash),
+                "ja4": ja4.hash,
+                "alerts": alerts,
+                "encrypted_alerts": s.encrypted_alerts
+            }),
+        ));
+    }
+
+    fn finish_conns(&mut self, mut conns: Vec<(Flow, TlsConnState)>) {
+        // Orden determinista de las conversaciones finalizadas en bloque
+        conns.sort_by_cached_key(|(f, _)| flow_label(f, "TCP"));
+        for (_, st) in conns {
+            self.finish_conn(st);
+        }
+    }
+
+    /// Suma el hallazgo a la conversación y al agregado por servidor
+    fn flag(
+        &mut self,
+        v: &mut Verdict,
+        code: &'static str,
+        subject: &str,
+        what: &str,
+        sev: Severity,
+    ) {
+        v.score += sev.score;
+        v.high |= sev.level == "ALTA";
+        v.reasons.push(sev.reason.clone());
+        self.add_finding(code, subject, what, sev);
+    }
+
+    /// Validez respecto al momento de la captura, nombre, autofirma y claves cortas
+    fn check_chain(
+        &mut self,
+        v: &mut Verdict,
+        subject: &str,
+        sni: Option<&str>,
+        chain: &[Certificate],
+        seen_us: u64,
+        label: &str,
+    ) {
+        let now = (seen_us / 1_000_000) as i64;
+        for (i, cert) in chain.iter().enumerate() {
+            let who = if i == 0 {
+                "certificado".to_string()
+            } else {
+                format!("certificado intermedio #{i}")
+            };
+            if now > cert.not_after.unix {
+                let sev = Severity {
+                    score: 100,
+                    level: "ALTA",
+                    reason: format!("{who} caducado el {}", cert.not_after.iso),
+                    flow: label,
+                };
+                self.flag(v, "CERT_EXPIRED", subject, &cert.subject, sev);
+            } else if now < cert.not_before.unix {
+                let sev = Severity {
+                    score: 100,
+                    level: "ALTA",
+                    reason: format!("{who} válido a partir del {}", cert.not_before.iso),
+                    flow: label,
+                };
+                self.flag(v, "CERT_NOT_YET_VALID", subject, &cert.subject, sev);
+            }
+            if cert.has_short_key() {
+                let bits = cert.key_bits.unwrap_or(0);
+                let (score, level) = if bits < 1024 {
+                    (100, "ALTA")
+                } else {
+                    (60, "MEDIA")
+                };
+                let key = format!("{} {bits} bits", cert.key_type);
+                let sev = Severity {
+                    score,
+                    level,
+                    reason: format!("{who} con clave corta {key}"),
+                    flow: label,
+                };
+                self.flag(v, "CERT_SHORT_KEY", subject, &key, sev);
+            }
+        }
+
+        let leaf = &chain[0];
+        if let Some(name) = sni {
+            if !leaf.matches_name(name) {
+                let sev = Severity {
+                    score: 60,
+                    level: "MEDIA",
+                    reason: format!("el certificado no cubre el SNI {name}"),
+                    flow: label,
+                };
+                self.flag(v, "CERT_NAME_MISMATCH", subject, &leaf.subject, sev);
+            }
+        }
+        if leaf.self_signed {
+            let sev = Severity {
+                score: 50,
+                level: "MEDIA",
+                reason: "certificado autofirmado".to_string(),
+                flow: label,
+            };
+            self.flag(v, "CERT_SELF_SIGNED", subject, &leaf.subject, sev);
+        }
+    }
+
+    /// Cadena por servidor, una entrada por certificado hoja distinto
+    fn record_chain(&mut self, subject: &str, server: &str, chain: &[Certificate]) {
+        let leaf = &chain[0];
+        let key = (
+            subject.to_string(),
+            format!("{}/{}", leaf.issuer, leaf.serial),
+        );
+        if let Some(entry) = self.certificates.get_mut(&key) {
+            entry.handshakes += 1;
+            return;
+        }
+        if self.certificates.len() >= MAX_CERTIFICATE_SERVERS {
+            self.certificates_truncated += 1;
+            return;
+        }
+        self.certificates.insert(
+            key,
+            CertificateServer {
+                server: server.to_string(),
+                handshakes: 1,
+                chain: chain.iter().map(certificate_json).collect(),
+            },
+        );
+    }
+
+    fn add_finding(&mut self, code: &'static str, subject: &str, what: &str, sev: Severity) {
+        let f = self
+            .findings
+            .entry((code, subject.to_string(), what.to_string()))
+            .or_insert_with(|| FindingStats {
+                score: sev.score,
+                level: sev.level,
+                reason: sev.reason.clone(),
+                count: 0,
+                examples: BTreeSet::new(),
+            });
+        f.count += 1;
+        if f.examples.len() < MAX_EXAMPLES {
+            f.examples.insert(sev.flow.to_string());
+        }
+    }
+
+    fn record_fingerprint(
+        &mut self,
+        kind: &'static str,
+        fp: &Fingerprint,
+        client: IpAddr,
+        server: &str,
+    ) {
+        let key = (kind, fp.hash.clone());
+        if !self.fingerprints.contains_key(&key)
+            && self.fingerprints.len() >= MAX_FINGERPRINTS_TRACKED
+        {
+            return;
+        }
+        let f = self
+            .fingerprints
+            .entry(key)
+            .or_insert_with(|| FingerprintStats {
+                raw: fp.raw.clone(),
+                ..FingerprintStats::default()
+            });
+        f.conversations += 1;
+        if f.clients.len() < MAX_FINGERPRINT_PEERS {
+            f.clients.insert(client);
+        }
+        if f.servers.len() < MAX_FINGERPRINT_PEERS {
+            f.servers.insert(server.to_string());
+        }
+    }
+
+    /// Huellas de un tipo, de más a menos conversaciones
+    fn fingerprints_json(&self, kind: &str) -> Vec<Value> {
+        let mut out: Vec<(&String, &FingerprintStats)> = self
+            .fingerprints
+            .iter()
+            .filter(|((k, _), _)| *k == kind)
+            .map(|((_, hash), f)| (hash, f))
+            .collect();
+        out.sort_by_key(|(_, f)| std::cmp::Reverse(f.conversations));
+        out.iter()
+            .map(|(hash, f)| {
+                json!({
+                    "fingerprint": hash,
+                    "raw": f.raw,
+                    "conversations": f.conversations,
+                    "clients": f.clients.len(),
+                    "servers": f.servers.iter().take(MAX_EXAMPLES).collect::<Vec<_>>(),
+                    "list": self.fingerprint_list.lookup(hash).map(|e| e.verdict.as_str())
+                })
+            })
+            .collect()
+    }
+
+    fn certificates_json(&self) -> Vec<Value> {
+        self.certificates
+            .iter()
+            .map(|((subject, _), c)| {
+                json!({
+                    "subject": subject,
+                    "server": c.server,
+                    "handshakes": c.handshakes,
+                    "chain": c.chain
+                })
+            })
+            .collect()
+    }
+
+    fn findings_json(&self) -> Vec<Value> {
+        let mut out: Vec<(u32, Value)> = self
+            .findings
+            .iter()
+            .map(|((code, subject, what), f)| {
+                (
+                    f.score,
+                    json!({
+                        "code": code,
+                        "subject": subject,
+                        "score": { "value": f.score, "level": f.level },
+                        "reason": f.reason,
+                        "count": f.count,
+                        "examples": f.examples,
+                        "detail": { "value": what }
+                    }),
+                )
+            })
+            .collect();
+        out.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        out.into_iter().map(|(_, v)| v).collect()
+    }
+}
+
+impl Detector for TlsDetector {
+    fn name(&self) -> &'static str {
+        "tls"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        let evicted = self.conns.evict(ts_micros);
+        if !evicted.is_empty() {
+            self.finish_conns(evicted);
+        }
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        if let Some(TransportSlice::Tcp(tcp)) = &sliced.transport {
+            let flow = Flow {
+                source_ip: src,
+                source_port: tcp.source_port(),
+                destination_ip: dst,
+                destination_port: tcp.destination_port(),
+            };
+            self.on_tcp(flow, tcp, ts_micros);
+        }
+    }
+
+    fn finalize(&mut self) -> Value {
+        let live = self.conns.drain();
+        self.finish_conns(live);
+
+        let mut conversations = std::mem::take(&mut self.conversations);
+        // Orden estable: score desc, luego orden de cierre
+        conversations.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        let truncated = conversations
+            .len()
+            .saturating_sub(MAX_CONVERSATIONS_REPORTED);
+        conversations.truncate(MAX_CONVERSATIONS_REPORTED);
+
+        json!({
+            "handshakes_total": self.handshakes_total,
+            "handshakes_completed": self.completed,
+            "resumed": self.resumed,
+            "fatal_alerts": self.fatal_alerts,
+            "parse_errors": self.parse_errors,
+            "stream_gaps": self.stream_gaps,
+            "versions": self.versions,
+            "ciphers": self.ciphers,
+            "alpn": self.alpn,
+            "handshake_ms": self.handshake_latency.to_json(),
+            "findings": self.findings_json(),
+            "certificates": self.certificates_json(),
+            "certificates_truncated": self.certificates_truncated,
+            "certificate_errors": self.certificate_errors,
+            "fingerprints": {
+                "ja3": self.fingerprints_json("JA3"),
+                "ja3s": self.fingerprints_json("JA3S"),
+                "ja4": self.fingerprints_json("JA4"),
+                "list_entries": self.fingerprint_list.len()
+            },
+            "top_by_severity": conversations.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+            "conversations_truncated": truncated
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tls::tests::{
+        certificate_record, client_hello_record, record, server_hello_record,
+    };
+    use crate::network::tls::{
+        CONTENT_ALERT, CONTENT_APPLICATION_DATA, CONTENT_CHANGE_CIPHER_SPEC,
+    };
+    use crate::network::x509::tests::certificate;
+    use etherparse::PacketBuilder;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    struct Conn {
+        port: u16,
+        c_seq: u32,
+        s_seq: u32,
+    }
+
+    fn seg(port: u16, from_client: bool, seq: u32, ack: u32, syn: bool, payload: &[u8]) -> Vec<u8> {
+        let (s, d, sp, dp) = if from_client {
+            (CLIENT, SERVER, port, 443)
+        } else {
+            (SERVER, CLIENT, 443, port)
+        };
+        let mut b = PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(s, d, 64)
+            .tcp(sp, dp, seq, 65535);
+        if syn {
+            b = b.syn();
+        }
+        let b = b.ack(ack);
+        let mut out = Vec::new();
+        b.write(&mut out, payload).unwrap();
+        out
+    }
+
+    impl Conn {
+        fn open(d: &mut TlsDetector, port: u16) -> Conn {
+            d.on_packet(&seg(port, true, 100, 0, true, b""), 0);
+            d.on_packet(&seg(port, false, 500, 101, true, b""), 10);
+            Conn {
+                port,
+                c_seq: 101,
+                s_seq: 501,
+            }
+        }
+        fn client(&mut self, d: &mut TlsDetector, data: &[u8], ts: u64) {
+            d.on_packet(
+                &seg(self.port, true, self.c_seq, self.s_seq, false, data),
+                ts,
+            );
+            self.c_seq += data.len() as u32;
+        }
+        fn server(&mut self, d: &mut TlsDetector, data: &[u8], ts: u64) {
+            d.on_packet(
+                &seg(self.port, false, self.s_seq, self.c_seq, false, data),
+                ts,
+            );
+            self.s_seq += data.len() as u32;
+        }
+    }
+
+    fn concat(parts: &[Vec<u8>]) -> Vec<u8> {
+        parts.concat()
+    }
+
+    #[test]
+    fn tls12_full_handshake_with_weak_cipher() {
+        let mut d = TlsDetector::new();
+        let mut c = Conn::open(&mut d, 40000);
+        let hello = client_hello_record("shop.example", &[0xC02F, 0x0005], &["http/1.1"]);
+        // ClientHello partido en dos segmentos
+        c.client(&mut d, &hello[..20], 1_000);
+        c.client(&mut d, &hello[20..], 1_100);
+        c.server(
+            &mut d,
+            &concat(&[
+                server_hello_record(0x0005, false, Some("http/1.1")),
+                certificate_record(&[b"leaf", b"ca"]),
+            ]),
+            20_000,
+        );
+        c.client(
+            &mut d,
+            &concat(&[
+                record(CONTENT_CHANGE_CIPHER_SPEC, &[1]),
+                record(22, &[0u8; 40]),
+            ]),
+            30_000,
+        );
+        c.server(
+            &mut d,
+            &concat(&[
+                record(CONTENT_CHANGE_CIPHER_SPEC, &[1]),
+                record(22, &[0u8; 40]),
+            ]),
+            41_000,
+        );
+        let out = d.finalize();
+
+        assert_eq!(out["handshakes_total"], 1);
+        assert_eq!(out["handshakes_completed"], 1);
+        let conv = &out["top_by_severity"][0];
+        assert_eq!(conv["sni"], "shop.example");
+        assert_eq!(conv["version"], "TLS1.2");
+        assert_eq!(conv["offered_version"], "TLS1.3");
+        assert_eq!(conv["cipher"], "TLS_RSA_WITH_RC4_128_SHA");
+        assert_eq!(conv["alpn"], "http/1.1");
+        assert_eq!(conv["handshake_ms"], 40.0);
+        assert_eq!(conv["resumed"], false);
+        assert_eq!(conv["certificates"], 2);
+        assert_eq!(conv["score"]["level"], "MEDIA");
+
+        let f = &out["findings"][0];
+        assert_eq!(f["code"], "WEAK_CIPHER");
+        assert_eq!(f["subject"], "shop.example");
+        assert_eq!(f["count"], 1);
+    }
+
+    #[test]
+    fn tls13_handshake_and_abbreviated_resumption() {
+        let mut d = TlsDetector::new();
+        let mut c = Conn::open(&mut d, 40000);
+        c.client(
+            &mut d,
+            &client_hello_record("a.example", &[0x1301], &["h2"]),
+            1_000,
+        );
+        c.server(
+            &mut d,
+            &concat(&[
+                server_hello_record(0x1301, true, Some("h2")),
+                record(CONTENT_CHANGE_CIPHER_SPEC, &[1]),
+                record(CONTENT_APPLICATION_DATA, &[0u8; 64]),
+            ]),
+            9_000,
+        );
+        c.client(
+            &mut d,
+            &record(CONTENT_APPLICATION_DATA, &[0u8; 32]),
+            16_000,
+        );
+
+        // TLS 1.2 abreviado: CCS del servidor sin certificado
+        let mut r = Conn::open(&mut d, 40001);
+        r.client(
+            &mut d,
+            &client_hello_record("b.example", &[0xC02F], &[]),
+            2_000,
+        );
+        r.server(
+            &mut d,
+            &concat(&[
+                server_hello_record(0xC02F, false, None),
+                record(CONTENT_CHANGE_CIPHER_SPEC, &[1]),
+                record(22, &[0u8; 40]),
+            ]),
+            7_000,
+        );
+        let out = d.finalize();
+
+        assert_eq!(out["handshakes_completed"], 2);
+        assert_eq!(out["resumed"], 1);
+        assert_eq!(out["versions"]["TLS1.3"], 1);
+        assert_eq!(out["alpn"]["h2"], 1);
+        let convs = out["top_by_severity"].as_array().unwrap();
+        let a = convs.iter().find(|v| v["sni"] == "a.example").unwrap();
+        assert_eq!(a["handshake_ms"], 15.0);
+        assert_eq!(a["resumed"], false);
+        let b = convs.iter().find(|v| v["sni"] == "b.example").unwrap();
+        assert_eq!(b["resumption"], "ticket");
+        assert_eq!(b["score"]["level"], "BAJA");
+    }
+
+    #[test]
+    fn fatal_alert_and_non_tls_streams() {
+        let mut d = TlsDetector::new();
+        let mut c = Conn::open(&mut d, 40000);
+        c.client(
+            &mut d,
+            &client_hello_record("old.example", &[0x002F], &[]),
+            1_000,
+        );
+        c.server(&mut d, &record(CONTENT_ALERT, &[2, 40]), 3_000);
+
+        let mut h = Conn::open(&mut d, 40001);
+        h.client(&mut d, b"GET / HTTP/1.1\r\n\r\n", 1_000);
+        let out = d.finalize();
+
+        assert_eq!(out["handshakes_total"], 1);
+        assert_eq!(out["fatal_alerts"], 1);
+        let conv = &out["top_by_severity"][0];
+        assert_eq!(conv["alerts"][0]["description"], "handshake_failure");
+        assert_eq!(conv["alerts"][0]["from"], "server");
+        assert_eq!(conv["complete"], false);
+        assert_eq!(out["findings"][0]["code"], "FATAL_ALERT");
+        assert_eq!(out["findings"][0]["detail"]["value"], "handshake_failure");
+    }
+
+    #[test]
+    fn certificate_findings_relative_to_capture_time() {
+        let mut d = TlsDetector::new();
+        let mut c = Conn::open(&mut d, 40000);
+        // Válido de 1969 a 1970-01-01T00:00:10Z; la captura va por t = 20 s
+        let leaf = certificate(
+            "intranet",
+            "intranet",
+            &["intranet.local"],
+            "690101000000Z",
+            "700101000010Z",
+            1024,
+        );
+        c.client(
+            &mut d,
+            &client_hello_record("portal.example", &[0xC02F], &[]),
+            19_000_000,
+        );
+        c.server(
+            &mut d,
+            &concat(&[
+                server_hello_record(0xC02F, false, None),
+                certificate_record(&[&leaf]),
+            ]),
+            20_000_000,
+        );
+        let out = d.finalize();
+
+        let mut codes: Vec<&str> = out["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["code"].as_str().unwrap())
+            .collect();
+        codes.sort();
+        assert_eq!(
+            codes,
+            vec![
+                "CERT_EXPIRED",
+                "CERT_NAME_MISMATCH",
+                "CERT_SELF_SIGNED",
+                "CERT_SHORT_KEY"
+            ]
+        );
+        let conv = &out["top_by_severity"][0];
+        assert_eq!(conv["score"]["level"], "ALTA");
+        assert_eq!(conv["certificate_subject"], "O=Example Org, CN=intranet");
+
+        let entry = &out["certificates"][0];
+        assert_eq!(entry["subject"], "portal.example");
+        assert_eq!(entry["server"], "10.0.0.2:443");
+        assert_eq!(entry["chain"][0]["key_bits"], 1024);
+        assert_eq!(entry["chain"][0]["sans"][0], "intranet.local");
+        assert_eq!(entry["chain"][0]["not_after"], "1970-01-01T00:00:10Z");
+    }
+
+    #[test]
+    fn fingerprints_aggregate_and_denylist_findings() {
+        let list =
+            FingerprintList::parse("deny 97737df38853b88c4324af06e211c4a1 familia de prueba")
+                .unwrap();
+        let mut d = TlsDetector::with_config(TlsConfig {
+            fingerprints: list,
+            ..TlsConfig::default()
+        });
+        for port in [40000, 40001] {
+            let mut c = Conn::open(&mut d, port);
+            let hello = client_hello_record("c2.example", &[0x1301, 0xC02F], &["h2", "http/1.1"]);
+            c.client(&mut d, &hello, 1_000);
+            c.server(
+                &mut d,
+                &server_hello_record(0x1301, true, Some("h2")),
+                2_000,
+            );
+        }
+        let out = d.finalize();
+
+        let ja3 = &out["fingerprints"]["ja3"][0];
+        assert_eq!(ja3["fingerprint"], "97737df38853b88c4324af06e211c4a1");
+        assert_eq!(ja3["conversations"], 2);
+        assert_eq!(ja3["clients"], 1);
+        assert_eq!(ja3["list"], "deny");
+        assert_eq!(out["fingerprints"]["ja4"][0]["list"], Value::Null);
+        assert_eq!(out["fingerprints"]["ja3s"].as_array().unwrap().len(), 1);
+
+        let f = &out["findings"][0];
+        assert_eq!(f["code"], "FINGERPRINT_DENYLISTED");
+        assert_eq!(f["count"], 2);
+        assert_eq!(
+            f["reason"],
+            "huella JA3 en la lista de bloqueo: familia de prueba"
+        );
+        let conv = &out["top_by_severity"][0];
+        assert_eq!(conv["score"]["level"], "ALTA");
+        assert_eq!(conv["ja4"], "t13d0206h2_c1929292aa6b_fb71836bce29");
+    }
+}
@@ -0,0 +1,350 @@
+use crate::engine::Detector;
+use crate::network::flow::Flow;
+use crate::network::flow_table::{FlowArchive, FlowTable, FlowTableConfig};
+use crate::network::format::flow_label;
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::net::IpAddr;
+
+/// Un sentido de la conversación UDP
+#[derive(Debug, Default, Clone)]
+pub struct UdpStreamState {
+    pub packets: u64,
+    /// Bytes en el cable (trama completa)
+    pub bytes: u64,
+    pub payload_bytes: u64,
+    pub first_us: Option<u64>,
+    pub last_us: Option<u64>,
+    last_interarrival_us: Option<u64>,
+    /// Variación entre llegadas suavizada (estimador de RFC 3550 §6.4.1), en µs
+    jitter_us: f64,
+}
+
+impl UdpStreamState {
+    fn on_packet(&mut self, frame_len: usize, payload_len: usize, ts_us: u64) {
+        self.packets += 1;
+        self.bytes += frame_len as u64;
+        self.payload_bytes += payload_len as u64;
+        if let Some(last) = self.last_us {
+            let iat = ts_us.saturating_sub(last);
+            if let Some(prev) = self.last_interarrival_us {
+                let d = (iat as f64 - prev as f64).abs();
+                self.jitter_us += (d - self.jitter_us) / 16.0;
+            }
+            self.last_interarrival_us = Some(iat);
+        }
+        self.first_us.get_or_insert(ts_us);
+        self.last_us = Some(ts_us);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UdpConversationState {
+    pub flow: Flow, // dirección canónica (emisor del primer datagrama)
+    pub c2s: UdpStreamState,
+    pub s2c: UdpStreamState,
+}
+
+/// Límites de memoria del detector
+#[derive(Debug, Clone, Copy)]
+pub struct UdpConfig {
+    pub flow_table: FlowTableConfig,
+    /// Máximo de resúmenes de conversaciones expulsadas que se conservan (0 = sin límite)
+    pub max_archived: usize,
+}
+
+impl Default for UdpConfig {
+    fn default() -> Self {
+        UdpConfig {
+            flow_table: FlowTableConfig::default(),
+            max_archived: 50_000,
+        }
+    }
+}
+
+/// Resumen compacto de una conversación finalizada
+struct ConversationSummary {
+    flow: String,
+    sort_key: (String, u16, String, u16),
+    total_packets: u64,
+    total_bytes: u64,
+    two_way: bool,
+    duration_ms: f64,
+    c2s: Value,
+    s2c: Value,
+}
+
+/// Conversaciones UDP bidireccionales: volumen, jitter y sentido único vs ida y vuelta
+pub struct UdpDetector {
+    conversations: FlowTable<UdpConversationState>,
+    archived: FlowArchive<ConversationSummary>,
+    // Se cuenta al archivar, así incluye las conversaciones que luego se podan
+    two_way_total: u64,
+}
+
+impl Default for UdpDetector {
+    fn default() -> Self {
+        Self::with_config(UdpConfig::default())
+    }
+}
+
+impl UdpDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_flow_table(flow_table: FlowTableConfig) -> Self {
+        Self::with_config(UdpConfig {
+            flow_table,
+            ..UdpConfig::default()
+        })
+    }
+
+    pub fn with_config(config: UdpConfig) -> Self {
+        UdpDetector {
+            conversations: FlowTable::new(config.flow_table),
+            // Poda: mitad superior por paquetes y mitad superior por bytes
+            archived: FlowArchive::new(config.max_archived, cmp_by_packets, cmp_by_bytes),
+            two_way_total: 0,
+        }
+    }
+
+    /// Resume las conversaciones UDP expulsadas y cuenta las de ida y vuelta
+    fn archive(&mut self, evicted: Vec<(Flow, UdpConversationState)>) {
+        if evicted.is_empty() {
+            return;
+        }
+        let summaries: Vec<ConversationSummary> =
+            evicted.iter().map(|(_, st)| summarize(st)).collect();
+        self.two_way_total += summaries.iter().filter(|c| c.two_way).count() as u64;
+        self.archived.extend(summaries);
+    }
+}
+
+impl Detector for UdpDetector {
+    fn name(&self) -> &'static str {
+        "udp"
+    }
+
+    fn on_packet(&mut self, packet_data: &[u8], ts_micros: u64) {
+        let evicted = self.conversations.evict(ts_micros);
+        self.archive(evicted);
+
+        let Ok(sliced) = SlicedPacket::from_ethernet(packet_data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(InternetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(InternetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        let Some(TransportSlice::Udp(udp)) = &sliced.transport else {
+            return;
+        };
+        let flow = Flow {
+            source_ip: src,
+            source_port: udp.source_port(),
+            destination_ip: dst,
+            destination_port: udp.destination_port(),
+        };
+
+        let (key, conv, forward) = self.conversations.get_or_insert(flow, ts_micros);
+        conv.flow = key;
+        let stream = if forward {
+            &mut conv.c2s
+        } else {
+            &mut conv.s2c
+        };
+        stream.on_packet(packet_data.len(), udp.payload().len(), ts_micros);
+    }
+
+    fn finalize(&mut self) -> Value {
+        let stats = self.conversations.stats();
+        let table_cfg = self.conversations.config();
+        let live = self.conversations.drain();
+        let mut summaries: Vec<ConversationSummary> = self.archived.take();
+        let live: Vec<ConversationSummary> = live.iter().map(|(_, st)| summarize(st)).collect();
+        self.two_way_total += live.iter().filter(|c| c.two_way).count() as u64;
+        summaries.extend(live);
+
+        let total = summaries.len() as u64 + self.archived.pruned();
+
+        summaries.sort_by(cmp_by_packets);
+        let by_packets: Vec<Value> = summaries.iter().map(summary_json).collect();
+        summaries.sort_by(cmp_by_bytes);
+        let by_bytes: Vec<Value> = summaries.iter().map(summary_json).collect();
+
+        json!({
+            "conversations_total": total,
+            "two_way": self.two_way_total,
+            "one_way": total - self.two_way_total,
+            "flow_table": {
+                "max_flows": table_cfg.max_flows,
+                "idle_timeout_ms": table_cfg.idle_timeout_us / 1_000,
+                "peak_active_flows": stats.peak_flows,
+                "evicted_idle": stats.evicted_idle,
+                "evicted_capacity": stats.evicted_capacity,
+                "summaries_pruned": self.archived.pruned()
+            },
+            "top_by_packets": by_packets,
+            "top_by_bytes": by_bytes
+        })
+    }
+}
+
+fn summarize(st: &UdpConversationState) -> ConversationSummary {
+    let first = [st.c2s.first_us, st.s2c.first_us]
+        .into_iter()
+        .flatten()
+        .min();
+    let last = [st.c2s.last_us, st.s2c.last_us].into_iter().flatten().max();
+    let duration_ms = match (first, last) {
+        (Some(f), Some(l)) => l.saturating_sub(f) as f64 / 1000.0,
+        _ => 0.0,
+    };
+    ConversationSummary {
+        flow: flow_label(&st.flow, "UDP"),
+        sort_key: (
+            st.flow.source_ip.to_string(),
+            st.flow.source_port,
+            st.flow.destination_ip.to_string(),
+            st.flow.destination_port,
+        ),
+        total_packets: st.c2s.packets + st.s2c.packets,
+        total_bytes: st.c2s.bytes + st.s2c.bytes,
+        two_way: st.c2s.packets > 0 && st.s2c.packets > 0,
+        duration_ms,
+        c2s: stream_json(&st.c2s),
+        s2c: stream_json(&st.s2c),
+    }
+}
+
+fn summary_json(c: &ConversationSummary) -> Value {
+    json!({
+        "flow": c.flow,
+        "direction": if c.two_way { "two_way" } else { "one_way" },
+        "total_packets": c.total_packets,
+        "total_bytes": c.total_bytes,
+        "duration_ms": c.duration_ms,
+        "c2s": c.c2s,
+        "s2c": c.s2c
+    })
+}
+
+fn cmp_by_packets(a: &ConversationSummary, b: &ConversationSummary) -> std::cmp::Ordering {
+    // Desempate lexicográfico estable por 4-tupla del flow
+    b.total_packets
+        .cmp(&a.total_packets)
+        .then_with(|| a.sort_key.cmp(&b.sort_key))
+}
+
+fn cmp_by_bytes(a: &ConversationSummary, b: &ConversationSummary) -> std::cmp::Ordering {
+    b.total_bytes
+        .cmp(&a.total_bytes)
+        .then_with(|| a.sort_key.cmp(&b.sort_key))
+}
+
+/// Vista JSON de un sentido de la conversación
+fn stream_json(st: &UdpStreamState) -> Value {
+    let mean_interarrival_ms = match (st.first_us, st.last_us) {
+        (Some(f), Some(l)) if st.packets > 1 => {
+            Some(l.saturating_sub(f) as f64 / 1000.0 / (st.packets - 1) as f64)
+        }
+        _ => None,
+    };
+    json!({
+        "packets": st.packets,
+        "bytes": st.bytes,
+        "payload_bytes": st.payload_bytes,
+        "mean_interarrival_ms": mean_interarrival_ms,
+        "jitter_ms": st.jitter_us / 1000.0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn udp_frame(src: ([u8; 4], u16), dst: ([u8; 4], u16), payload: &[u8]) -> Vec<u8> {
+        let builder = etherparse::PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(src.0, dst.0, 64)
+            .udp(src.1, dst.1);
+        let mut out = Vec::with_capacity(builder.size(payload.len()));
+        builder.write(&mut out, payload).unwrap();
+        out
+    }
+
+    const A: ([u8; 4], u16) = ([10, 0, 0, 1], 5004);
+    const B: ([u8; 4], u16) = ([10, 0, 0, 2], 5006);
+    const SYSLOG: ([u8; 4], u16) = ([10, 0, 0, 3], 514);
+
+    #[test]
+    fn bidirectional_counts_jitter_and_views() {
+        let mut d = UdpDetector::new();
+        // A -> B cada 20 ms, con un paquete que llega 10 ms tarde
+        for (i, ts) in [0u64, 20_000, 50_000, 60_000].iter().enumerate() {
+            d.on_packet(&udp_frame(A, B, &[i as u8; 160]), *ts);
+        }
+        d.on_packet(&udp_frame(B, A, &[0; 20]), 5_000);
+        // Syslog de un solo sentido con datagramas grandes
+        d.on_packet(&udp_frame(SYSLOG, B, &[b'x'; 1000]), 1_000);
+        d.on_packet(&udp_frame(SYSLOG, B, &[b'x'; 1000]), 2_000);
+
+        let out = d.finalize();
+        assert_eq!(out["conversations_total"], 2);
+        assert_eq!(out["two_way"], 1);
+        assert_eq!(out["one_way"], 1);
+
+        let top = &out["top_by_packets"][0];
+        assert_eq!(top["flow"], "10.0.0.1:5004 <-> 10.0.0.2:5006/UDP");
+        assert_eq!(top["direction"], "two_way");
+        assert_eq!(top["total_packets"], 5);
+        assert_eq!(top["duration_ms"], 60.0);
+        assert_eq!(top["c2s"]["payload_bytes"], 640);
+        assert_eq!(top["c2s"]["mean_interarrival_ms"], 20.0);
+        // |30-20|/16 = 0.625, luego |10-30| => 0.625 + (20-0.625)/16
+        let jitter = top["c2s"]["jitter_ms"].as_f64().unwrap();
+        assert!((jitter - 1.8359375).abs() < 1e-9, "{jitter}");
+        assert_eq!(top["s2c"]["packets"], 1);
+
+        let by_bytes = &out["top_by_bytes"][0];
+        assert_eq!(by_bytes["flow"], "10.0.0.3:514 <-> 10.0.0.2:5006/UDP");
+        assert_eq!(by_bytes["direction"], "one_way");
+        assert_eq!(by_bytes["c2s"]["bytes"], 2 * (14 + 20 + 8 + 1000));
+    }
+
+    #[test]
+    fn idle_timeout_splits_conversations() {
+        let mut d = UdpDetector::with_flow_table(FlowTableConfig {
+            max_flows: 0,
+            idle_timeout_us: 1_000_000,
+        });
+        d.on_packet(&udp_frame(A, B, b"uno"), 0);
+        d.on_packet(&udp_frame(B, A, b"dos"), 100);
+        // 5 s de silencio: la conversación se cierra y la siguiente es nueva
+        d.on_packet(&udp_frame(B, A, b"tres"), 5_000_000);
+        let out = d.finalize();
+        assert_eq!(out["conversations_total"], 2);
+        assert_eq!(out["flow_table"]["evicted_idle"], 1);
+        assert_eq!(out["two_way"], 1);
+        let flows: Vec<&str> = out["top_by_packets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["flow"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            flows,
+            [
+                "10.0.0.1:5004 <-> 10.0.0.2:5006/UDP",
+                "10.0.0.2:5006 <-> 10.0.0.1:5004/UDP"
+            ]
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::network::capture::{self, CaptureQuality};
+use crate::network::dedup::{DedupConfig, DedupMode, DuplicateFilter};
 use pcap::Capture;
 use serde_json::{json, Value};
 use std::path::Path;
@@ -15,6 +17,7 @@ pub trait Detector {
 #[derive(Default)]
 pub struct Engine {
     detectors: Vec<Box<dyn Detector>>,
+    dedup: DedupConfig,
 }
 
 impl Engine {
@@ -23,6 +26,15 @@ impl Engine {
         Self::default()
     }
 
+    /// Engine con detección de tramas duplicadas configurada
+    /// (por defecto solo se reportan, sin descartarlas)
+    pub fn with_dedup(dedup: DedupConfig) -> Self {
+        Engine {
+            detectors: Vec::new(),
+            dedup,
+        }
+    }
+
     /// Registra un detector
     pub fn register<D: Detector + 'static>(&mut self, detector: D) {
         self.detectors.push(Box::new(detector));
@@ -41,7 +53,13 @@ impl Engine {
         let mut cap =
             Capture::from_file(p).map_err(|e| format!("Error al abrir la captura: {e}"))?;
 
+        let file_info = capture::file_info(p).unwrap_or_default();
+        let mut quality = CaptureQuality::new();
+
         let mut packets_total: u64 = 0;
+        // Se filtra antes de cualquier detector para no contar copias como retransmisiones
+        let mut dedup = DuplicateFilter::new(self.dedup);
+        let mut dropped: u64 = 0;
 
         // Timestamps deterministas basados en PCAP
         let mut first_ts_us: Option<u64> = None;
@@ -60,6 +78,14 @@ impl Engine {
             }
             last_ts_us = Some(ts_micros);
 
+            // Antes de deduplicar: la calidad se mide sobre la captura tal cual
+            quality.observe(pkt.data, pkt.header.caplen, pkt.header.len, ts_micros);
+
+            if dedup.is_duplicate(pkt.data, ts_micros) && self.dedup.mode == DedupMode::Drop {
+                dropped += 1;
+                continue;
+            }
+
             for d in self.detectors.iter_mut() {
                 d.on_packet(pkt.data, ts_micros);
             }
@@ -80,6 +106,13 @@ impl Engine {
             0.0
         };
 
+        let duplicate_frames = dedup.duplicates();
+        let duplicate_rate = if dedup.inspected() > 0 {
+            duplicate_frames as f64 / dedup.inspected() as f64
+        } else {
+            0.0
+        };
+
         // Finalizar y recolectar reportes
         let mut det_map = serde_json::Map::new();
         for d in self.detectors.iter_mut() {
@@ -93,8 +126,16 @@ impl Engine {
                 "packets_total": packets_total,
                 "duration_ms": duration_ms,
                 "bytes_total": file_bytes,
-                "throughput_mbps": throughput_mbps
+                "throughput_mbps": throughput_mbps,
+                "duplicates": {
+                    "mode": self.dedup.mode.as_str(),
+                    "window_us": self.dedup.window_us,
+                    "frames": duplicate_frames,
+                    "rate": duplicate_rate,
+                    "dropped": dropped
+                }
             },
+            "capture_quality": quality.to_json(&file_info),
             "detectors": det_map
         }))
     }
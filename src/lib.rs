@@ -4,5 +4,25 @@ pub mod engine;
 pub mod network;
 
 // Re-exports para que el test de integración sea simple
-pub use detectors::tcp_health::TcpHealthDetector;
+pub use detectors::beacon::BeaconDetector;
+pub use detectors::credentials::{CredentialsConfig, CredentialsDetector};
+pub use detectors::dhcp::{DhcpConfig, DhcpDetector};
+pub use detectors::dns::DnsDetector;
+pub use detectors::hierarchy::HierarchyDetector;
+pub use detectors::http::HttpDetector;
+pub use detectors::icmp::IcmpDetector;
+pub use detectors::neighbor::{NeighborConfig, NeighborDetector};
+pub use detectors::pmtu::PmtuDetector;
+pub use detectors::quic::QuicDetector;
+pub use detectors::rtp::RtpDetector;
+pub use detectors::scan::ScanDetector;
+pub use detectors::sip::SipDetector;
+pub use detectors::synflood::SynFloodDetector;
+pub use detectors::talkers::TalkersDetector;
+pub use detectors::tcp_health::{ChecksumMode, TcpHealthConfig, TcpHealthDetector};
+pub use detectors::tls::{TlsConfig, TlsDetector};
+pub use detectors::udp::{UdpConfig, UdpDetector};
 pub use engine::{Detector, Engine};
+pub use network::dedup::{DedupConfig, DedupMode};
+pub use network::flow_table::FlowTableConfig;
+pub use network::tls_fingerprint::FingerprintList;
@@ -1,8 +1,15 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde_json::Value;
 
 // Importa desde tu crate de librería (re-exports en src/lib.rs)
-use packetmancer::{Engine, TcpHealthDetector};
+use packetmancer::network::format::endpoint_label;
+use packetmancer::{
+    BeaconDetector, ChecksumMode, CredentialsConfig, CredentialsDetector, DedupConfig, DedupMode,
+    DhcpConfig, DhcpDetector, DnsDetector, Engine, FingerprintList, FlowTableConfig,
+    HierarchyDetector, HttpDetector, IcmpDetector, NeighborConfig, NeighborDetector, PmtuDetector,
+    QuicDetector, RtpDetector, ScanDetector, SipDetector, SynFloodDetector, TalkersDetector,
+    TcpHealthConfig, TcpHealthDetector, TlsConfig, TlsDetector, UdpDetector,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about = "PacketMancer - Analizador de Red Inteligente", long_about = None)]
@@ -18,14 +25,145 @@ struct Args {
     /// Número de flujos a mostrar en el Top (por consola). Default: 5
     #[arg(long, default_value_t = 5)]
     top: usize,
+
+    /// Máximo de flujos TCP/UDP activos en memoria por detector (0 = sin límite). Default: 100000
+    #[arg(long, default_value_t = 100_000)]
+    max_flows: usize,
+
+    /// Segundos de inactividad tras los que un flujo se finaliza y sale de memoria (0 = nunca). Default: 600
+    #[arg(long, default_value_t = 600)]
+    idle_timeout: u64,
+
+    /// Verificación de checksums IPv4/TCP. Default: offload-aware
+    #[arg(long, value_enum, default_value_t = ChecksumArg::OffloadAware)]
+    checksum: ChecksumArg,
+
+    /// Tramas duplicadas (SPAN, capturas multi-interfaz). Default: report
+    #[arg(long, value_enum, default_value_t = DedupArg::Report)]
+    dedup: DedupArg,
+
+    /// Ventana en ms para considerar una copia idéntica como duplicado. Default: 20
+    #[arg(long, default_value_t = 20)]
+    dedup_window_ms: u64,
+
+    /// Lista local de huellas TLS (líneas `allow|deny <JA3/JA3S/JA4> [comentario]`)
+    #[arg(long)]
+    tls_fingerprints: Option<String>,
+
+    /// Routers IPv6 autorizados a enviar Router Advertisements (IP o MAC, separados por comas)
+    #[arg(long, value_delimiter = ',')]
+    trusted_routers: Vec<String>,
+
+    /// Servidores DHCP/DHCPv6 autorizados (IP, MAC o DUID en hex, separados por comas)
+    #[arg(long, value_delimiter = ',')]
+    trusted_dhcp_servers: Vec<String>,
+
+    /// Muestra sin enmascarar las credenciales y comunidades SNMP detectadas en claro
+    #[arg(long)]
+    reveal_credentials: bool,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum DedupArg {
+    /// Sin detección de duplicados
+    Off,
+    /// Cuenta los duplicados en el resumen sin descartarlos
+    Report,
+    /// Descarta los duplicados antes del análisis
+    Drop,
+}
+
+impl From<DedupArg> for DedupMode {
+    fn from(arg: DedupArg) -> Self {
+        match arg {
+            DedupArg::Off => DedupMode::Off,
+            DedupArg::Report => DedupMode::Report,
+            DedupArg::Drop => DedupMode::Drop,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ChecksumArg {
+    /// Sin verificación
+    Off,
+    /// Excluye de las métricas todo paquete con checksum incorrecto
+    Strict,
+    /// Mantiene los artefactos de offload de la NIC y excluye el resto
+    OffloadAware,
+}
+
+impl From<ChecksumArg> for ChecksumMode {
+    fn from(arg: ChecksumArg) -> Self {
+        match arg {
+            ChecksumArg::Off => ChecksumMode::Off,
+            ChecksumArg::Strict => ChecksumMode::Strict,
+            ChecksumArg::OffloadAware => ChecksumMode::OffloadAware,
+        }
+    }
 }
 
 fn main() {
     let args = Args::parse();
     println!("Iniciando análisis del archivo: {}", &args.file);
 
-    let mut engine = Engine::new();
-    engine.register(TcpHealthDetector::new());
+    let flow_table = FlowTableConfig {
+        max_flows: args.max_flows,
+        idle_timeout_us: args.idle_timeout.saturating_mul(1_000_000),
+    };
+
+    let fingerprints = match args.tls_fingerprints.as_ref() {
+        Some(path) => {
+            match std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| FingerprintList::parse(&text))
+            {
+                Ok(list) => list,
+                Err(e) => {
+                    eprintln!("⚠️  No se pudo cargar la lista de huellas TLS {path}: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => FingerprintList::default(),
+    };
+
+    let mut engine = Engine::with_dedup(DedupConfig {
+        mode: args.dedup.into(),
+        window_us: args.dedup_window_ms.saturating_mul(1_000),
+    });
+    engine.register(TcpHealthDetector::with_config(TcpHealthConfig {
+        flow_table,
+        checksum_mode: args.checksum.into(),
+        ..TcpHealthConfig::default()
+    }));
+    engine.register(PmtuDetector::with_flow_table(flow_table));
+    engine.register(DnsDetector::new());
+    engine.register(HttpDetector::with_flow_table(flow_table));
+    engine.register(TlsDetector::with_config(TlsConfig {
+        flow_table,
+        fingerprints,
+    }));
+    engine.register(QuicDetector::with_flow_table(flow_table));
+    engine.register(UdpDetector::with_flow_table(flow_table));
+    engine.register(IcmpDetector::with_flow_table(flow_table));
+    engine.register(NeighborDetector::with_config(NeighborConfig {
+        trusted_routers: args.trusted_routers.clone(),
+    }));
+    engine.register(DhcpDetector::with_config(DhcpConfig {
+        trusted_servers: args.trusted_dhcp_servers.clone(),
+    }));
+    engine.register(RtpDetector::new());
+    engine.register(SipDetector::new());
+    engine.register(CredentialsDetector::with_config(CredentialsConfig {
+        flow_table,
+        reveal: args.reveal_credentials,
+    }));
+    engine.register(ScanDetector::new());
+    engine.register(SynFloodDetector::new());
+    engine.register(BeaconDetector::new());
+    engine.register(TalkersDetector::new());
+    engine.register(HierarchyDetector::new());
 
     match engine.run(&args.file) {
         Ok(report) => {
@@ -39,6 +177,24 @@ fn main() {
 
             // Salida HUMANA por defecto
             print_human_tcp_health(&report, args.top);
+            print_human_capture_quality(&report);
+            print_human_pmtu(&report, args.top);
+            print_human_dns(&report, args.top);
+            print_human_http(&report, args.top);
+            print_human_tls(&report, args.top);
+            print_human_quic(&report, args.top);
+            print_human_udp(&report, args.top);
+            print_human_icmp(&report, args.top);
+            print_human_neighbor(&report, args.top);
+            print_human_dhcp(&report, args.top);
+            print_human_rtp(&report, args.top);
+            print_human_sip(&report, args.top);
+            print_human_credentials(&report, args.top);
+            print_human_scan(&report, args.top);
+            print_human_synflood(&report, args.top);
+            print_human_beacon(&report, args.top);
+            print_human_talkers(&report, args.top);
+            print_human_hierarchy(&report);
 
             println!("\n--- ANÁLISIS COMPLETADO ---");
         }
@@ -77,12 +233,41 @@ fn print_human_tcp_health(report: &Value, top_n: usize) {
         println!(
             "Archivo: {file} | Paquetes: {pkts} | Duración: {dur_ms} ms | Tasa: {thr:.2} Mbps | Schema: {schema}"
         );
+
+        let dups = &summary["duplicates"];
+        let dup_frames = dups["frames"].as_u64().unwrap_or(0);
+        if dup_frames > 0 {
+            let rate = dups["rate"].as_f64().unwrap_or(0.0) * 100.0;
+            let action = if dups["dropped"].as_u64().unwrap_or(0) > 0 {
+                "descartadas antes del análisis"
+            } else {
+                "incluidas en el análisis; usa --dedup drop para descartarlas"
+            };
+            println!("⚠️  Tramas duplicadas: {dup_frames} ({rate:.1}%), {action}");
+        }
     }
 
     let tcp = &report["detectors"]["tcp_health"];
     let convs = tcp["conversations_total"].as_u64().unwrap_or(0);
     println!("Se encontraron {convs} conversaciones TCP distintas.");
 
+    let table = &tcp["flow_table"];
+    let evicted = table["evicted_idle"].as_u64().unwrap_or(0)
+        + table["evicted_capacity"].as_u64().unwrap_or(0);
+    let truncated = table["truncated_flows"].as_u64().unwrap_or(0);
+    if evicted > 0 || truncated > 0 {
+        println!(
+            "Tabla de flujos: {evicted} expulsados (inactividad/capacidad), {truncated} con seguimiento truncado, pico {} activos.",
+            table["peak_active_flows"]
+        );
+    }
+
+    if let Some(findings) = tcp["validation"]["findings"].as_array() {
+        for f in findings.iter().filter_map(|f| f.as_str()) {
+            println!("⚠️  Validación: {f}");
+        }
+    }
+
     // Top por severidad
     println!("\nTop {top_n} conversaciones por SEVERIDAD:");
     if let Some(arr) = tcp["top_by_severity"].as_array() {
@@ -136,3 +321,1118 @@ fn print_human_tcp_health(report: &Value, top_n: usize) {
         }
     }
 }
+
+fn print_human_pmtu(report: &Value, top_n: usize) {
+    let pmtu = &report["detectors"]["pmtu"];
+    let frags = &pmtu["fragments"];
+    let ptb = &pmtu["icmp_packet_too_big"];
+    let flows = pmtu["flows_total"].as_u64().unwrap_or(0);
+    let datagrams = frags["datagrams"].as_u64().unwrap_or(0);
+    let ptb_total = ptb["total"].as_u64().unwrap_or(0);
+    if flows == 0 && datagrams == 0 && ptb_total == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte de PMTU / Fragmentación ---");
+    println!(
+        "Datagramas fragmentados: {datagrams} (reensamblados: {}, incompletos: {}, solapados: {})",
+        frags["reassembled"], frags["incomplete"], frags["overlapping"]
+    );
+    if ptb_total > 0 {
+        println!(
+            "ICMP Packet-Too-Big: {ptb_total} (correlacionados: {}, MTU mínima: {})",
+            ptb["correlated"], ptb["min_mtu"]
+        );
+    }
+    let black_holes = pmtu["black_holes_suspected"].as_u64().unwrap_or(0);
+    if black_holes > 0 {
+        println!("⚠️  Posibles agujeros negros PMTUD: {black_holes}");
+    }
+
+    if let Some(arr) = pmtu["top_by_severity"].as_array() {
+        for entry in arr.iter().take(top_n) {
+            let flow = entry["flow"].as_str().unwrap_or("");
+            let level = entry["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = entry["score"]["value"].as_u64().unwrap_or(0);
+            println!("  - [{level} | score={score}] {flow}");
+            for (dir, s) in [("C->S", &entry["c2s"]), ("S->C", &entry["s2c"])] {
+                println!(
+                    "    {dir}: MSS: {}, Máx. payload: {}, Grandes: {} (retrans.: {}, ACK: {}), PTB: {}",
+                    s["syn_mss"], s["max_payload"], s["large_segments"], s["large_retransmissions"], s["large_acked"], s["icmp_ptb"]["count"]
+                );
+            }
+            if let Some(reasons) = entry["reasons"].as_array() {
+                let pretty: Vec<&str> = reasons.iter().filter_map(|r| r.as_str()).collect();
+                if !pretty.is_empty() {
+                    println!("    Razones: {}", pretty.join(" · "));
+                }
+            }
+        }
+    }
+}
+
+fn print_human_dns(report: &Value, top_n: usize) {
+    let dns = &report["detectors"]["dns"];
+    let queries = dns["queries_total"].as_u64().unwrap_or(0);
+    if queries == 0 && dns["responses_total"].as_u64().unwrap_or(0) == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte DNS ---");
+    println!(
+        "Consultas: {queries} | Respuestas: {} | Malformados: {} | Resolvers: {}",
+        dns["responses_total"], dns["malformed"], dns["resolvers_total"]
+    );
+
+    let threats = &dns["threats"]["findings"];
+    if let Some(arr) = threats.as_array().filter(|a| !a.is_empty()) {
+        println!("\nAmenazas DNS (top {top_n}):");
+        for f in arr.iter().take(top_n) {
+            let examples: Vec<&str> = f["examples"]
+                .as_array()
+                .map(|e| e.iter().filter_map(|v| v.as_str()).take(3).collect())
+                .unwrap_or_default();
+            println!(
+                "  - [{} | score={}] {} {}: {}",
+                f["score"]["level"].as_str().unwrap_or("BAJA"),
+                f["score"]["value"],
+                f["code"].as_str().unwrap_or(""),
+                f["subject"].as_str().unwrap_or(""),
+                f["reason"].as_str().unwrap_or("")
+            );
+            if !examples.is_empty() {
+                println!("    Ejemplos: {}", examples.join(", "));
+            }
+        }
+    }
+
+    println!("\nTop {top_n} resolvers por SEVERIDAD:");
+    if let Some(arr) = dns["top_by_severity"].as_array() {
+        for entry in arr.iter().take(top_n) {
+            let resolver = entry["resolver"].as_str().unwrap_or("");
+            let level = entry["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = entry["score"]["value"].as_u64().unwrap_or(0);
+            let lat = &entry["latency_ms"];
+            println!("  - [{level} | score={score}] {resolver}");
+            println!(
+                "    Consultas: {}, Respuestas: {}, Sin respuesta: {}, Reintentos: {}, TC: {}",
+                entry["queries"],
+                entry["responses"],
+                entry["unanswered"],
+                entry["retries"],
+                entry["truncated"]
+            );
+            println!(
+                "    Latencia: p50={:.1} ms, p95={:.1} ms, p99={:.1} ms (n={})",
+                lat["p50"].as_f64().unwrap_or(0.0),
+                lat["p95"].as_f64().unwrap_or(0.0),
+                lat["p99"].as_f64().unwrap_or(0.0),
+                lat["samples"]
+            );
+            if let Some(rcodes) = entry["rcodes"].as_object() {
+                let pretty: Vec<String> = rcodes.iter().map(|(k, v)| format!("{k}={v}")).collect();
+                if !pretty.is_empty() {
+                    println!("    Códigos: {}", pretty.join(", "));
+                }
+            }
+            if let Some(reasons) = entry["reasons"].as_array() {
+                let pretty: Vec<&str> = reasons.iter().filter_map(|r| r.as_str()).collect();
+                if !pretty.is_empty() {
+                    println!("    Razones: {}", pretty.join(" · "));
+                }
+            }
+        }
+    }
+}
+
+fn print_human_http(report: &Value, top_n: usize) {
+    let http = &report["detectors"]["http"];
+    let total = http["transactions_total"].as_u64().unwrap_or(0);
+    if total == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte HTTP/1.x ---");
+    println!(
+        "Conexiones: {} | Transacciones: {total} | Respuestas sin petición: {} | Errores de parseo: {}",
+        http["connections_total"], http["unmatched_responses"], http["parse_errors"]
+    );
+
+    println!("\nTop {top_n} hosts por SEVERIDAD:");
+    if let Some(arr) = http["top_by_severity"].as_array() {
+        for entry in arr.iter().take(top_n) {
+            let host = entry["host"].as_str().unwrap_or("");
+            let level = entry["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = entry["score"]["value"].as_u64().unwrap_or(0);
+            let ttfb = &entry["ttfb_ms"];
+            let resp = &entry["response_ms"];
+            println!("  - [{level} | score={score}] {host}");
+            println!(
+                "    Peticiones: {}, Sin respuesta: {}, 5xx: {:.1}%, TTFB p50/p95: {:.1}/{:.1} ms, Respuesta p50/p95: {:.1}/{:.1} ms",
+                entry["requests"],
+                entry["unanswered"],
+                entry["rate_5xx"].as_f64().unwrap_or(0.0) * 100.0,
+                ttfb["p50"].as_f64().unwrap_or(0.0),
+                ttfb["p95"].as_f64().unwrap_or(0.0),
+                resp["p50"].as_f64().unwrap_or(0.0),
+                resp["p95"].as_f64().unwrap_or(0.0)
+            );
+            if let Some(eps) = entry["slowest_endpoints"].as_array() {
+                for ep in eps.iter().take(3) {
+                    println!(
+                        "    Lento: {} (máx {:.1} ms, n={})",
+                        ep["endpoint"].as_str().unwrap_or(""),
+                        ep["max_ms"].as_f64().unwrap_or(0.0),
+                        ep["requests"]
+                    );
+                }
+            }
+            if let Some(reasons) = entry["reasons"].as_array() {
+                let pretty: Vec<&str> = reasons.iter().filter_map(|r| r.as_str()).collect();
+                if !pretty.is_empty() {
+                    println!("    Razones: {}", pretty.join(" · "));
+                }
+            }
+        }
+    }
+}
+
+fn print_human_tls(report: &Value, top_n: usize) {
+    let tls = &report["detectors"]["tls"];
+    let total = tls["handshakes_total"].as_u64().unwrap_or(0);
+    if total == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte TLS ---");
+    let hs = &tls["handshake_ms"];
+    println!(
+        "Handshakes: {total} | Completados: {} | Reanudados: {} | Alertas fatales: {} | Handshake p50/p95: {:.1}/{:.1} ms",
+        tls["handshakes_completed"],
+        tls["resumed"],
+        tls["fatal_alerts"],
+        hs["p50"].as_f64().unwrap_or(0.0),
+        hs["p95"].as_f64().unwrap_or(0.0)
+    );
+    if let Some(versions) = tls["versions"].as_object() {
+        let pretty: Vec<String> = versions.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        if !pretty.is_empty() {
+            println!("Versiones: {}", pretty.join(", "));
+        }
+    }
+
+    if let Some(findings) = tls["findings"].as_array() {
+        if !findings.is_empty() {
+            println!("\nHallazgos:");
+            for f in findings.iter().take(top_n) {
+                println!(
+                    "  - [{} | score={}] {} {}: {} (x{})",
+                    f["score"]["level"].as_str().unwrap_or("BAJA"),
+                    f["score"]["value"].as_u64().unwrap_or(0),
+                    f["code"].as_str().unwrap_or(""),
+                    f["subject"].as_str().unwrap_or(""),
+                    f["reason"].as_str().unwrap_or(""),
+                    f["count"]
+                );
+            }
+        }
+    }
+
+    if let Some(certs) = tls["certificates"].as_array() {
+        if !certs.is_empty() {
+            println!("\nCertificados por servidor:");
+            for entry in certs.iter().take(top_n) {
+                let leaf = &entry["chain"][0];
+                println!(
+                    "  - {} ({}): {} | emisor: {} | válido hasta {} | {} {} bits | {}",
+                    entry["subject"].as_str().unwrap_or(""),
+                    entry["server"].as_str().unwrap_or(""),
+                    leaf["subject"].as_str().unwrap_or(""),
+                    leaf["issuer"].as_str().unwrap_or(""),
+                    leaf["not_after"].as_str().unwrap_or(""),
+                    leaf["key_type"].as_str().unwrap_or("?"),
+                    leaf["key_bits"],
+                    leaf["signature_algorithm"].as_str().unwrap_or("")
+                );
+            }
+        }
+    }
+
+    if let Some(ja3) = tls["fingerprints"]["ja3"].as_array() {
+        if !ja3.is_empty() {
+            println!("\nHuellas JA3 más frecuentes:");
+            for fp in ja3.iter().take(top_n) {
+                let list = fp["list"]
+                    .as_str()
+                    .map(|l| format!(" [{l}]"))
+                    .unwrap_or_default();
+                println!(
+                    "  - {} conversaciones={} clientes={}{list}",
+                    fp["fingerprint"].as_str().unwrap_or(""),
+                    fp["conversations"],
+                    fp["clients"]
+                );
+            }
+        }
+    }
+
+    println!("\nTop {top_n} conversaciones por SEVERIDAD:");
+    if let Some(arr) = tls["top_by_severity"].as_array() {
+        for entry in arr.iter().take(top_n) {
+            let flow = entry["flow"].as_str().unwrap_or("");
+            let level = entry["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = entry["score"]["value"].as_u64().unwrap_or(0);
+            println!("  - [{level} | score={score}] {flow}");
+            println!(
+                "    SNI: {}, Versión: {}, Cipher: {}, ALPN: {}, Handshake: {}, Reanudada: {}",
+                entry["sni"].as_str().unwrap_or("-"),
+                entry["version"].as_str().unwrap_or("-"),
+                entry["cipher"].as_str().unwrap_or("-"),
+                entry["alpn"].as_str().unwrap_or("-"),
+                entry["handshake_ms"]
+                    .as_f64()
+                    .map(|ms| format!("{ms:.1} ms"))
+                    .unwrap_or_else(|| "-".to_string()),
+                if entry["resumed"].as_bool().unwrap_or(false) {
+                    "sí"
+                } else {
+                    "no"
+                }
+            );
+            println!(
+                "    JA3: {} | JA3S: {} | JA4: {}",
+                entry["ja3"].as_str().unwrap_or("-"),
+                entry["ja3s"].as_str().unwrap_or("-"),
+                entry["ja4"].as_str().unwrap_or("-")
+            );
+            if let Some(reasons) = entry["reasons"].as_array() {
+                let pretty: Vec<&str> = reasons.iter().filter_map(|r| r.as_str()).collect();
+                if !pretty.is_empty() {
+                    println!("    Razones: {}", pretty.join(" · "));
+                }
+            }
+        }
+    }
+}
+
+fn print_human_quic(report: &Value, top_n: usize) {
+    let quic = &report["detectors"]["quic"];
+    let total = quic["connections_total"].as_u64().unwrap_or(0);
+    if total == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte QUIC ---");
+    let hs = &quic["handshake_ms"];
+    println!(
+        "Conexiones: {total} | Completadas: {} | HTTP/3: {} | Retry: {} | Migraciones: {} | Handshake p50/p95: {:.1}/{:.1} ms",
+        quic["handshakes_completed"],
+        quic["http3"],
+        quic["retries"],
+        quic["migrations_total"],
+        hs["p50"].as_f64().unwrap_or(0.0),
+        hs["p95"].as_f64().unwrap_or(0.0)
+    );
+    if let Some(versions) = quic["versions"].as_object() {
+        let pretty: Vec<String> = versions.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        if !pretty.is_empty() {
+            println!("Versiones: {}", pretty.join(", "));
+        }
+    }
+
+    println!("\nTop {top_n} conexiones por SEVERIDAD:");
+    if let Some(arr) = quic["top_by_severity"].as_array() {
+        for entry in arr.iter().take(top_n) {
+            let flow = entry["flow"].as_str().unwrap_or("");
+            let level = entry["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = entry["score"]["value"].as_u64().unwrap_or(0);
+            println!("  - [{level} | score={score}] {flow}");
+            println!(
+                "    SNI: {}, Versión: {}, Cipher: {}, HTTP/3: {}, Handshake: {}, Migraciones: {}, Pérdida Initial: {:.1}%",
+                entry["sni"].as_str().unwrap_or("-"),
+                entry["version"].as_str().unwrap_or("-"),
+                entry["cipher"].as_str().unwrap_or("-"),
+                if entry["http3"].as_bool().unwrap_or(false) {
+                    "sí"
+                } else {
+                    "no"
+                },
+                entry["handshake_ms"]
+                    .as_f64()
+                    .map(|ms| format!("{ms:.1} ms"))
+                    .unwrap_or_else(|| "-".to_string()),
+                entry["migrations"],
+                entry["initial_loss_estimate"].as_f64().unwrap_or(0.0) * 100.0
+            );
+            if let Some(reasons) = entry["reasons"].as_array() {
+                let pretty: Vec<&str> = reasons.iter().filter_map(|r| r.as_str()).collect();
+                if !pretty.is_empty() {
+                    println!("    Razones: {}", pretty.join(" · "));
+                }
+            }
+        }
+    }
+}
+
+fn print_human_udp(report: &Value, top_n: usize) {
+    let udp = &report["detectors"]["udp"];
+    let convs = udp["conversations_total"].as_u64().unwrap_or(0);
+    if convs == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte UDP ---");
+    println!(
+        "Se encontraron {convs} conversaciones UDP distintas ({} de ida y vuelta, {} de un solo sentido).",
+        udp["two_way"], udp["one_way"]
+    );
+
+    for (title, key) in [("PAQUETES", "top_by_packets"), ("BYTES", "top_by_bytes")] {
+        println!("\nTop {top_n} conversaciones por {title}:");
+        if let Some(arr) = udp[key].as_array() {
+            for entry in arr.iter().take(top_n) {
+                let c2s = &entry["c2s"];
+                let s2c = &entry["s2c"];
+                println!(
+                    "  - {} [{}] Pkts: {}, Bytes: {}, Duración: {:.1} ms",
+                    entry["flow"].as_str().unwrap_or(""),
+                    if entry["direction"] == "two_way" {
+                        "ida y vuelta"
+                    } else {
+                        "un sentido"
+                    },
+                    entry["total_packets"],
+                    entry["total_bytes"],
+                    entry["duration_ms"].as_f64().unwrap_or(0.0)
+                );
+                println!(
+                    "    -> C->S: Pkts: {}, Bytes: {}, Jitter: {:.2} ms | <- S->C: Pkts: {}, Bytes: {}, Jitter: {:.2} ms",
+                    c2s["packets"],
+                    c2s["bytes"],
+                    c2s["jitter_ms"].as_f64().unwrap_or(0.0),
+                    s2c["packets"],
+                    s2c["bytes"],
+                    s2c["jitter_ms"].as_f64().unwrap_or(0.0)
+                );
+            }
+        }
+    }
+}
+
+fn print_human_icmp(report: &Value, top_n: usize) {
+    let icmp = &report["detectors"]["icmp"];
+    if icmp["messages_total"].as_u64().unwrap_or(0) == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte ICMP ---");
+    println!(
+        "Mensajes: {} | Errores: {} (correlacionados con flujos vistos: {})",
+        icmp["messages_total"], icmp["errors_total"], icmp["correlated_errors"]
+    );
+    if let Some(types) = icmp["errors_by_type"].as_object() {
+        let pretty: Vec<String> = types.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        if !pretty.is_empty() {
+            println!("Tipos: {}", pretty.join(", "));
+        }
+    }
+
+    let echo = &icmp["echo"];
+    if echo["requests"].as_u64().unwrap_or(0) > 0 {
+        let rtt = &echo["rtt_ms"];
+        println!(
+            "Echo: {} peticiones, {} respuestas, pérdida {:.1}% | RTT p50/p95: {:.1}/{:.1} ms",
+            echo["requests"],
+            echo["replies"],
+            echo["loss_rate"].as_f64().unwrap_or(0.0) * 100.0,
+            rtt["p50"].as_f64().unwrap_or(0.0),
+            rtt["p95"].as_f64().unwrap_or(0.0)
+        );
+    }
+
+    if let Some(arr) = icmp["affected_flows"].as_array() {
+        if !arr.is_empty() {
+            println!("\nTop {top_n} flujos afectados por errores ICMP:");
+        }
+        for entry in arr.iter().take(top_n) {
+            let flow = entry["flow"].as_str().unwrap_or("");
+            let level = entry["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = entry["score"]["value"].as_u64().unwrap_or(0);
+            let seen = if entry["flow_seen"].as_bool().unwrap_or(false) {
+                ""
+            } else {
+                " (flujo no visto en la captura)"
+            };
+            println!("  - [{level} | score={score}] {flow}{seen}");
+            if let Some(reasons) = entry["reasons"].as_array() {
+                let pretty: Vec<&str> = reasons.iter().filter_map(|r| r.as_str()).collect();
+                if !pretty.is_empty() {
+                    println!("    Razones: {}", pretty.join(" · "));
+                }
+            }
+        }
+    }
+}
+
+fn print_human_neighbor(report: &Value, top_n: usize) {
+    let n = &report["detectors"]["neighbor"];
+    let arp = &n["arp"];
+    let ndp = &n["ndp"];
+    let ndp_total = [
+        "router_advertisements",
+        "neighbor_solicitations",
+        "neighbor_advertisements",
+    ]
+    .iter()
+    .map(|k| ndp[k].as_u64().unwrap_or(0))
+    .sum::<u64>();
+    if arp["packets"].as_u64().unwrap_or(0) == 0 && ndp_total == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte ARP / NDP ---");
+    println!(
+        "ARP: {} peticiones, {} respuestas, {} gratuitos, {} sondeos, {} sin respuesta",
+        arp["requests"], arp["replies"], arp["gratuitous"], arp["probes"], arp["unanswered"]
+    );
+    println!(
+        "NDP: {} RA, {} NS ({} DAD), {} NA, {} sin respuesta | Tabla IP→MAC: {} entradas",
+        ndp["router_advertisements"],
+        ndp["neighbor_solicitations"],
+        ndp["dad_probes"],
+        ndp["neighbor_advertisements"],
+        ndp["unanswered"],
+        n["bindings_total"]
+    );
+    if let Some(routers) = n["routers"].as_array() {
+        for r in routers {
+            let trusted = if r["trusted"].as_bool().unwrap_or(false) {
+                " (autorizado)"
+            } else {
+                ""
+            };
+            println!(
+                "Router IPv6: {} [{}] lifetime={}s pref={}{trusted}",
+                r["ip"].as_str().unwrap_or(""),
+                r["mac"].as_str().unwrap_or("?"),
+                r["router_lifetime_s"],
+                r["preference"].as_str().unwrap_or("")
+            );
+        }
+    }
+
+    if let Some(arr) = n["findings"].as_array() {
+        if !arr.is_empty() {
+            println!("\nTop {top_n} hallazgos:");
+        }
+        for f in arr.iter().take(top_n) {
+            let level = f["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = f["score"]["value"].as_u64().unwrap_or(0);
+            println!(
+                "  - [{level} | score={score}] {} {}: {}",
+                f["code"].as_str().unwrap_or(""),
+                f["subject"].as_str().unwrap_or(""),
+                f["reason"].as_str().unwrap_or("")
+            );
+        }
+    }
+}
+
+fn print_human_dhcp(report: &Value, top_n: usize) {
+    let d = &report["detectors"]["dhcp"];
+    let tx = &d["transactions"];
+    if tx["started"].as_u64().unwrap_or(0) == 0 && d["servers"].as_array().is_none_or(Vec::is_empty)
+    {
+        return;
+    }
+
+    println!("\n--- Reporte DHCP / DHCPv6 ---");
+    println!(
+        "Transacciones: {} iniciadas, {} completadas, {} NAK, {} DECLINE, {} sin OFFER, {} sin ACK, {} reintentos",
+        tx["started"],
+        tx["completed"],
+        tx["nak"],
+        tx["declined"],
+        tx["no_offer"],
+        tx["no_ack"],
+        tx["retransmissions"]
+    );
+    let lat = &d["latency_ms"];
+    println!(
+        "Latencia (p50/p95 ms): OFFER {}/{} | ACK {}/{} | transacción {}/{}",
+        lat["offer"]["p50"],
+        lat["offer"]["p95"],
+        lat["ack"]["p50"],
+        lat["ack"]["p95"],
+        lat["transaction"]["p50"],
+        lat["transaction"]["p95"]
+    );
+    if let Some(servers) = d["servers"].as_array() {
+        for s in servers {
+            let trusted = if s["trusted"].as_bool().unwrap_or(false) {
+                " (autorizado)"
+            } else {
+                ""
+            };
+            println!(
+                "Servidor {} {} [{}] ofertas={} ACK={} NAK={} clientes={}{trusted}",
+                s["version"].as_str().unwrap_or(""),
+                s["id"].as_str().unwrap_or(""),
+                s["mac"].as_str().unwrap_or("?"),
+                s["offers"],
+                s["acks"],
+                s["naks"],
+                s["clients"]
+            );
+        }
+    }
+
+    if let Some(inv) = d["inventory"].as_array() {
+        if !inv.is_empty() {
+            println!(
+                "\nInventario ({} clientes, top {top_n}):",
+                d["clients_total"]
+            );
+        }
+        for c in inv.iter().take(top_n) {
+            let addresses: Vec<&str> = c["addresses"]
+                .as_array()
+                .map(|a| a.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            println!(
+                "  - {} {} huella={} {}",
+                c["mac"].as_str().unwrap_or(""),
+                c["hostname"].as_str().unwrap_or("-"),
+                c["fingerprint"]
+                    .as_str()
+                    .or(c["fingerprint_v6"].as_str())
+                    .unwrap_or("-"),
+                addresses.join(", ")
+            );
+        }
+    }
+
+    if let Some(arr) = d["findings"].as_array() {
+        if !arr.is_empty() {
+            println!("\nTop {top_n} hallazgos:");
+        }
+        for f in arr.iter().take(top_n) {
+            let level = f["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = f["score"]["value"].as_u64().unwrap_or(0);
+            println!(
+                "  - [{level} | score={score}] {} {}: {}",
+                f["code"].as_str().unwrap_or(""),
+                f["subject"].as_str().unwrap_or(""),
+                f["reason"].as_str().unwrap_or("")
+            );
+        }
+    }
+}
+
+fn print_human_rtp(report: &Value, top_n: usize) {
+    let r = &report["detectors"]["rtp"];
+    if r["streams_total"].as_u64().unwrap_or(0) == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte RTP / RTCP ---");
+    println!(
+        "Flujos: {} ({} vía SDP, {} heurísticos) | RTCP: {} SR, {} RR, {} BYE",
+        r["streams_total"],
+        r["by_identification"]["sdp"].as_u64().unwrap_or(0),
+        r["by_identification"]["heuristic"].as_u64().unwrap_or(0),
+        r["rtcp"]["sender_reports"],
+        r["rtcp"]["receiver_reports"],
+        r["rtcp"]["bye"]
+    );
+
+    if let Some(arr) = r["top_by_severity"].as_array() {
+        println!("\nTop {top_n} flujos por severidad:");
+        for s in arr.iter().take(top_n) {
+            let level = s["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = s["score"]["value"].as_u64().unwrap_or(0);
+            let mos = match s["quality"]["mos"].as_f64() {
+                Some(m) => format!("{m:.2}"),
+                None => "-".to_string(),
+            };
+            let jitter = match s["jitter_ms"].as_f64() {
+                Some(j) => format!("{j:.1}"),
+                None => "?".to_string(),
+            };
+            println!(
+                "  - [{level} | score={score}] {} ssrc={} {} ({}): pérdida {}%, jitter {jitter} ms, MOS {mos}",
+                s["flow"].as_str().unwrap_or(""),
+                s["ssrc"].as_str().unwrap_or(""),
+                s["codec"].as_str().unwrap_or("?"),
+                s["media"].as_str().unwrap_or(""),
+                s["loss_pct"]
+            );
+            if let Some(reasons) = s["reasons"].as_array() {
+                if !reasons.is_empty() {
+                    let text: Vec<&str> = reasons.iter().filter_map(Value::as_str).collect();
+                    println!("    Razones: {}", text.join("; "));
+                }
+            }
+        }
+    }
+}
+
+fn print_human_sip(report: &Value, top_n: usize) {
+    let s = &report["detectors"]["sip"];
+    let regs = &s["registrations"];
+    if s["calls_total"].as_u64().unwrap_or(0) == 0 && regs["attempts"].as_u64().unwrap_or(0) == 0 {
+        return;
+    }
+
+    println!("\n--- Reporte SIP ---");
+    let outcome = |k: &str| s["by_outcome"][k].as_u64().unwrap_or(0);
+    println!(
+        "Llamadas: {} ({} contestadas, {} fallidas, {} abandonadas, {} sin respuesta final) | retransmisiones UDP: {} peticiones, {} respuestas",
+        s["calls_total"],
+        outcome("answered"),
+        outcome("failed"),
+        outcome("abandoned"),
+        outcome("no_final_response"),
+        s["retransmissions"]["requests"],
+        s["retransmissions"]["responses"]
+    );
+    println!(
+        "Post-dial delay (p50/p95 ms): {}/{} | establecimiento {}/{} | registros: {} intentos, {} OK, {} rechazados",
+        s["post_dial_delay_ms"]["p50"],
+        s["post_dial_delay_ms"]["p95"],
+        s["setup_time_ms"]["p50"],
+        s["setup_time_ms"]["p95"],
+        regs["attempts"],
+        regs["succeeded"],
+        regs["failed"]
+    );
+
+    if let Some(calls) = s["calls"].as_array() {
+        if !calls.is_empty() {
+            println!("\nLlamadas (primeras {top_n}):");
+        }
+        for c in calls.iter().take(top_n) {
+            let code = match c["final_code"].as_u64() {
+                Some(code) => format!("{code} {}", c["final_reason"].as_str().unwrap_or("")),
+                None => "-".to_string(),
+            };
+            let opt_ms = |v: &Value| match v.as_f64() {
+                Some(ms) => format!("{ms:.0} ms"),
+                None => "-".to_string(),
+            };
+            let packets: u64 = c["rtp_streams"]
+                .as_array()
+                .map(|a| a.iter().filter_map(|r| r["packets"].as_u64()).sum())
+                .unwrap_or(0);
+            println!(
+                "  - {} {} → {}: {} ({code}) PDD {} duración {} | RTP {} flujos, {packets} paquetes",
+                c["call_id"].as_str().unwrap_or(""),
+                c["from"].as_str().unwrap_or("?"),
+                c["to"].as_str().unwrap_or("?"),
+                c["outcome"].as_str().unwrap_or(""),
+                opt_ms(&c["post_dial_delay_ms"]),
+                opt_ms(&c["duration_ms"]),
+                c["rtp_streams"].as_array().map_or(0, Vec::len)
+            );
+        }
+    }
+
+    if let Some(arr) = s["findings"].as_array() {
+        if !arr.is_empty() {
+            println!("\nTop {top_n} hallazgos:");
+        }
+        for f in arr.iter().take(top_n) {
+            let level = f["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = f["score"]["value"].as_u64().unwrap_or(0);
+            println!(
+                "  - [{level} | score={score}] {} {}: {}",
+                f["code"].as_str().unwrap_or(""),
+                f["subject"].as_str().unwrap_or(""),
+                f["reason"].as_str().unwrap_or("")
+            );
+        }
+    }
+}
+
+fn print_human_credentials(report: &Value, top_n: usize) {
+    let c = &report["detectors"]["credentials"];
+    let by_protocol = c["by_protocol"].as_object();
+    let communities = c["snmp"]["communities"].as_array();
+    if by_protocol.is_none_or(|m| m.is_empty()) && communities.is_none_or(Vec::is_empty) {
+        return;
+    }
+
+    println!("\n--- Reporte de Credenciales en Claro ---");
+    if let Some(map) = by_protocol {
+        let parts: Vec<String> = map
+            .iter()
+            .map(|(name, s)| {
+                format!(
+                    "{name}: {} sesiones ({} con credenciales, {} con STARTTLS)",
+                    s["sessions"], s["with_credentials"], s["starttls"]
+                )
+            })
+            .collect();
+        println!("{}", parts.join(" | "));
+    }
+    if !c["reveal"].as_bool().unwrap_or(false) {
+        println!("(evidencia enmascarada; usa --reveal-credentials para verla completa)");
+    }
+
+    if let Some(sessions) = c["sessions"].as_array() {
+        let exposed: Vec<&Value> = sessions
+            .iter()
+            .filter(|s| s["credentials"].as_array().is_some_and(|a| !a.is_empty()))
+            .collect();
+        if !exposed.is_empty() {
+            println!("\nFlujos con credenciales expuestas (top {top_n}):");
+        }
+        for s in exposed.into_iter().take(top_n) {
+            println!(
+                "  - {} {} → {}",
+                s["protocol"].as_str().unwrap_or(""),
+                s["client"].as_str().unwrap_or(""),
+                s["server"].as_str().unwrap_or("")
+            );
+            for cred in s["credentials"].as_array().into_iter().flatten() {
+                let accepted = match cred["accepted"].as_bool() {
+                    Some(true) => "aceptada",
+                    Some(false) => "rechazada",
+                    None => "sin veredicto",
+                };
+                println!(
+                    "      {} ({accepted})",
+                    cred["evidence"].as_str().unwrap_or("")
+                );
+            }
+        }
+    }
+
+    if let Some(arr) = c["findings"].as_array() {
+        if !arr.is_empty() {
+            println!("\nTop {top_n} hallazgos:");
+        }
+        for f in arr.iter().take(top_n) {
+            let level = f["score"]["level"].as_str().unwrap_or("BAJA");
+            let score = f["score"]["value"].as_u64().unwrap_or(0);
+            println!(
+                "  - [{level} | score={score}] {} {}: {}",
+                f["code"].as_str().unwrap_or(""),
+                f["subject"].as_str().unwrap_or(""),
+                f["reason"].as_str().unwrap_or("")
+            );
+        }
+    }
+}
+
+fn print_human_scan(report: &Value, top_n: usize) {
+    let s = &report["detectors"]["scan"];
+    let Some(scanners) = s["scanners"].as_array().filter(|a| !a.is_empty()) else {
+        return;
+    };
+
+    println!("\n--- Reporte de Escaneos de Puertos ---");
+    println!(
+        "Scanners: {} | sondeos distintos: {} ({} descartados por límite de memoria)",
+        s["scanners_total"], s["probes_tracked"], s["probes_truncated"]
+    );
+    println!("\nTop {top_n} scanners por severidad:");
+    for sc in scanners.iter().take(top_n) {
+        let level = sc["score"]["level"].as_str().unwrap_or("BAJA");
+        let score = sc["score"]["value"].as_u64().unwrap_or(0);
+        let kinds: Vec<String> = sc["kinds"]
+            .as_object()
+            .map(|m| m.iter().map(|(k, n)| format!("{k}={n}")).collect())
+            .unwrap_or_default();
+        println!(
+            "  - [{level} | score={score}] {} ({}): {} sondeos [{}] a {} hosts, {:.1} pps",
+            sc["scanner"].as_str().unwrap_or(""),
+            sc["pattern"].as_str().unwrap_or(""),
+            sc["probes"],
+            kinds.join(", "),
+            sc["targets_total"],
+            sc["rate_pps"].as_f64().unwrap_or(0.0)
+        );
+        for (proto, ranges) in [
+            ("TCP", &sc["port_ranges"]["tcp"]),
+            ("UDP", &sc["port_ranges"]["udp"]),
+        ] {
+            if let Some(r) = ranges.as_str().filter(|r| !r.is_empty()) {
+                println!("    Puertos {proto}: {r}");
+            }
+        }
+        println!(
+            "    Respuestas: {} abiertos, {} cerrados, {} filtrados",
+            sc["responses"]["open"], sc["responses"]["closed"], sc["responses"]["filtered"]
+        );
+        if let Some(reasons) = sc["reasons"].as_array() {
+            let text: Vec<&str> = reasons.iter().filter_map(Value::as_str).collect();
+            println!("    Razones: {}", text.join("; "));
+        }
+    }
+}
+
+fn print_human_synflood(report: &Value, top_n: usize) {
+    let s = &report["detectors"]["synflood"];
+    let attacks = s["attacks"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let findings = s["findings"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if attacks.is_empty() && findings.is_empty() {
+        return;
+    }
+
+    println!("\n--- Reporte de SYN Floods y Conexiones Half-Open ---");
+    println!(
+        "Servicios: {} | ataques: {} | SYN sin seguir por límite de memoria: {}",
+        s["services_total"], s["attacks_total"], s["pending_truncated"]
+    );
+    if !attacks.is_empty() {
+        println!("\nTop {top_n} ventanas de ataque:");
+    }
+    for a in attacks.iter().take(top_n) {
+        let level = a["score"]["level"].as_str().unwrap_or("BAJA");
+        let score = a["score"]["value"].as_u64().unwrap_or(0);
+        println!(
+            "  - [{level} | score={score}] {}: {} SYN en {} s (pico {} SYN/s, media {:.1} SYN/s)",
+            a["service"].as_str().unwrap_or(""),
+            a["syn"],
+            a["duration_s"],
+            a["peak_syn_rate"],
+            a["avg_syn_rate"].as_f64().unwrap_or(0.0)
+        );
+        println!(
+            "    Ventana: {} → {} µs | SYN-ACK {:.1}% | completados {:.1}% | pico half-open {}",
+            a["start_ts_us"],
+            a["end_ts_us"],
+            a["synack_ratio"].as_f64().unwrap_or(0.0) * 100.0,
+            a["completion_ratio"].as_f64().unwrap_or(0.0) * 100.0,
+            a["peak_half_open"]
+        );
+        let sp = &a["spoofing"];
+        println!(
+            "    Falsificación: {} ({} orígenes, diversidad {:.2}, TTL {}-{})",
+            sp["verdict"].as_str().unwrap_or(""),
+            sp["distinct_sources"],
+            sp["source_diversity"].as_f64().unwrap_or(0.0),
+            sp["ttl_min"],
+            sp["ttl_max"]
+        );
+    }
+    let buildup: Vec<&Value> = findings
+        .iter()
+        .filter(|f| f["code"] == "HALF_OPEN_BUILDUP")
+        .collect();
+    if !buildup.is_empty() {
+        println!("\nAcumulación de half-open sin ataque:");
+    }
+    for f in buildup.into_iter().take(top_n) {
+        println!(
+            "  - {}: {}",
+            f["subject"].as_str().unwrap_or(""),
+            f["reason"].as_str().unwrap_or("")
+        );
+    }
+}
+
+fn print_human_beacon(report: &Value, top_n: usize) {
+    let b = &report["detectors"]["beacon"];
+    let Some(candidates) = b["candidates"].as_array().filter(|a| !a.is_empty()) else {
+        return;
+    };
+
+    println!("\n--- Reporte de Beaconing / C2 ---");
+    println!(
+        "Tuplas analizadas: {} | candidatos: {}",
+        b["tuples_total"], b["candidates_total"]
+    );
+    println!("\nTop {top_n} candidatos por periodicidad:");
+    for c in candidates.iter().take(top_n) {
+        let level = c["score"]["level"].as_str().unwrap_or("BAJA");
+        let port = c["port"].as_u64().unwrap_or(0) as u16;
+        let dst = c["dst"]
+            .as_str()
+            .and_then(|d| d.parse().ok())
+            .map(|ip| endpoint_label(ip, port))
+            .unwrap_or_default();
+        println!(
+            "  - [{level} | beacon={:.2}] {} → {dst}/{}: {} inicios en {:.0} s",
+            c["beacon_score"].as_f64().unwrap_or(0.0),
+            c["src"].as_str().unwrap_or(""),
+            c["proto"].as_str().unwrap_or(""),
+            c["events"],
+            c["duration_s"].as_f64().unwrap_or(0.0)
+        );
+        println!(
+            "    Intervalo: mediana {:.1} s, MAD {:.2} s (jitter {:.1}%), rango {:.1}-{:.1} s | bytes por sesión: mediana {:.0}, MAD {:.0}",
+            c["interval_s"]["median"].as_f64().unwrap_or(0.0),
+            c["interval_s"]["mad"].as_f64().unwrap_or(0.0),
+            c["interval_s"]["jitter_ratio"].as_f64().unwrap_or(0.0) * 100.0,
+            c["interval_s"]["min"].as_f64().unwrap_or(0.0),
+            c["interval_s"]["max"].as_f64().unwrap_or(0.0),
+            c["bytes"]["median"].as_f64().unwrap_or(0.0),
+            c["bytes"]["mad"].as_f64().unwrap_or(0.0)
+        );
+    }
+}
+
+fn print_human_talkers(report: &Value, top_n: usize) {
+    let t = &report["detectors"]["talkers"];
+    if t["packets_total"].as_u64().unwrap_or(0) == 0 {
+        return;
+    }
+    let counter = |c: &Value| {
+        format!(
+            "{} paquetes / {} bytes",
+            c["packets"].as_u64().unwrap_or(0),
+            c["bytes"].as_u64().unwrap_or(0)
+        )
+    };
+
+    println!("\n--- Reporte de Top Talkers y Conversaciones ---");
+    println!(
+        "Total: {} paquetes / {} bytes | hosts: {} | pares: {} | servicios: {} | no IP: {}",
+        t["packets_total"],
+        t["bytes_total"],
+        t["hosts_total"],
+        t["pairs_total"],
+        t["services_total"],
+        counter(&t["non_ip"])
+    );
+
+    println!("\nTop {top_n} hosts por bytes:");
+    for h in t["top_hosts_by_bytes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .take(top_n)
+    {
+        println!(
+            "  - {}: {} ({:.1}%) | enviados {} | recibidos {} | {} pares",
+            h["host"].as_str().unwrap_or(""),
+            counter(h),
+            h["bytes_share_pct"].as_f64().unwrap_or(0.0),
+            counter(&h["sent"]),
+            counter(&h["received"]),
+            h["peers"]
+        );
+    }
+
+    println!("\nTop {top_n} conversaciones por bytes:");
+    for p in t["top_pairs_by_bytes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .take(top_n)
+    {
+        let protocols: Vec<&str> = p["protocols"]
+            .as_object()
+            .map(|m| m.keys().map(String::as_str).collect())
+            .unwrap_or_default();
+        println!(
+            "  - {} <-> {}: {} ({:.1}%) [{}]",
+            p["a"].as_str().unwrap_or(""),
+            p["b"].as_str().unwrap_or(""),
+            counter(p),
+            p["bytes_share_pct"].as_f64().unwrap_or(0.0),
+            protocols.join(", ")
+        );
+        println!(
+            "    → {} | ← {}",
+            counter(&p["a_to_b"]),
+            counter(&p["b_to_a"])
+        );
+    }
+
+    println!("\nTop {top_n} servicios por bytes:");
+    for s in t["top_services_by_bytes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .take(top_n)
+    {
+        println!(
+            "  - {}: {} ({:.1}%)",
+            s["service"].as_str().unwrap_or(""),
+            counter(s),
+            s["bytes_share_pct"].as_f64().unwrap_or(0.0)
+        );
+    }
+}
+
+fn print_human_hierarchy(report: &Value) {
+    let h = &report["detectors"]["hierarchy"];
+    if h["frames_total"].as_u64().unwrap_or(0) == 0 {
+        return;
+    }
+
+    fn print_node(node: &Value, depth: usize) {
+        println!(
+            "{:indent$}{:<width$} {:>6.2}% {:>10} paq. {:>6.2}% {:>12} bytes",
+            "",
+            node["name"].as_str().unwrap_or(""),
+            node["packets_pct"].as_f64().unwrap_or(0.0),
+            node["packets"],
+            node["bytes_pct"].as_f64().unwrap_or(0.0),
+            node["bytes"],
+            indent = depth * 2,
+            width = 28usize.saturating_sub(depth * 2)
+        );
+        for c in node["children"].as_array().into_iter().flatten() {
+            print_node(c, depth + 1);
+        }
+    }
+
+    println!("\n--- Jerarquía de Protocolos ---");
+    print_node(&h["tree"], 0);
+    let undecoded = &h["undecoded"];
+    if undecoded["frames"].as_u64().unwrap_or(0) > 0 {
+        let layers: Vec<String> = undecoded["by_layer"]
+            .as_object()
+            .map(|m| m.iter().map(|(k, n)| format!("{k}={n}")).collect())
+            .unwrap_or_default();
+        println!(
+            "Tramas no decodificables: {} ({:.2}%) [{}]",
+            undecoded["frames"],
+            undecoded["frames_pct"].as_f64().unwrap_or(0.0),
+            layers.join(", ")
+        );
+    }
+}
+
+fn print_human_capture_quality(report: &Value) {
+    let q = &report["capture_quality"];
+    let Some(warnings) = q["warnings"].as_array().filter(|w| !w.is_empty()) else {
+        return;
+    };
+
+    println!("\n--- Calidad de la Captura ---");
+    println!(
+        "Formato: {} | snaplen: {}",
+        q["format"].as_str().unwrap_or("desconocido"),
+        q["snaplen"]
+    );
+    for iface in q["interfaces"].as_array().into_iter().flatten() {
+        let stats = &iface["stats"];
+        if stats.is_null() {
+            continue;
+        }
+        println!(
+            "Interfaz {}: recibidos {} | descartados por la interfaz {} | por el SO {}",
+            iface["name"].as_str().unwrap_or("?"),
+            stats["received"],
+            stats["dropped_interface"],
+            stats["dropped_os"]
+        );
+    }
+    for w in warnings.iter().filter_map(Value::as_str) {
+        println!("  ⚠️  {w}");
+    }
+    if q["tcp_health_reliable"] == false {
+        println!("  → Interpreta el reporte de salud TCP con cautela.");
+    }
+}
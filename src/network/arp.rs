@@ -0,0 +1,118 @@
+//! ARP sobre Ethernet/IPv4 (RFC 826) y las variantes gratuita y de sondeo (RFC 5227).
+
+use std::net::Ipv4Addr;
+
+pub const OP_REQUEST: u16 = 1;
+pub const OP_REPLY: u16 = 2;
+
+pub const BROADCAST_MAC: [u8; 6] = [0xff; 6];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpPacket {
+    pub op: u16,
+    pub sender_mac: [u8; 6],
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: [u8; 6],
+    pub target_ip: Ipv4Addr,
+}
+
+impl ArpPacket {
+    /// Anuncio no solicitado: el emisor "pregunta" o "responde" por su propia IP
+    pub fn is_gratuitous(&self) -> bool {
+        !self.sender_ip.is_unspecified() && self.sender_ip == self.target_ip
+    }
+
+    /// Sondeo de dirección duplicada: petición con IP de origen 0.0.0.0
+    pub fn is_probe(&self) -> bool {
+        self.op == OP_REQUEST && self.sender_ip.is_unspecified()
+    }
+}
+
+/// Solo ARP Ethernet (htype 1) / IPv4 (ptype 0x0800)
+pub fn parse(payload: &[u8]) -> Option<ArpPacket> {
+    if payload.len() < 28 {
+        return None;
+    }
+    let htype = u16::from_be_bytes([payload[0], payload[1]]);
+    let ptype = u16::from_be_bytes([payload[2], payload[3]]);
+    if htype != 1 || ptype != 0x0800 || payload[4] != 6 || payload[5] != 4 {
+        return None;
+    }
+    let mac = |at: usize| -> [u8; 6] { payload[at..at + 6].try_into().unwrap_or_default() };
+    let ip = |at: usize| {
+        Ipv4Addr::new(
+            payload[at],
+            payload[at + 1],
+            payload[at + 2],
+            payload[at + 3],
+        )
+    };
+    Some(ArpPacket {
+        op: u16::from_be_bytes([payload[6], payload[7]]),
+        sender_mac: mac(8),
+        sender_ip: ip(14),
+        target_mac: mac(18),
+        target_ip: ip(24),
+    })
+}
+
+pub fn format_mac(mac: &[u8; 6]) -> String {
+    mac.iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Cuerpo ARP Ethernet/IPv4
+    pub(crate) fn arp_body(
+        op: u16,
+        sender_mac: [u8; 6],
+        sender_ip: [u8; 4],
+        target_mac: [u8; 6],
+        target_ip: [u8; 4],
+    ) -> Vec<u8> {
+        let mut b = vec![0, 1, 8, 0, 6, 4];
+        b.extend(op.to_be_bytes());
+        b.extend(sender_mac);
+        b.extend(sender_ip);
+        b.extend(target_mac);
+        b.extend(target_ip);
+        b
+    }
+
+    #[test]
+    fn parses_request_probe_and_gratuitous() {
+        let req = parse(&arp_body(
+            OP_REQUEST,
+            [1; 6],
+            [10, 0, 0, 1],
+            [0; 6],
+            [10, 0, 0, 2],
+        ))
+        .unwrap();
+        assert_eq!(req.sender_ip, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(format_mac(&req.sender_mac), "01:01:01:01:01:01");
+        assert!(!req.is_gratuitous() && !req.is_probe());
+
+        let probe = parse(&arp_body(OP_REQUEST, [1; 6], [0; 4], [0; 6], [10, 0, 0, 9])).unwrap();
+        assert!(probe.is_probe() && !probe.is_gratuitous());
+
+        let garp = parse(&arp_body(
+            OP_REPLY,
+            [1; 6],
+            [10, 0, 0, 1],
+            BROADCAST_MAC,
+            [10, 0, 0, 1],
+        ));
+        assert!(garp.unwrap().is_gratuitous());
+
+        // Otro tipo de hardware
+        let mut other = arp_body(OP_REQUEST, [1; 6], [10, 0, 0, 1], [0; 6], [10, 0, 0, 2]);
+        other[1] = 6;
+        assert!(parse(&other).is_none());
+    }
+}
@@ -0,0 +1,639 @@
+//! Calidad de la captura: tramas truncadas por snaplen, timestamps fuera de orden o con
+//! saltos, datos TCP confirmados que nunca se capturaron y estadísticas de descarte de
+//! las interfaces (bloques ISB de pcapng).
+
+use crate::detectors::tcp_health::seq_gt;
+use etherparse::{LaxNetSlice, LaxSlicedPacket, TransportSlice};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::net::IpAddr;
+use std::path::Path;
+
+// Un avance mayor entre tramas consecutivas se reporta como salto de reloj
+const TIME_JUMP_US: u64 = 300_000_000;
+const MAX_TIME_EVENTS: usize = 20;
+const MAX_TCP_STREAMS: usize = 200_000;
+// Diferencias mayores entre ACK y último byte visto son basura, no un hueco
+const MAX_ACK_GAP: u32 = 1 << 30;
+
+const PCAPNG_SHB: u32 = 0x0a0d_0d0a;
+const PCAPNG_IDB: u32 = 1;
+const PCAPNG_ISB: u32 = 5;
+const PCAPNG_BYTE_ORDER: u32 = 0x1a2b_3c4d;
+// Un IDB/ISB legítimo son unos pocos cientos de bytes de opciones
+const PCAPNG_MAX_READ_BLOCK: u64 = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct StreamKey {
+    src: IpAddr,
+    sport: u16,
+    dst: IpAddr,
+    dport: u16,
+}
+
+impl StreamKey {
+    fn reverse(&self) -> Self {
+        StreamKey {
+            src: self.dst,
+            sport: self.dport,
+            dst: self.src,
+            dport: self.sport,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeEventKind {
+    Backwards,
+    Jump,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TimeEvent {
+    frame: u64,
+    ts_us: u64,
+    delta_us: u64,
+    kind: TimeEventKind,
+}
+
+/// Métricas de calidad trama a trama; el `Engine` las alimenta antes de la deduplicación
+#[derive(Debug, Default)]
+pub struct CaptureQuality {
+    frames: u64,
+    truncated_frames: u64,
+    truncated_bytes: u64,
+    max_truncated_caplen: u32,
+    last_ts_us: Option<u64>,
+    backwards: u64,
+    max_backward_us: u64,
+    jumps: u64,
+    max_gap_us: u64,
+    time_events: Vec<TimeEvent>,
+    // Último byte (seq + len) visto por sentido TCP
+    highest_seq: HashMap<StreamKey, u32>,
+    tcp_streams_truncated: u64,
+    ack_gap_events: u64,
+    ack_gap_bytes: u64,
+    ack_gap_streams: HashMap<StreamKey, u64>,
+}
+
+impl CaptureQuality {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, data: &[u8], caplen: u32, len: u32, ts_us: u64) {
+        self.frames += 1;
+        if caplen < len {
+            self.truncated_frames += 1;
+            self.truncated_bytes += (len - caplen) as u64;
+            self.max_truncated_caplen = self.max_truncated_caplen.max(caplen);
+        }
+        self.observe_ts(ts_us);
+        self.observe_tcp(data);
+    }
+
+    fn observe_ts(&mut self, ts_us: u64) {
+        let Some(last) = self.last_ts_us else {
+            self.last_ts_us = Some(ts_us);
+            return;
+        };
+        let event = if ts_us < last {
+            self.backwards += 1;
+            self.max_backward_us = self.max_backward_us.max(last - ts_us);
+            Some((TimeEventKind::Backwards, last - ts_us))
+        } else {
+            let gap = ts_us - last;
+            self.max_gap_us = self.max_gap_us.max(gap);
+            (gap >= TIME_JUMP_US).then(|| {
+                self.jumps += 1;
+                (TimeEventKind::Jump, gap)
+            })
+        };
+        if let Some((kind, delta_us)) = event {
+            if self.time_events.len() < MAX_TIME_EVENTS {
+                self.time_events.push(TimeEvent {
+                    frame: self.frames,
+                    ts_us,
+                    delta_us,
+                    kind,
+                });
+            }
+        }
+        // Tras un salto atrás se sigue comparando con el reloj nuevo
+        self.last_ts_us = Some(ts_us);
+    }
+
+    /// Un ACK por encima del último byte visto del otro sentido confirma datos que la
+    /// red entregó pero la captura perdió
+    fn observe_tcp(&mut self, data: &[u8]) {
+        let Ok(sliced) = LaxSlicedPacket::from_ethernet(data) else {
+            return;
+        };
+        let (src, dst) = match &sliced.net {
+            Some(LaxNetSlice::Ipv4(ip)) => (
+                IpAddr::V4(ip.header().source_addr()),
+                IpAddr::V4(ip.header().destination_addr()),
+            ),
+            Some(LaxNetSlice::Ipv6(ip)) => (
+                IpAddr::V6(ip.header().source_addr()),
+                IpAddr::V6(ip.header().destination_addr()),
+            ),
+            None => return,
+        };
+        let Some(TransportSlice::Tcp(tcp)) = &sliced.transport else {
+            return;
+        };
+        if tcp.rst() {
+            return;
+        }
+        let key = StreamKey {
+            src,
+            sport: tcp.source_port(),
+            dst,
+            dport: tcp.destination_port(),
+        };
+
+        // La longitud real del segmento sale de la cabecera IP, no del payload capturado
+        let ip_payload_len = match &sliced.net {
+            Some(LaxNetSlice::Ipv4(ip)) => {
+                (ip.header().total_len() as usize).saturating_sub(ip.header().slice().len())
+            }
+            Some(LaxNetSlice::Ipv6(ip)) => (ip.header().payload_length() as usize)
+                .saturating_sub(ip.extensions().slice().len()),
+            None => 0,
+        };
+        // Con TSO la longitud IP puede venir a 0: se usa lo capturado si es mayor
+        let data_len = ip_payload_len
+            .saturating_sub(tcp.header_len())
+            .max(tcp.payload().len());
+        let seg_len = data_len as u32 + tcp.syn() as u32 + tcp.fin() as u32;
+        let end = tcp.sequence_number().wrapping_add(seg_len);
+        let streams = self.highest_seq.len();
+        match self.highest_seq.get_mut(&key) {
+            Some(h) if seq_gt(end, *h) => *h = end,
+            Some(_) => {}
+            None if streams < MAX_TCP_STREAMS => {
+                self.highest_seq.insert(key, end);
+            }
+            None => self.tcp_streams_truncated += 1,
+        }
+
+        if !tcp.ack() {
+            return;
+        }
+        let reverse = key.reverse();
+        let ack = tcp.acknowledgment_number();
+        if let Some(h) = self.highest_seq.get_mut(&reverse) {
+            let gap = ack.wrapping_sub(*h);
+            if seq_gt(ack, *h) && gap < MAX_ACK_GAP {
+                self.ack_gap_events += 1;
+                self.ack_gap_bytes += gap as u64;
+                *self.ack_gap_streams.entry(reverse).or_default() += gap as u64;
+                // El hueco ya contado no se vuelve a contar con el siguiente ACK
+                *h = ack;
+            }
+        }
+    }
+
+    /// Sección `capture_quality` del reporte, con avisos sobre la fiabilidad de las
+    /// métricas TCP
+    pub fn to_json(&self, file: &FileInfo) -> Value {
+        let interface_drops: u64 = file
+            .interfaces
+            .iter()
+            .filter_map(|i| i.stats.as_ref())
+            .map(|s| s.if_drop.unwrap_or(0) + s.os_drop.unwrap_or(0))
+            .sum();
+
+        let mut warnings = Vec::new();
+        if self.truncated_frames > 0 {
+            warnings.push(format!(
+                "{} tramas truncadas por snaplen ({} bytes sin capturar): payloads y reensamblado incompletos",
+                self.truncated_frames, self.truncated_bytes
+            ));
+        }
+        if self.backwards > 0 {
+            warnings.push(format!(
+                "{} timestamps van hacia atrás (hasta {} µs): RTT, jitter y orden de llegada poco fiables",
+                self.backwards, self.max_backward_us
+            ));
+        }
+        if self.jumps > 0 {
+            warnings.push(format!(
+                "{} saltos de reloj de más de {} s: duración y tasas pueden estar distorsionadas",
+                self.jumps,
+                TIME_JUMP_US / 1_000_000
+            ));
+        }
+        if interface_drops > 0 {
+            warnings.push(format!(
+                "la interfaz de captura descartó {interface_drops} paquetes"
+            ));
+        }
+        if file.truncated {
+            warnings.push(
+                "el fichero está truncado o tiene un bloque corrupto: interfaces y estadísticas de descarte parciales"
+                    .to_string(),
+            );
+        }
+        if self.ack_gap_events > 0 {
+            warnings.push(format!(
+                "{} ACKs confirman {} bytes que nunca se capturaron en {} flujos: pérdida en la captura, no en la red",
+                self.ack_gap_events,
+                self.ack_gap_bytes,
+                self.ack_gap_streams.len()
+            ));
+        }
+        let tcp_health_reliable =
+            self.ack_gap_events == 0 && interface_drops == 0 && self.backwards == 0;
+        if !tcp_health_reliable {
+            warnings.push(
+                "las retransmisiones, fuera de orden, dup-ACKs y RTT de tcp_health pueden ser artefactos de la captura"
+                    .to_string(),
+            );
+        }
+
+        json!({
+            "format": file.format,
+            "file_truncated": file.truncated,
+            "snaplen": file.snaplen,
+            "interfaces": file.interfaces.iter().map(InterfaceInfo::to_json).collect::<Vec<_>>(),
+            "interface_drops": interface_drops,
+            "truncated": {
+                "frames": self.truncated_frames,
+                "bytes_missing": self.truncated_bytes,
+                "max_caplen": self.max_truncated_caplen
+            },
+            "timestamps": {
+                "backwards": self.backwards,
+                "max_backward_us": self.max_backward_us,
+                "jumps": self.jumps,
+                "max_gap_us": self.max_gap_us,
+                "events": self.time_events.iter().map(|e| json!({
+                    "frame": e.frame,
+                    "ts_us": e.ts_us,
+                    "delta_us": e.delta_us,
+                    "kind": match e.kind {
+                        TimeEventKind::Backwards => "backwards",
+                        TimeEventKind::Jump => "jump",
+                    }
+                })).collect::<Vec<_>>()
+            },
+            "tcp_unseen_data": {
+                "acks": self.ack_gap_events,
+                "bytes": self.ack_gap_bytes,
+                "streams": self.ack_gap_streams.len(),
+                "streams_truncated": self.tcp_streams_truncated
+            },
+            "tcp_health_reliable": tcp_health_reliable,
+            "warnings": warnings
+        })
+    }
+}
+
+/// Contadores de un bloque ISB; cada opción puede faltar
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceStats {
+    pub received: Option<u64>,
+    pub if_drop: Option<u64>,
+    pub filter_accept: Option<u64>,
+    pub os_drop: Option<u64>,
+    pub delivered: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceInfo {
+    pub linktype: u16,
+    pub snaplen: u32,
+    pub name: Option<String>,
+    /// Último ISB de la interfaz (los contadores son acumulados)
+    pub stats: Option<InterfaceStats>,
+}
+
+impl InterfaceInfo {
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "linktype": self.linktype,
+            "snaplen": self.snaplen,
+            "stats": self.stats.as_ref().map(|s| json!({
+                "received": s.received,
+                "dropped_interface": s.if_drop,
+                "dropped_os": s.os_drop,
+                "filter_accept": s.filter_accept,
+                "delivered": s.delivered
+            }))
+        })
+    }
+}
+
+/// Cabeceras del fichero: formato, snaplen (pcap clásico) e interfaces (pcapng)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileInfo {
+    pub format: &'static str,
+    pub snaplen: Option<u32>,
+    pub interfaces: Vec<InterfaceInfo>,
+    /// pcapng cortado o con un bloque inválido: lo anterior al fallo sí se conserva
+    pub truncated: bool,
+}
+
+fn u16_at(b: &[u8], at: usize, be: bool) -> Option<u16> {
+    let v: [u8; 2] = b.get(at..at + 2)?.try_into().ok()?;
+    Some(if be {
+        u16::from_be_bytes(v)
+    } else {
+        u16::from_le_bytes(v)
+    })
+}
+
+fn u32_at(b: &[u8], at: usize, be: bool) -> Option<u32> {
+    let v: [u8; 4] = b.get(at..at + 4)?.try_into().ok()?;
+    Some(if be {
+        u32::from_be_bytes(v)
+    } else {
+        u32::from_le_bytes(v)
+    })
+}
+
+fn u64_at(b: &[u8], at: usize, be: bool) -> Option<u64> {
+    let v: [u8; 8] = b.get(at..at + 8)?.try_into().ok()?;
+    Some(if be {
+        u64::from_be_bytes(v)
+    } else {
+        u64::from_le_bytes(v)
+    })
+}
+
+/// Opciones pcapng (código, valor) hasta `opt_endofopt`
+fn options(mut b: &[u8], be: bool) -> Vec<(u16, &[u8])> {
+    let mut out = Vec::new();
+    while let (Some(code), Some(len)) = (u16_at(b, 0, be), u16_at(b, 2, be)) {
+        if code == 0 {
+            break;
+        }
+        let len = len as usize;
+        let Some(value) = b.get(4..4 + len) else {
+            break;
+        };
+        out.push((code, value));
+        b = b.get(4 + len.div_ceil(4) * 4..).unwrap_or_default();
+    }
+    out
+}
+
+/// Lee las cabeceras recorriendo los bloques sin cargar los paquetes en memoria;
+/// formato `desconocido` si no es pcap ni pcapng
+pub fn file_info(path: &Path) -> std::io::Result<FileInfo> {
+    let mut r = BufReader::new(File::open(path)?);
+    let mut head = [0u8; 24];
+    let n = r.read(&mut head)?;
+    let magic = u32::from_le_bytes(head[..4].try_into().unwrap_or_default());
+    match magic {
+        0xa1b2_c3d4 | 0xa1b2_3c4d | 0xd4c3_b2a1 | 0x4d3c_b2a1 if n == 24 => {
+            let be = matches!(magic, 0xd4c3_b2a1 | 0x4d3c_b2a1);
+            Ok(FileInfo {
+                format: "pcap",
+                snaplen: u32_at(&head, 16, be),
+                ..FileInfo::default()
+            })
+        }
+        PCAPNG_SHB => Ok(read_pcapng(&mut r)),
+        _ => Ok(FileInfo {
+            format: "desconocido",
+            ..FileInfo::default()
+        }),
+    }
+}
+
+fn read_pcapng<R: Read + Seek>(r: &mut R) -> FileInfo {
+    let mut info = FileInfo {
+        format: "pcapng",
+        ..FileInfo::default()
+    };
+    // Un fichero cortado es justo la captura que hay que diagnosticar: se devuelve lo leído
+    if walk_pcapng(r, &mut info).is_err() {
+        info.truncated = true;
+    }
+    info
+}
+
+fn invalid_block() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, "bloque pcapng inválido")
+}
+
+fn walk_pcapng<R: Read + Seek>(r: &mut R, info: &mut FileInfo) -> std::io::Result<()> {
+    let file_len = r.seek(SeekFrom::End(0))?;
+    r.seek(SeekFrom::Start(0))?;
+    // Cada sección (SHB) reinicia la numeración de interfaces y puede cambiar el orden de bytes
+    let mut be = false;
+    let mut section_base = 0;
+    let mut head = [0u8; 8];
+    loop {
+        let pos = r.stream_position()?;
+        if pos == file_len {
+            return Ok(());
+        }
+        r.read_exact(&mut head)?;
+        // El tipo del SHB es capicúa: se reconoce antes de conocer el orden de bytes
+        if u32::from_le_bytes(head[..4].try_into().unwrap_or_default()) == PCAPNG_SHB {
+            let mut bom = [0u8; 4];
+            r.read_exact(&mut bom)?;
+            be = u32::from_be_bytes(bom) == PCAPNG_BYTE_ORDER;
+            section_base = info.interfaces.len();
+        }
+        let block_type = u32_at(&head, 0, be).unwrap_or(0);
+        let len = u32_at(&head, 4, be).unwrap_or(0) as u64;
+        // La longitud viene del fichero: se valida antes de saltar o reservar memoria
+        if len < 12 || !len.is_multiple_of(4) || len > file_len - pos {
+            return Err(invalid_block());
+        }
+        if block_type != PCAPNG_IDB && block_type != PCAPNG_ISB {
+            r.seek(SeekFrom::Start(pos + len))?;
+            continue;
+        }
+        if len > PCAPNG_MAX_READ_BLOCK {
+            return Err(invalid_block());
+        }
+        let mut body = vec![0u8; len as usize - 8];
+        r.read_exact(&mut body)?;
+        let body = &body[..body.len() - 4];
+        if block_type == PCAPNG_IDB {
+            let name = options(body.get(8..).unwrap_or_default(), be)
+                .into_iter()
+                .find(|(code, _)| *code == 2)
+                .map(|(_, v)| {
+                    String::from_utf8_lossy(v)
+                        .trim_end_matches('\0')
+                        .to_string()
+                });
+            info.interfaces.push(InterfaceInfo {
+                linktype: u16_at(body, 0, be).unwrap_or(0),
+                snaplen: u32_at(body, 4, be).unwrap_or(0),
+                name,
+                stats: None,
+            });
+        } else {
+            let id = section_base + u32_at(body, 0, be).unwrap_or(0) as usize;
+            let mut stats = InterfaceStats::default();
+            for (code, v) in options(body.get(12..).unwrap_or_default(), be) {
+                let value = u64_at(v, 0, be);
+                match code {
+                    4 => stats.received = value,
+                    5 => stats.if_drop = value,
+                    6 => stats.filter_accept = value,
+                    7 => stats.os_drop = value,
+                    8 => stats.delivered = value,
+                    _ => {}
+                }
+            }
+            if let Some(iface) = info.interfaces.get_mut(id) {
+                iface.stats = Some(stats);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etherparse::PacketBuilder;
+    use std::io::Cursor;
+
+    const A: [u8; 4] = [10, 0, 0, 1];
+    const B: [u8; 4] = [10, 0, 0, 2];
+
+    fn tcp(
+        src: [u8; 4],
+        dst: [u8; 4],
+        sport: u16,
+        dport: u16,
+        seq: u32,
+        ack: u32,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let mut out = Vec::new();
+        PacketBuilder::ethernet2([1; 6], [2; 6])
+            .ipv4(src, dst, 64)
+            .tcp(sport, dport, seq, 1024)
+            .ack(ack)
+            .write(&mut out, payload)
+            .unwrap();
+        out
+    }
+
+    fn observe(q: &mut CaptureQuality, frame: &[u8], ts: u64) {
+        q.observe(frame, frame.len() as u32, frame.len() as u32, ts);
+    }
+
+    #[test]
+    fn truncation_timestamp_anomalies_and_acked_unseen_data() {
+        let mut q = CaptureQuality::new();
+        // Servidor envía 1000 bytes, la captura pierde los 1000 siguientes, el cliente confirma 2000
+        observe(&mut q, &tcp(B, A, 80, 5000, 1, 1, &[0; 1000]), 1_000_000);
+        observe(&mut q, &tcp(A, B, 5000, 80, 1, 2001, &[]), 1_100_000);
+        // El siguiente ACK igual no cuenta otra vez
+        observe(&mut q, &tcp(A, B, 5000, 80, 1, 2001, &[]), 1_200_000);
+        // Trama truncada a 60 bytes: la longitud TCP sale de la cabecera IP
+        let full = tcp(B, A, 80, 5000, 2001, 1, &[0; 500]);
+        q.observe(&full[..60], 60, full.len() as u32, 1_300_000);
+        observe(&mut q, &tcp(A, B, 5000, 80, 1, 2501, &[]), 1_400_000);
+        // Reloj hacia atrás y salto de 10 minutos
+        observe(&mut q, &tcp(A, B, 5000, 80, 1, 2501, &[]), 900_000);
+        observe(&mut q, &tcp(A, B, 5000, 80, 1, 2501, &[]), 600_900_000);
+
+        let r = q.to_json(&FileInfo::default());
+        assert_eq!(r["truncated"]["frames"], 1);
+        assert_eq!(r["truncated"]["bytes_missing"], full.len() - 60);
+        assert_eq!(r["truncated"]["max_caplen"], 60);
+        assert_eq!(r["tcp_unseen_data"]["acks"], 1);
+        assert_eq!(r["tcp_unseen_data"]["bytes"], 1000);
+        assert_eq!(r["tcp_unseen_data"]["streams"], 1);
+        assert_eq!(r["timestamps"]["backwards"], 1);
+        assert_eq!(r["timestamps"]["max_backward_us"], 500_000);
+        assert_eq!(r["timestamps"]["jumps"], 1);
+        assert_eq!(r["timestamps"]["events"][0]["frame"], 6);
+        assert_eq!(r["tcp_health_reliable"], false);
+        assert_eq!(r["warnings"].as_array().unwrap().len(), 5);
+    }
+
+    /// Bloque pcapng little-endian con relleno a 32 bits
+    fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let mut body = body.to_vec();
+        body.resize(body.len().div_ceil(4) * 4, 0);
+        let len = (body.len() + 12) as u32;
+        let mut out = block_type.to_le_bytes().to_vec();
+        out.extend(len.to_le_bytes());
+        out.extend(body);
+        out.extend(len.to_le_bytes());
+        out
+    }
+
+    fn option(code: u16, value: &[u8]) -> Vec<u8> {
+        let mut out = code.to_le_bytes().to_vec();
+        out.extend((value.len() as u16).to_le_bytes());
+        out.extend(value);
+        out.resize(4 + value.len().div_ceil(4) * 4, 0);
+        out
+    }
+
+    #[test]
+    fn reads_pcapng_interface_statistics_and_pcap_snaplen() {
+        let mut shb = PCAPNG_BYTE_ORDER.to_le_bytes().to_vec();
+        shb.extend([1, 0, 0, 0]);
+        shb.extend(u64::MAX.to_le_bytes());
+        let mut idb = vec![1, 0, 0, 0];
+        idb.extend(262_144u32.to_le_bytes());
+        idb.extend(option(2, b"eth0"));
+        idb.extend([0; 4]);
+        let epb = block(6, &[0; 20]);
+        let mut isb = vec![0; 12];
+        isb.extend(option(4, &1000u64.to_le_bytes()));
+        isb.extend(option(5, &7u64.to_le_bytes()));
+        isb.extend(option(7, &3u64.to_le_bytes()));
+        isb.extend([0; 4]);
+
+        let mut file = block(PCAPNG_SHB, &shb);
+        file.extend(block(PCAPNG_IDB, &idb));
+        file.extend(epb);
+        file.extend(block(PCAPNG_ISB, &isb));
+        let info = read_pcapng(&mut Cursor::new(file.clone()));
+        assert!(!info.truncated);
+        assert_eq!(info.interfaces.len(), 1);
+        let iface = &info.interfaces[0];
+        assert_eq!(iface.name.as_deref(), Some("eth0"));
+        assert_eq!((iface.linktype, iface.snaplen), (1, 262_144));
+        let stats = iface.stats.as_ref().unwrap();
+        assert_eq!(stats.received, Some(1000));
+        assert_eq!(stats.if_drop, Some(7));
+        assert_eq!(stats.os_drop, Some(3));
+        assert_eq!(stats.delivered, None);
+
+        let r = CaptureQuality::new().to_json(&info);
+        assert_eq!(r["interface_drops"], 10);
+        assert_eq!(r["tcp_health_reliable"], false);
+
+        // Cortado dentro del ISB: se conserva la interfaz y se marca el fichero
+        let cut = file.len() - 10;
+        let partial = read_pcapng(&mut Cursor::new(&file[..cut]));
+        assert!(partial.truncated);
+        assert_eq!(partial.interfaces.len(), 1);
+        assert_eq!(partial.interfaces[0].name.as_deref(), Some("eth0"));
+        assert_eq!(partial.interfaces[0].stats, None);
+
+        // Longitud de bloque absurda (4 GiB): se rechaza sin reservar memoria
+        let mut hostile = block(PCAPNG_SHB, &shb);
+        hostile.extend(PCAPNG_IDB.to_le_bytes());
+        hostile.extend(0xffff_fff0u32.to_le_bytes());
+        hostile.extend([0; 16]);
+        let r = read_pcapng(&mut Cursor::new(hostile));
+        assert!(r.truncated);
+        assert!(r.interfaces.is_empty());
+
+        let classic = file_info(Path::new("tests/fixtures/tiny.pcap")).unwrap();
+        assert_eq!(classic.format, "pcap");
+        assert!(classic.snaplen.is_some_and(|s| s > 0));
+    }
+}
@@ -0,0 +1,146 @@
+use std::net::IpAddr;
+
+/// Resultado de verificar un checksum de capa de transporte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    Valid,
+    /// Checksum a 0 (la NIC lo rellena después de la captura)
+    Zero,
+    /// Solo contiene la suma del pseudo-header (CHECKSUM_PARTIAL de Linux)
+    Partial,
+    Invalid,
+}
+
+impl ChecksumStatus {
+    /// Firma típica de checksum offload (captura en el host emisor)
+    pub fn is_offload_signature(self) -> bool {
+        matches!(self, ChecksumStatus::Zero | ChecksumStatus::Partial)
+    }
+}
+
+/// Suma en complemento a uno de `data` (plegada a 16 bits), acumulada sobre `initial`
+pub fn ones_complement_sum(data: &[u8], initial: u32) -> u32 {
+    let mut sum = initial as u64;
+    let mut chunks = data.chunks_exact(2);
+    for c in &mut chunks {
+        sum += u16::from_be_bytes([c[0], c[1]]) as u64;
+    }
+    if let [last] = chunks.remainder() {
+        sum += (*last as u64) << 8;
+    }
+    fold(sum)
+}
+
+/// Pliega los acarreos a 16 bits
+#[inline]
+fn fold(mut sum: u64) -> u32 {
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u32
+}
+
+/// Suma del pseudo-header IPv4/IPv6 usado por TCP/UDP/ICMPv6
+pub fn pseudo_header_sum(src: IpAddr, dst: IpAddr, protocol: u8, length: u32) -> u32 {
+    let mut sum = 0u32;
+    match (src, dst) {
+        (IpAddr::V4(s), IpAddr::V4(d)) => {
+            sum = ones_complement_sum(&s.octets(), sum);
+            sum = ones_complement_sum(&d.octets(), sum);
+        }
+        _ => {
+            sum = ones_complement_sum(&to_v6_octets(src), sum);
+            sum = ones_complement_sum(&to_v6_octets(dst), sum);
+        }
+    }
+    sum = ones_complement_sum(&length.to_be_bytes(), sum);
+    ones_complement_sum(&[0, protocol], sum)
+}
+
+fn to_v6_octets(ip: IpAddr) -> [u8; 16] {
+    match ip {
+        IpAddr::V4(v4) => v4.to_ipv6_mapped().octets(),
+        IpAddr::V6(v6) => v6.octets(),
+    }
+}
+
+/// Verifica la cabecera IPv4 completa (incluye opciones)
+pub fn ipv4_header_valid(header: &[u8]) -> bool {
+    ones_complement_sum(header, 0) == 0xFFFF
+}
+
+/// Verifica el checksum de un segmento de transporte completo (cabecera + payload)
+///
+/// `stored` es el valor tal cual viaja en la cabecera (se usa para detectar las
+/// firmas de offload).
+pub fn transport_status(
+    src: IpAddr,
+    dst: IpAddr,
+    protocol: u8,
+    segment: &[u8],
+    stored: u16,
+) -> ChecksumStatus {
+    let pseudo = pseudo_header_sum(src, dst, protocol, segment.len() as u32);
+    if ones_complement_sum(segment, pseudo) == 0xFFFF {
+        return ChecksumStatus::Valid;
+    }
+    if stored == 0 {
+        return ChecksumStatus::Zero;
+    }
+    if stored as u32 == pseudo || stored as u32 == (!pseudo & 0xFFFF) {
+        return ChecksumStatus::Partial;
+    }
+    ChecksumStatus::Invalid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn ipv4_header_checksum_roundtrip() {
+        // Cabecera de ejemplo (RFC 1071 / wikipedia): checksum 0xB861
+        let h = [
+            0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8,
+            0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7,
+        ];
+        assert!(ipv4_header_valid(&h));
+        let mut bad = h;
+        bad[11] ^= 0x01;
+        assert!(!ipv4_header_valid(&bad));
+    }
+
+    #[test]
+    fn transport_status_detects_offload_signatures() {
+        let src = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let dst = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let builder = etherparse::PacketBuilder::ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .tcp(1000, 80, 1, 1024);
+        let mut pkt = Vec::new();
+        builder.write(&mut pkt, b"hola").unwrap();
+        let mut seg = pkt[20..].to_vec();
+        let stored = u16::from_be_bytes([seg[16], seg[17]]);
+        assert_eq!(
+            transport_status(src, dst, 6, &seg, stored),
+            ChecksumStatus::Valid
+        );
+
+        // Solo pseudo-header (CHECKSUM_PARTIAL)
+        let partial = pseudo_header_sum(src, dst, 6, seg.len() as u32) as u16;
+        seg[16..18].copy_from_slice(&partial.to_be_bytes());
+        assert_eq!(
+            transport_status(src, dst, 6, &seg, partial),
+            ChecksumStatus::Partial
+        );
+
+        seg[16..18].copy_from_slice(&[0, 0]);
+        assert_eq!(transport_status(src, dst, 6, &seg, 0), ChecksumStatus::Zero);
+
+        seg[16..18].copy_from_slice(&[0x12, 0x34]);
+        assert_eq!(
+            transport_status(src, dst, 6, &seg, 0x1234),
+            ChecksumStatus::Invalid
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Utilidades para protocolos de texto claro: opciones Telnet, base64, SASL PLAIN,
+//! argumentos IMAP y cabeceras `Authorization: Basic`.
+
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+// WILL/WONT/DO/DONT llevan un byte de opción detrás
+const WILL: u8 = 251;
+const DONT: u8 = 254;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum TelnetState {
+    #[default]
+    Data,
+    Iac,
+    Option,
+    Sub,
+    SubIac,
+    Cr,
+}
+
+/// Elimina los comandos Telnet (RFC 854) de un sentido del stream; el estado se
+/// conserva entre segmentos para no romper una secuencia IAC partida
+#[derive(Debug, Clone, Default)]
+pub struct TelnetDecoder {
+    state: TelnetState,
+}
+
+impl TelnetDecoder {
+    pub fn feed(&mut self, data: &[u8], out: &mut Vec<u8>) {
+        for &b in data {
+            self.state = match (self.state, b) {
+                (TelnetState::Data | TelnetState::Cr, IAC) => TelnetState::Iac,
+                // CR NUL es un retorno de carro sin salto de línea
+                (TelnetState::Cr, 0) => TelnetState::Data,
+                (TelnetState::Data | TelnetState::Cr, b'\r') => {
+                    out.push(b);
+                    TelnetState::Cr
+                }
+                (TelnetState::Data | TelnetState::Cr, _) => {
+                    out.push(b);
+                    TelnetState::Data
+                }
+                (TelnetState::Iac, IAC) => {
+                    out.push(IAC);
+                    TelnetState::Data
+                }
+                (TelnetState::Iac, SB) => TelnetState::Sub,
+                (TelnetState::Iac, WILL..=DONT) => TelnetState::Option,
+                (TelnetState::Iac | TelnetState::Option, _) => TelnetState::Data,
+                (TelnetState::Sub, IAC) => TelnetState::SubIac,
+                (TelnetState::Sub, _) => TelnetState::Sub,
+                (TelnetState::SubIac, SE) => TelnetState::Data,
+                (TelnetState::SubIac, _) => TelnetState::Sub,
+            };
+        }
+    }
+}
+
+fn base64_value(c: u8) -> Option<u32> {
+    Some(match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    } as u32)
+}
+
+/// Base64 estándar (RFC 4648) con o sin relleno; `None` ante cualquier carácter inválido
+pub fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim().trim_end_matches('=');
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut acc = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            acc |= base64_value(c)? << (18 - 6 * i);
+        }
+        let bytes = acc.to_be_bytes();
+        out.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+/// SASL PLAIN (RFC 4616): `authzid NUL authcid NUL passwd` → (authcid, passwd)
+pub fn sasl_plain(decoded: &[u8]) -> Option<(String, String)> {
+    let mut parts = decoded.split(|b| *b == 0);
+    let (_authzid, user, pass) = (parts.next()?, parts.next()?, parts.next()?);
+    Some((
+        String::from_utf8_lossy(user).into_owned(),
+        String::from_utf8_lossy(pass).into_owned(),
+    ))
+}
+
+/// Valor de `Authorization: Basic ...` → (usuario, contraseña)
+pub fn basic_auth(value: &str) -> Option<(String, String)> {
+    let (scheme, token) = value.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(base64_decode(token)?).ok()?;
+    let (user, pass) = decoded.split_once(':')?;
+    Some((user.to_string(), pass.to_string()))
+}
+
+/// Argumentos de una línea IMAP: átomos y cadenas entre comillas (los literales `{n}` no se siguen)
+pub fn imap_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == ' ' {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::new();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => s.extend(chars.next()),
+                    '"' => break,
+                    _ => s.push(c),
+                }
+            }
+            args.push(s);
+        } else {
+            let mut s = String::new();
+            while let Some(&c) = chars.peek() {
+                if c == ' ' {
+                    break;
+                }
+                s.push(c);
+                chars.next();
+            }
+            args.push(s);
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_telnet_negotiation_across_segments() {
+        let mut d = TelnetDecoder::default();
+        let mut out = Vec::new();
+        d.feed(b"\xff\xfd\x03\xff\xfa\x18\x00xterm", &mut out);
+        d.feed(b"\xff\xf0login: \xff", &mut out);
+        d.feed(b"\xfb\x01ro\xff\xffot\r\0", &mut out);
+        assert_eq!(out, b"login: ro\xffot\r");
+    }
+
+    #[test]
+    fn decodes_base64_sasl_plain_basic_and_imap_args() {
+        assert_eq!(base64_decode("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(base64_decode("aGVsbG8").unwrap(), b"hello");
+        assert!(base64_decode("a$==").is_none());
+        assert_eq!(
+            sasl_plain(&base64_decode("AGFsaWNlAHMzY3JldA==").unwrap()),
+            Some(("alice".to_string(), "s3cret".to_string()))
+        );
+        assert_eq!(
+            basic_auth("Basic YWxhZGRpbjpvcGVuc2VzYW1l"),
+            Some(("aladdin".to_string(), "opensesame".to_string()))
+        );
+        assert!(basic_auth("Bearer abc").is_none());
+        assert_eq!(
+            imap_args(r#"a1 LOGIN "bob smith" "p\"w""#),
+            ["a1", "LOGIN", "bob smith", "p\"w"]
+        );
+    }
+}
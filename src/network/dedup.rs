@@ -0,0 +1,219 @@
+use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+/// Qué hacer con las tramas duplicadas (SPAN, capturas multi-interfaz)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupMode {
+    /// Sin detección
+    Off,
+    /// Se cuentan pero se entregan a los detectores
+    #[default]
+    Report,
+    /// Se cuentan y se descartan antes de los detectores
+    Drop,
+}
+
+impl DedupMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DedupMode::Off => "off",
+            DedupMode::Report => "report",
+            DedupMode::Drop => "drop",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DedupConfig {
+    pub mode: DedupMode,
+    /// Ventana en la que una copia idéntica se considera duplicado, en µs
+    pub window_us: u64,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        DedupConfig {
+            mode: DedupMode::default(),
+            // Las copias de SPAN llegan en µs; una retransmisión real tarda ≥ RTO mínimo (200 ms)
+            window_us: 20_000,
+        }
+    }
+}
+
+/// Detector de tramas duplicadas a nivel IP.
+///
+/// La huella cubre el paquete IP completo (direcciones, IP-ID, TTL, checksum de
+/// cabecera y payload de transporte con su checksum), sin la capa de enlace, que
+/// difiere entre interfaces o VLANs. Una retransmisión TCP legítima lleva otro
+/// IP-ID (o, en IPv6, normalmente otro timestamp TCP), así que no coincide.
+///
+/// Los segmentos TCP sin datos quedan fuera: los ACK duplicados legítimos son
+/// idénticos byte a byte sin timestamps TCP y con IP-ID 0 (o en IPv6), y son
+/// justo la señal de dupACK/fast retransmit que mide `tcp_health`.
+#[derive(Debug)]
+pub struct DuplicateFilter {
+    config: DedupConfig,
+    recent: HashMap<u64, u64>,   // huella -> ts de la primera copia
+    order: VecDeque<(u64, u64)>, // (ts, huella) para expirar en orden
+    inspected: u64,
+    duplicates: u64,
+}
+
+impl DuplicateFilter {
+    pub fn new(config: DedupConfig) -> Self {
+        DuplicateFilter {
+            config,
+            recent: HashMap::new(),
+            order: VecDeque::new(),
+            inspected: 0,
+            duplicates: 0,
+        }
+    }
+
+    pub fn config(&self) -> DedupConfig {
+        self.config
+    }
+
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    pub fn inspected(&self) -> u64 {
+        self.inspected
+    }
+
+    /// Devuelve `true` si la trama es copia de otra vista dentro de la ventana
+    pub fn is_duplicate(&mut self, data: &[u8], ts_us: u64) -> bool {
+        if self.config.mode == DedupMode::Off {
+            return false;
+        }
+        let Some(fp) = fingerprint(data) else {
+            return false;
+        };
+        self.inspected += 1;
+
+        let window = self.config.window_us;
+        while let Some(&(ts, old)) = self.order.front() {
+            if ts_us.saturating_sub(ts) <= window {
+                break;
+            }
+            self.order.pop_front();
+            // Solo se borra si no se ha vuelto a ver después
+            if self.recent.get(&old) == Some(&ts) {
+                self.recent.remove(&old);
+            }
+        }
+
+        let dup = self
+            .recent
+            .get(&fp)
+            .is_some_and(|&last| ts_us.saturating_sub(last) <= window);
+        if dup {
+            self.duplicates += 1;
+        } else {
+            // La huella queda anclada a la primera copia: una ráfaga de copias
+            // no extiende la ventana indefinidamente
+            self.recent.insert(fp, ts_us);
+            self.order.push_back((ts_us, fp));
+        }
+        dup
+    }
+}
+
+/// Huella del paquete IP (None si no es IP o es un segmento TCP sin datos)
+fn fingerprint(data: &[u8]) -> Option<u64> {
+    let sliced = SlicedPacket::from_ethernet(data).ok()?;
+    if let Some(TransportSlice::Tcp(tcp)) = &sliced.transport {
+        if tcp.payload().is_empty() {
+            return None;
+        }
+    }
+    let mut h = DefaultHasher::new();
+    match &sliced.net {
+        Some(InternetSlice::Ipv4(ip)) => {
+            4u8.hash(&mut h);
+            ip.header().slice().hash(&mut h);
+            ip.payload().payload.hash(&mut h);
+        }
+        Some(InternetSlice::Ipv6(ip)) => {
+            6u8.hash(&mut h);
+            ip.header().slice().hash(&mut h);
+            ip.extensions().slice().hash(&mut h);
+            ip.payload().payload.hash(&mut h);
+        }
+        None => return None,
+    }
+    Some(h.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(mac: u8, ip_id: u16, payload: &[u8]) -> Vec<u8> {
+        let mut ip = etherparse::Ipv4Header::new(
+            (20 + payload.len()) as u16,
+            64,
+            etherparse::IpNumber::TCP,
+            [10, 0, 0, 1],
+            [10, 0, 0, 2],
+        )
+        .unwrap();
+        ip.identification = ip_id;
+        let b = etherparse::PacketBuilder::ethernet2([mac; 6], [2; 6])
+            .ip(etherparse::IpHeaders::Ipv4(ip, Default::default()))
+            .tcp(1000, 80, 1, 1024);
+        let mut out = Vec::new();
+        b.write(&mut out, payload).unwrap();
+        out
+    }
+
+    #[test]
+    fn span_copies_are_detected_within_window() {
+        let mut f = DuplicateFilter::new(DedupConfig::default());
+        // Misma trama por otra interfaz (MAC distinta): duplicado
+        assert!(!f.is_duplicate(&frame(1, 7, b"hola"), 0));
+        assert!(f.is_duplicate(&frame(9, 7, b"hola"), 50));
+        // Fuera de la ventana ya no cuenta
+        assert!(!f.is_duplicate(&frame(1, 7, b"hola"), 1_000_000));
+        assert_eq!(f.duplicates(), 1);
+        assert_eq!(f.inspected(), 3);
+    }
+
+    #[test]
+    fn retransmission_with_new_ip_id_is_not_duplicate() {
+        let mut f = DuplicateFilter::new(DedupConfig::default());
+        assert!(!f.is_duplicate(&frame(1, 7, b"hola"), 0));
+        assert!(!f.is_duplicate(&frame(1, 8, b"hola"), 10));
+        let mut off = DuplicateFilter::new(DedupConfig {
+            mode: DedupMode::Off,
+            ..DedupConfig::default()
+        });
+        assert!(!off.is_duplicate(&frame(1, 7, b"hola"), 0));
+        assert!(!off.is_duplicate(&frame(1, 7, b"hola"), 1));
+    }
+
+    #[test]
+    fn identical_ipv6_pure_acks_are_not_duplicates() {
+        // DupACKs sin timestamps TCP: mismos bytes, pero son señal de pérdida real
+        let ack = {
+            let b = etherparse::PacketBuilder::ethernet2([1; 6], [2; 6])
+                .ipv6([0x20; 16], [0x30; 16], 64)
+                .tcp(80, 1000, 5000, 1024)
+                .ack(1);
+            let mut out = Vec::new();
+            b.write(&mut out, &[]).unwrap();
+            out
+        };
+        let mut f = DuplicateFilter::new(DedupConfig {
+            mode: DedupMode::Drop,
+            ..DedupConfig::default()
+        });
+        assert!(!f.is_duplicate(&ack, 0));
+        assert!(!f.is_duplicate(&ack, 5));
+        assert_eq!(f.duplicates(), 0);
+        assert_eq!(f.inspected(), 0);
+    }
+}
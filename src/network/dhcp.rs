@@ -0,0 +1,462 @@
+//! DHCP sobre IPv4 (RFC 2131/2132) y DHCPv6 (RFC 8415): tipo de mensaje,
+//! identificadores de servidor/cliente y las opciones útiles para inventario.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+pub const DHCP_SERVER_PORT: u16 = 67;
+pub const DHCP_CLIENT_PORT: u16 = 68;
+pub const DHCPV6_CLIENT_PORT: u16 = 546;
+pub const DHCPV6_SERVER_PORT: u16 = 547;
+
+// Tipos de mensaje DHCPv4 (opción 53)
+pub const DISCOVER: u8 = 1;
+pub const OFFER: u8 = 2;
+pub const REQUEST: u8 = 3;
+pub const DECLINE: u8 = 4;
+pub const ACK: u8 = 5;
+pub const NAK: u8 = 6;
+pub const RELEASE: u8 = 7;
+pub const INFORM: u8 = 8;
+
+// Tipos de mensaje DHCPv6
+pub const V6_SOLICIT: u8 = 1;
+pub const V6_ADVERTISE: u8 = 2;
+pub const V6_REQUEST: u8 = 3;
+pub const V6_CONFIRM: u8 = 4;
+pub const V6_RENEW: u8 = 5;
+pub const V6_REBIND: u8 = 6;
+pub const V6_REPLY: u8 = 7;
+pub const V6_RELEASE: u8 = 8;
+pub const V6_DECLINE: u8 = 9;
+pub const V6_INFORMATION_REQUEST: u8 = 11;
+pub const V6_RELAY_FORW: u8 = 12;
+pub const V6_RELAY_REPL: u8 = 13;
+
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+const BOOTP_HEADER_LEN: usize = 236;
+
+const OPT_PAD: u8 = 0;
+const OPT_HOSTNAME: u8 = 12;
+const OPT_REQUESTED_IP: u8 = 50;
+const OPT_LEASE_TIME: u8 = 51;
+const OPT_MESSAGE_TYPE: u8 = 53;
+const OPT_SERVER_ID: u8 = 54;
+const OPT_PARAM_REQUEST_LIST: u8 = 55;
+const OPT_VENDOR_CLASS: u8 = 60;
+const OPT_CLIENT_FQDN: u8 = 81;
+const OPT_END: u8 = 255;
+
+const OPT6_CLIENTID: u16 = 1;
+const OPT6_SERVERID: u16 = 2;
+const OPT6_IA_NA: u16 = 3;
+const OPT6_IAADDR: u16 = 5;
+const OPT6_ORO: u16 = 6;
+const OPT6_RELAY_MSG: u16 = 9;
+const OPT6_STATUS_CODE: u16 = 13;
+const OPT6_VENDOR_CLASS: u16 = 16;
+const OPT6_FQDN: u16 = 39;
+// Relays anidados admitidos antes de descartar el mensaje
+const MAX_RELAY_DEPTH: usize = 8;
+
+/// Mensaje DHCPv4 con las opciones relevantes ya interpretadas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpMessage {
+    pub op: u8,
+    pub xid: u32,
+    pub secs: u16,
+    pub ciaddr: Ipv4Addr,
+    pub yiaddr: Ipv4Addr,
+    pub giaddr: Ipv4Addr,
+    /// chaddr, solo si htype es Ethernet
+    pub client_mac: Option<[u8; 6]>,
+    pub message_type: Option<u8>,
+    pub server_id: Option<Ipv4Addr>,
+    pub requested_ip: Option<Ipv4Addr>,
+    pub lease_time_s: Option<u32>,
+    pub hostname: Option<String>,
+    /// Opción 55 en orden: la huella del cliente
+    pub param_request_list: Vec<u8>,
+    pub vendor_class: Option<String>,
+}
+
+/// Mensaje DHCPv6 (ya desencapsulado si llegó a través de relays)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Dhcpv6Message {
+    pub msg_type: u8,
+    pub xid: u32,
+    /// Relays atravesados hasta el mensaje del cliente/servidor
+    pub relay_hops: usize,
+    pub client_duid: Option<Vec<u8>>,
+    pub server_duid: Option<Vec<u8>>,
+    /// Opción ORO (6) en orden: la huella del cliente
+    pub option_request: Vec<u16>,
+    pub addresses: Vec<Ipv6Addr>,
+    /// Primer código de estado distinto de Success (en el mensaje o dentro de IA_NA)
+    pub status_code: Option<u16>,
+    pub fqdn: Option<String>,
+    pub vendor_class: Option<String>,
+}
+
+pub fn message_type_name(t: u8) -> &'static str {
+    match t {
+        DISCOVER => "DISCOVER",
+        OFFER => "OFFER",
+        REQUEST => "REQUEST",
+        DECLINE => "DECLINE",
+        ACK => "ACK",
+        NAK => "NAK",
+        RELEASE => "RELEASE",
+        INFORM => "INFORM",
+        _ => "OTHER",
+    }
+}
+
+pub fn v6_message_type_name(t: u8) -> &'static str {
+    match t {
+        V6_SOLICIT => "SOLICIT",
+        V6_ADVERTISE => "ADVERTISE",
+        V6_REQUEST => "REQUEST",
+        V6_CONFIRM => "CONFIRM",
+        V6_RENEW => "RENEW",
+        V6_REBIND => "REBIND",
+        V6_REPLY => "REPLY",
+        V6_RELEASE => "RELEASE",
+        V6_DECLINE => "DECLINE",
+        10 => "RECONFIGURE",
+        V6_INFORMATION_REQUEST => "INFORMATION-REQUEST",
+        _ => "OTHER",
+    }
+}
+
+/// Nombre de los códigos de estado DHCPv6 que implican rechazo
+pub fn v6_status_name(code: u16) -> &'static str {
+    match code {
+        0 => "Success",
+        1 => "UnspecFail",
+        2 => "NoAddrsAvail",
+        3 => "NoBinding",
+        4 => "NotOnLink",
+        5 => "UseMulticast",
+        6 => "NoPrefixAvail",
+        _ => "Other",
+    }
+}
+
+fn ipv4(b: &[u8]) -> Option<Ipv4Addr> {
+    let o: [u8; 4] = b.get(..4)?.try_into().ok()?;
+    Some(Ipv4Addr::from(o))
+}
+
+/// Texto imprimible de una opción (nombres de host, vendor class)
+fn text(b: &[u8]) -> Option<String> {
+    let s: String = String::from_utf8_lossy(b)
+        .trim_end_matches('\0')
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    (!s.is_empty()).then_some(s)
+}
+
+/// Nombre DNS en formato de etiquetas (FQDN de DHCPv6 y codificación canónica de la opción 81)
+fn dns_labels(mut b: &[u8]) -> Option<String> {
+    let mut labels = Vec::new();
+    while let Some((&len, rest)) = b.split_first() {
+        let len = len as usize;
+        if len == 0 || len > rest.len() {
+            break;
+        }
+        labels.push(String::from_utf8_lossy(&rest[..len]).into_owned());
+        b = &rest[len..];
+    }
+    (!labels.is_empty()).then(|| labels.join("."))
+}
+
+/// Interpreta un payload UDP de los puertos 67/68
+pub fn parse(payload: &[u8]) -> Option<DhcpMessage> {
+    if payload.len() < BOOTP_HEADER_LEN + MAGIC_COOKIE.len()
+        || payload[BOOTP_HEADER_LEN..BOOTP_HEADER_LEN + 4] != MAGIC_COOKIE
+    {
+        return None;
+    }
+    let (htype, hlen) = (payload[1], payload[2]);
+    let mut m = DhcpMessage {
+        op: payload[0],
+        xid: u32::from_be_bytes(payload[4..8].try_into().ok()?),
+        secs: u16::from_be_bytes([payload[8], payload[9]]),
+        ciaddr: ipv4(&payload[12..])?,
+        yiaddr: ipv4(&payload[16..])?,
+        giaddr: ipv4(&payload[24..])?,
+        client_mac: (htype == 1 && hlen == 6)
+            .then(|| payload[28..34].try_into().ok())
+            .flatten(),
+        message_type: None,
+        server_id: None,
+        requested_ip: None,
+        lease_time_s: None,
+        hostname: None,
+        param_request_list: Vec::new(),
+        vendor_class: None,
+    };
+
+    let mut opts = &payload[BOOTP_HEADER_LEN + 4..];
+    while let Some((&code, rest)) = opts.split_first() {
+        match code {
+            OPT_PAD => {
+                opts = rest;
+                continue;
+            }
+            OPT_END => break,
+            _ => {}
+        }
+        let Some((&len, rest)) = rest.split_first() else {
+            break;
+        };
+        let Some(body) = rest.get(..len as usize) else {
+            break;
+        };
+        match code {
+            OPT_MESSAGE_TYPE => m.message_type = body.first().copied(),
+            OPT_SERVER_ID => m.server_id = ipv4(body),
+            OPT_REQUESTED_IP => m.requested_ip = ipv4(body),
+            OPT_LEASE_TIME => {
+                m.lease_time_s = body
+                    .get(..4)
+                    .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            OPT_HOSTNAME => m.hostname = text(body),
+            OPT_PARAM_REQUEST_LIST => m.param_request_list = body.to_vec(),
+            OPT_VENDOR_CLASS => m.vendor_class = text(body),
+            // Flags (1) + RCODE1/RCODE2 (2) + nombre; solo si no hubo opción 12
+            OPT_CLIENT_FQDN if m.hostname.is_none() && body.len() > 3 => {
+                m.hostname = if body[0] & 0x04 != 0 {
+                    dns_labels(&body[3..])
+                } else {
+                    text(&body[3..])
+                };
+            }
+            _ => {}
+        }
+        opts = &rest[len as usize..];
+    }
+    Some(m)
+}
+
+/// Opciones DHCPv6: (código, cuerpo)
+fn v6_options(mut data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut out = Vec::new();
+    while data.len() >= 4 {
+        let code = u16::from_be_bytes([data[0], data[1]]);
+        let len = u16::from_be_bytes([data[2], data[3]]) as usize;
+        let Some(body) = data.get(4..4 + len) else {
+            break;
+        };
+        out.push((code, body));
+        data = &data[4 + len..];
+    }
+    out
+}
+
+fn v6_status(body: &[u8]) -> Option<u16> {
+    let code = u16::from_be_bytes(body.get(..2)?.try_into().ok()?);
+    (code != 0).then_some(code)
+}
+
+/// Interpreta un payload UDP de los puertos 546/547, desencapsulando RELAY-FORW/REPL
+pub fn parse_v6(payload: &[u8]) -> Option<Dhcpv6Message> {
+    let mut data = payload;
+    let mut relay_hops = 0;
+    while matches!(data.first(), Some(&V6_RELAY_FORW) | Some(&V6_RELAY_REPL)) {
+        if relay_hops == MAX_RELAY_DEPTH {
+            return None;
+        }
+        // tipo (1) + hop count (1) + link-address (16) + peer-address (16)
+        let (_, inner) = v6_options(data.get(34..)?)
+            .into_iter()
+            .find(|(code, _)| *code == OPT6_RELAY_MSG)?;
+        data = inner;
+        relay_hops += 1;
+    }
+    if data.len() < 4 {
+        return None;
+    }
+    let mut m = Dhcpv6Message {
+        msg_type: data[0],
+        xid: u32::from_be_bytes([0, data[1], data[2], data[3]]),
+        relay_hops,
+        ..Dhcpv6Message::default()
+    };
+    for (code, body) in v6_options(&data[4..]) {
+        match code {
+            OPT6_CLIENTID => m.client_duid = Some(body.to_vec()),
+            OPT6_SERVERID => m.server_duid = Some(body.to_vec()),
+            OPT6_ORO => {
+                m.option_request = body
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect()
+            }
+            OPT6_STATUS_CODE => m.status_code = m.status_code.or(v6_status(body)),
+            // IAID (4) + T1 (4) + T2 (4), luego opciones IAADDR / STATUS_CODE
+            OPT6_IA_NA if body.len() >= 12 => {
+                for (code, sub) in v6_options(&body[12..]) {
+                    match code {
+                        OPT6_IAADDR if sub.len() >= 16 => {
+                            let o: [u8; 16] = sub[..16].try_into().ok()?;
+                            m.addresses.push(Ipv6Addr::from(o));
+                        }
+                        OPT6_STATUS_CODE => m.status_code = m.status_code.or(v6_status(sub)),
+                        _ => {}
+                    }
+                }
+            }
+            // Flags (1) + nombre en formato de etiquetas
+            OPT6_FQDN if body.len() > 1 => m.fqdn = dns_labels(&body[1..]),
+            // Enterprise number (4) + datos de clase con prefijo de longitud (2)
+            OPT6_VENDOR_CLASS if body.len() > 6 => {
+                let len = u16::from_be_bytes([body[4], body[5]]) as usize;
+                m.vendor_class = body.get(6..6 + len).and_then(text);
+            }
+            _ => {}
+        }
+    }
+    Some(m)
+}
+
+/// MAC contenida en un DUID-LLT (1) o DUID-LL (3) de tipo Ethernet
+pub fn duid_mac(duid: &[u8]) -> Option<[u8; 6]> {
+    let kind = u16::from_be_bytes(duid.get(..2)?.try_into().ok()?);
+    let hw = u16::from_be_bytes(duid.get(2..4)?.try_into().ok()?);
+    let lladdr = match kind {
+        1 => duid.get(8..)?,
+        3 => duid.get(4..)?,
+        _ => return None,
+    };
+    if hw != 1 || lladdr.len() != 6 {
+        return None;
+    }
+    lladdr.try_into().ok()
+}
+
+/// Huella de cliente al estilo Fingerbank: códigos pedidos separados por comas
+pub fn fingerprint<T: std::fmt::Display>(codes: &[T]) -> String {
+    codes
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Mensaje BOOTP/DHCPv4 con las opciones indicadas (código, cuerpo)
+    pub(crate) fn dhcp_body(
+        op: u8,
+        xid: u32,
+        mac: [u8; 6],
+        yiaddr: [u8; 4],
+        options: &[(u8, Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut b = vec![0u8; BOOTP_HEADER_LEN];
+        b[0] = op;
+        b[1] = 1;
+        b[2] = 6;
+        b[4..8].copy_from_slice(&xid.to_be_bytes());
+        b[16..20].copy_from_slice(&yiaddr);
+        b[28..34].copy_from_slice(&mac);
+        b.extend(MAGIC_COOKIE);
+        for (code, body) in options {
+            b.push(*code);
+            b.push(body.len() as u8);
+            b.extend(body);
+        }
+        b.push(OPT_END);
+        b
+    }
+
+    /// Mensaje DHCPv6 con opciones (código, cuerpo)
+    pub(crate) fn dhcpv6_body(msg_type: u8, xid: u32, options: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut b = vec![msg_type];
+        b.extend(&xid.to_be_bytes()[1..]);
+        for (code, body) in options {
+            b.extend(code.to_be_bytes());
+            b.extend((body.len() as u16).to_be_bytes());
+            b.extend(body);
+        }
+        b
+    }
+
+    /// DUID-LL Ethernet
+    pub(crate) fn duid_ll(mac: [u8; 6]) -> Vec<u8> {
+        let mut d = vec![0, 3, 0, 1];
+        d.extend(mac);
+        d
+    }
+
+    #[test]
+    fn parses_dhcpv4_options_and_fingerprint() {
+        let mac = [0x02, 0, 0, 0, 0, 0x42];
+        let body = dhcp_body(
+            1,
+            0xdeadbeef,
+            mac,
+            [0; 4],
+            &[
+                (OPT_MESSAGE_TYPE, vec![REQUEST]),
+                (OPT_REQUESTED_IP, vec![192, 168, 1, 50]),
+                (OPT_SERVER_ID, vec![192, 168, 1, 1]),
+                (OPT_HOSTNAME, b"laptop-ana\0".to_vec()),
+                (OPT_PARAM_REQUEST_LIST, vec![1, 121, 3, 6, 15, 119, 252]),
+                (OPT_VENDOR_CLASS, b"MSFT 5.0".to_vec()),
+            ],
+        );
+        let m = parse(&body).unwrap();
+        assert_eq!(m.xid, 0xdeadbeef);
+        assert_eq!(m.client_mac, Some(mac));
+        assert_eq!(m.message_type, Some(REQUEST));
+        assert_eq!(m.requested_ip, Some(Ipv4Addr::new(192, 168, 1, 50)));
+        assert_eq!(m.server_id, Some(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(m.hostname.as_deref(), Some("laptop-ana"));
+        assert_eq!(m.vendor_class.as_deref(), Some("MSFT 5.0"));
+        assert_eq!(fingerprint(&m.param_request_list), "1,121,3,6,15,119,252");
+
+        // Sin magic cookie no es DHCP (BOOTP puro)
+        let mut bootp = body.clone();
+        bootp[BOOTP_HEADER_LEN] = 0;
+        assert!(parse(&bootp).is_none());
+        assert!(parse(&body[..100]).is_none());
+    }
+
+    #[test]
+    fn parses_relayed_dhcpv6_reply_with_status() {
+        let mac = [0x02, 0, 0, 0, 0, 0x66];
+        let mut ia_na = vec![0; 12];
+        ia_na.extend(OPT6_STATUS_CODE.to_be_bytes());
+        ia_na.extend(2u16.to_be_bytes());
+        ia_na.extend(2u16.to_be_bytes()); // NoAddrsAvail
+        let inner = dhcpv6_body(
+            V6_REPLY,
+            0x123456,
+            &[
+                (OPT6_CLIENTID, duid_ll(mac)),
+                (OPT6_SERVERID, vec![0, 2, 0, 0, 0, 9, 1]),
+                (OPT6_IA_NA, ia_na),
+            ],
+        );
+        let mut relay = vec![V6_RELAY_REPL, 0];
+        relay.extend([0; 32]);
+        relay.extend(OPT6_RELAY_MSG.to_be_bytes());
+        relay.extend((inner.len() as u16).to_be_bytes());
+        relay.extend(&inner);
+
+        let m = parse_v6(&relay).unwrap();
+        assert_eq!(m.msg_type, V6_REPLY);
+        assert_eq!(m.xid, 0x123456);
+        assert_eq!(m.relay_hops, 1);
+        assert_eq!(m.status_code, Some(2));
+        assert_eq!(duid_mac(m.client_duid.as_deref().unwrap()), Some(mac));
+        assert_eq!(duid_mac(m.server_duid.as_deref().unwrap()), None);
+        assert!(parse_v6(&relay[..20]).is_none());
+    }
+}
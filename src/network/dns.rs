@@ -0,0 +1,271 @@
+//! Parser mínimo de mensajes DNS (RFC 1035): cabecera, preguntas y registros
+//! de respuesta, con descompresión de nombres.
+
+const HEADER_LEN: usize = 12;
+// Límite de saltos de compresión para cortar bucles de punteros
+const MAX_POINTER_JUMPS: usize = 32;
+const MAX_NAME_LEN: usize = 255;
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_NULL: u16 = 10;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_OPT: u16 = 41;
+
+pub const RCODE_NOERROR: u8 = 0;
+pub const RCODE_SERVFAIL: u8 = 2;
+pub const RCODE_NXDOMAIN: u8 = 3;
+pub const RCODE_REFUSED: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsHeader {
+    pub id: u16,
+    pub response: bool,
+    pub opcode: u8,
+    pub truncated: bool,
+    pub rcode: u8,
+    pub qdcount: u16,
+    pub ancount: u16,
+    pub nscount: u16,
+    pub arcount: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsQuestion {
+    /// Nombre en minúsculas, sin punto final ("" = raíz)
+    pub name: String,
+    pub qtype: u16,
+    pub qclass: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsRecord {
+    pub name: String,
+    pub rtype: u16,
+    pub ttl: u32,
+    pub rdata_len: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsMessage {
+    pub header: DnsHeader,
+    pub questions: Vec<DnsQuestion>,
+    /// Sección answer (las secciones authority/additional no se conservan)
+    pub answers: Vec<DnsRecord>,
+}
+
+impl DnsMessage {
+    /// Primera pregunta (las consultas reales llevan exactamente una)
+    pub fn question(&self) -> Option<&DnsQuestion> {
+        self.questions.first()
+    }
+}
+
+/// Parsea un mensaje DNS completo (payload UDP o mensaje TCP sin el prefijo de longitud)
+pub fn parse(msg: &[u8]) -> Option<DnsMessage> {
+    if msg.len() < HEADER_LEN {
+        return None;
+    }
+    let be16 = |i: usize| u16::from_be_bytes([msg[i], msg[i + 1]]);
+    let flags = be16(2);
+    let header = DnsHeader {
+        id: be16(0),
+        response: flags & 0x8000 != 0,
+        opcode: ((flags >> 11) & 0x0F) as u8,
+        truncated: flags & 0x0200 != 0,
+        rcode: (flags & 0x000F) as u8,
+        qdcount: be16(4),
+        ancount: be16(6),
+        nscount: be16(8),
+        arcount: be16(10),
+    };
+
+    let mut pos = HEADER_LEN;
+    let mut questions = Vec::with_capacity(header.qdcount.min(4) as usize);
+    for _ in 0..header.qdcount {
+        let (name, next) = read_name(msg, pos)?;
+        let fixed = msg.get(next..next + 4)?;
+        questions.push(DnsQuestion {
+            name,
+            qtype: u16::from_be_bytes([fixed[0], fixed[1]]),
+            qclass: u16::from_be_bytes([fixed[2], fixed[3]]),
+        });
+        pos = next + 4;
+    }
+
+    // Una respuesta truncada (TC) puede cortar registros: se conserva lo legible
+    let mut answers = Vec::with_capacity(header.ancount.min(16) as usize);
+    for _ in 0..header.ancount {
+        let Some((name, next)) = read_name(msg, pos) else {
+            break;
+        };
+        let Some(fixed) = msg.get(next..next + 10) else {
+            break;
+        };
+        let rdata_len = u16::from_be_bytes([fixed[8], fixed[9]]);
+        answers.push(DnsRecord {
+            name,
+            rtype: u16::from_be_bytes([fixed[0], fixed[1]]),
+            ttl: u32::from_be_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]),
+            rdata_len,
+        });
+        pos = next + 10 + rdata_len as usize;
+        if pos > msg.len() {
+            break;
+        }
+    }
+
+    Some(DnsMessage {
+        header,
+        questions,
+        answers,
+    })
+}
+
+/// Lee un nombre (con punteros de compresión) y devuelve la posición tras él
+fn read_name(msg: &[u8], start: usize) -> Option<(String, usize)> {
+    let mut name = String::new();
+    let mut pos = start;
+    let mut end: Option<usize> = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *msg.get(pos)? as usize;
+        match len & 0xC0 {
+            0x00 => {
+                if len == 0 {
+                    return Some((name, end.unwrap_or(pos + 1)));
+                }
+                let label = msg.get(pos + 1..pos + 1 + len)?;
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                for &b in label {
+                    // Bytes no imprimibles se escapan como en dig (\DDD)
+                    if b.is_ascii_graphic() && b != b'.' && b != b'\\' {
+                        name.push(b.to_ascii_lowercase() as char);
+                    } else {
+                        name.push_str(&format!("\\{b:03}"));
+                    }
+                }
+                if name.len() > MAX_NAME_LEN * 4 {
+                    return None;
+                }
+                pos += 1 + len;
+            }
+            0xC0 => {
+                let lo = *msg.get(pos + 1)? as usize;
+                if end.is_none() {
+                    end = Some(pos + 2);
+                }
+                jumps += 1;
+                if jumps > MAX_POINTER_JUMPS {
+                    return None;
+                }
+                pos = ((len & 0x3F) << 8) | lo;
+            }
+            // 0x40/0x80: tipos de etiqueta extendidos obsoletos
+            _ => return None,
+        }
+    }
+}
+
+pub fn rcode_name(rcode: u8) -> String {
+    match rcode {
+        0 => "NOERROR".to_string(),
+        1 => "FORMERR".to_string(),
+        2 => "SERVFAIL".to_string(),
+        3 => "NXDOMAIN".to_string(),
+        4 => "NOTIMP".to_string(),
+        5 => "REFUSED".to_string(),
+        n => format!("RCODE{n}"),
+    }
+}
+
+pub fn type_name(rtype: u16) -> String {
+    match rtype {
+        1 => "A".to_string(),
+        2 => "NS".to_string(),
+        5 => "CNAME".to_string(),
+        6 => "SOA".to_string(),
+        10 => "NULL".to_string(),
+        12 => "PTR".to_string(),
+        15 => "MX".to_string(),
+        16 => "TXT".to_string(),
+        28 => "AAAA".to_string(),
+        33 => "SRV".to_string(),
+        35 => "NAPTR".to_string(),
+        41 => "OPT".to_string(),
+        64 => "SVCB".to_string(),
+        65 => "HTTPS".to_string(),
+        255 => "ANY".to_string(),
+        n => format!("TYPE{n}"),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Construye una consulta (o su respuesta con un registro A) para tests
+    pub(crate) fn message(id: u16, name: &str, qtype: u16, response: Option<u8>) -> Vec<u8> {
+        let mut m = Vec::new();
+        m.extend_from_slice(&id.to_be_bytes());
+        let flags: u16 = match response {
+            Some(rcode) => 0x8180 | rcode as u16,
+            None => 0x0100,
+        };
+        m.extend_from_slice(&flags.to_be_bytes());
+        let ancount = u16::from(response == Some(RCODE_NOERROR));
+        for c in [1u16, ancount, 0, 0] {
+            m.extend_from_slice(&c.to_be_bytes());
+        }
+        for label in name.split('.').filter(|l| !l.is_empty()) {
+            m.push(label.len() as u8);
+            m.extend_from_slice(label.as_bytes());
+        }
+        m.push(0);
+        m.extend_from_slice(&qtype.to_be_bytes());
+        m.extend_from_slice(&1u16.to_be_bytes());
+        if ancount == 1 {
+            // Puntero al nombre de la pregunta (offset 12)
+            m.extend_from_slice(&[0xC0, 12]);
+            m.extend_from_slice(&TYPE_A.to_be_bytes());
+            m.extend_from_slice(&1u16.to_be_bytes());
+            m.extend_from_slice(&300u32.to_be_bytes());
+            m.extend_from_slice(&4u16.to_be_bytes());
+            m.extend_from_slice(&[93, 184, 216, 34]);
+        }
+        m
+    }
+
+    #[test]
+    fn parses_query_and_compressed_answer() {
+        let q = parse(&message(0x1234, "Www.Example.com", TYPE_A, None)).unwrap();
+        assert!(!q.header.response);
+        assert_eq!(q.question().unwrap().name, "www.example.com");
+
+        let r = parse(&message(
+            0x1234,
+            "www.example.com",
+            TYPE_A,
+            Some(RCODE_NOERROR),
+        ))
+        .unwrap();
+        assert!(r.header.response);
+        assert_eq!(r.header.rcode, RCODE_NOERROR);
+        assert_eq!(r.answers.len(), 1);
+        assert_eq!(r.answers[0].name, "www.example.com");
+        assert_eq!(r.answers[0].ttl, 300);
+    }
+
+    #[test]
+    fn pointer_loops_are_rejected() {
+        let mut m = message(1, "a", TYPE_A, None);
+        // La pregunta apunta a sí misma
+        m.truncate(HEADER_LEN);
+        m.extend_from_slice(&[0xC0, 12, 0, 1, 0, 1]);
+        assert!(parse(&m).is_none());
+        assert!(parse(&[0u8; 5]).is_none());
+    }
+}
@@ -1,6 +1,12 @@
 use std::net::{IpAddr, Ipv4Addr};
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+// Números de protocolo IP (IANA) de los transportes que se siguen por flujo
+pub const PROTO_ICMP: u8 = 1;
+pub const PROTO_TCP: u8 = 6;
+pub const PROTO_UDP: u8 = 17;
+pub const PROTO_ICMPV6: u8 = 58;
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Flow {
     pub source_ip: IpAddr,
     pub source_port: u16,
@@ -0,0 +1,330 @@
+use crate::network::flow::Flow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Límites de la tabla de flujos (memoria acotada)
+#[derive(Debug, Clone, Copy)]
+pub struct FlowTableConfig {
+    /// Máximo de flujos activos simultáneos (0 = sin límite)
+    pub max_flows: usize,
+    /// Inactividad tras la cual un flujo se expulsa, en µs (0 = sin timeout)
+    pub idle_timeout_us: u64,
+}
+
+impl Default for FlowTableConfig {
+    fn default() -> Self {
+        FlowTableConfig {
+            max_flows: 100_000,
+            idle_timeout_us: 600 * 1_000_000, // 10 min
+        }
+    }
+}
+
+/// Contadores de expulsión de la tabla
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FlowTableStats {
+    pub evicted_idle: u64,
+    pub evicted_capacity: u64,
+    pub peak_flows: usize,
+}
+
+#[derive(Debug)]
+struct FlowEntry<T> {
+    state: T,
+    last_seen_us: u64,
+}
+
+/// Tabla de flujos bidireccional con expulsión por inactividad y por capacidad.
+///
+/// La clave es la dirección canónica (la del primer paquete visto); las búsquedas
+/// aceptan cualquiera de los dos sentidos. Los flujos expulsados se devuelven al
+/// llamador para que los finalice en lugar de descartarlos.
+#[derive(Debug)]
+pub struct FlowTable<T> {
+    entries: HashMap<Flow, FlowEntry<T>>,
+    config: FlowTableConfig,
+    last_sweep_us: Option<u64>,
+    stats: FlowTableStats,
+}
+
+impl<T: Default> FlowTable<T> {
+    /// Fracción de la tabla que se libera de una vez al llegar al límite
+    const CAPACITY_EVICT_DIVISOR: usize = 10;
+
+    pub fn new(config: FlowTableConfig) -> Self {
+        FlowTable {
+            entries: HashMap::new(),
+            config,
+            last_sweep_us: None,
+            stats: FlowTableStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> FlowTableConfig {
+        self.config
+    }
+
+    pub fn stats(&self) -> FlowTableStats {
+        self.stats
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Devuelve `(flujo canónico, estado, es_sentido_canónico)` creando el flujo si no existe
+    pub fn get_or_insert(&mut self, flow: Flow, ts_us: u64) -> (Flow, &mut T, bool) {
+        let (key, forward) = if self.entries.contains_key(&flow) {
+            (flow, true)
+        } else if self.entries.contains_key(&flow.reverse()) {
+            (flow.reverse(), false)
+        } else {
+            (flow, true)
+        };
+
+        let len_after = self.entries.len() + usize::from(!self.entries.contains_key(&key));
+        self.stats.peak_flows = self.stats.peak_flows.max(len_after);
+
+        let entry = self.entries.entry(key).or_insert_with(|| FlowEntry {
+            state: T::default(),
+            last_seen_us: ts_us,
+        });
+        entry.last_seen_us = entry.last_seen_us.max(ts_us);
+        (key, &mut entry.state, forward)
+    }
+
+    /// Como `get_or_insert`, pero sin crear el flujo ni refrescar su actividad
+    pub fn get_mut(&mut self, flow: &Flow) -> Option<(Flow, &mut T, bool)> {
+        let (key, forward) = if self.entries.contains_key(flow) {
+            (*flow, true)
+        } else {
+            (flow.reverse(), false)
+        };
+        self.entries
+            .get_mut(&key)
+            .map(|e| (key, &mut e.state, forward))
+    }
+
+    /// Quita el flujo, en cualquiera de los dos sentidos (p. ej. al cerrarse la conexión)
+    pub fn remove(&mut self, flow: &Flow) -> Option<T> {
+        self.entries
+            .remove(flow)
+            .or_else(|| self.entries.remove(&flow.reverse()))
+            .map(|e| e.state)
+    }
+
+    /// Expulsa flujos inactivos (barrido periódico) y, si la tabla está llena,
+    /// los menos recientes. Llamar antes de `get_or_insert`.
+    pub fn evict(&mut self, now_us: u64) -> Vec<(Flow, T)> {
+        let mut evicted = Vec::new();
+
+        let timeout = self.config.idle_timeout_us;
+        if timeout > 0 {
+            // Barrido como mucho cada timeout/2 (coste amortizado O(1) por paquete)
+            let due = self
+                .last_sweep_us
+                .is_none_or(|last| now_us.saturating_sub(last) >= timeout / 2);
+            if due {
+                self.last_sweep_us = Some(now_us);
+                let idle: Vec<Flow> = self
+                    .entries
+                    .iter()
+                    .filter(|(_, e)| now_us.saturating_sub(e.last_seen_us) > timeout)
+                    .map(|(k, _)| *k)
+                    .collect();
+                self.stats.evicted_idle += idle.len() as u64;
+                evicted.extend(self.remove_all(idle));
+            }
+        }
+
+        let max = self.config.max_flows;
+        if max > 0 && self.entries.len() >= max {
+            // Libera un lote de los menos recientes para no seleccionar en cada paquete;
+            // el flujo desempata de forma determinista entre iguales en last_seen
+            let batch = (max / Self::CAPACITY_EVICT_DIVISOR).max(1);
+            let mut by_age: Vec<(u64, Flow)> = self
+                .entries
+                .iter()
+                .map(|(k, e)| (e.last_seen_us, *k))
+                .collect();
+            if batch < by_age.len() {
+                by_age.select_nth_unstable(batch);
+                by_age.truncate(batch);
+            }
+            by_age.sort_unstable();
+            let oldest: Vec<Flow> = by_age.into_iter().map(|(_, k)| k).collect();
+            self.stats.evicted_capacity += oldest.len() as u64;
+            evicted.extend(self.remove_all(oldest));
+        }
+
+        evicted
+    }
+
+    /// Vacía la tabla (fin de la captura)
+    pub fn drain(&mut self) -> Vec<(Flow, T)> {
+        self.entries.drain().map(|(k, e)| (k, e.state)).collect()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.values().map(|e| &e.state)
+    }
+
+    fn remove_all(&mut self, keys: Vec<Flow>) -> Vec<(Flow, T)> {
+        keys.into_iter()
+            .filter_map(|k| self.entries.remove(&k).map(|e| (k, e.state)))
+            .collect()
+    }
+}
+
+impl<T: Default> Default for FlowTable<T> {
+    fn default() -> Self {
+        Self::new(FlowTableConfig::default())
+    }
+}
+
+/// Resúmenes de flujos ya expulsados (finalizados) con memoria acotada.
+///
+/// Al superar el tope con cierta holgura se poda de una vez: se conserva la mitad
+/// superior según `primary` y, del resto, la mitad superior según `secondary`. La
+/// holgura hace que la poda (lineal) se amortice entre muchas expulsiones.
+#[derive(Debug)]
+pub struct FlowArchive<S> {
+    items: Vec<S>,
+    max: usize,
+    pruned: u64,
+    primary: fn(&S, &S) -> Ordering,
+    secondary: fn(&S, &S) -> Ordering,
+}
+
+impl<S> FlowArchive<S> {
+    /// `max = 0` desactiva la poda
+    pub fn new(
+        max: usize,
+        primary: fn(&S, &S) -> Ordering,
+        secondary: fn(&S, &S) -> Ordering,
+    ) -> Self {
+        FlowArchive {
+            items: Vec::new(),
+            max,
+            pruned: 0,
+            primary,
+            secondary,
+        }
+    }
+
+    pub fn extend(&mut self, items: impl IntoIterator<Item = S>) {
+        self.items.extend(items);
+        let slack = (self.max / 4).max(1);
+        if self.max > 0 && self.items.len() > self.max + slack {
+            self.prune();
+        }
+    }
+
+    /// Resúmenes descartados por la poda
+    pub fn pruned(&self) -> u64 {
+        self.pruned
+    }
+
+    /// Vacía el archivo (fin de la captura), ya podado al tope
+    pub fn take(&mut self) -> Vec<S> {
+        if self.max > 0 && self.items.len() > self.max {
+            self.prune();
+        }
+        std::mem::take(&mut self.items)
+    }
+
+    fn prune(&mut self) {
+        let before = self.items.len();
+        let keep = (self.max / 2).max(1);
+        if before > keep {
+            self.items.select_nth_unstable_by(keep, self.primary);
+            let mut rest = self.items.split_off(keep);
+            if rest.len() > keep {
+                rest.select_nth_unstable_by(keep, self.secondary);
+                rest.truncate(keep);
+            }
+            self.items.extend(rest);
+        }
+        self.pruned += (before - self.items.len()) as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn flow(port: u16) -> Flow {
+        Flow {
+            source_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            source_port: port,
+            destination_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            destination_port: 80,
+        }
+    }
+
+    #[test]
+    fn lookup_matches_both_directions() {
+        let mut t: FlowTable<u32> = FlowTable::default();
+        let (k1, st, fwd) = t.get_or_insert(flow(1000), 0);
+        *st += 1;
+        assert!(fwd);
+        let (k2, st, fwd) = t.get_or_insert(flow(1000).reverse(), 1);
+        *st += 1;
+        assert!(!fwd);
+        assert_eq!(k1, k2);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t.values().copied().sum::<u32>(), 2);
+    }
+
+    #[test]
+    fn idle_flows_are_evicted_not_dropped() {
+        let mut t: FlowTable<u32> = FlowTable::new(FlowTableConfig {
+            max_flows: 0,
+            idle_timeout_us: 1_000,
+        });
+        t.evict(0);
+        t.get_or_insert(flow(1), 0);
+        t.get_or_insert(flow(2), 1_800);
+        let out = t.evict(2_000);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, flow(1));
+        assert_eq!(t.len(), 1);
+        assert_eq!(t.stats().evicted_idle, 1);
+    }
+
+    #[test]
+    fn capacity_evicts_least_recent_batch() {
+        let mut t: FlowTable<u32> = FlowTable::new(FlowTableConfig {
+            max_flows: 10,
+            idle_timeout_us: 0,
+        });
+        for p in 0..10u16 {
+            t.evict(p as u64);
+            t.get_or_insert(flow(p), p as u64);
+        }
+        let out = t.evict(100);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].0, flow(0));
+        assert_eq!(t.stats().evicted_capacity, 1);
+        assert_eq!(t.stats().peak_flows, 10);
+    }
+
+    #[test]
+    fn archive_prunes_with_slack_keeping_top_by_both_orders() {
+        // (paquetes, bytes): mitad superior por paquetes y mitad superior por bytes del resto
+        let mut a: FlowArchive<(u32, u32)> =
+            FlowArchive::new(4, |x, y| y.0.cmp(&x.0), |x, y| y.1.cmp(&x.1));
+        a.extend([(1, 1), (2, 2), (3, 3), (4, 4), (5, 5)]);
+        assert_eq!(a.pruned(), 0);
+        a.extend([(0, 100), (0, 0)]);
+        assert_eq!(a.pruned(), 3);
+        let mut kept = a.take();
+        kept.sort_unstable();
+        assert_eq!(kept, vec![(0, 100), (3, 3), (4, 4), (5, 5)]);
+    }
+}
@@ -0,0 +1,86 @@
+//! Formato común de extremos, flujos y cocientes para el JSON de los detectores.
+//! Convención única: una IPv6 seguida de puerto va entre corchetes (`[2001:db8::1]:443`).
+
+use crate::network::flow::Flow;
+use std::net::IpAddr;
+
+/// `ip:puerto`, con la IPv6 entre corchetes
+pub fn endpoint_label(ip: IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V6(ip) => format!("[{ip}]:{port}"),
+        IpAddr::V4(ip) => format!("{ip}:{port}"),
+    }
+}
+
+/// Conversación bidireccional: `origen <-> destino/PROTO`
+pub fn flow_label(flow: &Flow, proto: &str) -> String {
+    let src = endpoint_label(flow.source_ip, flow.source_port);
+    let dst = endpoint_label(flow.destination_ip, flow.destination_port);
+    format!("{src} <-> {dst}/{proto}")
+}
+
+/// Servicio contactado por un host, sin puerto de origen: `origen -> destino/PROTO`
+pub fn service_label(src: IpAddr, dst: IpAddr, port: u16, proto: &str) -> String {
+    format!("{src} -> {}/{proto}", endpoint_label(dst, port))
+}
+
+/// `part / total` (0 si no hay total)
+pub fn rate(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Como `rate`, en porcentaje
+pub fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Redondeo a dos decimales para métricas del JSON
+pub fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+/// Bytes en hexadecimal en minúsculas, sin separadores
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn ipv6_endpoints_are_bracketed() {
+        let flow = Flow {
+            source_ip: IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+            source_port: 50000,
+            destination_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            destination_port: 443,
+        };
+        assert_eq!(
+            flow_label(&flow, "TCP"),
+            "[2001:db8::1]:50000 <-> 10.0.0.1:443/TCP"
+        );
+        assert_eq!(
+            service_label(flow.destination_ip, flow.source_ip, 443, "UDP"),
+            "10.0.0.1 -> [2001:db8::1]:443/UDP"
+        );
+    }
+
+    #[test]
+    fn rates_and_hex() {
+        assert_eq!(rate(1, 4), 0.25);
+        assert_eq!(rate(3, 0), 0.0);
+        assert_eq!(percent(1, 4), 25.0);
+        assert_eq!(round2(2.0 / 3.0), 0.67);
+        assert_eq!(hex(&[0x00, 0xab, 0x10]), "00ab10");
+    }
+}
@@ -0,0 +1,330 @@
+//! Parser incremental de HTTP/1.x: líneas de inicio, cabeceras relevantes y
+//! delimitación del cuerpo (Content-Length, chunked o hasta el cierre).
+
+/// Métodos reconocidos al inicio de un stream de cliente
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS", "PATCH", "CONNECT", "TRACE",
+];
+
+/// Tamaño máximo de cabeceras antes de dar el mensaje por inválido
+pub const MAX_HEAD_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestHead {
+    pub method: String,
+    pub uri: String,
+    pub version: String,
+    pub host: Option<String>,
+    pub framing: BodyFraming,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseHead {
+    pub version: String,
+    pub status: u16,
+    pub framing: BodyFraming,
+}
+
+/// Cómo se delimita el cuerpo según las cabeceras
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFraming {
+    None,
+    Length(u64),
+    Chunked,
+    /// Solo respuestas: el cuerpo termina al cerrar la conexión
+    UntilClose,
+}
+
+/// ¿Empieza `data` como una petición HTTP/1.x?
+pub fn looks_like_request(data: &[u8]) -> bool {
+    METHODS
+        .iter()
+        .any(|m| data.len() > m.len() && data.starts_with(m.as_bytes()) && data[m.len()] == b' ')
+}
+
+/// ¿Empieza `data` como una respuesta HTTP/1.x?
+pub fn looks_like_response(data: &[u8]) -> bool {
+    data.starts_with(b"HTTP/1.")
+}
+
+/// Posición justo después de "\r\n\r\n" (o "\n\n"), si las cabeceras están completas
+pub fn head_end(buf: &[u8]) -> Option<usize> {
+    let crlf = buf.windows(4).position(|w| w == b"\r\n\r\n").map(|p| p + 4);
+    let lf = buf.windows(2).position(|w| w == b"\n\n").map(|p| p + 2);
+    match (crlf, lf) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+struct Head<'a> {
+    start_line: &'a str,
+    headers: Vec<(&'a str, &'a str)>,
+}
+
+fn split_head(head: &[u8]) -> Option<Head<'_>> {
+    // Cabeceras en ISO-8859-1 en la práctica: se ignoran líneas no UTF-8
+    let text = std::str::from_utf8(head).ok()?;
+    let mut lines = text.split('\n').map(|l| l.trim_end_matches('\r'));
+    let start_line = lines.next()?;
+    let headers = lines
+        .filter(|l| !l.is_empty())
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .collect();
+    Some(Head {
+        start_line,
+        headers,
+    })
+}
+
+fn header<'a>(headers: &[(&'a str, &'a str)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| *v)
+}
+
+fn framing(headers: &[(&str, &str)]) -> Option<BodyFraming> {
+    if header(headers, "transfer-encoding")
+        .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"))
+    {
+        return Some(BodyFraming::Chunked);
+    }
+    match header(headers, "content-length") {
+        Some(v) => v.parse::<u64>().ok().map(|n| {
+            if n == 0 {
+                BodyFraming::None
+            } else {
+                BodyFraming::Length(n)
+            }
+        }),
+        None => Some(BodyFraming::None),
+    }
+}
+
+/// Parsea la cabecera completa de una petición (incluye la línea en blanco final)
+pub fn parse_request_head(head: &[u8]) -> Option<RequestHead> {
+    let h = split_head(head)?;
+    let mut parts = h.start_line.split(' ');
+    let method = parts.next()?;
+    let uri = parts.next()?;
+    let version = parts.next()?;
+    if !version.starts_with("HTTP/1.") || !METHODS.contains(&method) {
+        return None;
+    }
+    Some(RequestHead {
+        method: method.to_string(),
+        uri: uri.to_string(),
+        version: version.to_string(),
+        host: header(&h.headers, "host").map(|v| v.to_ascii_lowercase()),
+        framing: framing(&h.headers)?,
+    })
+}
+
+/// Parsea la cabecera de una respuesta. `request_method` decide si hay cuerpo (HEAD)
+pub fn parse_response_head(head: &[u8], request_method: Option<&str>) -> Option<ResponseHead> {
+    let h = split_head(head)?;
+    let mut parts = h.start_line.splitn(3, ' ');
+    let version = parts.next()?;
+    let status: u16 = parts.next()?.parse().ok()?;
+    if !version.starts_with("HTTP/1.") || !(100..1000).contains(&status) {
+        return None;
+    }
+    let no_body = request_method == Some("HEAD")
+        || (100..200).contains(&status)
+        || status == 204
+        || status == 304;
+    let framing = if no_body {
+        BodyFraming::None
+    } else {
+        match framing(&h.headers)? {
+            // Sin Content-Length ni chunked, la respuesta dura hasta el cierre
+            BodyFraming::None if header(&h.headers, "content-length").is_none() => {
+                BodyFraming::UntilClose
+            }
+            f => f,
+        }
+    };
+    Some(ResponseHead {
+        version: version.to_string(),
+        status,
+        framing,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ChunkState {
+    Size(Vec<u8>),
+    Data(u64),
+    DataEnd(u8), // CRLF tras los datos del chunk
+    Trailer(Vec<u8>),
+}
+
+/// Consume el cuerpo de un mensaje de forma incremental
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyDecoder {
+    framing: BodyFraming,
+    remaining: u64,
+    chunk: ChunkState,
+    /// Bytes de cuerpo (decodificados si es chunked)
+    pub body_bytes: u64,
+    done: bool,
+}
+
+impl BodyDecoder {
+    pub fn new(framing: BodyFraming) -> Self {
+        BodyDecoder {
+            framing,
+            remaining: match framing {
+                BodyFraming::Length(n) => n,
+                _ => 0,
+            },
+            chunk: ChunkState::Size(Vec::new()),
+            body_bytes: 0,
+            done: framing == BodyFraming::None,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    pub fn until_close(&self) -> bool {
+        self.framing == BodyFraming::UntilClose
+    }
+
+    /// Consume hasta el final del cuerpo; devuelve los bytes usados de `data`,
+    /// o `None` si el formato chunked es inválido
+    pub fn consume(&mut self, data: &[u8]) -> Option<usize> {
+        if self.done {
+            return Some(0);
+        }
+        match self.framing {
+            BodyFraming::None => Some(0),
+            BodyFraming::UntilClose => {
+                self.body_bytes += data.len() as u64;
+                Some(data.len())
+            }
+            BodyFraming::Length(_) => {
+                let n = (data.len() as u64).min(self.remaining) as usize;
+                self.remaining -= n as u64;
+                self.body_bytes += n as u64;
+                self.done = self.remaining == 0;
+                Some(n)
+            }
+            BodyFraming::Chunked => self.consume_chunked(data),
+        }
+    }
+
+    fn consume_chunked(&mut self, data: &[u8]) -> Option<usize> {
+        let mut pos = 0;
+        while pos < data.len() && !self.done {
+            match &mut self.chunk {
+                ChunkState::Size(line) => {
+                    let b = data[pos];
+                    pos += 1;
+                    if b != b'\n' {
+                        line.push(b);
+                        if line.len() > 1024 {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let text = std::str::from_utf8(line).ok()?;
+                    // Extensiones de chunk tras ';'
+                    let size_str = text.trim_end_matches('\r').split(';').next()?.trim();
+                    let size = u64::from_str_radix(size_str, 16).ok()?;
+                    self.chunk = if size == 0 {
+                        ChunkState::Trailer(Vec::new())
+                    } else {
+                        ChunkState::Data(size)
+                    };
+                }
+                ChunkState::Data(left) => {
+                    let n = ((data.len() - pos) as u64).min(*left);
+                    *left -= n;
+                    pos += n as usize;
+                    self.body_bytes += n;
+                    if *left == 0 {
+                        self.chunk = ChunkState::DataEnd(0);
+                    }
+                }
+                ChunkState::DataEnd(seen) => {
+                    let b = data[pos];
+                    pos += 1;
+                    if b == b'\n' {
+                        self.chunk = ChunkState::Size(Vec::new());
+                    } else if b == b'\r' && *seen == 0 {
+                        *seen = 1;
+                    } else {
+                        return None;
+                    }
+                }
+                ChunkState::Trailer(line) => {
+                    let b = data[pos];
+                    pos += 1;
+                    if b != b'\n' {
+                        line.push(b);
+                        if line.len() > MAX_HEAD_BYTES {
+                            return None;
+                        }
+                        continue;
+                    }
+                    if line.is_empty() || line.as_slice() == b"\r" {
+                        self.done = true;
+                    } else {
+                        line.clear();
+                    }
+                }
+            }
+        }
+        Some(pos)
+    }
+
+    /// Cierre de la conexión: termina los cuerpos delimitados por cierre
+    pub fn on_close(&mut self) {
+        if self.framing == BodyFraming::UntilClose {
+            self.done = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_request_and_response_heads() {
+        let req = b"GET /a?b=1 HTTP/1.1\r\nHost: Example.com\r\nContent-Length: 3\r\n\r\n";
+        assert!(looks_like_request(req));
+        assert_eq!(head_end(req), Some(req.len()));
+        let r = parse_request_head(req).unwrap();
+        assert_eq!((r.method.as_str(), r.uri.as_str()), ("GET", "/a?b=1"));
+        assert_eq!(r.host.as_deref(), Some("example.com"));
+        assert_eq!(r.framing, BodyFraming::Length(3));
+
+        let resp = b"HTTP/1.1 404 Not Found\r\nServer: x\r\n\r\n";
+        let h = parse_response_head(resp, Some("GET")).unwrap();
+        assert_eq!(h.status, 404);
+        assert_eq!(h.framing, BodyFraming::UntilClose);
+        let h = parse_response_head(resp, Some("HEAD")).unwrap();
+        assert_eq!(h.framing, BodyFraming::None);
+    }
+
+    #[test]
+    fn chunked_body_across_fragments() {
+        let body = b"4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\nX-Trailer: 1\r\n\r\nNEXT";
+        let mut d = BodyDecoder::new(BodyFraming::Chunked);
+        let mut used = 0;
+        for part in body.chunks(3) {
+            if d.is_done() {
+                break;
+            }
+            used += d.consume(part).unwrap();
+        }
+        assert!(d.is_done());
+        assert_eq!(d.body_bytes, 9);
+        assert_eq!(&body[used..], b"NEXT");
+    }
+}
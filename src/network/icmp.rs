@@ -0,0 +1,412 @@
+use crate::network::flow::{Flow, PROTO_TCP, PROTO_UDP};
+use etherparse::{Ipv4HeaderSlice, Ipv6HeaderSlice};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+// Cabeceras de extensión IPv6 que se pueden saltar para llegar al transporte
+const IPV6_HOP_BY_HOP: u8 = 0;
+const IPV6_ROUTING: u8 = 43;
+const IPV6_FRAGMENT: u8 = 44;
+const IPV6_DEST_OPTS: u8 = 60;
+
+/// Paquete original citado dentro de un error ICMP/ICMPv6
+/// (cabecera IP + al menos los 8 primeros bytes del transporte).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotedPacket {
+    /// Dirección tal cual la envió el host original (src = quien recibe el error)
+    pub flow: Flow,
+    pub protocol: u8,
+}
+
+/// Extrae el flujo citado en el payload de un error ICMP/ICMPv6
+pub fn quoted_flow(quoted: &[u8]) -> Option<QuotedPacket> {
+    let version = quoted.first()? >> 4;
+    let (src, dst, protocol, transport) = match version {
+        4 => {
+            let h = Ipv4HeaderSlice::from_slice(quoted).ok()?;
+            let rest = &quoted[h.slice().len()..];
+            // Fragmentos no iniciales no llevan puertos
+            let transport = if h.fragments_offset().value() == 0 {
+                rest
+            } else {
+                &[][..]
+            };
+            (
+                IpAddr::V4(h.source_addr()),
+                IpAddr::V4(h.destination_addr()),
+                h.protocol().0,
+                transport,
+            )
+        }
+        6 => {
+            let h = Ipv6HeaderSlice::from_slice(quoted).ok()?;
+            let (protocol, transport) = skip_ipv6_extensions(h.next_header().0, &quoted[40..]);
+            (
+                IpAddr::V6(h.source_addr()),
+                IpAddr::V6(h.destination_addr()),
+                protocol,
+                transport,
+            )
+        }
+        _ => return None,
+    };
+
+    let (source_port, destination_port) = match protocol {
+        PROTO_TCP | PROTO_UDP if transport.len() >= 4 => (
+            u16::from_be_bytes([transport[0], transport[1]]),
+            u16::from_be_bytes([transport[2], transport[3]]),
+        ),
+        _ => (0, 0),
+    };
+
+    Some(QuotedPacket {
+        flow: Flow {
+            source_ip: src,
+            source_port,
+            destination_ip: dst,
+            destination_port,
+        },
+        protocol,
+    })
+}
+
+/// Familia de error ICMP/ICMPv6 que afecta a un flujo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcmpErrorKind {
+    DestinationUnreachable,
+    TimeExceeded,
+    PacketTooBig,
+    Redirect,
+    ParameterProblem,
+}
+
+/// Error ICMP/ICMPv6 con el paquete original que lo provocó
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcmpError<'a> {
+    pub kind: IcmpErrorKind,
+    /// Nombre estable del tipo/código (`port_unreachable`, `ttl_exceeded`, ...)
+    pub name: &'static str,
+    /// MTU anunciada en "fragmentation needed" / Packet Too Big
+    pub mtu: Option<u32>,
+    /// Siguiente salto propuesto por un redirect
+    pub gateway: Option<IpAddr>,
+    /// Paquete citado (cabecera IP + inicio del transporte)
+    pub quoted: &'a [u8],
+}
+
+/// Mensajes ICMP/ICMPv6 que interesan al diagnóstico
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IcmpMessage<'a> {
+    Error(IcmpError<'a>),
+    Echo { request: bool, id: u16, seq: u16 },
+}
+
+fn unreachable_v4_name(code: u8) -> &'static str {
+    match code {
+        0 | 6 => "net_unreachable",
+        1 | 7 => "host_unreachable",
+        2 => "protocol_unreachable",
+        3 => "port_unreachable",
+        4 => "fragmentation_needed",
+        5 => "source_route_failed",
+        9 | 10 | 13 => "admin_prohibited",
+        _ => "unreachable",
+    }
+}
+
+fn unreachable_v6_name(code: u8) -> &'static str {
+    match code {
+        0 => "no_route",
+        1 | 5 | 6 => "admin_prohibited",
+        2 => "beyond_scope",
+        3 => "host_unreachable",
+        4 => "port_unreachable",
+        _ => "unreachable",
+    }
+}
+
+/// Clasifica un mensaje a partir de tipo, código, bytes 5-8 de la cabecera y payload
+pub fn classify<'a>(
+    ipv6: bool,
+    icmp_type: u8,
+    code: u8,
+    bytes5to8: [u8; 4],
+    payload: &'a [u8],
+) -> Option<IcmpMessage<'a>> {
+    let id = u16::from_be_bytes([bytes5to8[0], bytes5to8[1]]);
+    let seq = u16::from_be_bytes([bytes5to8[2], bytes5to8[3]]);
+    let error = |kind, name, mtu, gateway, quoted| {
+        Some(IcmpMessage::Error(IcmpError {
+            kind,
+            name,
+            mtu,
+            gateway,
+            quoted,
+        }))
+    };
+    if !ipv6 {
+        return match icmp_type {
+            0 | 8 => Some(IcmpMessage::Echo {
+                request: icmp_type == 8,
+                id,
+                seq,
+            }),
+            3 => {
+                let mtu = (code == 4).then_some(seq as u32);
+                let name = unreachable_v4_name(code);
+                error(
+                    IcmpErrorKind::DestinationUnreachable,
+                    name,
+                    mtu,
+                    None,
+                    payload,
+                )
+            }
+            5 => {
+                let gw = IpAddr::V4(Ipv4Addr::from(bytes5to8));
+                error(IcmpErrorKind::Redirect, "redirect", None, Some(gw), payload)
+            }
+            11 => {
+                let name = if code == 0 {
+                    "ttl_exceeded"
+                } else {
+                    "reassembly_time_exceeded"
+                };
+                error(IcmpErrorKind::TimeExceeded, name, None, None, payload)
+            }
+            12 => error(
+                IcmpErrorKind::ParameterProblem,
+                "parameter_problem",
+                None,
+                None,
+                payload,
+            ),
+            _ => None,
+        };
+    }
+    match icmp_type {
+        128 | 129 => Some(IcmpMessage::Echo {
+            request: icmp_type == 128,
+            id,
+            seq,
+        }),
+        1 => {
+            let name = unreachable_v6_name(code);
+            error(
+                IcmpErrorKind::DestinationUnreachable,
+                name,
+                None,
+                None,
+                payload,
+            )
+        }
+        2 => {
+            let mtu = Some(u32::from_be_bytes(bytes5to8));
+            error(
+                IcmpErrorKind::PacketTooBig,
+                "packet_too_big",
+                mtu,
+                None,
+                payload,
+            )
+        }
+        3 => {
+            let name = if code == 0 {
+                "hop_limit_exceeded"
+            } else {
+                "reassembly_time_exceeded"
+            };
+            error(IcmpErrorKind::TimeExceeded, name, None, None, payload)
+        }
+        4 => error(
+            IcmpErrorKind::ParameterProblem,
+            "parameter_problem",
+            None,
+            None,
+            payload,
+        ),
+        137 => {
+            // Redirect NDP: destino (16) + destino final (16) + opciones;
+            // el paquete original va en la opción "Redirected Header" (tipo 4)
+            let target: [u8; 16] = payload.get(..16)?.try_into().ok()?;
+            let gw = IpAddr::V6(Ipv6Addr::from(target));
+            let quoted = ndp_redirected_header(payload.get(32..)?).unwrap_or(&[]);
+            error(IcmpErrorKind::Redirect, "redirect", None, Some(gw), quoted)
+        }
+        _ => None,
+    }
+}
+
+fn ndp_redirected_header(mut options: &[u8]) -> Option<&[u8]> {
+    while options.len() >= 8 {
+        let len = options[1] as usize * 8;
+        if len == 0 || len > options.len() {
+            return None;
+        }
+        if options[0] == 4 {
+            return Some(&options[8..len]);
+        }
+        options = &options[len..];
+    }
+    None
+}
+
+/// Datos de la cabecera de fragmento IPv6 (si existe)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv6Fragment {
+    pub identification: u32,
+    /// Offset en bytes
+    pub offset: u32,
+    pub more_fragments: bool,
+}
+
+/// Recorre las extensiones IPv6 (`exts` = bytes tras la cabecera fija) buscando
+/// la cabecera de fragmento
+pub fn ipv6_fragment(first_header: u8, exts: &[u8]) -> Option<Ipv6Fragment> {
+    let mut next = first_header;
+    let mut rest = exts;
+    loop {
+        match next {
+            IPV6_FRAGMENT => {
+                if rest.len() < 8 {
+                    return None;
+                }
+                let off_flags = u16::from_be_bytes([rest[2], rest[3]]);
+                return Some(Ipv6Fragment {
+                    identification: u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]),
+                    offset: ((off_flags >> 3) as u32) * 8,
+                    more_fragments: off_flags & 1 == 1,
+                });
+            }
+            IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DEST_OPTS => {
+                if rest.len() < 2 {
+                    return None;
+                }
+                let len = (rest[1] as usize + 1) * 8;
+                if rest.len() < len {
+                    return None;
+                }
+                next = rest[0];
+                rest = &rest[len..];
+            }
+            _ => return None,
+        }
+    }
+}
+
+fn skip_ipv6_extensions(first_header: u8, exts: &[u8]) -> (u8, &[u8]) {
+    let mut next = first_header;
+    let mut rest = exts;
+    loop {
+        match next {
+            IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DEST_OPTS | IPV6_FRAGMENT => {
+                if rest.len() < 8 {
+                    return (next, &[]);
+                }
+                let len = if next == IPV6_FRAGMENT {
+                    8
+                } else {
+                    (rest[1] as usize + 1) * 8
+                };
+                if rest.len() < len {
+                    return (next, &[]);
+                }
+                next = rest[0];
+                rest = &rest[len..];
+            }
+            _ => return (next, rest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn quoted_ipv4_tcp_flow() {
+        let builder = etherparse::PacketBuilder::ipv4([10, 0, 0, 1], [8, 8, 8, 8], 64)
+            .tcp(40000, 443, 1, 1024);
+        let mut pkt = Vec::new();
+        builder.write(&mut pkt, &[0u8; 32]).unwrap();
+        // Los errores ICMP suelen citar solo cabecera IP + 8 bytes
+        let q = quoted_flow(&pkt[..28]).unwrap();
+        assert_eq!(q.protocol, PROTO_TCP);
+        assert_eq!(q.flow.source_ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(q.flow.source_port, 40000);
+        assert_eq!(q.flow.destination_port, 443);
+    }
+
+    #[test]
+    fn quoted_ipv6_udp_flow() {
+        let builder = etherparse::PacketBuilder::ipv6([1; 16], [2; 16], 64).udp(5353, 53);
+        let mut pkt = Vec::new();
+        builder.write(&mut pkt, &[0u8; 8]).unwrap();
+        let q = quoted_flow(&pkt).unwrap();
+        assert_eq!(q.protocol, PROTO_UDP);
+        assert_eq!((q.flow.source_port, q.flow.destination_port), (5353, 53));
+    }
+
+    #[test]
+    fn classifies_errors_and_echo() {
+        let Some(IcmpMessage::Error(e)) = classify(false, 3, 4, [0, 0, 0x05, 0xdc], b"q") else {
+            panic!("se esperaba un error")
+        };
+        assert_eq!(
+            (e.name, e.mtu, e.quoted),
+            ("fragmentation_needed", Some(1500), &b"q"[..])
+        );
+        let Some(IcmpMessage::Error(e)) = classify(false, 5, 1, [10, 0, 0, 254], b"") else {
+            panic!("se esperaba un redirect")
+        };
+        assert_eq!(e.gateway, Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 254))));
+        assert_eq!(
+            classify(true, 129, 0, [0, 7, 0, 3], b""),
+            Some(IcmpMessage::Echo {
+                request: false,
+                id: 7,
+                seq: 3
+            })
+        );
+
+        // Redirect NDP con la opción Redirected Header al final
+        let mut ndp = vec![0xfe; 16];
+        ndp.extend([0x20; 16]);
+        ndp.extend([1, 1, 0, 0, 0, 0, 0, 0]); // opción 1 (link-layer), 8 bytes
+        ndp.extend([4, 2, 0, 0, 0, 0, 0, 0]);
+        ndp.extend([0x60; 8]);
+        let Some(IcmpMessage::Error(e)) = classify(true, 137, 0, [0; 4], &ndp) else {
+            panic!("se esperaba un redirect")
+        };
+        assert_eq!(e.kind, IcmpErrorKind::Redirect);
+        assert_eq!(e.quoted, &[0x60; 8][..]);
+        assert!(classify(true, 135, 0, [0; 4], b"").is_none());
+    }
+
+    #[test]
+    fn ipv6_fragment_header_is_found_after_hop_by_hop() {
+        // Hop-by-hop (8 bytes, next = fragment) + fragment(next=UDP, offset=185*8, MF, id=7)
+        let exts = [
+            IPV6_FRAGMENT,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0, //
+            PROTO_UDP,
+            0,
+            0x05,
+            0xC9,
+            0,
+            0,
+            0,
+            7,
+        ];
+        let f = ipv6_fragment(IPV6_HOP_BY_HOP, &exts).unwrap();
+        assert_eq!(f.identification, 7);
+        assert_eq!(f.offset, 185 * 8);
+        assert!(f.more_fragments);
+    }
+}
@@ -1 +1,22 @@
+pub mod arp;
+pub mod capture;
+pub mod checksum;
+pub mod cleartext;
+pub mod dedup;
+pub mod dhcp;
+pub mod dns;
 pub mod flow;
+pub mod flow_table;
+pub mod format;
+pub mod http;
+pub mod icmp;
+pub mod ndp;
+pub mod quic;
+pub mod reassembly;
+pub mod rtp;
+pub mod sdp;
+pub mod sip;
+pub mod snmp;
+pub mod tls;
+pub mod tls_fingerprint;
+pub mod x509;
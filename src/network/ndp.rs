@@ -0,0 +1,202 @@
+//! Neighbor Discovery de IPv6 (RFC 4861): solicitudes y anuncios de vecino y router.
+
+use std::net::Ipv6Addr;
+
+pub const ICMPV6_ROUTER_SOLICITATION: u8 = 133;
+pub const ICMPV6_ROUTER_ADVERTISEMENT: u8 = 134;
+pub const ICMPV6_NEIGHBOR_SOLICITATION: u8 = 135;
+pub const ICMPV6_NEIGHBOR_ADVERTISEMENT: u8 = 136;
+
+/// Todo mensaje NDP legítimo sale con hop limit 255 (no atraviesa routers)
+pub const NDP_HOP_LIMIT: u8 = 255;
+
+const OPT_SOURCE_LLA: u8 = 1;
+const OPT_TARGET_LLA: u8 = 2;
+const OPT_PREFIX_INFO: u8 = 3;
+const OPT_MTU: u8 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouterAdvert {
+    pub cur_hop_limit: u8,
+    pub managed: bool,
+    pub other_config: bool,
+    /// Preferencia de router (RFC 4191): "high", "medium", "low"
+    pub preference: &'static str,
+    pub router_lifetime_s: u16,
+    pub source_mac: Option<[u8; 6]>,
+    pub prefixes: Vec<(Ipv6Addr, u8)>,
+    pub mtu: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NdpMessage {
+    RouterSolicitation {
+        source_mac: Option<[u8; 6]>,
+    },
+    RouterAdvertisement(RouterAdvert),
+    NeighborSolicitation {
+        target: Ipv6Addr,
+        source_mac: Option<[u8; 6]>,
+    },
+    NeighborAdvertisement {
+        target: Ipv6Addr,
+        target_mac: Option<[u8; 6]>,
+        router: bool,
+        solicited: bool,
+        override_flag: bool,
+    },
+}
+
+/// Opciones NDP (TLV en unidades de 8 bytes): (tipo, cuerpo sin tipo/longitud)
+fn options(mut data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut out = Vec::new();
+    while data.len() >= 8 {
+        let len = data[1] as usize * 8;
+        if len == 0 || len > data.len() {
+            break;
+        }
+        out.push((data[0], &data[2..len]));
+        data = &data[len..];
+    }
+    out
+}
+
+fn link_layer(opts: &[(u8, &[u8])], kind: u8) -> Option<[u8; 6]> {
+    opts.iter()
+        .find(|(t, _)| *t == kind)
+        .and_then(|(_, body)| body.get(..6)?.try_into().ok())
+}
+
+fn addr(data: &[u8]) -> Option<Ipv6Addr> {
+    let bytes: [u8; 16] = data.get(..16)?.try_into().ok()?;
+    Some(Ipv6Addr::from(bytes))
+}
+
+/// Interpreta un mensaje NDP a partir del tipo ICMPv6, los bytes 5-8 y el payload
+pub fn parse(icmp_type: u8, bytes5to8: [u8; 4], payload: &[u8]) -> Option<NdpMessage> {
+    match icmp_type {
+        ICMPV6_ROUTER_SOLICITATION => Some(NdpMessage::RouterSolicitation {
+            source_mac: link_layer(&options(payload), OPT_SOURCE_LLA),
+        }),
+        ICMPV6_ROUTER_ADVERTISEMENT => {
+            // Reachable time (4) + retrans timer (4), luego opciones
+            let opts = options(payload.get(8..)?);
+            let flags = bytes5to8[1];
+            let preference = match (flags >> 3) & 0x03 {
+                0b01 => "high",
+                0b11 => "low",
+                _ => "medium",
+            };
+            let prefixes = opts
+                .iter()
+                .filter(|(t, body)| *t == OPT_PREFIX_INFO && body.len() >= 30)
+                .filter_map(|(_, body)| Some((addr(&body[14..])?, body[0])))
+                .collect();
+            let mtu = opts
+                .iter()
+                .find(|(t, body)| *t == OPT_MTU && body.len() >= 6)
+                .map(|(_, body)| u32::from_be_bytes([body[2], body[3], body[4], body[5]]));
+            Some(NdpMessage::RouterAdvertisement(RouterAdvert {
+                cur_hop_limit: bytes5to8[0],
+                managed: flags & 0x80 != 0,
+                other_config: flags & 0x40 != 0,
+                preference,
+                router_lifetime_s: u16::from_be_bytes([bytes5to8[2], bytes5to8[3]]),
+                source_mac: link_layer(&opts, OPT_SOURCE_LLA),
+                prefixes,
+                mtu,
+            }))
+        }
+        ICMPV6_NEIGHBOR_SOLICITATION => Some(NdpMessage::NeighborSolicitation {
+            target: addr(payload)?,
+            source_mac: link_layer(&options(payload.get(16..)?), OPT_SOURCE_LLA),
+        }),
+        ICMPV6_NEIGHBOR_ADVERTISEMENT => Some(NdpMessage::NeighborAdvertisement {
+            target: addr(payload)?,
+            target_mac: link_layer(&options(payload.get(16..)?), OPT_TARGET_LLA),
+            router: bytes5to8[0] & 0x80 != 0,
+            solicited: bytes5to8[0] & 0x40 != 0,
+            override_flag: bytes5to8[0] & 0x20 != 0,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Cuerpo de un Router Advertisement con MAC de origen y un prefijo /64
+    pub(crate) fn router_advert_body(mac: [u8; 6], prefix: Ipv6Addr) -> Vec<u8> {
+        let mut b = vec![0u8; 8]; // reachable + retrans
+        b.extend([OPT_SOURCE_LLA, 1]);
+        b.extend(mac);
+        b.extend([OPT_PREFIX_INFO, 4, 64, 0xc0]);
+        b.extend(86400u32.to_be_bytes());
+        b.extend(14400u32.to_be_bytes());
+        b.extend([0; 4]);
+        b.extend(prefix.octets());
+        b.extend([OPT_MTU, 1, 0, 0]);
+        b.extend(1500u32.to_be_bytes());
+        b
+    }
+
+    /// Cuerpo de NS/NA: destino + opción de dirección de enlace
+    pub(crate) fn neighbor_body(target: Ipv6Addr, option: u8, mac: Option<[u8; 6]>) -> Vec<u8> {
+        let mut b = target.octets().to_vec();
+        if let Some(mac) = mac {
+            b.extend([option, 1]);
+            b.extend(mac);
+        }
+        b
+    }
+
+    #[test]
+    fn parses_router_and_neighbor_messages() {
+        let prefix: Ipv6Addr = "2001:db8:1::".parse().unwrap();
+        let ra = parse(
+            ICMPV6_ROUTER_ADVERTISEMENT,
+            [64, 0x88, 0x07, 0x08],
+            &router_advert_body([2; 6], prefix),
+        );
+        let Some(NdpMessage::RouterAdvertisement(ra)) = ra else {
+            panic!("se esperaba un RA")
+        };
+        assert!(ra.managed && !ra.other_config);
+        assert_eq!(ra.preference, "high");
+        assert_eq!(ra.router_lifetime_s, 1800);
+        assert_eq!(ra.source_mac, Some([2; 6]));
+        assert_eq!(ra.prefixes, vec![(prefix, 64)]);
+        assert_eq!(ra.mtu, Some(1500));
+
+        let target: Ipv6Addr = "fe80::1".parse().unwrap();
+        let na = parse(
+            ICMPV6_NEIGHBOR_ADVERTISEMENT,
+            [0xe0, 0, 0, 0],
+            &neighbor_body(target, OPT_TARGET_LLA, Some([3; 6])),
+        );
+        assert_eq!(
+            na,
+            Some(NdpMessage::NeighborAdvertisement {
+                target,
+                target_mac: Some([3; 6]),
+                router: true,
+                solicited: true,
+                override_flag: true
+            })
+        );
+        let ns = parse(
+            ICMPV6_NEIGHBOR_SOLICITATION,
+            [0; 4],
+            &neighbor_body(target, 1, None),
+        );
+        assert_eq!(
+            ns,
+            Some(NdpMessage::NeighborSolicitation {
+                target,
+                source_mac: None
+            })
+        );
+        assert!(parse(ICMPV6_NEIGHBOR_SOLICITATION, [0; 4], &[0; 8]).is_none());
+    }
+}
@@ -0,0 +1,564 @@
+//! QUIC (RFC 9000/9001/9369): cabeceras largas y cortas, claves Initial
+//! derivadas del connection ID de destino, descifrado de paquetes Initial y
+//! extracción de frames CRYPTO.
+
+use crate::network::tls;
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes128Gcm, Nonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+
+pub const VERSION_1: u32 = 0x0000_0001;
+pub const VERSION_2: u32 = 0x6b33_43cf;
+
+const SALT_V1: [u8; 20] = [
+    0x38, 0x76, 0x2c, 0xf7, 0xf5, 0x59, 0x34, 0xb3, 0x4d, 0x17, 0x9a, 0xe6, 0xa4, 0xc8, 0x0c, 0xad,
+    0xcc, 0xbb, 0x7f, 0x0a,
+];
+const SALT_V2: [u8; 20] = [
+    0x0d, 0xed, 0xe3, 0xde, 0xf7, 0x00, 0xa6, 0xdb, 0x81, 0x93, 0x81, 0xbe, 0x6e, 0x26, 0x9d, 0xcb,
+    0xf9, 0xbd, 0x2e, 0xd9,
+];
+
+/// Longitud máxima de connection ID en QUIC v1
+pub const MAX_CID_LEN: usize = 20;
+// Datos CRYPTO de Initial que se reensamblan por sentido (ClientHello con PQ incluido)
+const MAX_CRYPTO_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LongType {
+    Initial,
+    ZeroRtt,
+    Handshake,
+    Retry,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet<'a> {
+    Long(LongHeader<'a>),
+    /// Cabecera corta (1-RTT): el DCID no lleva longitud, se resuelve con los CIDs conocidos
+    Short(&'a [u8]),
+    VersionNegotiation {
+        dcid: &'a [u8],
+        scid: &'a [u8],
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LongHeader<'a> {
+    pub kind: LongType,
+    pub version: u32,
+    pub dcid: &'a [u8],
+    pub scid: &'a [u8],
+    /// Paquete completo (cabecera + payload protegido)
+    pub bytes: &'a [u8],
+    /// Offset del número de paquete dentro de `bytes` (solo Initial/0-RTT/Handshake)
+    pub pn_offset: usize,
+}
+
+pub fn version_name(v: u32) -> String {
+    match v {
+        VERSION_1 => "QUICv1".to_string(),
+        VERSION_2 => "QUICv2".to_string(),
+        v if v >> 8 == 0xff_0000 => format!("draft-{}", v & 0xff),
+        v if v & 0x0f0f_0f0f == 0x0a0a_0a0a => format!("grease-0x{v:08x}"),
+        v => format!("0x{v:08x}"),
+    }
+}
+
+/// Nombre de un código de error de transporte (RFC 9000 §20.1); el rango
+/// 0x0100-0x01ff lleva una alerta TLS
+pub fn error_name(code: u64) -> String {
+    let name = match code {
+        0x00 => "NO_ERROR",
+        0x01 => "INTERNAL_ERROR",
+        0x02 => "CONNECTION_REFUSED",
+        0x03 => "FLOW_CONTROL_ERROR",
+        0x04 => "STREAM_LIMIT_ERROR",
+        0x05 => "STREAM_STATE_ERROR",
+        0x06 => "FINAL_SIZE_ERROR",
+        0x07 => "FRAME_ENCODING_ERROR",
+        0x08 => "TRANSPORT_PARAMETER_ERROR",
+        0x09 => "CONNECTION_ID_LIMIT_ERROR",
+        0x0a => "PROTOCOL_VIOLATION",
+        0x0b => "INVALID_TOKEN",
+        0x0c => "APPLICATION_ERROR",
+        0x0d => "CRYPTO_BUFFER_EXCEEDED",
+        0x0e => "KEY_UPDATE_ERROR",
+        0x0f => "AEAD_LIMIT_REACHED",
+        0x10 => "NO_VIABLE_PATH",
+        0x0100..=0x01ff => {
+            return format!("CRYPTO_ERROR({})", tls::alert_name((code - 0x0100) as u8))
+        }
+        _ => return format!("0x{code:x}"),
+    };
+    name.to_string()
+}
+
+/// Entero de longitud variable (RFC 9000 §16): valor y bytes consumidos
+pub fn read_varint(data: &[u8]) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let len = 1usize << (first >> 6);
+    let bytes = data.get(..len)?;
+    let mut v = (first & 0x3f) as u64;
+    for b in &bytes[1..] {
+        v = (v << 8) | *b as u64;
+    }
+    Some((v, len))
+}
+
+fn long_type(version: u32, bits: u8) -> LongType {
+    // QUIC v2 rota los códigos de tipo (RFC 9369 §3.2)
+    let bits = if version == VERSION_2 {
+        (bits + 3) % 4
+    } else {
+        bits
+    };
+    match bits {
+        0 => LongType::Initial,
+        1 => LongType::ZeroRtt,
+        2 => LongType::Handshake,
+        _ => LongType::Retry,
+    }
+}
+
+/// Separa los paquetes (posiblemente coalescidos) de un datagrama
+pub fn parse_datagram(data: &[u8]) -> Vec<Packet<'_>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let rest = &data[pos..];
+        let first = rest[0];
+        if first & 0x80 == 0 {
+            // Cabecera corta: ocupa el resto del datagrama
+            if first & 0x40 != 0 {
+                out.push(Packet::Short(rest));
+            }
+            break;
+        }
+        let Some((packet, len)) = parse_long(rest) else {
+            break;
+        };
+        out.push(packet);
+        pos += len;
+    }
+    out
+}
+
+fn parse_long(data: &[u8]) -> Option<(Packet<'_>, usize)> {
+    let version = u32::from_be_bytes(data.get(1..5)?.try_into().ok()?);
+    let dcid_len = *data.get(5)? as usize;
+    let dcid = data.get(6..6 + dcid_len)?;
+    let scid_len = *data.get(6 + dcid_len)? as usize;
+    let scid_start = 7 + dcid_len;
+    let scid = data.get(scid_start..scid_start + scid_len)?;
+    let mut pos = scid_start + scid_len;
+
+    if version == 0 {
+        return Some((Packet::VersionNegotiation { dcid, scid }, data.len()));
+    }
+    if dcid_len > MAX_CID_LEN || scid_len > MAX_CID_LEN || data[0] & 0x40 == 0 {
+        return None;
+    }
+    let kind = long_type(version, (data[0] >> 4) & 0x03);
+    if kind == LongType::Retry {
+        // Token de reintento + tag de integridad hasta el final del datagrama
+        let header = LongHeader {
+            kind,
+            version,
+            dcid,
+            scid,
+            bytes: data,
+            pn_offset: pos,
+        };
+        return Some((Packet::Long(header), data.len()));
+    }
+    if kind == LongType::Initial {
+        let (token_len, n) = read_varint(data.get(pos..)?)?;
+        pos = pos.checked_add(n + token_len as usize)?;
+    }
+    let (length, n) = read_varint(data.get(pos..)?)?;
+    pos += n;
+    let end = pos.checked_add(length as usize)?;
+    if end > data.len() {
+        return None;
+    }
+    let header = LongHeader {
+        kind,
+        version,
+        dcid,
+        scid,
+        bytes: &data[..end],
+        pn_offset: pos,
+    };
+    Some((Packet::Long(header), end))
+}
+
+fn expand_label(secret: &[u8], label: &str, len: usize) -> Option<Vec<u8>> {
+    let full = format!("tls13 {label}");
+    let mut info = (len as u16).to_be_bytes().to_vec();
+    info.push(full.len() as u8);
+    info.extend_from_slice(full.as_bytes());
+    info.push(0); // contexto vacío
+    let hk = Hkdf::<Sha256>::from_prk(secret).ok()?;
+    let mut out = vec![0u8; len];
+    hk.expand(&info, &mut out).ok()?;
+    Some(out)
+}
+
+/// Claves de protección de los paquetes Initial de un sentido
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitialKeys {
+    pub key: [u8; 16],
+    pub iv: [u8; 12],
+    pub hp: [u8; 16],
+}
+
+impl InitialKeys {
+    /// Claves derivadas del DCID original del cliente (RFC 9001 §5.2)
+    pub fn derive(version: u32, dcid: &[u8], server: bool) -> Option<InitialKeys> {
+        let (salt, prefix): (&[u8], &str) = match version {
+            VERSION_1 => (&SALT_V1, "quic"),
+            VERSION_2 => (&SALT_V2, "quicv2"),
+            _ => return None,
+        };
+        let (initial, _) = Hkdf::<Sha256>::extract(Some(salt), dcid);
+        let side = if server { "server in" } else { "client in" };
+        let secret = expand_label(&initial, side, 32)?;
+        Some(InitialKeys {
+            key: expand_label(&secret, &format!("{prefix} key"), 16)?
+                .try_into()
+                .ok()?,
+            iv: expand_label(&secret, &format!("{prefix} iv"), 12)?
+                .try_into()
+                .ok()?,
+            hp: expand_label(&secret, &format!("{prefix} hp"), 16)?
+                .try_into()
+                .ok()?,
+        })
+    }
+
+    fn mask(&self, sample: &[u8]) -> [u8; 16] {
+        let cipher = Aes128::new(GenericArray::from_slice(&self.hp));
+        let mut block = GenericArray::clone_from_slice(sample);
+        cipher.encrypt_block(&mut block);
+        block.into()
+    }
+
+    fn nonce(&self, pn: u64) -> [u8; 12] {
+        let mut nonce = self.iv;
+        for (i, b) in pn.to_be_bytes().iter().enumerate() {
+            nonce[4 + i] ^= b;
+        }
+        nonce
+    }
+}
+
+/// Reconstruye el número de paquete completo a partir del truncado (RFC 9000 §A.3)
+pub fn decode_packet_number(largest: Option<u64>, truncated: u64, bits: u32) -> u64 {
+    let expected = largest.map_or(0, |l| l + 1);
+    let win = 1u64 << bits;
+    let hwin = win / 2;
+    let mask = win - 1;
+    let candidate = (expected & !mask) | truncated;
+    if candidate + hwin <= expected && candidate < (1u64 << 62) - win {
+        candidate + win
+    } else if candidate > expected + hwin && candidate >= win {
+        candidate - win
+    } else {
+        candidate
+    }
+}
+
+/// Quita la protección de cabecera y descifra un paquete Initial.
+/// Devuelve el número de paquete completo y el payload en claro.
+pub fn decrypt_initial(
+    hdr: &LongHeader,
+    keys: &InitialKeys,
+    largest_pn: Option<u64>,
+) -> Option<(u64, Vec<u8>)> {
+    let bytes = hdr.bytes;
+    let sample = bytes.get(hdr.pn_offset + 4..hdr.pn_offset + 20)?;
+    let mask = keys.mask(sample);
+
+    let mut header = bytes[..hdr.pn_offset].to_vec();
+    header[0] ^= mask[0] & 0x0f;
+    let pn_len = (header[0] & 0x03) as usize + 1;
+    let mut truncated = 0u64;
+    for i in 0..pn_len {
+        let b = bytes.get(hdr.pn_offset + i)? ^ mask[1 + i];
+        header.push(b);
+        truncated = (truncated << 8) | b as u64;
+    }
+    let pn = decode_packet_number(largest_pn, truncated, (pn_len * 8) as u32);
+
+    let cipher = Aes128Gcm::new(GenericArray::from_slice(&keys.key));
+    let nonce = keys.nonce(pn);
+    let plain = cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &bytes[hdr.pn_offset + pn_len..],
+                aad: &header,
+            },
+        )
+        .ok()?;
+    Some((pn, plain))
+}
+
+/// Frames relevantes de un paquete Initial en claro
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InitialFrames {
+    /// (offset, datos) de cada frame CRYPTO
+    pub crypto: Vec<(u64, Vec<u8>)>,
+    /// Código de error de CONNECTION_CLOSE, si lo hubo
+    pub close_code: Option<u64>,
+}
+
+/// Recorre los frames permitidos en Initial (RFC 9000 §12.4)
+pub fn parse_initial_frames(payload: &[u8]) -> Option<InitialFrames> {
+    let mut out = InitialFrames::default();
+    let mut pos = 0;
+    let varint = |pos: &mut usize| -> Option<u64> {
+        let (v, n) = read_varint(payload.get(*pos..)?)?;
+        *pos += n;
+        Some(v)
+    };
+    while pos < payload.len() {
+        let ty = varint(&mut pos)?;
+        match ty {
+            0x00 | 0x01 => {} // PADDING, PING
+            0x02 | 0x03 => {
+                // ACK: largest, delay, count, first range, (gap, range)*, [ECN]
+                varint(&mut pos)?;
+                varint(&mut pos)?;
+                let ranges = varint(&mut pos)?;
+                varint(&mut pos)?;
+                for _ in 0..ranges {
+                    varint(&mut pos)?;
+                    varint(&mut pos)?;
+                }
+                if ty == 0x03 {
+                    for _ in 0..3 {
+                        varint(&mut pos)?;
+                    }
+                }
+            }
+            0x06 => {
+                let offset = varint(&mut pos)?;
+                let len = varint(&mut pos)? as usize;
+                let data = payload.get(pos..pos.checked_add(len)?)?;
+                out.crypto.push((offset, data.to_vec()));
+                pos += len;
+            }
+            0x1c | 0x1d => {
+                let code = varint(&mut pos)?;
+                if ty == 0x1c {
+                    varint(&mut pos)?; // tipo de frame
+                }
+                let reason = varint(&mut pos)? as usize;
+                pos = pos.checked_add(reason)?;
+                out.close_code = Some(code);
+            }
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Reensambla el stream CRYPTO de un sentido y extrae el primer mensaje de handshake
+#[derive(Debug, Default)]
+pub struct CryptoStream {
+    contiguous: Vec<u8>,
+    pending: BTreeMap<u64, Vec<u8>>,
+    pending_bytes: usize,
+    /// Bytes recibidos más de una vez (retransmisiones de Initial)
+    pub retransmitted_bytes: u64,
+}
+
+impl CryptoStream {
+    pub fn insert(&mut self, offset: u64, data: &[u8]) {
+        let have = self.contiguous.len() as u64;
+        let end = offset + data.len() as u64;
+        if end <= have {
+            self.retransmitted_bytes += data.len() as u64;
+            return;
+        }
+        if offset > have {
+            if self.pending_bytes + data.len() <= MAX_CRYPTO_BYTES
+                && !self.pending.contains_key(&offset)
+            {
+                self.pending_bytes += data.len();
+                self.pending.insert(offset, data.to_vec());
+            }
+            return;
+        }
+        if self.contiguous.len() + data.len() > MAX_CRYPTO_BYTES {
+            return;
+        }
+        self.contiguous
+            .extend_from_slice(&data[(have - offset) as usize..]);
+        // Lo pendiente que ya encaja
+        while let Some((&off, _)) = self.pending.iter().next() {
+            if off > self.contiguous.len() as u64 {
+                break;
+            }
+            let seg = self.pending.remove(&off).unwrap_or_default();
+            self.pending_bytes -= seg.len();
+            let have = self.contiguous.len() as u64;
+            if off + seg.len() as u64 > have {
+                self.contiguous
+                    .extend_from_slice(&seg[(have - off) as usize..]);
+            }
+        }
+    }
+
+    /// Primer mensaje de handshake completo: (tipo, cuerpo)
+    pub fn first_message(&self) -> Option<(u8, &[u8])> {
+        let h = &self.contiguous;
+        let len =
+            ((*h.get(1)? as usize) << 16) | ((*h.get(2)? as usize) << 8) | *h.get(3)? as usize;
+        Some((h[0], h.get(4..4 + len)?))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn varint(v: u64) -> Vec<u8> {
+        match v {
+            0..=63 => vec![v as u8],
+            64..=16383 => ((v as u16) | 0x4000).to_be_bytes().to_vec(),
+            _ => ((v as u32) | 0x8000_0000).to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Construye un paquete Initial v1 protegido con `frames` como payload
+    pub(crate) fn initial_packet(
+        dcid: &[u8],
+        scid: &[u8],
+        key_dcid: &[u8],
+        server: bool,
+        pn: u32,
+        frames: &[u8],
+    ) -> Vec<u8> {
+        let keys = InitialKeys::derive(VERSION_1, key_dcid, server).unwrap();
+        let pn_bytes = pn.to_be_bytes();
+        let mut header = vec![0xc3]; // Initial, PN de 4 bytes
+        header.extend_from_slice(&VERSION_1.to_be_bytes());
+        header.push(dcid.len() as u8);
+        header.extend_from_slice(dcid);
+        header.push(scid.len() as u8);
+        header.extend_from_slice(scid);
+        header.push(0); // sin token
+        header.extend(varint((4 + frames.len() + 16) as u64));
+        let pn_offset = header.len();
+        header.extend_from_slice(&pn_bytes);
+
+        let cipher = Aes128Gcm::new(GenericArray::from_slice(&keys.key));
+        let sealed = cipher
+            .encrypt(
+                Nonce::from_slice(&keys.nonce(pn as u64)),
+                Payload {
+                    msg: frames,
+                    aad: &header,
+                },
+            )
+            .unwrap();
+        let mut packet = header;
+        packet.extend(sealed);
+        let mask = keys.mask(&packet[pn_offset + 4..pn_offset + 20]);
+        packet[0] ^= mask[0] & 0x0f;
+        for i in 0..4 {
+            packet[pn_offset + i] ^= mask[1 + i];
+        }
+        packet
+    }
+
+    /// Frame CRYPTO con el mensaje de handshake contenido en un registro TLS de prueba
+    pub(crate) fn crypto_frame(offset: u64, tls_record: &[u8]) -> Vec<u8> {
+        let msg = &tls_record[5..];
+        let mut f = vec![0x06];
+        f.extend(varint(offset));
+        f.extend(varint(msg.len() as u64));
+        f.extend_from_slice(msg);
+        f
+    }
+
+    #[test]
+    fn initial_keys_match_rfc9001_vectors() {
+        let dcid = unhex("8394c8f03e515708");
+        let c = InitialKeys::derive(VERSION_1, &dcid, false).unwrap();
+        assert_eq!(c.key.to_vec(), unhex("1f369613dd76d5467730efcbe3b1a22d"));
+        assert_eq!(c.iv.to_vec(), unhex("fa044b2f42a3fd3b46fb255c"));
+        assert_eq!(c.hp.to_vec(), unhex("9f50449e04a0e810283a1e9933adedd2"));
+        let mask = c.mask(&unhex("d1b1c98dd7689fb8ec11d242b123dc9b"));
+        assert_eq!(mask[..5].to_vec(), unhex("437b9aec36"));
+        let s = InitialKeys::derive(VERSION_1, &dcid, true).unwrap();
+        assert_eq!(s.key.to_vec(), unhex("cf3a5331653c364c88f0f379b6067e37"));
+        assert_eq!(
+            decode_packet_number(Some(0xa82f30ea), 0x9b32, 16),
+            0xa82f9b32
+        );
+    }
+
+    #[test]
+    fn decrypts_coalesced_initial_and_reassembles_crypto() {
+        let hello =
+            crate::network::tls::tests::client_hello_record("quic.example", &[0x1301], &["h3"]);
+        let msg_len = hello.len() - 5;
+        let split = msg_len / 2;
+        let first = crypto_frame(0, &hello[..5 + split]);
+        let mut second = vec![0x06];
+        second.extend(varint(split as u64));
+        second.extend(varint((msg_len - split) as u64));
+        second.extend_from_slice(&hello[5 + split..]);
+        second.extend_from_slice(&[0u8; 20]); // PADDING
+
+        let dcid = unhex("8394c8f03e515708");
+        let mut datagram = initial_packet(&dcid, b"cli", &dcid, false, 1, &second);
+        datagram.extend(initial_packet(&dcid, b"cli", &dcid, false, 0, &first));
+
+        let keys = InitialKeys::derive(VERSION_1, &dcid, false).unwrap();
+        let mut stream = CryptoStream::default();
+        let packets = parse_datagram(&datagram);
+        assert_eq!(packets.len(), 2);
+        let mut pns = Vec::new();
+        for p in &packets {
+            let Packet::Long(h) = p else {
+                panic!("se esperaba cabecera larga")
+            };
+            assert_eq!((h.kind, h.scid), (LongType::Initial, &b"cli"[..]));
+            let (pn, plain) = decrypt_initial(h, &keys, None).unwrap();
+            pns.push(pn);
+            for (off, data) in parse_initial_frames(&plain).unwrap().crypto {
+                stream.insert(off, &data);
+            }
+        }
+        assert_eq!(pns, vec![1, 0]);
+        let (ty, body) = stream.first_message().unwrap();
+        assert_eq!(ty, crate::network::tls::HS_CLIENT_HELLO);
+        let ch = crate::network::tls::parse_client_hello(body).unwrap();
+        assert_eq!(ch.sni.as_deref(), Some("quic.example"));
+        assert_eq!(ch.alpn, vec!["h3"]);
+
+        // Con las claves del otro sentido no descifra
+        let wrong = InitialKeys::derive(VERSION_1, &dcid, true).unwrap();
+        let Packet::Long(h) = &packets[0] else {
+            unreachable!()
+        };
+        assert!(decrypt_initial(h, &wrong, None).is_none());
+    }
+}
@@ -0,0 +1,235 @@
+/// Reensamblado de un sentido de un stream TCP.
+///
+/// Entrega los bytes en orden de secuencia, una sola vez, junto con el timestamp
+/// del segmento que los trajo. Los segmentos adelantados se guardan hasta un
+/// límite de memoria; si se supera (o la captura perdió datos), se salta el hueco
+/// y se contabiliza para que el parser de aplicación sepa que hubo pérdida.
+#[derive(Debug)]
+pub struct StreamReassembler {
+    next_seq: Option<u32>,
+    pending: Vec<PendingSegment>,
+    pending_bytes: usize,
+    max_pending_bytes: usize,
+    delivered_bytes: u64,
+    gap_bytes: u64,
+    gaps: u32,
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct PendingSegment {
+    seq: u32,
+    data: Vec<u8>,
+    ts_us: u64,
+}
+
+impl Default for StreamReassembler {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_PENDING)
+    }
+}
+
+impl StreamReassembler {
+    pub const DEFAULT_MAX_PENDING: usize = 1024 * 1024; // 1 MiB por sentido
+
+    pub fn new(max_pending_bytes: usize) -> Self {
+        StreamReassembler {
+            next_seq: None,
+            pending: Vec::new(),
+            pending_bytes: 0,
+            max_pending_bytes,
+            delivered_bytes: 0,
+            gap_bytes: 0,
+            gaps: 0,
+            closed: false,
+        }
+    }
+
+    /// SYN visto: el primer byte de datos es ISN + 1
+    pub fn on_syn(&mut self, isn: u32) {
+        if self.next_seq.is_none() {
+            self.next_seq = Some(isn.wrapping_add(1));
+        }
+    }
+
+    /// FIN/RST: no se esperan más datos
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn delivered_bytes(&self) -> u64 {
+        self.delivered_bytes
+    }
+
+    pub fn gap_bytes(&self) -> u64 {
+        self.gap_bytes
+    }
+
+    pub fn gaps(&self) -> u32 {
+        self.gaps
+    }
+
+    /// Añade un segmento y entrega a `sink` todos los bytes que quedan contiguos.
+    /// `sink` recibe `(datos, ts_us, hueco_previo)`.
+    pub fn push<F>(&mut self, seq: u32, data: &[u8], ts_us: u64, sink: &mut F)
+    where
+        F: FnMut(&[u8], u64, bool),
+    {
+        if data.is_empty() {
+            return;
+        }
+        // Captura empezada a mitad del stream: se sincroniza con el primer segmento
+        let next = *self.next_seq.get_or_insert(seq);
+        let offset = seq.wrapping_sub(next) as i32;
+
+        if offset <= 0 {
+            let skip = offset.unsigned_abs() as usize;
+            if skip < data.len() {
+                self.deliver(&data[skip..], ts_us, false, sink);
+                self.drain_pending(sink);
+            }
+            // Si no, es una retransmisión de bytes ya entregados
+            return;
+        }
+
+        if self.pending_bytes + data.len() > self.max_pending_bytes {
+            // Sin memoria para esperar al hueco: se da por perdido
+            self.pending.push(PendingSegment {
+                seq,
+                data: data.to_vec(),
+                ts_us,
+            });
+            self.pending_bytes += data.len();
+            self.skip_gap(sink);
+            return;
+        }
+        if !self
+            .pending
+            .iter()
+            .any(|p| p.seq == seq && p.data.len() >= data.len())
+        {
+            self.pending.push(PendingSegment {
+                seq,
+                data: data.to_vec(),
+                ts_us,
+            });
+            self.pending_bytes += data.len();
+        }
+    }
+
+    /// Salta el hueco hasta el primer segmento pendiente (fin de captura o falta de memoria)
+    pub fn skip_gap<F>(&mut self, sink: &mut F)
+    where
+        F: FnMut(&[u8], u64, bool),
+    {
+        let Some(next) = self.next_seq else {
+            return;
+        };
+        let Some(first) = self
+            .pending
+            .iter()
+            .map(|p| p.seq)
+            .min_by_key(|s| s.wrapping_sub(next))
+        else {
+            return;
+        };
+        let gap = first.wrapping_sub(next);
+        if gap as i32 > 0 {
+            self.gaps += 1;
+            self.gap_bytes += gap as u64;
+            self.next_seq = Some(first);
+            // El primer trozo tras el hueco se marca para que el parser se resincronice
+            let idx = self
+                .pending
+                .iter()
+                .position(|p| p.seq == first)
+                .expect("segmento pendiente");
+            let seg = self.pending.swap_remove(idx);
+            self.pending_bytes -= seg.data.len();
+            self.deliver(&seg.data, seg.ts_us, true, sink);
+        }
+        self.drain_pending(sink);
+    }
+
+    fn deliver<F>(&mut self, data: &[u8], ts_us: u64, after_gap: bool, sink: &mut F)
+    where
+        F: FnMut(&[u8], u64, bool),
+    {
+        let next = self.next_seq.unwrap_or(0);
+        self.next_seq = Some(next.wrapping_add(data.len() as u32));
+        self.delivered_bytes += data.len() as u64;
+        sink(data, ts_us, after_gap);
+    }
+
+    fn drain_pending<F>(&mut self, sink: &mut F)
+    where
+        F: FnMut(&[u8], u64, bool),
+    {
+        loop {
+            let Some(next) = self.next_seq else {
+                return;
+            };
+            // Segmento que empieza en (o antes de) next y aporta bytes nuevos
+            let Some(idx) = self.pending.iter().position(|p| {
+                let off = p.seq.wrapping_sub(next) as i32;
+                off <= 0
+            }) else {
+                return;
+            };
+            let seg = self.pending.swap_remove(idx);
+            self.pending_bytes -= seg.data.len();
+            let skip = (next.wrapping_sub(seg.seq)) as usize;
+            if skip < seg.data.len() {
+                self.deliver(&seg.data[skip..], seg.ts_us, false, sink);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(r: &mut StreamReassembler, seq: u32, data: &[u8], out: &mut Vec<u8>) -> bool {
+        let mut gap = false;
+        r.push(seq, data, 0, &mut |d, _, g| {
+            out.extend_from_slice(d);
+            gap |= g;
+        });
+        gap
+    }
+
+    #[test]
+    fn out_of_order_and_overlapping_segments_are_delivered_once() {
+        let mut r = StreamReassembler::default();
+        r.on_syn(999);
+        let mut out = Vec::new();
+        collect(&mut r, 1005, b"world", &mut out);
+        assert!(out.is_empty());
+        collect(&mut r, 1000, b"hello", &mut out);
+        assert_eq!(out, b"helloworld");
+        // Retransmisión parcialmente solapada
+        collect(&mut r, 1008, b"ld!!", &mut out);
+        assert_eq!(out, b"helloworld!!");
+        assert_eq!(r.delivered_bytes(), 12);
+    }
+
+    #[test]
+    fn sequence_wraparound_and_gap_skip() {
+        let mut r = StreamReassembler::new(8);
+        let mut out = Vec::new();
+        collect(&mut r, u32::MAX - 1, b"ab", &mut out);
+        collect(&mut r, 0, b"cd", &mut out);
+        assert_eq!(out, b"abcd");
+        // Hueco de 4 bytes y sin memoria para esperar
+        let gap = collect(&mut r, 6, b"0123456789", &mut out);
+        assert!(gap);
+        assert_eq!(out, b"abcd0123456789");
+        assert_eq!(r.gap_bytes(), 4);
+        assert_eq!(r.gaps(), 1);
+    }
+}
@@ -0,0 +1,254 @@
+//! Cabecera RTP y paquetes RTCP compuestos (RFC 3550), más la tabla de payload types estáticos (RFC 3551).
+
+pub const RTCP_SR: u8 = 200;
+pub const RTCP_RR: u8 = 201;
+pub const RTCP_SDES: u8 = 202;
+pub const RTCP_BYE: u8 = 203;
+pub const RTCP_APP: u8 = 204;
+pub const RTCP_XR: u8 = 207;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RtpHeader {
+    pub marker: bool,
+    pub payload_type: u8,
+    pub sequence: u16,
+    pub timestamp: u32,
+    pub ssrc: u32,
+    /// Bytes de carga útil (sin CSRC, extensión ni relleno)
+    pub payload_len: usize,
+}
+
+/// Bloque de recepción de un SR/RR: cómo ve el emisor del informe a la fuente `ssrc`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportBlock {
+    pub ssrc: u32,
+    /// Fracción perdida desde el informe anterior (x/256)
+    pub fraction_lost: u8,
+    pub cumulative_lost: i32,
+    pub highest_seq: u32,
+    /// Jitter entre llegadas en unidades del reloj RTP
+    pub jitter: u32,
+    /// 32 bits centrales del NTP del último SR recibido (0 si ninguno)
+    pub lsr: u32,
+    /// Retardo desde ese SR, en unidades de 1/65536 s
+    pub dlsr: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RtcpPacket {
+    SenderReport {
+        ssrc: u32,
+        /// 32 bits centrales del timestamp NTP (lo que los receptores devuelven como LSR)
+        ntp_middle: u32,
+        packet_count: u32,
+        octet_count: u32,
+        reports: Vec<ReportBlock>,
+    },
+    ReceiverReport {
+        ssrc: u32,
+        reports: Vec<ReportBlock>,
+    },
+    Bye {
+        ssrcs: Vec<u32>,
+    },
+    /// SDES, APP, XR y demás: solo se cuentan
+    Other {
+        packet_type: u8,
+    },
+}
+
+/// Códec y reloj de los payload types estáticos
+pub fn static_payload(pt: u8) -> Option<(&'static str, u32)> {
+    Some(match pt {
+        0 => ("PCMU", 8000),
+        3 => ("GSM", 8000),
+        4 => ("G723", 8000),
+        8 => ("PCMA", 8000),
+        // G.722 usa reloj RTP de 8 kHz por error histórico de la RFC 1890
+        9 => ("G722", 8000),
+        13 => ("CN", 8000),
+        18 => ("G729", 8000),
+        26 => ("JPEG", 90000),
+        31 => ("H261", 90000),
+        34 => ("H263", 90000),
+        _ => return None,
+    })
+}
+
+/// Cabecera RTP v2; rechaza los payload types que colisionan con RTCP (72-76)
+pub fn parse_rtp(data: &[u8]) -> Option<RtpHeader> {
+    if data.len() < 12 || data[0] >> 6 != 2 {
+        return None;
+    }
+    let payload_type = data[1] & 0x7f;
+    if (72..=76).contains(&payload_type) {
+        return None;
+    }
+    let csrc = (data[0] & 0x0f) as usize;
+    let mut header_len = 12 + 4 * csrc;
+    if data[0] & 0x10 != 0 {
+        let ext = data.get(header_len..header_len + 4)?;
+        header_len += 4 + 4 * u16::from_be_bytes([ext[2], ext[3]]) as usize;
+    }
+    let padding = if data[0] & 0x20 != 0 {
+        *data.last()? as usize
+    } else {
+        0
+    };
+    let payload_len = data.len().checked_sub(header_len + padding)?;
+    Some(RtpHeader {
+        marker: data[1] & 0x80 != 0,
+        payload_type,
+        sequence: u16::from_be_bytes([data[2], data[3]]),
+        timestamp: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+        ssrc: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+        payload_len,
+    })
+}
+
+fn be32(b: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+fn report_blocks(body: &[u8], count: usize) -> Vec<ReportBlock> {
+    body.chunks_exact(24)
+        .take(count)
+        .map(|b| {
+            // Pérdida acumulada: entero con signo de 24 bits
+            let lost = (((b[5] as i32) << 16 | (b[6] as i32) << 8 | b[7] as i32) << 8) >> 8;
+            ReportBlock {
+                ssrc: be32(b, 0),
+                fraction_lost: b[4],
+                cumulative_lost: lost,
+                highest_seq: be32(b, 8),
+                jitter: be32(b, 12),
+                lsr: be32(b, 16),
+                dlsr: be32(b, 20),
+            }
+        })
+        .collect()
+}
+
+/// Paquete RTCP compuesto; `None` si el primero no es un RTCP v2 válido
+pub fn parse_rtcp(mut data: &[u8]) -> Option<Vec<RtcpPacket>> {
+    let mut out = Vec::new();
+    while data.len() >= 4 {
+        let count = (data[0] & 0x1f) as usize;
+        let packet_type = data[1];
+        let len = (u16::from_be_bytes([data[2], data[3]]) as usize + 1) * 4;
+        if data[0] >> 6 != 2 || !(RTCP_SR..=RTCP_XR).contains(&packet_type) || len > data.len() {
+            break;
+        }
+        let body = &data[4..len];
+        let packet = match packet_type {
+            RTCP_SR if body.len() >= 24 => RtcpPacket::SenderReport {
+                ssrc: be32(body, 0),
+                ntp_middle: be32(body, 6),
+                packet_count: be32(body, 16),
+                octet_count: be32(body, 20),
+                reports: report_blocks(&body[24..], count),
+            },
+            RTCP_RR if body.len() >= 4 => RtcpPacket::ReceiverReport {
+                ssrc: be32(body, 0),
+                reports: report_blocks(&body[4..], count),
+            },
+            RTCP_BYE => RtcpPacket::Bye {
+                ssrcs: body
+                    .chunks_exact(4)
+                    .take(count)
+                    .map(|c| be32(c, 0))
+                    .collect(),
+            },
+            _ => RtcpPacket::Other { packet_type },
+        };
+        out.push(packet);
+        data = &data[len..];
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn rtp_packet(pt: u8, seq: u16, ts: u32, ssrc: u32, payload: usize) -> Vec<u8> {
+        let mut p = vec![0x80, pt];
+        p.extend(seq.to_be_bytes());
+        p.extend(ts.to_be_bytes());
+        p.extend(ssrc.to_be_bytes());
+        p.extend(vec![0xd5; payload]);
+        p
+    }
+
+    /// SR de `ssrc` seguido de un RR con un bloque sobre `about`
+    pub(crate) fn rtcp_sr_rr(ssrc: u32, ntp_middle: u32, about: u32, block: [u32; 5]) -> Vec<u8> {
+        let mut p = vec![0x80, RTCP_SR, 0, 6];
+        p.extend(ssrc.to_be_bytes());
+        p.extend([0, 0]);
+        p.extend(ntp_middle.to_be_bytes());
+        p.extend([0, 0]);
+        p.extend([0; 4]); // timestamp RTP
+        p.extend(100u32.to_be_bytes());
+        p.extend(16000u32.to_be_bytes());
+        p.extend([0x81, RTCP_RR, 0, 7]);
+        p.extend(ssrc.to_be_bytes());
+        p.extend(about.to_be_bytes());
+        for v in block {
+            p.extend(v.to_be_bytes());
+        }
+        p.extend(0u32.to_be_bytes());
+        p
+    }
+
+    #[test]
+    fn parses_rtp_header_with_csrc_extension_and_padding() {
+        let mut p = vec![0xb1, 0x80 | 96];
+        p.extend(0x1234u16.to_be_bytes());
+        p.extend(1000u32.to_be_bytes());
+        p.extend(0xdeadbeefu32.to_be_bytes());
+        p.extend([0, 0, 0, 1]); // CSRC
+        p.extend([0xbe, 0xde, 0, 1, 1, 2, 3, 4]); // extensión de una palabra
+        p.extend([9; 20]);
+        p.extend([0, 0, 3]); // relleno
+        let h = parse_rtp(&p).unwrap();
+        assert!(h.marker);
+        assert_eq!(h.payload_type, 96);
+        assert_eq!(h.sequence, 0x1234);
+        assert_eq!(h.timestamp, 1000);
+        assert_eq!(h.ssrc, 0xdeadbeef);
+        assert_eq!(h.payload_len, 20);
+        // PT 72-76 es RTCP, no RTP
+        assert!(parse_rtp(&rtp_packet(72, 1, 1, 1, 10)).is_none());
+        assert!(parse_rtp(&[0x40; 20]).is_none());
+    }
+
+    #[test]
+    fn parses_compound_sender_and_receiver_report() {
+        let raw = rtcp_sr_rr(
+            7,
+            0x1234_5678,
+            9,
+            [0x20ff_fffe, 500, 40, 0x1234_5678, 65536],
+        );
+        let packets = parse_rtcp(&raw).unwrap();
+        assert_eq!(packets.len(), 2);
+        let RtcpPacket::SenderReport {
+            ntp_middle,
+            packet_count,
+            ..
+        } = &packets[0]
+        else {
+            panic!("se esperaba un SR")
+        };
+        assert_eq!((*ntp_middle, *packet_count), (0x1234_5678, 100));
+        let RtcpPacket::ReceiverReport { reports, .. } = &packets[1] else {
+            panic!("se esperaba un RR")
+        };
+        assert_eq!(reports[0].ssrc, 9);
+        assert_eq!(reports[0].fraction_lost, 0x20);
+        assert_eq!(reports[0].cumulative_lost, -2);
+        assert_eq!(reports[0].jitter, 40);
+        assert_eq!(reports[0].dlsr, 65536);
+        assert!(parse_rtcp(&rtp_packet(0, 1, 1, 1, 10)).is_none());
+    }
+}
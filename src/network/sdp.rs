@@ -0,0 +1,125 @@
+//! Descripciones de sesión SDP (RFC 4566): dirección de conexión, medios y `rtpmap`.
+
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtpMap {
+    pub payload_type: u8,
+    pub codec: String,
+    pub clock_hz: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpMedia {
+    /// "audio", "video", ...
+    pub kind: String,
+    /// Dirección de la línea `c=` del medio o, si falta, de la sesión
+    pub addr: Option<IpAddr>,
+    pub port: u16,
+    /// Puerto RTCP explícito (`a=rtcp:`); por convención es `port + 1`
+    pub rtcp_port: Option<u16>,
+    pub payload_types: Vec<u8>,
+    pub rtpmaps: Vec<RtpMap>,
+}
+
+impl SdpMedia {
+    pub fn rtcp_port(&self) -> u16 {
+        self.rtcp_port.unwrap_or(self.port.wrapping_add(1))
+    }
+
+    /// Códec y reloj del payload type, del `rtpmap` o de la tabla estática
+    pub fn codec(&self, pt: u8) -> Option<(String, u32)> {
+        self.rtpmaps
+            .iter()
+            .find(|m| m.payload_type == pt)
+            .map(|m| (m.codec.clone(), m.clock_hz))
+            .or_else(|| crate::network::rtp::static_payload(pt).map(|(c, hz)| (c.to_string(), hz)))
+    }
+}
+
+fn connection(value: &str) -> Option<IpAddr> {
+    // c=IN IP4 198.51.100.1[/ttl]
+    let addr = value.split_whitespace().nth(2)?;
+    addr.split('/').next()?.parse().ok()
+}
+
+/// Interpreta el cuerpo SDP; `None` si no hay ninguna línea `m=`
+pub fn parse(body: &[u8]) -> Option<Vec<SdpMedia>> {
+    let text = std::str::from_utf8(body).ok()?;
+    let mut session_addr = None;
+    let mut media: Vec<SdpMedia> = Vec::new();
+    for line in text.lines() {
+        let Some((key, value)) = line.trim_end().split_once('=') else {
+            continue;
+        };
+        match (key, media.last_mut()) {
+            ("c", None) => session_addr = connection(value),
+            ("c", Some(m)) => m.addr = connection(value).or(m.addr),
+            ("m", _) => {
+                // m=audio 49170[/2] RTP/AVP 0 8 101
+                let mut it = value.split_whitespace();
+                let (Some(kind), Some(port)) = (it.next(), it.next()) else {
+                    continue;
+                };
+                let Some(port) = port.split('/').next().and_then(|p| p.parse().ok()) else {
+                    continue;
+                };
+                let _proto = it.next();
+                media.push(SdpMedia {
+                    kind: kind.to_string(),
+                    addr: session_addr,
+                    port,
+                    rtcp_port: None,
+                    payload_types: it.filter_map(|pt| pt.parse().ok()).collect(),
+                    rtpmaps: Vec::new(),
+                });
+            }
+            ("a", Some(m)) => {
+                if let Some(rest) = value.strip_prefix("rtpmap:") {
+                    // a=rtpmap:101 telephone-event/8000[/canales]
+                    let Some((pt, enc)) = rest.split_once(' ') else {
+                        continue;
+                    };
+                    let mut enc = enc.split('/');
+                    let (Ok(pt), Some(codec), Some(Ok(clock_hz))) =
+                        (pt.parse(), enc.next(), enc.next().map(|c| c.trim().parse()))
+                    else {
+                        continue;
+                    };
+                    m.rtpmaps.push(RtpMap {
+                        payload_type: pt,
+                        codec: codec.to_string(),
+                        clock_hz,
+                    });
+                } else if let Some(rest) = value.strip_prefix("rtcp:") {
+                    m.rtcp_port = rest.split_whitespace().next().and_then(|p| p.parse().ok());
+                }
+            }
+            _ => {}
+        }
+    }
+    (!media.is_empty()).then_some(media)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_level_connection_overrides_session() {
+        let body = b"v=0\r\nc=IN IP4 10.0.0.1\r\nt=0 0\r\n\
+            m=audio 40000 RTP/AVP 0 96\r\na=rtpmap:96 opus/48000/2\r\n\
+            m=video 40002 RTP/AVP 97\r\nc=IN IP4 10.0.0.2/127\r\na=rtpmap:97 H264/90000\r\na=rtcp:40010\r\n";
+        let media = parse(body).unwrap();
+        assert_eq!(media.len(), 2);
+        assert_eq!(media[0].addr, Some("10.0.0.1".parse().unwrap()));
+        assert_eq!(media[0].payload_types, vec![0, 96]);
+        assert_eq!(media[0].codec(96), Some(("opus".to_string(), 48000)));
+        assert_eq!(media[0].codec(0), Some(("PCMU".to_string(), 8000)));
+        assert_eq!(media[0].rtcp_port(), 40001);
+        assert_eq!(media[1].kind, "video");
+        assert_eq!(media[1].addr, Some("10.0.0.2".parse().unwrap()));
+        assert_eq!(media[1].rtcp_port(), 40010);
+        assert!(parse(b"v=0\r\n").is_none());
+    }
+}
@@ -0,0 +1,189 @@
+//! Mensajes SIP (RFC 3261): línea inicial, cabeceras (incluidas las formas compactas) y cuerpo.
+
+pub const SIP_PORT: u16 = 5060;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartLine {
+    Request { method: String, uri: String },
+    Response { code: u16, reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SipMessage {
+    pub start: StartLine,
+    /// Cabeceras en orden, con el nombre en minúsculas y las formas compactas expandidas
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl SipMessage {
+    /// Primer valor de la cabecera `name` (sin distinguir mayúsculas)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn call_id(&self) -> Option<&str> {
+        self.header("call-id")
+    }
+
+    /// CSeq: (número, método)
+    pub fn cseq(&self) -> Option<(u32, &str)> {
+        let mut it = self.header("cseq")?.split_whitespace();
+        Some((it.next()?.parse().ok()?, it.next()?))
+    }
+
+    /// Cuerpo SDP, si el Content-Type lo declara
+    pub fn sdp(&self) -> Option<&[u8]> {
+        let ct = self.header("content-type")?;
+        (ct.to_ascii_lowercase().starts_with("application/sdp") && !self.body.is_empty())
+            .then_some(self.body.as_slice())
+    }
+}
+
+/// Formas compactas de RFC 3261 §7.3.3
+fn expand_compact(name: &str) -> &str {
+    match name {
+        "i" => "call-id",
+        "m" => "contact",
+        "e" => "content-encoding",
+        "l" => "content-length",
+        "c" => "content-type",
+        "f" => "from",
+        "s" => "subject",
+        "k" => "supported",
+        "t" => "to",
+        "v" => "via",
+        _ => name,
+    }
+}
+
+/// Comprobación rápida antes de interpretar: ¿parece una línea inicial SIP?
+pub fn looks_like_sip(data: &[u8]) -> bool {
+    const METHODS: [&[u8]; 14] = [
+        b"INVITE ",
+        b"ACK ",
+        b"BYE ",
+        b"CANCEL ",
+        b"REGISTER ",
+        b"OPTIONS ",
+        b"PRACK ",
+        b"SUBSCRIBE ",
+        b"NOTIFY ",
+        b"PUBLISH ",
+        b"INFO ",
+        b"REFER ",
+        b"MESSAGE ",
+        b"UPDATE ",
+    ];
+    data.starts_with(b"SIP/2.0 ") || METHODS.iter().any(|m| data.starts_with(m))
+}
+
+/// Interpreta un mensaje SIP completo (un datagrama UDP o un segmento TCP con el mensaje entero)
+pub fn parse(data: &[u8]) -> Option<SipMessage> {
+    if !looks_like_sip(data) {
+        return None;
+    }
+    let head_end = data.windows(4).position(|w| w == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&data[..head_end]).ok()?;
+    let mut lines = head.split("\r\n");
+    let first = lines.next()?;
+    let start = if let Some(rest) = first.strip_prefix("SIP/2.0 ") {
+        let (code, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+        StartLine::Response {
+            code: code.parse().ok()?,
+            reason: reason.to_string(),
+        }
+    } else {
+        let mut parts = first.split(' ');
+        let method = parts.next()?.to_string();
+        let uri = parts.next()?.to_string();
+        if parts.next() != Some("SIP/2.0") {
+            return None;
+        }
+        StartLine::Request { method, uri }
+    };
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in lines {
+        // Continuación de la cabecera anterior (line folding)
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, v)) = headers.last_mut() {
+                v.push(' ');
+                v.push_str(line.trim());
+            }
+            continue;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        headers.push((expand_compact(&name).to_string(), value.trim().to_string()));
+    }
+
+    let body = &data[head_end + 4..];
+    let msg = SipMessage {
+        start,
+        headers,
+        body: Vec::new(),
+    };
+    let len = msg
+        .header("content-length")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(body.len())
+        .min(body.len());
+    Some(SipMessage {
+        body: body[..len].to_vec(),
+        ..msg
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// INVITE con cuerpo SDP (audio PCMU en `addr:port`)
+    pub(crate) fn invite(call_id: &str, addr: &str, port: u16) -> Vec<u8> {
+        let sdp = format!(
+            "v=0\r\no=- 1 1 IN IP4 {addr}\r\ns=-\r\nc=IN IP4 {addr}\r\nt=0 0\r\n\
+             m=audio {port} RTP/AVP 0 101\r\na=rtpmap:0 PCMU/8000\r\na=rtpmap:101 telephone-event/8000\r\n"
+        );
+        format!(
+            "INVITE sip:bob@example.com SIP/2.0\r\nVia: SIP/2.0/UDP {addr}:5060;branch=z9hG4bK1\r\n\
+             From: <sip:alice@example.com>;tag=a1\r\nTo: <sip:bob@example.com>\r\n\
+             i: {call_id}\r\nCSeq: 1 INVITE\r\nc: application/sdp\r\nContent-Length: {}\r\n\r\n{sdp}",
+            sdp.len()
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn parses_request_with_compact_headers_and_sdp_body() {
+        let m = parse(&invite("abc@host", "10.0.0.1", 40000)).unwrap();
+        assert_eq!(
+            m.start,
+            StartLine::Request {
+                method: "INVITE".to_string(),
+                uri: "sip:bob@example.com".to_string()
+            }
+        );
+        assert_eq!(m.call_id(), Some("abc@host"));
+        assert_eq!(m.cseq(), Some((1, "INVITE")));
+        assert!(m.sdp().unwrap().starts_with(b"v=0"));
+
+        let resp =
+            parse(b"SIP/2.0 486 Busy Here\r\nCall-ID: x\r\nContent-Length: 0\r\n\r\n").unwrap();
+        assert_eq!(
+            resp.start,
+            StartLine::Response {
+                code: 486,
+                reason: "Busy Here".to_string()
+            }
+        );
+        assert!(resp.sdp().is_none());
+        assert!(parse(b"GET / HTTP/1.1\r\n\r\n").is_none());
+    }
+}
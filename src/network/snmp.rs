@@ -0,0 +1,137 @@
+//! Cabecera de mensajes SNMP v1/v2c/v3 (RFC 1157, RFC 3416): versión, comunidad y tipo de PDU.
+
+pub const SNMP_PORT: u16 = 161;
+pub const SNMP_TRAP_PORT: u16 = 162;
+
+pub const PDU_GET: u8 = 0xa0;
+pub const PDU_GET_NEXT: u8 = 0xa1;
+pub const PDU_RESPONSE: u8 = 0xa2;
+pub const PDU_SET: u8 = 0xa3;
+pub const PDU_TRAP_V1: u8 = 0xa4;
+pub const PDU_GET_BULK: u8 = 0xa5;
+pub const PDU_INFORM: u8 = 0xa6;
+pub const PDU_TRAP_V2: u8 = 0xa7;
+pub const PDU_REPORT: u8 = 0xa8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnmpMessage {
+    /// Valor del campo versión: 0 = v1, 1 = v2c, 3 = v3
+    pub version: u8,
+    /// Comunidad en texto claro (solo v1/v2c)
+    pub community: Option<Vec<u8>>,
+    /// Etiqueta BER de la PDU (v3 la lleva cifrada o dentro de scopedPDU: 0)
+    pub pdu: u8,
+}
+
+pub fn version_name(version: u8) -> &'static str {
+    match version {
+        0 => "v1",
+        1 => "v2c",
+        3 => "v3",
+        _ => "desconocida",
+    }
+}
+
+pub fn pdu_name(pdu: u8) -> &'static str {
+    match pdu {
+        PDU_GET => "get",
+        PDU_GET_NEXT => "get-next",
+        PDU_RESPONSE => "response",
+        PDU_SET => "set",
+        PDU_TRAP_V1 => "trap",
+        PDU_GET_BULK => "get-bulk",
+        PDU_INFORM => "inform",
+        PDU_TRAP_V2 => "trap-v2",
+        PDU_REPORT => "report",
+        _ => "otra",
+    }
+}
+
+/// TLV BER: (etiqueta, contenido, resto)
+fn tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *data.first()?;
+    let first = *data.get(1)? as usize;
+    let (len, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let n = first & 0x7f;
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let bytes = data.get(2..2 + n)?;
+        (
+            bytes.iter().fold(0usize, |acc, b| acc << 8 | *b as usize),
+            2 + n,
+        )
+    };
+    let end = header.checked_add(len)?;
+    Some((tag, data.get(header..end)?, &data[end..]))
+}
+
+/// Mensaje SNMP; `None` si no es una SEQUENCE con versión entera conocida
+pub fn parse(data: &[u8]) -> Option<SnmpMessage> {
+    let (0x30, body, _) = tlv(data)? else {
+        return None;
+    };
+    let (0x02, version, rest) = tlv(body)? else {
+        return None;
+    };
+    let version = match version {
+        [v @ (0 | 1 | 3)] => *v,
+        _ => return None,
+    };
+    if version == 3 {
+        return Some(SnmpMessage {
+            version,
+            community: None,
+            pdu: 0,
+        });
+    }
+    let (0x04, community, rest) = tlv(rest)? else {
+        return None;
+    };
+    let pdu = *rest.first()?;
+    if !(PDU_GET..=PDU_REPORT).contains(&pdu) {
+        return None;
+    }
+    Some(SnmpMessage {
+        version,
+        community: Some(community.to_vec()),
+        pdu,
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// GetRequest v1/v2c con un varbind vacío
+    pub(crate) fn snmp_request(version: u8, community: &str, pdu: u8) -> Vec<u8> {
+        let pdu_body = [
+            0x02, 0x01, 0x01, // request-id
+            0x02, 0x01, 0x00, // error-status
+            0x02, 0x01, 0x00, // error-index
+            0x30, 0x00, // varbinds
+        ];
+        let mut inner = vec![0x02, 0x01, version, 0x04, community.len() as u8];
+        inner.extend(community.as_bytes());
+        inner.extend([pdu, pdu_body.len() as u8]);
+        inner.extend(pdu_body);
+        let mut out = vec![0x30, inner.len() as u8];
+        out.extend(inner);
+        out
+    }
+
+    #[test]
+    fn parses_community_and_pdu_type() {
+        let m = parse(&snmp_request(1, "public", PDU_GET)).unwrap();
+        assert_eq!(m.version, 1);
+        assert_eq!(m.community.as_deref(), Some(&b"public"[..]));
+        assert_eq!(pdu_name(m.pdu), "get");
+
+        let v3 = [0x30, 0x05, 0x02, 0x01, 0x03, 0x30, 0x00];
+        assert_eq!(parse(&v3).unwrap().community, None);
+        assert!(parse(b"\x30\x03\x02\x01\x07").is_none());
+        assert!(parse(b"GET / HTTP/1.1").is_none());
+    }
+}
@@ -0,0 +1,632 @@
+//! Parser de TLS en claro: capa de registros (incremental) y mensajes de
+//! handshake ClientHello, ServerHello y Certificate, más alertas.
+
+pub const CONTENT_CHANGE_CIPHER_SPEC: u8 = 20;
+pub const CONTENT_ALERT: u8 = 21;
+pub const CONTENT_HANDSHAKE: u8 = 22;
+pub const CONTENT_APPLICATION_DATA: u8 = 23;
+
+pub const HS_CLIENT_HELLO: u8 = 1;
+pub const HS_SERVER_HELLO: u8 = 2;
+pub const HS_CERTIFICATE: u8 = 11;
+
+pub const EXT_SERVER_NAME: u16 = 0;
+pub const EXT_SUPPORTED_GROUPS: u16 = 10;
+pub const EXT_EC_POINT_FORMATS: u16 = 11;
+pub const EXT_SIGNATURE_ALGORITHMS: u16 = 13;
+pub const EXT_ALPN: u16 = 16;
+pub const EXT_SESSION_TICKET: u16 = 35;
+pub const EXT_PRE_SHARED_KEY: u16 = 41;
+pub const EXT_SUPPORTED_VERSIONS: u16 = 43;
+
+pub const VERSION_TLS13: u16 = 0x0304;
+
+// Registro máximo: 2^14 + expansión permitida
+const MAX_RECORD_LEN: usize = 16384 + 2048;
+// Mensajes de handshake (cadenas de certificados grandes incluidas)
+const MAX_HANDSHAKE_BUFFER: usize = 256 * 1024;
+
+/// Valores GREASE (RFC 8701): 0x?A?A con ambos bytes iguales
+pub fn is_grease(v: u16) -> bool {
+    v & 0x0F0F == 0x0A0A && (v >> 8) == (v & 0xFF)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientHello {
+    pub legacy_version: u16,
+    pub session_id: Vec<u8>,
+    pub cipher_suites: Vec<u16>,
+    /// Tipos de extensión en el orden de envío
+    pub extensions: Vec<u16>,
+    pub sni: Option<String>,
+    pub alpn: Vec<String>,
+    pub supported_versions: Vec<u16>,
+    pub supported_groups: Vec<u16>,
+    pub ec_point_formats: Vec<u8>,
+    pub signature_algorithms: Vec<u16>,
+    pub session_ticket: bool,
+    pub psk_offered: bool,
+}
+
+impl ClientHello {
+    /// Versión máxima ofrecida (supported_versions tiene prioridad)
+    pub fn max_version(&self) -> u16 {
+        self.supported_versions
+            .iter()
+            .copied()
+            .filter(|v| !is_grease(*v))
+            .max()
+            .unwrap_or(self.legacy_version)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerHello {
+    pub legacy_version: u16,
+    pub session_id: Vec<u8>,
+    pub cipher_suite: u16,
+    pub extensions: Vec<u16>,
+    pub selected_version: Option<u16>,
+    pub alpn: Option<String>,
+    pub psk_accepted: bool,
+}
+
+impl ServerHello {
+    pub fn version(&self) -> u16 {
+        self.selected_version.unwrap_or(self.legacy_version)
+    }
+}
+
+/// Lector con comprobación de límites sobre un mensaje
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        let v = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(v)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let b = self.bytes(2)?;
+        Some(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u24(&mut self) -> Option<usize> {
+        let b = self.bytes(3)?;
+        Some(((b[0] as usize) << 16) | ((b[1] as usize) << 8) | b[2] as usize)
+    }
+
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let v = self.data.get(self.pos..self.pos + n)?;
+        self.pos += n;
+        Some(v)
+    }
+
+    fn vec8(&mut self) -> Option<&'a [u8]> {
+        let n = self.u8()? as usize;
+        self.bytes(n)
+    }
+
+    fn vec16(&mut self) -> Option<&'a [u8]> {
+        let n = self.u16()? as usize;
+        self.bytes(n)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+fn u16_list(data: &[u8]) -> Vec<u16> {
+    data.chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect()
+}
+
+fn alpn_list(data: &[u8]) -> Vec<String> {
+    let mut r = Reader::new(data);
+    let Some(list) = r.vec16() else {
+        return Vec::new();
+    };
+    let mut r = Reader::new(list);
+    let mut out = Vec::new();
+    while let Some(p) = r.vec8() {
+        out.push(String::from_utf8_lossy(p).into_owned());
+    }
+    out
+}
+
+fn server_name(data: &[u8]) -> Option<String> {
+    let mut r = Reader::new(data);
+    let mut list = Reader::new(r.vec16()?);
+    while !list.is_empty() {
+        let kind = list.u8()?;
+        let name = list.vec16()?;
+        if kind == 0 {
+            return Some(String::from_utf8_lossy(name).to_ascii_lowercase());
+        }
+    }
+    None
+}
+
+/// Cuerpo de un ClientHello (sin la cabecera de handshake de 4 bytes)
+pub fn parse_client_hello(body: &[u8]) -> Option<ClientHello> {
+    let mut r = Reader::new(body);
+    let mut ch = ClientHello {
+        legacy_version: r.u16()?,
+        ..ClientHello::default()
+    };
+    r.bytes(32)?; // random
+    ch.session_id = r.vec8()?.to_vec();
+    ch.cipher_suites = u16_list(r.vec16()?);
+    r.vec8()?; // compression_methods
+    if r.is_empty() {
+        return Some(ch);
+    }
+
+    let mut exts = Reader::new(r.vec16()?);
+    while !exts.is_empty() {
+        let ty = exts.u16()?;
+        let data = exts.vec16()?;
+        ch.extensions.push(ty);
+        match ty {
+            EXT_SERVER_NAME => ch.sni = server_name(data),
+            EXT_SUPPORTED_GROUPS => {
+                ch.supported_groups = u16_list(Reader::new(data).vec16().unwrap_or(&[]))
+            }
+            EXT_EC_POINT_FORMATS => {
+                ch.ec_point_formats = Reader::new(data).vec8().unwrap_or(&[]).to_vec()
+            }
+            EXT_SIGNATURE_ALGORITHMS => {
+                ch.signature_algorithms = u16_list(Reader::new(data).vec16().unwrap_or(&[]))
+            }
+            EXT_ALPN => ch.alpn = alpn_list(data),
+            EXT_SESSION_TICKET => ch.session_ticket = true,
+            EXT_PRE_SHARED_KEY => ch.psk_offered = true,
+            EXT_SUPPORTED_VERSIONS => {
+                ch.supported_versions = u16_list(Reader::new(data).vec8().unwrap_or(&[]))
+            }
+            _ => {}
+        }
+    }
+    Some(ch)
+}
+
+/// Cuerpo de un ServerHello (sin la cabecera de handshake de 4 bytes)
+pub fn parse_server_hello(body: &[u8]) -> Option<ServerHello> {
+    let mut r = Reader::new(body);
+    let mut sh = ServerHello {
+        legacy_version: r.u16()?,
+        ..ServerHello::default()
+    };
+    r.bytes(32)?; // random
+    sh.session_id = r.vec8()?.to_vec();
+    sh.cipher_suite = r.u16()?;
+    r.u8()?; // compression_method
+    if r.is_empty() {
+        return Some(sh);
+    }
+
+    let mut exts = Reader::new(r.vec16()?);
+    while !exts.is_empty() {
+        let ty = exts.u16()?;
+        let data = exts.vec16()?;
+        sh.extensions.push(ty);
+        match ty {
+            EXT_SUPPORTED_VERSIONS if data.len() == 2 => {
+                sh.selected_version = Some(u16::from_be_bytes([data[0], data[1]]))
+            }
+            EXT_ALPN => sh.alpn = alpn_list(data).into_iter().next(),
+            EXT_PRE_SHARED_KEY => sh.psk_accepted = true,
+            _ => {}
+        }
+    }
+    Some(sh)
+}
+
+/// Cadena de certificados DER de un mensaje Certificate (TLS ≤ 1.2)
+pub fn parse_certificate_chain(body: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut r = Reader::new(body);
+    let total = r.u24()?;
+    let mut list = Reader::new(r.bytes(total)?);
+    let mut certs = Vec::new();
+    while !list.is_empty() {
+        let len = list.u24()?;
+        certs.push(list.bytes(len)?.to_vec());
+    }
+    Some(certs)
+}
+
+/// Evento producido por el lector de un sentido
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsEvent {
+    /// Mensaje de handshake completo: (tipo, cuerpo)
+    Handshake(u8, Vec<u8>),
+    ChangeCipherSpec,
+    /// Alerta en claro: (nivel, descripción)
+    Alert(u8, u8),
+    /// Alerta cifrada (tras ChangeCipherSpec)
+    EncryptedAlert,
+    ApplicationData,
+}
+
+/// Reensambla registros y mensajes de handshake de un sentido
+#[derive(Debug, Default)]
+pub struct TlsReader {
+    records: Vec<u8>,
+    handshake: Vec<u8>,
+    encrypted: bool,
+    invalid: bool,
+}
+
+impl TlsReader {
+    pub fn is_invalid(&self) -> bool {
+        self.invalid
+    }
+
+    /// Añade bytes del stream y devuelve los eventos completos
+    pub fn feed(&mut self, data: &[u8], events: &mut Vec<TlsEvent>) {
+        if self.invalid {
+            return;
+        }
+        self.records.extend_from_slice(data);
+        let mut pos = 0;
+        while self.records.len() - pos >= 5 {
+            let hdr = &self.records[pos..pos + 5];
+            let (ctype, major) = (hdr[0], hdr[1]);
+            let len = u16::from_be_bytes([hdr[3], hdr[4]]) as usize;
+            if !(CONTENT_CHANGE_CIPHER_SPEC..=CONTENT_APPLICATION_DATA).contains(&ctype)
+                || major != 3
+                || len > MAX_RECORD_LEN
+            {
+                self.invalid = true;
+                self.records.clear();
+                return;
+            }
+            if self.records.len() - pos < 5 + len {
+                break;
+            }
+            let body = self.records[pos + 5..pos + 5 + len].to_vec();
+            pos += 5 + len;
+            self.on_record(ctype, &body, events);
+            if self.invalid {
+                return;
+            }
+        }
+        self.records.drain(..pos);
+    }
+
+    fn on_record(&mut self, ctype: u8, body: &[u8], events: &mut Vec<TlsEvent>) {
+        match ctype {
+            CONTENT_CHANGE_CIPHER_SPEC => {
+                self.encrypted = true;
+                events.push(TlsEvent::ChangeCipherSpec);
+            }
+            CONTENT_ALERT => {
+                if !self.encrypted && body.len() == 2 {
+                    events.push(TlsEvent::Alert(body[0], body[1]));
+                } else {
+                    events.push(TlsEvent::EncryptedAlert);
+                }
+            }
+            CONTENT_HANDSHAKE if !self.encrypted => {
+                self.handshake.extend_from_slice(body);
+                if self.handshake.len() > MAX_HANDSHAKE_BUFFER {
+                    self.invalid = true;
+                    return;
+                }
+                let mut pos = 0;
+                while self.handshake.len() - pos >= 4 {
+                    let h = &self.handshake[pos..];
+                    let len = ((h[1] as usize) << 16) | ((h[2] as usize) << 8) | h[3] as usize;
+                    if h.len() < 4 + len {
+                        break;
+                    }
+                    events.push(TlsEvent::Handshake(h[0], h[4..4 + len].to_vec()));
+                    pos += 4 + len;
+                }
+                self.handshake.drain(..pos);
+            }
+            // Handshake cifrado (Finished tras CCS) o datos de aplicación
+            _ => events.push(TlsEvent::ApplicationData),
+        }
+    }
+}
+
+/// ¿Empieza `data` como un registro de handshake TLS con ClientHello?
+pub fn looks_like_client_hello(data: &[u8]) -> bool {
+    data.len() >= 6 && data[0] == CONTENT_HANDSHAKE && data[1] == 3 && data[5] == HS_CLIENT_HELLO
+}
+
+pub fn version_name(v: u16) -> String {
+    match v {
+        0x0300 => "SSLv3".to_string(),
+        0x0301 => "TLS1.0".to_string(),
+        0x0302 => "TLS1.1".to_string(),
+        0x0303 => "TLS1.2".to_string(),
+        0x0304 => "TLS1.3".to_string(),
+        v => format!("0x{v:04X}"),
+    }
+}
+
+pub fn alert_name(desc: u8) -> String {
+    let name = match desc {
+        0 => "close_notify",
+        10 => "unexpected_message",
+        20 => "bad_record_mac",
+        21 => "decryption_failed",
+        22 => "record_overflow",
+        30 => "decompression_failure",
+        40 => "handshake_failure",
+        41 => "no_certificate",
+        42 => "bad_certificate",
+        43 => "unsupported_certificate",
+        44 => "certificate_revoked",
+        45 => "certificate_expired",
+        46 => "certificate_unknown",
+        47 => "illegal_parameter",
+        48 => "unknown_ca",
+        49 => "access_denied",
+        50 => "decode_error",
+        51 => "decrypt_error",
+        70 => "protocol_version",
+        71 => "insufficient_security",
+        80 => "internal_error",
+        86 => "inappropriate_fallback",
+        90 => "user_canceled",
+        100 => "no_renegotiation",
+        109 => "missing_extension",
+        110 => "unsupported_extension",
+        112 => "unrecognized_name",
+        113 => "bad_certificate_status_response",
+        115 => "unknown_psk_identity",
+        116 => "certificate_required",
+        120 => "no_application_protocol",
+        d => return format!("alert_{d}"),
+    };
+    name.to_string()
+}
+
+/// Nombres IANA de los cipher suites más habituales (y de los débiles conocidos)
+pub fn cipher_name(code: u16) -> String {
+    let name = match code {
+        0x0000 => "TLS_NULL_WITH_NULL_NULL",
+        0x0001 => "TLS_RSA_WITH_NULL_MD5",
+        0x0002 => "TLS_RSA_WITH_NULL_SHA",
+        0x0003 => "TLS_RSA_EXPORT_WITH_RC4_40_MD5",
+        0x0004 => "TLS_RSA_WITH_RC4_128_MD5",
+        0x0005 => "TLS_RSA_WITH_RC4_128_SHA",
+        0x0006 => "TLS_RSA_EXPORT_WITH_RC2_CBC_40_MD5",
+        0x0008 => "TLS_RSA_EXPORT_WITH_DES40_CBC_SHA",
+        0x0009 => "TLS_RSA_WITH_DES_CBC_SHA",
+        0x000A => "TLS_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x0011 => "TLS_DHE_DSS_EXPORT_WITH_DES40_CBC_SHA",
+        0x0012 => "TLS_DHE_DSS_WITH_DES_CBC_SHA",
+        0x0013 => "TLS_DHE_DSS_WITH_3DES_EDE_CBC_SHA",
+        0x0014 => "TLS_DHE_RSA_EXPORT_WITH_DES40_CBC_SHA",
+        0x0015 => "TLS_DHE_RSA_WITH_DES_CBC_SHA",
+        0x0016 => "TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x0018 => "TLS_DH_anon_WITH_RC4_128_MD5",
+        0x001B => "TLS_DH_anon_WITH_3DES_EDE_CBC_SHA",
+        0x002F => "TLS_RSA_WITH_AES_128_CBC_SHA",
+        0x0032 => "TLS_DHE_DSS_WITH_AES_128_CBC_SHA",
+        0x0033 => "TLS_DHE_RSA_WITH_AES_128_CBC_SHA",
+        0x0034 => "TLS_DH_anon_WITH_AES_128_CBC_SHA",
+        0x0035 => "TLS_RSA_WITH_AES_256_CBC_SHA",
+        0x0038 => "TLS_DHE_DSS_WITH_AES_256_CBC_SHA",
+        0x0039 => "TLS_DHE_RSA_WITH_AES_256_CBC_SHA",
+        0x003A => "TLS_DH_anon_WITH_AES_256_CBC_SHA",
+        0x003C => "TLS_RSA_WITH_AES_128_CBC_SHA256",
+        0x003D => "TLS_RSA_WITH_AES_256_CBC_SHA256",
+        0x0041 => "TLS_RSA_WITH_CAMELLIA_128_CBC_SHA",
+        0x0067 => "TLS_DHE_RSA_WITH_AES_128_CBC_SHA256",
+        0x006B => "TLS_DHE_RSA_WITH_AES_256_CBC_SHA256",
+        0x0084 => "TLS_RSA_WITH_CAMELLIA_256_CBC_SHA",
+        0x009C => "TLS_RSA_WITH_AES_128_GCM_SHA256",
+        0x009D => "TLS_RSA_WITH_AES_256_GCM_SHA384",
+        0x009E => "TLS_DHE_RSA_WITH_AES_128_GCM_SHA256",
+        0x009F => "TLS_DHE_RSA_WITH_AES_256_GCM_SHA384",
+        0x00FF => "TLS_EMPTY_RENEGOTIATION_INFO_SCSV",
+        0x1301 => "TLS_AES_128_GCM_SHA256",
+        0x1302 => "TLS_AES_256_GCM_SHA384",
+        0x1303 => "TLS_CHACHA20_POLY1305_SHA256",
+        0x1304 => "TLS_AES_128_CCM_SHA256",
+        0x5600 => "TLS_FALLBACK_SCSV",
+        0xC007 => "TLS_ECDHE_ECDSA_WITH_RC4_128_SHA",
+        0xC008 => "TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA",
+        0xC009 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA",
+        0xC00A => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA",
+        0xC011 => "TLS_ECDHE_RSA_WITH_RC4_128_SHA",
+        0xC012 => "TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        0xC013 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+        0xC014 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA",
+        0xC016 => "TLS_ECDH_anon_WITH_RC4_128_SHA",
+        0xC018 => "TLS_ECDH_anon_WITH_AES_128_CBC_SHA",
+        0xC023 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256",
+        0xC024 => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384",
+        0xC027 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256",
+        0xC028 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384",
+        0xC02B => "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        0xC02C => "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        0xC02F => "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        0xC030 => "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        0xCCA8 => "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xCCA9 => "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xCCAA => "TLS_DHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        c => return format!("0x{c:04X}"),
+    };
+    name.to_string()
+}
+
+/// Debilidad de un cipher suite según su nombre IANA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherWeakness {
+    /// Sin cifrado, export o sin autenticación
+    Broken,
+    /// RC4, DES/3DES o MD5
+    Weak,
+}
+
+pub fn cipher_weakness(code: u16) -> Option<CipherWeakness> {
+    let name = cipher_name(code);
+    if name.contains("NULL") || name.contains("EXPORT") || name.contains("_anon_") {
+        Some(CipherWeakness::Broken)
+    } else if name.contains("RC4") || name.contains("DES") || name.ends_with("MD5") {
+        Some(CipherWeakness::Weak)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn ext(ty: u16, data: &[u8]) -> Vec<u8> {
+        let mut v = ty.to_be_bytes().to_vec();
+        v.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        v.extend_from_slice(data);
+        v
+    }
+
+    fn handshake(ty: u8, body: &[u8]) -> Vec<u8> {
+        let mut v = vec![ty];
+        v.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        v.extend_from_slice(body);
+        v
+    }
+
+    pub(crate) fn record(ctype: u8, body: &[u8]) -> Vec<u8> {
+        let mut v = vec![ctype, 3, 3];
+        v.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        v.extend_from_slice(body);
+        v
+    }
+
+    /// Registro con un ClientHello (GREASE al principio de ciphers y extensiones)
+    pub(crate) fn client_hello_record(sni: &str, ciphers: &[u16], alpn: &[&str]) -> Vec<u8> {
+        let mut body = 0x0303u16.to_be_bytes().to_vec();
+        body.extend_from_slice(&[7u8; 32]);
+        body.push(0); // session id
+        let mut cs = vec![0x0A, 0x0A];
+        for c in ciphers {
+            cs.extend_from_slice(&c.to_be_bytes());
+        }
+        body.extend_from_slice(&(cs.len() as u16).to_be_bytes());
+        body.extend_from_slice(&cs);
+        body.extend_from_slice(&[1, 0]); // compression: null
+
+        let mut exts = ext(0x1A1A, &[]);
+        let mut sn = vec![0u8];
+        sn.extend_from_slice(&(sni.len() as u16).to_be_bytes());
+        sn.extend_from_slice(sni.as_bytes());
+        let mut sn_list = (sn.len() as u16).to_be_bytes().to_vec();
+        sn_list.extend_from_slice(&sn);
+        exts.extend(ext(EXT_SERVER_NAME, &sn_list));
+        exts.extend(ext(EXT_SUPPORTED_GROUPS, &[0, 4, 0, 0x1D, 0, 0x17]));
+        exts.extend(ext(EXT_EC_POINT_FORMATS, &[1, 0]));
+        exts.extend(ext(
+            EXT_SIGNATURE_ALGORITHMS,
+            &[0, 4, 0x04, 0x03, 0x08, 0x04],
+        ));
+        if !alpn.is_empty() {
+            let mut protos = Vec::new();
+            for p in alpn {
+                protos.push(p.len() as u8);
+                protos.extend_from_slice(p.as_bytes());
+            }
+            let mut a = (protos.len() as u16).to_be_bytes().to_vec();
+            a.extend_from_slice(&protos);
+            exts.extend(ext(EXT_ALPN, &a));
+        }
+        exts.extend(ext(EXT_SUPPORTED_VERSIONS, &[4, 0x03, 0x04, 0x03, 0x03]));
+        body.extend_from_slice(&(exts.len() as u16).to_be_bytes());
+        body.extend_from_slice(&exts);
+        record(CONTENT_HANDSHAKE, &handshake(HS_CLIENT_HELLO, &body))
+    }
+
+    /// Registro con un ServerHello; `tls13` añade supported_versions = TLS 1.3
+    pub(crate) fn server_hello_record(cipher: u16, tls13: bool, alpn: Option<&str>) -> Vec<u8> {
+        let mut body = 0x0303u16.to_be_bytes().to_vec();
+        body.extend_from_slice(&[9u8; 32]);
+        body.push(0);
+        body.extend_from_slice(&cipher.to_be_bytes());
+        body.push(0);
+        let mut exts = Vec::new();
+        if tls13 {
+            exts.extend(ext(EXT_SUPPORTED_VERSIONS, &[0x03, 0x04]));
+        }
+        if let Some(p) = alpn {
+            let mut a = ((p.len() + 1) as u16).to_be_bytes().to_vec();
+            a.push(p.len() as u8);
+            a.extend_from_slice(p.as_bytes());
+            exts.extend(ext(EXT_ALPN, &a));
+        }
+        body.extend_from_slice(&(exts.len() as u16).to_be_bytes());
+        body.extend_from_slice(&exts);
+        record(CONTENT_HANDSHAKE, &handshake(HS_SERVER_HELLO, &body))
+    }
+
+    /// Registro con un mensaje Certificate que contiene `certs`
+    pub(crate) fn certificate_record(certs: &[&[u8]]) -> Vec<u8> {
+        let mut list = Vec::new();
+        for c in certs {
+            list.extend_from_slice(&(c.len() as u32).to_be_bytes()[1..]);
+            list.extend_from_slice(c);
+        }
+        let mut body = (list.len() as u32).to_be_bytes()[1..].to_vec();
+        body.extend_from_slice(&list);
+        record(CONTENT_HANDSHAKE, &handshake(HS_CERTIFICATE, &body))
+    }
+
+    #[test]
+    fn client_hello_fields_across_fragmented_records() {
+        let rec = client_hello_record("Example.COM", &[0x1301, 0xC02F], &["h2", "http/1.1"]);
+        assert!(looks_like_client_hello(&rec));
+        let mut reader = TlsReader::default();
+        let mut events = Vec::new();
+        for part in rec.chunks(7) {
+            reader.feed(part, &mut events);
+        }
+        let [TlsEvent::Handshake(HS_CLIENT_HELLO, body)] = events.as_slice() else {
+            panic!("se esperaba un ClientHello: {events:?}");
+        };
+        let ch = parse_client_hello(body).unwrap();
+        assert_eq!(ch.sni.as_deref(), Some("example.com"));
+        assert_eq!(ch.cipher_suites, vec![0x0A0A, 0x1301, 0xC02F]);
+        assert_eq!(ch.alpn, vec!["h2", "http/1.1"]);
+        assert_eq!(ch.max_version(), VERSION_TLS13);
+        assert_eq!(ch.extensions[0], 0x1A1A);
+        assert!(is_grease(0x1A1A) && !is_grease(0x1301));
+    }
+
+    #[test]
+    fn server_hello_alerts_and_weak_ciphers() {
+        let mut reader = TlsReader::default();
+        let mut events = Vec::new();
+        let mut stream = server_hello_record(0x0005, false, Some("http/1.1"));
+        stream.extend(record(CONTENT_ALERT, &[2, 40]));
+        stream.extend(record(CONTENT_CHANGE_CIPHER_SPEC, &[1]));
+        stream.extend(record(CONTENT_ALERT, &[0u8; 26]));
+        reader.feed(&stream, &mut events);
+        let TlsEvent::Handshake(HS_SERVER_HELLO, body) = &events[0] else {
+            panic!("se esperaba un ServerHello");
+        };
+        let sh = parse_server_hello(body).unwrap();
+        assert_eq!(sh.version(), 0x0303);
+        assert_eq!(sh.alpn.as_deref(), Some("http/1.1"));
+        assert_eq!(events[1], TlsEvent::Alert(2, 40));
+        assert_eq!(events[3], TlsEvent::EncryptedAlert);
+        assert_eq!(cipher_weakness(0x0005), Some(CipherWeakness::Weak));
+        assert_eq!(cipher_weakness(0x0003), Some(CipherWeakness::Broken));
+        assert_eq!(cipher_weakness(0x1301), None);
+    }
+}
@@ -0,0 +1,263 @@
+//! Huellas JA3/JA3S (MD5) y JA4 (SHA-256 truncado) a partir de los Hello en
+//! claro, y listas locales de huellas permitidas/bloqueadas.
+
+use crate::network::format::hex;
+use crate::network::tls::{is_grease, ClientHello, ServerHello, EXT_ALPN, EXT_SERVER_NAME};
+use md5::{Digest, Md5};
+use sha2::Sha256;
+use std::collections::HashMap;
+
+/// Huella con su cadena original y el hash que se publica
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub raw: String,
+    pub hash: String,
+}
+
+fn dashed<T: ToString>(values: impl Iterator<Item = T>) -> String {
+    values.map(|v| v.to_string()).collect::<Vec<_>>().join("-")
+}
+
+fn md5_fingerprint(raw: String) -> Fingerprint {
+    let hash = hex(&Md5::digest(raw.as_bytes()));
+    Fingerprint { raw, hash }
+}
+
+/// JA3: versión,ciphers,extensiones,curvas,formatos de punto (sin GREASE)
+pub fn ja3(ch: &ClientHello) -> Fingerprint {
+    let no_grease = |v: &&u16| !is_grease(**v);
+    md5_fingerprint(format!(
+        "{},{},{},{},{}",
+        ch.legacy_version,
+        dashed(ch.cipher_suites.iter().filter(no_grease)),
+        dashed(ch.extensions.iter().filter(no_grease)),
+        dashed(ch.supported_groups.iter().filter(no_grease)),
+        dashed(ch.ec_point_formats.iter())
+    ))
+}
+
+/// JA3S: versión,cipher,extensiones del ServerHello
+pub fn ja3s(sh: &ServerHello) -> Fingerprint {
+    md5_fingerprint(format!(
+        "{},{},{}",
+        sh.legacy_version,
+        sh.cipher_suite,
+        dashed(sh.extensions.iter())
+    ))
+}
+
+/// Transporte del ClientHello, primer carácter de JA4
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ja4Transport {
+    Tcp,
+    Quic,
+}
+
+fn ja4_version(v: u16) -> &'static str {
+    match v {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        0x0002 => "s2",
+        0xFEFF => "d1",
+        0xFEFD => "d2",
+        0xFEFC => "d3",
+        _ => "00",
+    }
+}
+
+/// Primer y último carácter del primer ALPN (en hex si no son alfanuméricos)
+fn ja4_alpn(alpn: Option<&String>) -> String {
+    let Some(p) = alpn.map(|p| p.as_bytes()).filter(|p| !p.is_empty()) else {
+        return "00".to_string();
+    };
+    let (first, last) = (p[0], p[p.len() - 1]);
+    if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+        format!("{}{}", first as char, last as char)
+    } else {
+        let h = hex(p);
+        format!("{}{}", &h[..1], &h[h.len() - 1..])
+    }
+}
+
+fn sha256_12(input: &str) -> String {
+    if input.is_empty() {
+        return "000000000000".to_string();
+    }
+    hex(&Sha256::digest(input.as_bytes()))[..12].to_string()
+}
+
+/// JA4 (a_b_c): resumen legible, ciphers ordenados y extensiones ordenadas + firmas
+pub fn ja4(ch: &ClientHello, transport: Ja4Transport) -> Fingerprint {
+    let mut ciphers: Vec<u16> = ch
+        .cipher_suites
+        .iter()
+        .copied()
+        .filter(|c| !is_grease(*c))
+        .collect();
+    let exts: Vec<u16> = ch
+        .extensions
+        .iter()
+        .copied()
+        .filter(|e| !is_grease(*e))
+        .collect();
+
+    let a = format!(
+        "{}{}{}{:02}{:02}{}",
+        match transport {
+            Ja4Transport::Tcp => 't',
+            Ja4Transport::Quic => 'q',
+        },
+        ja4_version(ch.max_version()),
+        if ch.sni.is_some() { 'd' } else { 'i' },
+        ciphers.len().min(99),
+        exts.len().min(99),
+        ja4_alpn(ch.alpn.first())
+    );
+
+    let list = |v: &[u16]| {
+        v.iter()
+            .map(|x| format!("{x:04x}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    ciphers.sort_unstable();
+    let b_raw = list(&ciphers);
+
+    // SNI y ALPN ya cuentan en la parte a
+    let mut sorted_exts: Vec<u16> = exts
+        .into_iter()
+        .filter(|e| *e != EXT_SERVER_NAME && *e != EXT_ALPN)
+        .collect();
+    sorted_exts.sort_unstable();
+    let sigs: Vec<u16> = ch
+        .signature_algorithms
+        .iter()
+        .copied()
+        .filter(|s| !is_grease(*s))
+        .collect();
+    let c_raw = if sigs.is_empty() || sorted_exts.is_empty() {
+        list(&sorted_exts)
+    } else {
+        format!("{}_{}", list(&sorted_exts), list(&sigs))
+    };
+
+    Fingerprint {
+        hash: format!("{a}_{}_{}", sha256_12(&b_raw), sha256_12(&c_raw)),
+        raw: format!("{a}_{b_raw}_{c_raw}"),
+    }
+}
+
+/// Veredicto de una huella en la lista local
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListVerdict {
+    Allow,
+    Deny,
+}
+
+impl ListVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListVerdict::Allow => "allow",
+            ListVerdict::Deny => "deny",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListEntry {
+    pub verdict: ListVerdict,
+    pub comment: String,
+}
+
+/// Lista local de huellas: una por línea, `allow|deny <huella> [comentario]`.
+/// La huella es el hash JA3/JA3S o la cadena JA4; `#` inicia un comentario.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FingerprintList {
+    entries: HashMap<String, ListEntry>,
+}
+
+impl FingerprintList {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut entries = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let verdict = match parts.next().map(str::to_ascii_lowercase).as_deref() {
+                Some("allow") => ListVerdict::Allow,
+                Some("deny") => ListVerdict::Deny,
+                _ => return Err(format!("línea {}: se esperaba 'allow' o 'deny'", i + 1)),
+            };
+            let Some(fp) = parts.next() else {
+                return Err(format!("línea {}: falta la huella", i + 1));
+            };
+            let comment = parts.collect::<Vec<_>>().join(" ");
+            entries.insert(fp.to_ascii_lowercase(), ListEntry { verdict, comment });
+        }
+        Ok(FingerprintList { entries })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn lookup(&self, fingerprint: &str) -> Option<&ListEntry> {
+        self.entries.get(&fingerprint.to_ascii_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::tls::tests::{client_hello_record, server_hello_record};
+    use crate::network::tls::{parse_client_hello, parse_server_hello};
+
+    // Cuerpo del mensaje de handshake dentro de un registro de prueba
+    fn body(record: &[u8]) -> &[u8] {
+        &record[9..]
+    }
+
+    #[test]
+    fn ja3_ja3s_and_ja4_of_synthetic_hellos() {
+        let rec = client_hello_record("example.com", &[0x1301, 0xC02F], &["h2", "http/1.1"]);
+        let ch = parse_client_hello(body(&rec)).unwrap();
+
+        let j = ja3(&ch);
+        assert_eq!(j.raw, "771,4865-49199,0-10-11-13-16-43,29-23,0");
+        assert_eq!(j.hash, "97737df38853b88c4324af06e211c4a1");
+
+        let j4 = ja4(&ch, Ja4Transport::Tcp);
+        assert_eq!(j4.raw, "t13d0206h2_1301,c02f_000a,000b,000d,002b_0403,0804");
+        assert_eq!(j4.hash, "t13d0206h2_c1929292aa6b_fb71836bce29");
+        assert!(ja4(&ch, Ja4Transport::Quic).hash.starts_with("q13d"));
+
+        let rec = server_hello_record(0x1301, true, Some("h2"));
+        let sh = parse_server_hello(body(&rec)).unwrap();
+        assert_eq!(ja3s(&sh).raw, "771,4865,43-16");
+    }
+
+    #[test]
+    fn fingerprint_list_parsing() {
+        let list = FingerprintList::parse(
+            "# lista local\n\
+             deny  97737DF38853B88C4324AF06E211C4A1 familia de prueba\n\
+             allow t13d0206h2_c1929292aa6b_fb71836bce29\n",
+        )
+        .unwrap();
+        assert_eq!(list.len(), 2);
+        let e = list.lookup("97737df38853b88c4324af06e211c4a1").unwrap();
+        assert_eq!(e.verdict, ListVerdict::Deny);
+        assert_eq!(e.comment, "familia de prueba");
+        assert!(FingerprintList::parse("block abc").is_err());
+        assert!(FingerprintList::parse("deny").is_err());
+    }
+}
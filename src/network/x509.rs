@@ -0,0 +1,579 @@
+//! Parser DER mínimo para certificados X.509: nombres, SANs, validez, clave
+//! pública y algoritmo de firma. Sin verificación criptográfica.
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+// [0] y [3] EXPLICIT del TBSCertificate
+const TAG_VERSION: u8 = 0xA0;
+const TAG_EXTENSIONS: u8 = 0xA3;
+// GeneralName: dNSName [2] e iPAddress [7] (IMPLICIT, primitivos)
+const TAG_SAN_DNS: u8 = 0x82;
+const TAG_SAN_IP: u8 = 0x87;
+
+const OID_SUBJECT_ALT_NAME: &str = "2.5.29.17";
+const OID_RSA: &str = "1.2.840.113549.1.1.1";
+const OID_EC: &str = "1.2.840.10045.2.1";
+const OID_DSA: &str = "1.2.840.10040.4.1";
+const OID_ED25519: &str = "1.3.101.112";
+const OID_ED448: &str = "1.3.101.113";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    /// Número de serie en hexadecimal
+    pub serial: String,
+    pub subject: String,
+    pub issuer: String,
+    pub common_name: Option<String>,
+    pub sans: Vec<String>,
+    pub not_before: Asn1Time,
+    pub not_after: Asn1Time,
+    pub key_type: String,
+    pub key_bits: Option<u32>,
+    pub signature_algorithm: String,
+    /// Emisor y sujeto idénticos (comparación del DER de ambos nombres)
+    pub self_signed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asn1Time {
+    /// Segundos desde la época Unix
+    pub unix: i64,
+    /// Formato ISO 8601 en UTC
+    pub iso: String,
+}
+
+/// Elemento TLV: etiqueta, contenido y codificación completa
+#[derive(Debug, Clone, Copy)]
+struct Tlv<'a> {
+    tag: u8,
+    content: &'a [u8],
+    raw: &'a [u8],
+}
+
+/// Lector secuencial de elementos DER
+struct Der<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Der<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Der { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Tlv<'a>> {
+        let start = self.pos;
+        let tag = *self.data.get(self.pos)?;
+        let first = *self.data.get(self.pos + 1)? as usize;
+        let mut pos = self.pos + 2;
+        let len = if first < 0x80 {
+            first
+        } else {
+            // Forma larga; más de 4 bytes de longitud no cabe en un certificado razonable
+            let n = first & 0x7F;
+            if n == 0 || n > 4 {
+                return None;
+            }
+            let mut len = 0usize;
+            for _ in 0..n {
+                len = (len << 8) | *self.data.get(pos)? as usize;
+                pos += 1;
+            }
+            len
+        };
+        let content = self.data.get(pos..pos.checked_add(len)?)?;
+        self.pos = pos + len;
+        Some(Tlv {
+            tag,
+            content,
+            raw: &self.data[start..self.pos],
+        })
+    }
+
+    fn expect(&mut self, tag: u8) -> Option<Tlv<'a>> {
+        let tlv = self.next()?;
+        (tlv.tag == tag).then_some(tlv)
+    }
+}
+
+fn oid_to_string(content: &[u8]) -> Option<String> {
+    let (&first, rest) = content.split_first()?;
+    // Los dos primeros arcos van juntos en el primer byte (40 * X + Y)
+    let (a, b) = if first < 80 {
+        (first / 40, first % 40)
+    } else {
+        (2, first - 80)
+    };
+    let mut parts = vec![a as u64, b as u64];
+    let mut acc = 0u64;
+    for &b in rest {
+        acc = (acc << 7) | (b & 0x7F) as u64;
+        if b & 0x80 == 0 {
+            parts.push(acc);
+            acc = 0;
+        }
+    }
+    Some(
+        parts
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join("."),
+    )
+}
+
+fn attribute_name(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "2.5.4.3" => "CN",
+        "2.5.4.5" => "serialNumber",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "ST",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "1.2.840.113549.1.9.1" => "emailAddress",
+        _ => return None,
+    })
+}
+
+pub fn signature_algorithm_name(oid: &str) -> String {
+    let name = match oid {
+        "1.2.840.113549.1.1.2" => "md2WithRSAEncryption",
+        "1.2.840.113549.1.1.4" => "md5WithRSAEncryption",
+        "1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+        "1.2.840.113549.1.1.10" => "rsassaPss",
+        "1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+        "1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+        "1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+        "1.2.840.10040.4.3" => "dsaWithSHA1",
+        "1.2.840.10045.4.1" => "ecdsa-with-SHA1",
+        "1.2.840.10045.4.3.2" => "ecdsa-with-SHA256",
+        "1.2.840.10045.4.3.3" => "ecdsa-with-SHA384",
+        "1.2.840.10045.4.3.4" => "ecdsa-with-SHA512",
+        OID_ED25519 => "ed25519",
+        OID_ED448 => "ed448",
+        other => return other.to_string(),
+    };
+    name.to_string()
+}
+
+/// Texto de un valor de atributo (PrintableString, UTF8String, IA5String, ...)
+fn string_value(tlv: &Tlv) -> String {
+    match tlv.tag {
+        // BMPString: UTF-16BE
+        0x1E => {
+            let units: Vec<u16> = tlv
+                .content
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(tlv.content).into_owned(),
+    }
+}
+
+/// Name → "CN=..., O=..." en el orden del certificado, más el CN si existe
+fn parse_name(content: &[u8]) -> Option<(String, Option<String>)> {
+    let mut rdns = Der::new(content);
+    let mut parts = Vec::new();
+    let mut cn = None;
+    while !rdns.is_empty() {
+        let set = rdns.expect(TAG_SET)?;
+        let mut attrs = Der::new(set.content);
+        while !attrs.is_empty() {
+            let attr = attrs.expect(TAG_SEQUENCE)?;
+            let mut a = Der::new(attr.content);
+            let oid = oid_to_string(a.expect(TAG_OID)?.content)?;
+            let value = string_value(&a.next()?);
+            if oid == "2.5.4.3" && cn.is_none() {
+                cn = Some(value.clone());
+            }
+            let key = attribute_name(&oid).map(str::to_string).unwrap_or(oid);
+            parts.push(format!("{key}={value}"));
+        }
+    }
+    Some((parts.join(", "), cn))
+}
+
+/// Días desde 1970-01-01 para una fecha del calendario gregoriano
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn parse_time(tlv: &Tlv) -> Option<Asn1Time> {
+    let text = std::str::from_utf8(tlv.content).ok()?;
+    let text = text.strip_suffix('Z')?;
+    let (year, rest) = match tlv.tag {
+        TAG_UTC_TIME => {
+            let yy: i64 = text.get(..2)?.parse().ok()?;
+            // RFC 5280: YY >= 50 es 19YY
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, text.get(2..)?)
+        }
+        TAG_GENERALIZED_TIME => (text.get(..4)?.parse().ok()?, text.get(4..)?),
+        _ => return None,
+    };
+    let field = |i: usize| -> Option<u32> { rest.get(i..i + 2)?.parse().ok() };
+    let (month, day, hour, min) = (field(0)?, field(2)?, field(4)?, field(6)?);
+    let sec = field(8).unwrap_or(0);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || min > 59 || sec > 60 {
+        return None;
+    }
+    let unix = days_from_civil(year, month, day) * 86_400 + (hour * 3600 + min * 60 + sec) as i64;
+    Some(Asn1Time {
+        unix,
+        iso: format!("{year:04}-{month:02}-{day:02}T{hour:02}:{min:02}:{sec:02}Z"),
+    })
+}
+
+/// Bits significativos de un INTEGER DER sin signo
+fn integer_bits(content: &[u8]) -> u32 {
+    let digits: &[u8] = match content.iter().position(|b| *b != 0) {
+        Some(i) => &content[i..],
+        None => return 0,
+    };
+    (digits.len() as u32 - 1) * 8 + (8 - digits[0].leading_zeros())
+}
+
+fn curve_bits(oid: &str) -> Option<u32> {
+    Some(match oid {
+        "1.2.840.10045.3.1.1" => 192, // secp192r1
+        "1.3.132.0.33" => 224,        // secp224r1
+        "1.2.840.10045.3.1.7" => 256, // prime256v1
+        "1.3.132.0.10" => 256,        // secp256k1
+        "1.3.132.0.34" => 384,        // secp384r1
+        "1.3.132.0.35" => 521,        // secp521r1
+        _ => return None,
+    })
+}
+
+/// SubjectPublicKeyInfo → (tipo de clave, bits)
+fn parse_public_key(content: &[u8]) -> Option<(String, Option<u32>)> {
+    let mut spki = Der::new(content);
+    let alg = spki.expect(TAG_SEQUENCE)?;
+    let key = spki.expect(TAG_BIT_STRING)?;
+    let mut a = Der::new(alg.content);
+    let oid = oid_to_string(a.expect(TAG_OID)?.content)?;
+    let params = a.next();
+    // El primer byte del BIT STRING indica los bits de relleno
+    let key_bytes = key.content.get(1..)?;
+
+    Some(match oid.as_str() {
+        OID_RSA => {
+            let bits = Der::new(key_bytes)
+                .expect(TAG_SEQUENCE)
+                .and_then(|seq| Der::new(seq.content).expect(TAG_INTEGER))
+                .map(|modulus| integer_bits(modulus.content));
+            ("RSA".to_string(), bits)
+        }
+        OID_EC => {
+            let bits = params
+                .filter(|p| p.tag == TAG_OID)
+                .and_then(|p| oid_to_string(p.content))
+                .and_then(|curve| curve_bits(&curve));
+            ("EC".to_string(), bits)
+        }
+        OID_DSA => {
+            let bits = params
+                .filter(|p| p.tag == TAG_SEQUENCE)
+                .and_then(|p| Der::new(p.content).expect(TAG_INTEGER))
+                .map(|prime| integer_bits(prime.content));
+            ("DSA".to_string(), bits)
+        }
+        OID_ED25519 => ("Ed25519".to_string(), Some(256)),
+        OID_ED448 => ("Ed448".to_string(), Some(456)),
+        other => (other.to_string(), None),
+    })
+}
+
+fn parse_sans(extnvalue: &[u8]) -> Option<Vec<String>> {
+    let names = Der::new(extnvalue).expect(TAG_SEQUENCE)?;
+    let mut r = Der::new(names.content);
+    let mut out = Vec::new();
+    while !r.is_empty() {
+        let gn = r.next()?;
+        match gn.tag {
+            TAG_SAN_DNS => out.push(String::from_utf8_lossy(gn.content).to_ascii_lowercase()),
+            TAG_SAN_IP => match gn.content.len() {
+                4 => {
+                    let b: [u8; 4] = gn.content.try_into().ok()?;
+                    out.push(std::net::Ipv4Addr::from(b).to_string());
+                }
+                16 => {
+                    let b: [u8; 16] = gn.content.try_into().ok()?;
+                    out.push(std::net::Ipv6Addr::from(b).to_string());
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    Some(out)
+}
+
+/// Parsea un certificado X.509 codificado en DER
+pub fn parse_certificate(der: &[u8]) -> Option<Certificate> {
+    let cert = Der::new(der).expect(TAG_SEQUENCE)?;
+    let mut c = Der::new(cert.content);
+    let tbs = c.expect(TAG_SEQUENCE)?;
+    let sig_alg = c.expect(TAG_SEQUENCE)?;
+    let sig_oid = oid_to_string(Der::new(sig_alg.content).expect(TAG_OID)?.content)?;
+
+    let mut t = Der::new(tbs.content);
+    if t.peek_tag() == Some(TAG_VERSION) {
+        t.next()?;
+    }
+    let serial = t.expect(TAG_INTEGER)?;
+    t.expect(TAG_SEQUENCE)?; // signature (repetido en el TBS)
+    let issuer = t.expect(TAG_SEQUENCE)?;
+    let validity = t.expect(TAG_SEQUENCE)?;
+    let subject = t.expect(TAG_SEQUENCE)?;
+    let spki = t.expect(TAG_SEQUENCE)?;
+
+    let mut sans = Vec::new();
+    while let Some(field) = t.next() {
+        if field.tag != TAG_EXTENSIONS {
+            continue; // issuerUniqueID / subjectUniqueID
+        }
+        let exts = Der::new(field.content).expect(TAG_SEQUENCE)?;
+        let mut e = Der::new(exts.content);
+        while !e.is_empty() {
+            let ext = e.expect(TAG_SEQUENCE)?;
+            let mut x = Der::new(ext.content);
+            let oid = oid_to_string(x.expect(TAG_OID)?.content)?;
+            if x.peek_tag() == Some(TAG_BOOLEAN) {
+                x.next()?;
+            }
+            let value = x.expect(TAG_OCTET_STRING)?;
+            if oid == OID_SUBJECT_ALT_NAME {
+                sans = parse_sans(value.content)?;
+            }
+        }
+    }
+
+    let mut v = Der::new(validity.content);
+    let not_before = parse_time(&v.next()?)?;
+    let not_after = parse_time(&v.next()?)?;
+    let (subject_text, common_name) = parse_name(subject.content)?;
+    let (issuer_text, _) = parse_name(issuer.content)?;
+    let (key_type, key_bits) = parse_public_key(spki.content)?;
+
+    Some(Certificate {
+        serial: serial.content.iter().map(|b| format!("{b:02x}")).collect(),
+        subject: subject_text,
+        issuer: issuer_text,
+        common_name,
+        sans,
+        not_before,
+        not_after,
+        key_type,
+        key_bits,
+        signature_algorithm: signature_algorithm_name(&sig_oid),
+        self_signed: issuer.raw == subject.raw,
+    })
+}
+
+impl Certificate {
+    /// ¿Cubre el certificado este nombre? Usa los SANs, o el CN si no hay SANs
+    pub fn matches_name(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        let cn = self.common_name.as_ref().map(|c| c.to_ascii_lowercase());
+        let candidates: Vec<&str> = if self.sans.is_empty() {
+            cn.as_deref().into_iter().collect()
+        } else {
+            self.sans.iter().map(String::as_str).collect()
+        };
+        candidates
+            .iter()
+            .any(|pattern| name_matches(pattern, &name))
+    }
+
+    /// Clave por debajo del mínimo recomendado para su tipo
+    pub fn has_short_key(&self) -> bool {
+        match (self.key_type.as_str(), self.key_bits) {
+            ("RSA" | "DSA", Some(bits)) => bits < 2048,
+            ("EC", Some(bits)) => bits < 224,
+            _ => false,
+        }
+    }
+}
+
+/// Comodín RFC 6125: solo en la etiqueta más a la izquierda y cubre una sola etiqueta
+fn name_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => name
+            .split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+        None => pattern == name,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut v = vec![tag];
+        match content.len() {
+            n if n < 0x80 => v.push(n as u8),
+            n if n < 0x100 => v.extend_from_slice(&[0x81, n as u8]),
+            n => v.extend_from_slice(&[0x82, (n >> 8) as u8, n as u8]),
+        }
+        v.extend_from_slice(content);
+        v
+    }
+
+    fn name(cn: &str) -> Vec<u8> {
+        let cn_oid = [0x55, 0x04, 0x03];
+        let o_oid = [0x55, 0x04, 0x0A];
+        let mut rdns = Vec::new();
+        let attr = [tlv(TAG_OID, &o_oid), tlv(0x0C, b"Example Org")].concat();
+        rdns.extend(tlv(TAG_SET, &tlv(TAG_SEQUENCE, &attr)));
+        let attr = [tlv(TAG_OID, &cn_oid), tlv(0x13, cn.as_bytes())].concat();
+        rdns.extend(tlv(TAG_SET, &tlv(TAG_SEQUENCE, &attr)));
+        tlv(TAG_SEQUENCE, &rdns)
+    }
+
+    /// Certificado sintético RSA (sha256WithRSAEncryption) con validez en UTCTime
+    /// ("YYMMDDHHMMSSZ"), SANs DNS y un módulo de `key_bits` bits
+    pub(crate) fn certificate(
+        subject_cn: &str,
+        issuer_cn: &str,
+        sans: &[&str],
+        not_before: &str,
+        not_after: &str,
+        key_bits: usize,
+    ) -> Vec<u8> {
+        let sha256_rsa = [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B];
+        let rsa = [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+        let sig_alg = tlv(
+            TAG_SEQUENCE,
+            &[tlv(TAG_OID, &sha256_rsa), vec![0x05, 0]].concat(),
+        );
+
+        let mut modulus = vec![0x00, 0x80];
+        modulus.resize(key_bits / 8 + 1, 0xAB);
+        let rsa_key = tlv(
+            TAG_SEQUENCE,
+            &[tlv(TAG_INTEGER, &modulus), tlv(TAG_INTEGER, &[1, 0, 1])].concat(),
+        );
+        let mut bits = vec![0];
+        bits.extend(rsa_key);
+        let spki = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_SEQUENCE, &[tlv(TAG_OID, &rsa), vec![0x05, 0]].concat()),
+                tlv(TAG_BIT_STRING, &bits),
+            ]
+            .concat(),
+        );
+
+        let general_names: Vec<u8> = sans
+            .iter()
+            .flat_map(|s| tlv(TAG_SAN_DNS, s.as_bytes()))
+            .collect();
+        let san_ext = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_OID, &[0x55, 0x1D, 0x11]),
+                tlv(TAG_OCTET_STRING, &tlv(TAG_SEQUENCE, &general_names)),
+            ]
+            .concat(),
+        );
+        let validity = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_UTC_TIME, not_before.as_bytes()),
+                tlv(TAG_UTC_TIME, not_after.as_bytes()),
+            ]
+            .concat(),
+        );
+        let tbs = tlv(
+            TAG_SEQUENCE,
+            &[
+                tlv(TAG_VERSION, &tlv(TAG_INTEGER, &[2])),
+                tlv(TAG_INTEGER, &[0x01, 0x23]),
+                sig_alg.clone(),
+                name(issuer_cn),
+                validity,
+                name(subject_cn),
+                spki,
+                tlv(TAG_EXTENSIONS, &tlv(TAG_SEQUENCE, &san_ext)),
+            ]
+            .concat(),
+        );
+        tlv(
+            TAG_SEQUENCE,
+            &[tbs, sig_alg, tlv(TAG_BIT_STRING, &[0, 0xDE, 0xAD])].concat(),
+        )
+    }
+
+    #[test]
+    fn parses_fields_of_synthetic_certificate() {
+        let der = certificate(
+            "www.example.com",
+            "Example CA",
+            &["www.example.com", "*.cdn.example.com"],
+            "240101000000Z",
+            "250101120000Z",
+            2048,
+        );
+        let c = parse_certificate(&der).unwrap();
+        assert_eq!(c.serial, "0123");
+        assert_eq!(c.subject, "O=Example Org, CN=www.example.com");
+        assert_eq!(c.issuer, "O=Example Org, CN=Example CA");
+        assert_eq!(c.common_name.as_deref(), Some("www.example.com"));
+        assert_eq!(c.sans, vec!["www.example.com", "*.cdn.example.com"]);
+        assert_eq!(c.not_before.unix, 1_704_067_200);
+        assert_eq!(c.not_after.iso, "2025-01-01T12:00:00Z");
+        assert_eq!((c.key_type.as_str(), c.key_bits), ("RSA", Some(2048)));
+        assert_eq!(c.signature_algorithm, "sha256WithRSAEncryption");
+        assert!(!c.self_signed && !c.has_short_key());
+
+        assert!(c.matches_name("WWW.example.com."));
+        assert!(c.matches_name("img.cdn.example.com"));
+        assert!(!c.matches_name("a.b.cdn.example.com"));
+        assert!(!c.matches_name("example.com"));
+    }
+
+    #[test]
+    fn self_signed_short_key_and_truncated_input() {
+        let der = certificate(
+            "router",
+            "router",
+            &[],
+            "991231235959Z",
+            "491231235959Z",
+            1024,
+        );
+        let c = parse_certificate(&der).unwrap();
+        assert!(c.self_signed);
+        assert!(c.has_short_key());
+        assert_eq!(c.not_before.iso, "1999-12-31T23:59:59Z");
+        assert_eq!(c.not_after.iso, "2049-12-31T23:59:59Z");
+        // Sin SANs, el CN decide
+        assert!(c.matches_name("router"));
+        assert!(parse_certificate(&der[..der.len() - 10]).is_none());
+    }
+}